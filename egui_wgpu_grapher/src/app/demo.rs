@@ -0,0 +1,67 @@
+//! Demo mode that cycles through a curated set of scenes for showcasing.
+
+use crate::grapher_egui::GrapherSceneMode;
+
+/// One scene shown by the demo.
+#[derive(Clone, Debug)]
+pub enum DemoStep {
+    /// Graph of a function of x and z.
+    Graph(&'static str),
+    /// A scene that needs no input, like the wave simulation.
+    Scene(GrapherSceneMode),
+    /// A model loaded from a file.
+    Model(String),
+}
+
+/// Scenes shown in order, switching every `interval_secs`
+/// while the demo is enabled.
+pub struct Demo {
+    steps: Vec<DemoStep>,
+    // index of the step being shown, once running
+    current: Option<usize>,
+    elapsed_secs: f32,
+}
+
+impl Demo {
+    /// The built-in scenes, with the model given on the command line last.
+    pub fn new(model_path: Option<String>) -> Self {
+        let mut steps = vec![
+            DemoStep::Graph("sin(x)*cos(z)"),
+            DemoStep::Scene(GrapherSceneMode::Solver),
+            DemoStep::Graph("x^2 - z^2"),
+            DemoStep::Scene(GrapherSceneMode::Schrodinger),
+            DemoStep::Graph("cos(3*sqrt(x^2 + z^2))"),
+        ];
+        if let Some(path) = model_path {
+            steps.push(DemoStep::Model(path));
+        }
+        Self {
+            steps,
+            current: None,
+            elapsed_secs: 0.0,
+        }
+    }
+
+    /// Advance the demo timer by `secs`. Returns the step to switch to when
+    /// the demo starts or the current scene's time is up. Disabling the demo
+    /// stops it, and it starts over from the first scene when enabled again.
+    pub fn tick(&mut self, enabled: bool, interval_secs: f32, secs: f32) -> Option<&DemoStep> {
+        if !enabled {
+            self.current = None;
+            return None;
+        }
+        let next = match self.current {
+            None => 0,
+            Some(current) => {
+                self.elapsed_secs += secs;
+                if self.elapsed_secs < interval_secs {
+                    return None;
+                }
+                (current + 1) % self.steps.len()
+            }
+        };
+        self.current = Some(next);
+        self.elapsed_secs = 0.0;
+        self.steps.get(next)
+    }
+}
@@ -0,0 +1,60 @@
+//! Collect frame times and summarize them for the framerate display.
+
+use std::fmt;
+
+#[derive(Default)]
+pub struct FrameTimes {
+    // seconds between consecutive rendered frames
+    samples: Vec<f32>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FrameTimeSummary {
+    pub avg_fps: f32,
+    // frame time percentiles in milliseconds
+    pub p50_ms: f32,
+    pub p95_ms: f32,
+    pub p99_ms: f32,
+}
+
+impl FrameTimes {
+    pub fn record(&mut self, secs: f32) {
+        self.samples.push(secs);
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Summarize and clear the recorded samples.
+    pub fn take_summary(&mut self) -> Option<FrameTimeSummary> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut samples = std::mem::take(&mut self.samples);
+        samples.sort_by(f32::total_cmp);
+
+        let total: f32 = samples.iter().sum();
+        let percentile_ms = |p: f32| {
+            let index = ((samples.len() - 1) as f32 * p).round() as usize;
+            1000.0 * samples[index]
+        };
+
+        Some(FrameTimeSummary {
+            avg_fps: samples.len() as f32 / total.max(f32::EPSILON),
+            p50_ms: percentile_ms(0.50),
+            p95_ms: percentile_ms(0.95),
+            p99_ms: percentile_ms(0.99),
+        })
+    }
+}
+
+impl fmt::Display for FrameTimeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.1} fps, p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms",
+            self.avg_fps, self.p50_ms, self.p95_ms, self.p99_ms
+        )
+    }
+}
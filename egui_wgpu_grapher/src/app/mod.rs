@@ -1,11 +1,23 @@
+mod demo;
+mod frame_timing;
+mod scene_file;
+mod settings;
 mod state;
+use demo::Demo;
 use egui_file_dialog::DialogState;
+use frame_timing::FrameTimes;
+use scene_file::SceneDescriptor;
 use state::*;
 
+pub use state::AdapterSelection;
+
 use crate::{
-    egui::{components, ui::create_gui},
+    egui::{
+        components, shortcuts,
+        ui::{DemoSettings, FRAME_TIME_HISTORY_LEN, FrameRateSettings, UI_SCALE_RANGE, create_gui},
+    },
     grapher,
-    grapher_egui::GrapherSceneMode,
+    grapher_egui::{GrapherScene, GrapherSceneMode, graph_scene, model_scene},
 };
 use egui_wgpu::{
     ScreenDescriptor,
@@ -13,18 +25,19 @@ use egui_wgpu::{
 };
 use std::{
     sync::Arc,
-    thread,
     time::{self, Instant},
 };
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
-    event::{ElementState, KeyEvent, WindowEvent},
-    event_loop::ActiveEventLoop,
+    event::{ElementState, InnerSizeWriter, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow},
     keyboard::{KeyCode, PhysicalKey},
-    window::{Window, WindowAttributes, WindowId},
+    window::{Icon, Window, WindowAttributes, WindowId},
 };
 
+const ICON_PNG: &[u8] = include_bytes!("../../assets/icon.png");
+
 // ---------------------------------------
 // Top-level structure of the application.
 
@@ -33,49 +46,118 @@ pub struct App {
     state: Option<AppState>,
     window: Option<Arc<Window>>,
     window_attributes: WindowAttributes,
+    // Window scale factor the surface was last sized for.
+    scale_factor: f64,
 
     // Timing variables.
     last_update_time: Instant,
     last_render_time: Instant,
     accumulated_secs: f32,
-    render_count: usize,
-    avg_framerate: f32,
+    // When the next frame is due; the event loop waits until then.
+    next_frame_time: Instant,
+    frame_times: FrameTimes,
+
+    // Allows user to pass scene at startup; overrides saved settings.
+    initial_scene: Option<GrapherSceneMode>,
+    // Function to graph at startup, in graph mode.
+    initial_function: Option<String>,
+    // Scene description loaded at startup; overrides the scene and function.
+    scene_file: Option<String>,
+    // Framerate cap passed at startup.
+    initial_frame_rate: FrameRateSettings,
+    // GPU requested at startup.
+    adapter_selection: AdapterSelection,
+    // Solver compute workgroup width passed at startup.
+    solver_workgroup_size: Option<u32>,
+    // Demo settings passed at startup.
+    initial_demo: DemoSettings,
+    demo: Demo,
+}
+
+/// Icon decoded from the embedded PNG; the window has none if that fails.
+fn window_icon() -> Option<Icon> {
+    let image = match image::load_from_memory(ICON_PNG) {
+        Ok(image) => image.to_rgba8(),
+        Err(err) => {
+            log::warn!("Failed to decode window icon: {err}");
+            return None;
+        }
+    };
+    let (width, height) = image.dimensions();
+    Icon::from_rgba(image.into_raw(), width, height)
+        .inspect_err(|err| log::warn!("Failed to create window icon: {err}"))
+        .ok()
+}
 
-    // Allows user to pass scene at startup.
-    initial_scene: GrapherSceneMode,
+/// How a frame recovers when the surface texture can't be acquired.
+#[derive(Debug, PartialEq)]
+enum SurfaceRecovery {
+    // Reconfigure the surface and skip the frame.
+    Reconfigure,
+    // Skip the frame and try again on the next one.
+    SkipFrame,
+    // Nothing to recover; the error is a bug.
+    Fail,
+}
+
+/// Surfaces go outdated, lost, or time out after sleep or a display change,
+/// and on some drivers transiently; reconfiguring them is enough.
+fn surface_recovery(err: &SurfaceError) -> SurfaceRecovery {
+    match err {
+        SurfaceError::Outdated | SurfaceError::Lost | SurfaceError::Timeout => {
+            SurfaceRecovery::Reconfigure
+        }
+        SurfaceError::OutOfMemory => SurfaceRecovery::SkipFrame,
+        _ => SurfaceRecovery::Fail,
+    }
 }
 
 impl App {
-    // Pause at end of redraw handler.
-    const RENDER_TIMEOUT: time::Duration = time::Duration::from_millis(20);
-    // Time between redraws; we target 60 fps.
-    const RENDER_TIME_INCR: f32 = 1.0 / 60.0;
     // How often to update average framerate.
     const REPORT_FRAMES_INTERVAL: usize = 100;
 
-    pub fn new(initial_scene: Option<GrapherSceneMode>) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        initial_scene: Option<GrapherSceneMode>,
+        initial_function: Option<String>,
+        scene_file: Option<String>,
+        frame_rate: FrameRateSettings,
+        adapter_selection: AdapterSelection,
+        solver_workgroup_size: Option<u32>,
+        demo: DemoSettings,
+        demo_model: Option<String>,
+        title: &str,
+    ) -> Self {
         let instance = egui_wgpu::wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
-        let window_attributes = Window::default_attributes().with_title("Wgpu Grapher");
+        let window_attributes = Window::default_attributes()
+            .with_title(title)
+            .with_window_icon(window_icon());
 
         let last_update_time = time::Instant::now();
         let last_render_time = time::Instant::now();
         let accumulated_secs = 0.0_f32;
-        let render_count = 0_usize;
-        let avg_framerate = 60.0f32;
 
         Self {
             instance,
             state: None,
             window: None,
             window_attributes,
+            scale_factor: 1.0,
 
             last_update_time,
             last_render_time,
             accumulated_secs,
-            render_count,
-            avg_framerate,
-
-            initial_scene: initial_scene.unwrap_or_default(),
+            next_frame_time: last_update_time,
+            frame_times: FrameTimes::default(),
+
+            initial_scene,
+            initial_function,
+            scene_file,
+            initial_frame_rate: frame_rate,
+            adapter_selection,
+            solver_workgroup_size,
+            initial_demo: demo,
+            demo: Demo::new(demo_model),
         }
     }
 
@@ -91,18 +173,41 @@ impl App {
             .create_surface(window.clone())
             .expect("Failed to create surface!");
 
-        let state = AppState::new(
+        let mut state = AppState::new(
             &self.instance,
             surface,
             &window,
             initial_width,
             initial_height,
-            self.initial_scene,
+            self.initial_scene.unwrap_or_default(),
+            &self.adapter_selection,
         )
         .await;
+        // The file is read first, so an invalid one doesn't keep the
+        // saved scene from being restored.
+        let scene_file = self.scene_file.as_deref().and_then(|path| {
+            SceneDescriptor::read(path)
+                .map_err(|err| log::error!("Invalid scene file {err}"))
+                .ok()
+                .map(|scene| (path, scene))
+        });
+        state.load_settings(self.initial_scene.is_none() && scene_file.is_none());
+        if let Some(function_string) = &self.initial_function {
+            state.set_initial_function(function_string);
+        }
+        if let Some(size) = self.solver_workgroup_size {
+            state.set_solver_workgroup_size(size);
+        }
+        if let Some((path, (descriptor, scene_mode))) = scene_file {
+            state.load_scene_file(path, &descriptor, scene_mode);
+        }
+        state.ui_data.frame_rate = self.initial_frame_rate;
+        state.apply_frame_rate_settings();
+        state.ui_data.demo = self.initial_demo;
 
         // egui docs: Gracefully handle redundant Resumed events.
         if self.window.is_none() {
+            self.scale_factor = window.scale_factor();
             self.window.replace(window);
             self.state.replace(state);
         }
@@ -114,6 +219,36 @@ impl App {
         }
     }
 
+    /// Keep the window's logical size when it moves to a monitor with a
+    /// different scale factor, or the OS scaling changes. The surface is
+    /// resized right away, since not every platform follows this event with
+    /// a resize; egui picks up the new factor from its own event handling.
+    fn handle_scale_factor_changed(
+        &mut self,
+        scale_factor: f64,
+        mut inner_size_writer: InnerSizeWriter,
+    ) {
+        let Some(state) = self.state.as_ref() else {
+            return;
+        };
+        let ratio = scale_factor / self.scale_factor;
+        self.scale_factor = scale_factor;
+
+        let width = (state.surface_config.width as f64 * ratio).round() as u32;
+        let height = (state.surface_config.height as f64 * ratio).round() as u32;
+        if let Err(err) = inner_size_writer.request_inner_size(PhysicalSize::new(width, height)) {
+            log::warn!("Failed to resize window for new scale factor: {err}");
+        }
+        self.handle_resized(width, height);
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    /// Render one frame. If the surface texture can't be acquired because the
+    /// surface is outdated, lost, or timed out, as can happen after sleep or a
+    /// display change, the surface is reconfigured and the frame is skipped.
     fn handle_redraw(&mut self) {
         if let Some(window) = self.window.as_ref()
             && let Some(min) = window.is_minimized()
@@ -124,13 +259,23 @@ impl App {
         let state = self.state.as_mut().unwrap();
 
         let surface_texture = match state.surface.get_current_texture() {
-            Err(SurfaceError::Outdated) => {
-                panic!("Surface texture outdated in redraw.")
-            }
-            Err(_) => {
-                panic!("Failed to acquire next swap chain texture.");
-            }
             Ok(surface_texture) => surface_texture,
+            Err(err) => match surface_recovery(&err) {
+                SurfaceRecovery::Reconfigure => {
+                    // Next redraw uses the reconfigured surface.
+                    state
+                        .surface
+                        .configure(&state.device, &state.surface_config);
+                    return;
+                }
+                SurfaceRecovery::SkipFrame => {
+                    log::error!("Failed to acquire surface texture ({err}); skipping frame.");
+                    return;
+                }
+                SurfaceRecovery::Fail => {
+                    panic!("Failed to acquire next swap chain texture: {err}");
+                }
+            },
         };
         let surface_view = surface_texture
             .texture
@@ -141,19 +286,31 @@ impl App {
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
         if state.grapher_scene.is_some() {
-            // Run any compute passes.
-            state.grapher_scene.compute(&state.device, &state.queue);
-            // Render grapher scene.
+            // Render grapher scene to offscreen target.
+            state.grapher_scene.render(
+                &state.grapher_state.offscreen_target.view,
+                &mut encoder,
+                &state.grapher_state,
+            );
+            state
+                .grapher_state
+                .fxaa
+                .render(&state.grapher_state.offscreen_target, &mut encoder);
+            // Tone map offscreen target onto surface.
             state
-                .grapher_scene
-                .render(&surface_view, &mut encoder, &state.grapher_state);
+                .grapher_state
+                .tone_map
+                .render(&surface_view, &mut encoder);
         }
 
         // Render GUI.
+        let ui_scale = state
+            .ui_data
+            .scale_factor
+            .clamp(*UI_SCALE_RANGE.start(), *UI_SCALE_RANGE.end());
         let screen_descriptor = ScreenDescriptor {
             size_in_pixels: [state.surface_config.width, state.surface_config.height],
-            pixels_per_point: self.window.as_ref().unwrap().scale_factor() as f32
-                * state.ui_data.scale_factor,
+            pixels_per_point: self.window.as_ref().unwrap().scale_factor() as f32 * ui_scale,
         };
         let window = self.window.as_ref().unwrap();
         state.egui_renderer.begin_frame(window);
@@ -168,10 +325,25 @@ impl App {
         );
 
         state.queue.submit(Some(encoder.finish()));
+
+        // The offscreen target now holds this frame's resolved scene.
+        if std::mem::take(&mut state.ui_data.screenshot_requested) {
+            state.save_screenshot();
+        }
+
         surface_texture.present();
     }
 
     fn build_gui(state: &mut AppState) {
+        // With the UI hidden, only its own shortcut and the screenshot
+        // shortcut are handled, so no window takes input from the scene.
+        let context = &state.egui_renderer.context();
+        shortcuts::handle_hide_ui(context, &mut state.ui_data.show_ui);
+        shortcuts::handle_screenshot(context, &mut state.ui_data.screenshot_requested);
+        if !state.ui_data.show_ui {
+            return;
+        }
+
         // File chooser.
         match state.file_input_state {
             FileInputState::NeedsInput => {
@@ -192,14 +364,19 @@ impl App {
                 let context = state.egui_renderer.context();
                 let modal = egui::containers::Modal::new("file_load_failed_modal".into());
                 let mut close_clicked = false;
+                let file_error = &state.ui_data.file_error;
                 let _ = modal.show(context, |ui| {
                     ui.heading("Load Failed");
                     ui.label("Failed to load the selected file.");
+                    if let Some(error) = file_error {
+                        ui.label(error);
+                    }
                     if ui.button("Close").clicked() {
                         close_clicked = true;
                     }
                 });
                 if close_clicked {
+                    state.ui_data.file_error = None;
                     state.file_input_state = FileInputState::Hidden;
                     state.show_file_input();
                 }
@@ -209,6 +386,13 @@ impl App {
 
         let context = &state.egui_renderer.context();
 
+        // Keyboard shortcut help overlay.
+        shortcuts::handle_toggle(context, &mut state.ui_data.show_shortcuts);
+        shortcuts::shortcut_help_window(context, &mut state.ui_data.show_shortcuts);
+
+        // Recent log messages.
+        state.ui_data.log_console.show(context);
+
         // Main controls window.
         egui::Window::new("Settings")
             .resizable(true)
@@ -227,10 +411,22 @@ impl App {
                 );
             });
 
+        // Pick meshes under the cursor only where it isn't over a window.
+        if let GrapherScene::Model(data) = &mut state.grapher_scene {
+            data.ui_data.cursor_position = state
+                .ui_data
+                .cursor_position
+                .filter(|_| !context.is_pointer_over_area());
+            if data.ui_data.highlight_hovered {
+                model_scene::hovered_mesh_label(context, data);
+            }
+        }
+
         // Show function input in graph mode.
         if matches!(state.scene_mode, GrapherSceneMode::Graph) {
             let mut is_valid = state.ui_data.function_valid;
             let mut function = None;
+            let noise_parameters = state.grapher_scene.noise_parameters();
             {
                 let is_valid_ref = &mut is_valid;
                 let _ = components::validated_text_input_window(
@@ -238,21 +434,41 @@ impl App {
                     "Function",
                     &mut state.ui_data.function_string,
                     |func_str| {
-                        function = grapher::math::try_parse_function_string(func_str);
+                        function =
+                            grapher::math::try_parse_function_string(func_str, &noise_parameters);
                         *is_valid_ref = function.is_some();
                     },
                     state.ui_data.function_valid,
+                    &mut state.ui_data.function_manual_apply,
                 );
             }
             if let Some(func) = function {
-                state.grapher_scene.update_graph(
-                    &state.device,
-                    &state.surface_config,
-                    &state.grapher_state,
-                    func,
-                );
+                state.grapher_scene.update_graph(&state.grapher_state, func);
             }
             state.ui_data.function_valid = is_valid;
+
+            if let GrapherScene::Graph(data) = &mut state.grapher_scene {
+                graph_scene::handle_preset_keys(context, data, &mut state.ui_data);
+            }
+
+            // Show coordinates of the graph point under the cursor.
+            let hover_point = state.ui_data.cursor_position.and_then(|cursor| {
+                state.grapher_scene.graph_point_at_cursor(
+                    &state.grapher_state,
+                    &state.surface_config,
+                    cursor,
+                )
+            });
+            egui::Area::new("cursor_readout".into())
+                .anchor(egui::Align2::LEFT_BOTTOM, [15.0, -15.0])
+                .interactable(false)
+                .show(context, |ui| {
+                    let text = match hover_point {
+                        Some([x, y, z]) => format!("x: {x:.3}  y: {y:.3}  z: {z:.3}"),
+                        None => "\u{2014}".to_string(),
+                    };
+                    ui.label(egui::RichText::new(text).monospace());
+                });
         }
     }
 }
@@ -272,6 +488,22 @@ impl ApplicationHandler for App {
         pollster::block_on(self.set_window(window));
     }
 
+    /// Request a redraw once the next frame is due, otherwise wait until
+    /// then. Input events wake the loop in between and are handled right
+    /// away without drawing an extra frame, and a static scene costs no
+    /// more than one frame per deadline.
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(window) = self.window.as_ref() else {
+            return;
+        };
+        if Instant::now() >= self.next_frame_time {
+            window.request_redraw();
+            event_loop.set_control_flow(ControlFlow::Wait);
+        } else {
+            event_loop.set_control_flow(ControlFlow::WaitUntil(self.next_frame_time));
+        }
+    }
+
     fn device_event(
         &mut self,
         _: &ActiveEventLoop,
@@ -304,11 +536,27 @@ impl ApplicationHandler for App {
 
         match event {
             WindowEvent::CloseRequested => {
+                state.save_settings();
                 event_loop.exit();
             }
             WindowEvent::Resized(new_size) => {
                 self.handle_resized(new_size.width, new_size.height);
             }
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                inner_size_writer,
+            } => {
+                self.handle_scale_factor_changed(scale_factor, inner_size_writer);
+            }
+            WindowEvent::DroppedFile(path) => {
+                state.handle_dropped_file(&path);
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                state.ui_data.cursor_position = Some([position.x as f32, position.y as f32]);
+            }
+            WindowEvent::CursorLeft { .. } => {
+                state.ui_data.cursor_position = None;
+            }
 
             WindowEvent::KeyboardInput {
                 event:
@@ -319,57 +567,126 @@ impl ApplicationHandler for App {
                     },
                 ..
             } => {
+                state.save_settings();
                 event_loop.exit();
             }
 
-            WindowEvent::RedrawRequested => {
-                // Request continuous redraw events.
-                window.request_redraw();
-
-                // Let scene run any of its own internal updates.
-                if !state.scene_updates_paused && state.grapher_scene.is_some() {
-                    state.grapher_scene.update(
-                        &state.device,
-                        &state.surface_config,
-                        &state.queue,
-                        &state.grapher_state,
-                    );
-                }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::KeyR),
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } if !context.wants_keyboard_input() => {
+                state.reset_camera();
+            }
 
+            WindowEvent::RedrawRequested => {
                 // Update preference uniform if needed.
                 if state.ui_data.render_ui_state.needs_prefs_uniform_write {
                     state
                         .grapher_state
                         .render_preferences
                         .update_uniform(&state.queue);
+                    state.grapher_state.tone_map.update_uniform(&state.queue);
                     state.ui_data.render_ui_state.needs_prefs_uniform_write = false;
                 }
 
-                // Target 60 fps.
+                // Switch vsync to match the framerate cap if it changed.
+                state.apply_frame_rate_settings();
+                state.apply_render_scale();
+                let frame_rate = state.ui_data.frame_rate;
+                // Time between redraws, or none when uncapped.
+                let frame_secs = (!frame_rate.uncapped).then(|| 1.0 / frame_rate.target_fps as f32);
+
                 self.accumulated_secs += self.last_update_time.elapsed().as_secs_f32();
                 self.last_update_time = time::Instant::now();
 
                 // Redraw the scene.
-                if self.accumulated_secs >= Self::RENDER_TIME_INCR {
-                    self.accumulated_secs -= Self::RENDER_TIME_INCR;
-                    self.render_count += 1;
+                if frame_secs.is_none_or(|secs| self.accumulated_secs >= secs) {
+                    // Don't try to catch up after slow frames.
+                    self.accumulated_secs =
+                        frame_secs.map_or(0.0, |secs| (self.accumulated_secs - secs).min(secs));
+
+                    let demo = state.ui_data.demo;
+                    let since_render = self.last_render_time.elapsed().as_secs_f32();
+                    if let Some(step) =
+                        self.demo
+                            .tick(demo.enabled, demo.interval_secs, since_render)
+                    {
+                        state.show_demo_step(step);
+                    }
 
                     state.grapher_state.update_camera(&mut state.queue);
                     state.handle_scene_changes();
+                    for _ in 0..frame_rate.sim_steps_per_frame.max(1) {
+                        state.step_scene();
+                    }
                     self.handle_redraw();
 
-                    if self.render_count >= Self::REPORT_FRAMES_INTERVAL {
-                        self.avg_framerate = Self::REPORT_FRAMES_INTERVAL as f32
-                            / self.last_render_time.elapsed().as_secs_f32();
-                        self.render_count = 0;
-                        self.last_render_time = time::Instant::now();
+                    let state = self.state.as_mut().unwrap();
+                    let render_secs = self.last_render_time.elapsed().as_secs_f32();
+                    self.frame_times.record(render_secs);
+                    self.last_render_time = time::Instant::now();
+
+                    if state.ui_data.show_frame_time_plot {
+                        let history = &mut state.ui_data.frame_time_history;
+                        if history.len() == FRAME_TIME_HISTORY_LEN {
+                            history.pop_front();
+                        }
+                        history.push_back(render_secs);
+                    }
+
+                    if self.frame_times.sample_count() >= Self::REPORT_FRAMES_INTERVAL
+                        && let Some(summary) = self.frame_times.take_summary()
+                    {
+                        // Camera speed is given per frame.
+                        state.grapher_state.framerate = summary.avg_fps;
+                        if frame_rate.uncapped {
+                            log::info!("Frame times: {summary}");
+                        }
+                        state.ui_data.frame_time_summary = Some(summary.to_string());
                     }
                 }
 
-                // Delay event loop, including internal updates.
-                thread::sleep(Self::RENDER_TIMEOUT);
+                // Schedule the next frame; uncapped frames are due at once.
+                let remaining =
+                    frame_secs.map_or(0.0, |secs| (secs - self.accumulated_secs).max(0.0));
+                self.next_frame_time =
+                    self.last_update_time + time::Duration::from_secs_f32(remaining);
             }
             _ => (),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_surface_errors_reconfigure() {
+        for err in [
+            SurfaceError::Outdated,
+            SurfaceError::Lost,
+            SurfaceError::Timeout,
+        ] {
+            assert_eq!(
+                surface_recovery(&err),
+                SurfaceRecovery::Reconfigure,
+                "{err}"
+            );
+        }
+        assert_eq!(
+            surface_recovery(&SurfaceError::OutOfMemory),
+            SurfaceRecovery::SkipFrame
+        );
+        assert_eq!(
+            surface_recovery(&SurfaceError::Other),
+            SurfaceRecovery::Fail
+        );
+    }
+}
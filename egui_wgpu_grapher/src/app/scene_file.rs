@@ -0,0 +1,365 @@
+//! Describe a scene in a JSON file, loaded at startup with `--scene-file`,
+//! to reproduce an exact configuration. Only the scene type is required:
+//!
+//! ```json
+//! {
+//!   "scene": "graph",
+//!   "function": "sin(x) * cos(z)",
+//!   "camera": { "eye": [4.0, 3.0, 4.0], "target": [0.0, 0.0, 0.0] },
+//!   "lighting": { "shadow": true, "position": [2.0, 4.0, 1.0] },
+//!   "render": { "fog": true, "smooth_shading": false }
+//! }
+//! ```
+//!
+//! Settings the file leaves out keep their saved values.
+
+use super::state::AppState;
+use crate::{
+    grapher::math::{graph::Normals, noise::NoiseParameters, try_parse_function_string},
+    grapher_egui::{GrapherSceneMode, RenderUiState},
+};
+
+use cgmath::{InnerSpace, Point3, Vector3};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescriptor {
+    // scene mode, named as for --scene
+    scene: String,
+    // function of x and z, for graph scenes
+    function: Option<String>,
+    // model, image, heightmap or data file, relative to the scene file
+    file: Option<String>,
+    camera: Option<CameraDescriptor>,
+    #[serde(default)]
+    lighting: LightingDescriptor,
+    #[serde(default)]
+    render: RenderDescriptor,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct CameraDescriptor {
+    eye: [f32; 3],
+    target: [f32; 3],
+    #[serde(default = "default_up")]
+    up: [f32; 3],
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct LightingDescriptor {
+    enabled: Option<bool>,
+    shadow: Option<bool>,
+    two_sided: Option<bool>,
+    ssao: Option<bool>,
+    // placed light position; the scene's own light is used otherwise
+    position: Option<[f32; 3]>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct RenderDescriptor {
+    wireframe: Option<bool>,
+    backface_culling: Option<bool>,
+    smooth_shading: Option<bool>,
+    fog: Option<bool>,
+    fog_density: Option<f32>,
+    opacity: Option<f32>,
+}
+
+/// Scene modes loaded from a file, which must be given in the scene file.
+fn loads_file(scene_mode: GrapherSceneMode) -> bool {
+    matches!(
+        scene_mode,
+        GrapherSceneMode::Model
+            | GrapherSceneMode::ImageViewer
+            | GrapherSceneMode::Heightmap
+            | GrapherSceneMode::DataPlot
+    )
+}
+
+fn scene_mode_names() -> String {
+    GrapherSceneMode::value_variants()
+        .iter()
+        .filter_map(|mode| mode.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl SceneDescriptor {
+    /// Read and check a scene file. Errors name the file and, for JSON
+    /// syntax and type errors, the line and column.
+    pub fn read(path: &str) -> Result<(Self, GrapherSceneMode), String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+        let mut descriptor: SceneDescriptor =
+            serde_json::from_str(&text).map_err(|err| format!("{path}: {err}"))?;
+        let base = Path::new(path).parent().unwrap_or(Path::new(""));
+        let scene_mode = descriptor
+            .validate(base)
+            .map_err(|err| format!("{path}: {err}"))?;
+        Ok((descriptor, scene_mode))
+    }
+
+    // Check values serde can't, and resolve the data file path.
+    fn validate(&mut self, base: &Path) -> Result<GrapherSceneMode, String> {
+        let scene_mode =
+            <GrapherSceneMode as ValueEnum>::from_str(&self.scene, true).map_err(|_| {
+                format!(
+                    "unknown scene \"{}\"; expected one of {}",
+                    self.scene,
+                    scene_mode_names()
+                )
+            })?;
+
+        if let Some(function) = &self.function {
+            if scene_mode != GrapherSceneMode::Graph {
+                return Err("\"function\" only applies to graph scenes".into());
+            }
+            if try_parse_function_string(function, &NoiseParameters::default()).is_none() {
+                return Err(format!("function \"{function}\" doesn't parse"));
+            }
+        }
+
+        match (&self.file, loads_file(scene_mode)) {
+            (Some(file), true) => {
+                let resolved = base.join(file);
+                if !resolved.is_file() {
+                    return Err(format!("file \"{}\" doesn't exist", resolved.display()));
+                }
+                self.file = Some(resolved.to_string_lossy().to_string());
+            }
+            (None, true) => {
+                return Err(format!("\"file\" is required for {} scenes", self.scene));
+            }
+            (Some(_), false) => {
+                return Err(format!("\"file\" doesn't apply to {} scenes", self.scene));
+            }
+            (None, false) => {}
+        }
+
+        if let Some(camera) = &self.camera {
+            let direction = Vector3::from(camera.target) - Vector3::from(camera.eye);
+            let up = Vector3::from(camera.up);
+            if direction.magnitude() < 1e-4 {
+                return Err("the camera eye and target must differ".into());
+            }
+            if up.magnitude() < 1e-4
+                || direction.normalize().cross(up.normalize()).magnitude() < 1e-4
+            {
+                return Err("the camera up vector must not be along the view direction".into());
+            }
+        }
+
+        if let Some(opacity) = self.render.opacity
+            && !(0.05..=1.0).contains(&opacity)
+        {
+            return Err(format!("opacity {opacity} is outside 0.05 to 1"));
+        }
+        if let Some(density) = self.render.fog_density
+            && (density.is_nan() || density < 0.0)
+        {
+            return Err(format!("fog density {density} isn't zero or more"));
+        }
+
+        Ok(scene_mode)
+    }
+}
+
+impl AppState {
+    /// Load the scene a scene file read from `path` describes, in place
+    /// of the one chosen otherwise, and apply its settings.
+    pub fn load_scene_file(
+        &mut self,
+        path: &str,
+        descriptor: &SceneDescriptor,
+        scene_mode: GrapherSceneMode,
+    ) {
+        // Pipelines are built from the render preferences, so these come
+        // before the scene.
+        self.apply_scene_preferences(descriptor);
+
+        match &descriptor.file {
+            Some(file) => self.open_file_in_mode(scene_mode, Path::new(file)),
+            None => self.set_scene_mode(scene_mode),
+        }
+        if let Some(function) = &descriptor.function {
+            self.set_initial_function(function);
+        }
+        // Once to pick up the new mode or file, and once to load the file.
+        self.handle_scene_changes();
+        self.handle_scene_changes();
+        if !self.grapher_scene.is_some() {
+            // the load failure is already reported
+            return;
+        }
+
+        // Scenes frame the camera when they're created.
+        if let Some(camera) = &descriptor.camera {
+            let camera_state = &mut self.grapher_state.camera_state;
+            camera_state.camera.set_world_view(
+                Point3::from(camera.eye),
+                Point3::from(camera.target),
+                Vector3::from(camera.up),
+            );
+            camera_state.update_view_matrix(&self.queue);
+            // reset returns to the file's camera
+            self.capture_default_camera();
+        }
+        log::info!("Loaded scene file {path}");
+    }
+
+    fn apply_scene_preferences(&mut self, descriptor: &SceneDescriptor) {
+        let lighting = &descriptor.lighting;
+        let render = &descriptor.render;
+        let render_preferences = &mut self.grapher_state.render_preferences;
+        if let Some(enabled) = lighting.enabled {
+            render_preferences.set_lighting_enabled(enabled);
+        }
+        if let Some(enabled) = lighting.shadow {
+            render_preferences.set_shadow_enabled(enabled);
+        }
+        if let Some(enabled) = lighting.two_sided {
+            render_preferences.set_two_sided_lighting(enabled);
+        }
+        if let Some(enabled) = lighting.ssao {
+            render_preferences.set_ssao_enabled(enabled && self.grapher_state.ssao.supported());
+        }
+        if let Some(enabled) = render.wireframe {
+            render_preferences.set_wireframe(enabled);
+        }
+        if let Some(enabled) = render.backface_culling {
+            render_preferences.set_backface_culling(enabled);
+        }
+        if let Some(smooth) = render.smooth_shading {
+            render_preferences.shading = if smooth {
+                Normals::Smooth
+            } else {
+                Normals::Flat
+            };
+        }
+        if let Some(enabled) = render.fog {
+            render_preferences.set_fog_enabled(enabled);
+        }
+        if let Some(density) = render.fog_density {
+            render_preferences.uniform.fog_density = density;
+        }
+        if let Some(opacity) = render.opacity {
+            render_preferences.uniform.opacity = opacity;
+        }
+        render_preferences.update_uniform(&self.queue);
+
+        if let Some(position) = lighting.position {
+            let light_placement = &mut self.grapher_state.light_placement;
+            light_placement.manual = true;
+            light_placement.position = position;
+        }
+
+        self.ui_data.render_ui_state = RenderUiState::from(&self.grapher_state.render_preferences);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Parse and check a scene file's text, with data files relative to the crate.
+    fn validate(json: &str) -> Result<GrapherSceneMode, String> {
+        let mut descriptor: SceneDescriptor =
+            serde_json::from_str(json).map_err(|err| err.to_string())?;
+        descriptor.validate(Path::new(env!("CARGO_MANIFEST_DIR")))
+    }
+
+    fn assert_rejected(json: &str, expected: &str) {
+        let err = validate(json).expect_err(json);
+        assert!(
+            err.contains(expected),
+            "\"{err}\" doesn't mention \"{expected}\""
+        );
+    }
+
+    #[test]
+    fn valid_scenes() {
+        assert_eq!(
+            validate(r#"{ "scene": "graph", "function": "sin(x) * cos(z)" }"#),
+            Ok(GrapherSceneMode::Graph)
+        );
+        assert_eq!(
+            validate(r#"{ "scene": "image-viewer", "file": "assets/icon.png" }"#),
+            Ok(GrapherSceneMode::ImageViewer)
+        );
+    }
+
+    #[test]
+    fn unknown_scene() {
+        assert_rejected(r#"{ "scene": "teapot" }"#, "unknown scene \"teapot\"");
+    }
+
+    #[test]
+    fn function_only_for_graphs() {
+        assert_rejected(
+            r#"{ "scene": "fractal", "function": "x * z" }"#,
+            "only applies to graph scenes",
+        );
+    }
+
+    #[test]
+    fn data_file_required_and_present() {
+        assert_rejected(r#"{ "scene": "model" }"#, "\"file\" is required");
+        assert_rejected(
+            r#"{ "scene": "model", "file": "assets/missing.glb" }"#,
+            "doesn't exist",
+        );
+        assert_rejected(
+            r#"{ "scene": "graph", "file": "assets/icon.png" }"#,
+            "doesn't apply",
+        );
+    }
+
+    #[test]
+    fn degenerate_camera() {
+        assert_rejected(
+            r#"{ "scene": "graph", "camera": { "eye": [1, 2, 3], "target": [1, 2, 3] } }"#,
+            "eye and target must differ",
+        );
+        assert_rejected(
+            r#"{ "scene": "graph",
+                 "camera": { "eye": [0, 0, 5], "target": [0, 0, 0], "up": [0, 0, 2] } }"#,
+            "along the view direction",
+        );
+    }
+
+    #[test]
+    fn out_of_range_render_values() {
+        for opacity in ["0", "1.5"] {
+            assert_rejected(
+                &format!(r#"{{ "scene": "graph", "render": {{ "opacity": {opacity} }} }}"#),
+                "opacity",
+            );
+        }
+        assert_rejected(
+            r#"{ "scene": "graph", "render": { "fog_density": -0.1 } }"#,
+            "fog density",
+        );
+    }
+
+    #[test]
+    fn unknown_fields_rejected() {
+        assert_rejected(
+            r#"{ "scene": "graph", "colour": "red" }"#,
+            "unknown field `colour`",
+        );
+        assert_rejected(
+            r#"{ "scene": "graph", "camera": { "eye": [4, 3, 4], "target": [0, 0, 0], "fov": 45 } }"#,
+            "unknown field `fov`",
+        );
+    }
+}
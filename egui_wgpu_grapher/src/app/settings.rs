@@ -0,0 +1,211 @@
+//! Persist UI and render settings between runs in a TOML file.
+
+use super::state::AppState;
+use crate::{
+    egui::ui::UI_SCALE_RANGE,
+    grapher::{
+        math::graph::Normals,
+        pipeline::{antialiasing::Antialiasing, tone_map::ToneMapOperator},
+    },
+    grapher_egui::{GrapherScene, GrapherSceneMode, RenderUiState},
+};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_FILE: &str = "config.toml";
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    selected_scene: usize,
+    scale_factor: f32,
+    function_manual_apply: bool,
+    lighting_enabled: bool,
+    shadow_enabled: bool,
+    two_sided_lighting: bool,
+    ssao_enabled: bool,
+    ssao_radius: f32,
+    ssao_intensity: f32,
+    use_wireframe: bool,
+    backface_culling: bool,
+    smooth_shading: bool,
+    pivot_gizmo: bool,
+    manual_light: bool,
+    light_position: [f32; 3],
+    light_marker: bool,
+    fog_enabled: bool,
+    fog_density: f32,
+    fog_color: [f32; 3],
+    tone_mapping_enabled: bool,
+    tone_map_aces: bool,
+    exposure: f32,
+    gamma: f32,
+    msaa_samples: u32,
+    fxaa: bool,
+    reverse_z: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        use crate::grapher::pipeline::{
+            light::LightState, render_preferences, ssao::SsaoState, tone_map::ToneMapState,
+        };
+        Self {
+            selected_scene: GrapherSceneMode::default().into(),
+            scale_factor: 1.0,
+            function_manual_apply: false,
+            lighting_enabled: true,
+            shadow_enabled: false,
+            two_sided_lighting: true,
+            ssao_enabled: false,
+            ssao_radius: SsaoState::DEFAULT_RADIUS,
+            ssao_intensity: SsaoState::DEFAULT_INTENSITY,
+            use_wireframe: false,
+            backface_culling: false,
+            smooth_shading: true,
+            pivot_gizmo: false,
+            manual_light: false,
+            light_position: LightState::DEFAULT_LIGHT_POS,
+            light_marker: true,
+            fog_enabled: false,
+            fog_density: render_preferences::DEFAULT_FOG_DENSITY,
+            fog_color: [0.0, 0.0, 0.0],
+            tone_mapping_enabled: false,
+            tone_map_aces: true,
+            exposure: ToneMapState::NEUTRAL_EXPOSURE,
+            gamma: ToneMapState::NEUTRAL_GAMMA,
+            msaa_samples: Antialiasing::default().sample_count(),
+            fxaa: false,
+            reverse_z: false,
+        }
+    }
+}
+
+impl AppState {
+    pub fn save_settings(&self) {
+        let render_preferences = &self.grapher_state.render_preferences;
+        let tone_map = &self.grapher_state.tone_map;
+        let settings = Settings {
+            selected_scene: self.scene_mode.into(),
+            scale_factor: self.ui_data.scale_factor,
+            function_manual_apply: self.ui_data.function_manual_apply,
+            lighting_enabled: render_preferences.lighting_enabled(),
+            shadow_enabled: render_preferences.shadow_enabled(),
+            two_sided_lighting: render_preferences.two_sided_lighting_enabled(),
+            ssao_enabled: render_preferences.ssao_enabled(),
+            ssao_radius: self.grapher_state.ssao.uniform.radius,
+            ssao_intensity: self.grapher_state.ssao.uniform.intensity,
+            use_wireframe: render_preferences.wireframe_enabled(),
+            backface_culling: render_preferences.backface_culling_enabled(),
+            smooth_shading: render_preferences.shading == Normals::Smooth,
+            pivot_gizmo: self.grapher_state.gizmo.enabled,
+            manual_light: self.grapher_state.light_placement.manual,
+            light_position: self.grapher_state.light_placement.position,
+            light_marker: self.grapher_state.light_placement.show_marker,
+            fog_enabled: render_preferences.fog_enabled(),
+            fog_density: render_preferences.uniform.fog_density,
+            fog_color: render_preferences.uniform.fog_color,
+            tone_mapping_enabled: tone_map.enabled(),
+            tone_map_aces: tone_map.operator() == ToneMapOperator::Aces,
+            exposure: tone_map.uniform.exposure,
+            gamma: tone_map.uniform.gamma,
+            // the selected mode, which may not be applied until restart
+            msaa_samples: self.ui_data.antialiasing.sample_count(),
+            fxaa: self.ui_data.antialiasing == Antialiasing::Fxaa,
+            // also applied at startup only
+            reverse_z: self.ui_data.reverse_z,
+        };
+
+        let result = toml::to_string(&settings)
+            .map_err(|err| err.to_string())
+            .and_then(|text| std::fs::write(SETTINGS_FILE, text).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            log::warn!("Failed to save settings: {err}");
+        }
+    }
+
+    /// Load saved settings, keeping defaults for any that are missing or invalid.
+    /// The saved scene is only restored if `restore_scene` is set.
+    pub fn load_settings(&mut self, restore_scene: bool) {
+        let Some(settings) = read_settings() else {
+            return;
+        };
+
+        let scene_count = GrapherSceneMode::value_variants().len();
+        if restore_scene && settings.selected_scene < scene_count {
+            self.scene_mode = settings.selected_scene.into();
+            self.ui_data.selected_scene_index = settings.selected_scene;
+            // Load the scene as if it had been selected in the UI.
+            self.grapher_scene = GrapherScene::Changed;
+        }
+        self.ui_data.scale_factor = settings
+            .scale_factor
+            .clamp(*UI_SCALE_RANGE.start(), *UI_SCALE_RANGE.end());
+        self.ui_data.function_manual_apply = settings.function_manual_apply;
+
+        let render_preferences = &mut self.grapher_state.render_preferences;
+        render_preferences.set_lighting_enabled(settings.lighting_enabled);
+        render_preferences.set_shadow_enabled(settings.shadow_enabled);
+        render_preferences.set_two_sided_lighting(settings.two_sided_lighting);
+        render_preferences
+            .set_ssao_enabled(settings.ssao_enabled && self.grapher_state.ssao.supported());
+        render_preferences.set_wireframe(settings.use_wireframe);
+        render_preferences.set_backface_culling(settings.backface_culling);
+        render_preferences.shading = if settings.smooth_shading {
+            Normals::Smooth
+        } else {
+            Normals::Flat
+        };
+        render_preferences.set_fog_enabled(settings.fog_enabled);
+        render_preferences.uniform.fog_density = settings.fog_density;
+        render_preferences.uniform.fog_color = settings.fog_color;
+        render_preferences.update_uniform(&self.queue);
+        self.grapher_state.gizmo.enabled = settings.pivot_gizmo;
+        let light_placement = &mut self.grapher_state.light_placement;
+        light_placement.manual = settings.manual_light;
+        light_placement.position = settings.light_position;
+        light_placement.show_marker = settings.light_marker;
+        let ssao = &mut self.grapher_state.ssao.uniform;
+        ssao.radius = settings.ssao_radius.max(0.05);
+        ssao.intensity = settings.ssao_intensity;
+
+        let tone_map = &mut self.grapher_state.tone_map;
+        tone_map.set_enabled(settings.tone_mapping_enabled);
+        tone_map.set_operator(if settings.tone_map_aces {
+            ToneMapOperator::Aces
+        } else {
+            ToneMapOperator::Reinhard
+        });
+        tone_map.uniform.exposure = settings.exposure;
+        tone_map.uniform.gamma = settings.gamma;
+        tone_map.update_uniform(&self.queue);
+
+        self.ui_data.render_ui_state = RenderUiState::from(&self.grapher_state.render_preferences);
+    }
+}
+
+fn read_settings() -> Option<Settings> {
+    let text = std::fs::read_to_string(SETTINGS_FILE).ok()?;
+    match toml::from_str(&text) {
+        Ok(settings) => Some(settings),
+        Err(err) => {
+            log::warn!("Ignoring invalid settings file: {err}");
+            None
+        }
+    }
+}
+
+/// The saved antialiasing mode. This is read on its own, before the render
+/// state is created, since the MSAA sample count is fixed at startup.
+pub fn saved_antialiasing() -> Antialiasing {
+    read_settings()
+        .map(|settings| Antialiasing::from_settings(settings.msaa_samples, settings.fxaa))
+        .unwrap_or_default()
+}
+
+/// Whether reverse-Z depth was saved as on. Like the antialiasing mode,
+/// it's read before the render state is created.
+pub fn saved_reverse_z() -> bool {
+    read_settings().is_some_and(|settings| settings.reverse_z)
+}
@@ -1,13 +1,39 @@
+use super::{demo::DemoStep, settings};
 use crate::{
     egui::{egui_tools::EguiRenderer, ui::UiState},
-    grapher::{self, scene::solid::graph::GraphScene},
+    grapher::{
+        self, Camera,
+        math::{FunctionHolder, noise::NoiseParameters},
+        pipeline::{
+            antialiasing::{self, Antialiasing},
+            screenshot,
+            skybox::Skybox,
+            texture,
+        },
+        scene::{
+            solid::graph::GraphScene,
+            solver::{DEFAULT_WORKGROUP_SIZE, check_workgroup_size},
+        },
+    },
     grapher_egui::{
-        GrapherScene, GrapherSceneMode, RenderUiState, graph_scene, image_scene, model_scene,
+        GrapherScene, GrapherSceneMode, RenderUiState,
+        complex_domain_scene::ComplexDomainSceneData,
+        data_plot_scene::DataPlotSceneData,
+        fractal_scene::FractalSceneData,
+        graph_scene,
+        heightmap_scene::HeightmapSceneData,
+        image_scene,
+        line_plot_scene::LinePlotSceneData,
+        model_scene,
+        pde_scene::{HeatEquationSceneData, WaveEquationSceneData},
+        schrodinger_scene::SchrodingerSceneData,
         solver_scene::SolverSceneData,
+        sphere_scene::SphereSceneData,
     },
 };
 use egui_file_dialog::FileDialog;
 use egui_wgpu::wgpu::{self, Limits};
+use std::path::Path;
 use winit::window::Window;
 
 // ---------------------------------------------------------
@@ -37,6 +63,31 @@ pub struct AppState {
     // Graphics scene state.
     pub grapher_state: grapher::render::RenderState,
     pub grapher_scene: GrapherScene,
+
+    // Function passed at startup, graphed once the graph scene is created.
+    initial_function: Option<FunctionHolder>,
+    // File the current scene was loaded from, reloaded to reset it.
+    scene_file: String,
+    // Starting function and camera of the current scene, restored by reset.
+    scene_defaults: Option<SceneDefaults>,
+    // Compute workgroup width the solver scene starts with.
+    solver_workgroup_size: u32,
+}
+
+/// What the current scene started with, captured once it's created,
+/// including any function or camera from the command line or a scene file.
+struct SceneDefaults {
+    // text of the function a graph started with; none for the default graph
+    function_string: Option<String>,
+    camera: Camera,
+}
+
+/// Which GPU to render with, from the command line.
+#[derive(Clone, Debug, Default)]
+pub struct AdapterSelection {
+    // adapter index or case-insensitive name substring
+    pub gpu: Option<String>,
+    pub power_preference: wgpu::PowerPreference,
 }
 
 pub enum FileInputState {
@@ -65,16 +116,11 @@ impl AppState {
         width: u32,
         height: u32,
         initial_scene: GrapherSceneMode,
+        adapter_selection: &AdapterSelection,
     ) -> Self {
-        let power_pref = wgpu::PowerPreference::default();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: power_pref,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .await
-            .expect("Failed to find an appropriate adapter");
+        let adapter = select_adapter(instance, &surface, adapter_selection).await;
+        let info = adapter.get_info();
+        log::info!("Using adapter: {} ({:?})", info.name, info.backend);
 
         let features = wgpu::Features::POLYGON_MODE_LINE
             | wgpu::Features::FLOAT32_FILTERABLE
@@ -90,6 +136,7 @@ impl AppState {
             })
             .await
             .expect("Failed to create Wgpu device.");
+        grapher::pipeline::log_uncaptured_errors(&device);
 
         let swapchain_capabilities = surface.get_capabilities(&adapter);
         let selected_format = wgpu::TextureFormat::Bgra8UnormSrgb;
@@ -111,8 +158,29 @@ impl AppState {
         };
         surface.configure(&device, &surface_config);
 
+        // Automatic modes are always supported; they fall back as needed.
+        let mut present_modes = vec![wgpu::PresentMode::AutoVsync, wgpu::PresentMode::AutoNoVsync];
+        present_modes.extend(swapchain_capabilities.present_modes.iter().copied());
+
+        // Scene pipelines are built for one MSAA sample count, so it's set before any exist.
+        let supported_sample_counts = antialiasing::supported_sample_counts(&adapter);
+        let mut antialiasing = settings::saved_antialiasing();
+        if !supported_sample_counts.contains(&antialiasing.sample_count()) {
+            log::warn!(
+                "{} isn't supported by this adapter; using {}.",
+                antialiasing.label(),
+                Antialiasing::default().label()
+            );
+            antialiasing = Antialiasing::default();
+        }
+        antialiasing::set_sample_count(antialiasing.sample_count());
+        let reverse_z = settings::saved_reverse_z();
+        texture::set_reverse_z(reverse_z);
+        texture::set_depth_readable(texture::adapter_reads_depth(&adapter));
+
         let egui_renderer = EguiRenderer::new(&device, surface_config.format, None, 1, window);
-        let grapher_state = grapher::render::RenderState::new(&device, &surface_config).await;
+        let mut grapher_state = grapher::render::RenderState::new(&device, &surface_config).await;
+        grapher_state.fxaa.enabled = antialiasing == Antialiasing::Fxaa;
         let render_ui_state: RenderUiState = (&grapher_state.render_preferences).into();
         let scale_factor = 1.0;
         let ui_data = UiState {
@@ -120,6 +188,12 @@ impl AppState {
             selected_scene_index: initial_scene.into(),
             scale_factor,
             function_valid: true,
+            show_ui: true,
+            present_modes,
+            antialiasing,
+            supported_sample_counts,
+            reverse_z,
+            render_scale: 1.0,
             ..Default::default()
         };
 
@@ -140,8 +214,54 @@ impl AppState {
             //
             grapher_state,
             grapher_scene: GrapherScene::None,
+            initial_function: None,
+            scene_file: String::new(),
+            scene_defaults: None,
+            solver_workgroup_size: DEFAULT_WORKGROUP_SIZE,
+        }
+    }
+}
+
+/// Find the adapter requested by index or name, falling back
+/// to one chosen by power preference if there is no match.
+async fn select_adapter(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface<'static>,
+    selection: &AdapterSelection,
+) -> wgpu::Adapter {
+    if let Some(gpu) = &selection.gpu {
+        let adapters: Vec<wgpu::Adapter> = instance
+            .enumerate_adapters(wgpu::Backends::all())
+            .into_iter()
+            .filter(|adapter| adapter.is_surface_supported(surface))
+            .collect();
+
+        let query = gpu.to_lowercase();
+        let found = match gpu.parse::<usize>() {
+            Ok(index) => adapters.get(index),
+            Err(_) => adapters
+                .iter()
+                .find(|adapter| adapter.get_info().name.to_lowercase().contains(&query)),
+        };
+        if let Some(adapter) = found {
+            return adapter.clone();
+        }
+
+        log::warn!("No adapter matching \"{gpu}\"; available adapters are:");
+        for (index, adapter) in adapters.iter().enumerate() {
+            let info = adapter.get_info();
+            log::warn!("  {index}: {} ({:?})", info.name, info.backend);
         }
     }
+
+    instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: selection.power_preference,
+            force_fallback_adapter: false,
+            compatible_surface: Some(surface),
+        })
+        .await
+        .expect("Failed to find an appropriate adapter")
 }
 
 // -----------------------------
@@ -163,9 +283,148 @@ impl AppState {
         self.grapher_state.update_camera(&mut self.queue);
     }
 
+    /// Reconfigure the surface if the selected present mode changed.
+    /// Uncapped mode always turns vsync off.
+    pub fn apply_frame_rate_settings(&mut self) {
+        let frame_rate = &mut self.ui_data.frame_rate;
+        if !self
+            .ui_data
+            .present_modes
+            .contains(&frame_rate.present_mode)
+        {
+            log::warn!(
+                "Present mode {:?} is not supported by this surface; using AutoVsync.",
+                frame_rate.present_mode
+            );
+            frame_rate.present_mode = wgpu::PresentMode::AutoVsync;
+        }
+        let present_mode = if frame_rate.uncapped {
+            wgpu::PresentMode::AutoNoVsync
+        } else {
+            frame_rate.present_mode
+        };
+        if self.surface_config.present_mode != present_mode {
+            self.surface_config.present_mode = present_mode;
+            self.surface.configure(&self.device, &self.surface_config);
+        }
+    }
+
+    /// Recreate the scene render targets if the render scale changed.
+    pub fn apply_render_scale(&mut self) {
+        if self.ui_data.render_scale != self.grapher_state.render_scale {
+            self.grapher_state.set_render_scale(
+                &self.device,
+                &self.surface_config,
+                self.ui_data.render_scale,
+            );
+        }
+    }
+
+    pub fn reset_camera(&mut self) {
+        self.ui_data.reset_camera_requested = false;
+        self.grapher_scene
+            .reset_camera(&self.queue, &self.surface_config, &mut self.grapher_state);
+    }
+
+    /// Record the function and camera a scene was just created with, as
+    /// the ones it's reset to.
+    fn capture_scene_defaults(&mut self, function_string: Option<String>) {
+        self.scene_defaults = Some(SceneDefaults {
+            function_string,
+            camera: self.grapher_state.camera_state.camera.clone(),
+        });
+    }
+
+    /// Make the current camera the one the scene is reset to, as when a
+    /// scene file places it.
+    pub fn capture_default_camera(&mut self) {
+        if let Some(defaults) = &mut self.scene_defaults {
+            defaults.camera = self.grapher_state.camera_state.camera.clone();
+        }
+    }
+
+    /// Return the current scene to the parameters, function and camera it
+    /// was created with, without changing the scene mode. Generated scenes
+    /// are rebuilt and file scenes are reloaded from their file. Render
+    /// preferences are kept.
+    pub fn reset_scene(&mut self) {
+        self.ui_data.reset_scene_requested = false;
+        if !self.grapher_scene.is_some() {
+            return;
+        }
+        let Some(defaults) = self.scene_defaults.take() else {
+            return;
+        };
+        match self.scene_mode {
+            GrapherSceneMode::Model
+            | GrapherSceneMode::ImageViewer
+            | GrapherSceneMode::Heightmap
+            | GrapherSceneMode::DataPlot => {
+                // Loads over the current scene, as when the file was picked.
+                self.ui_data.filename = self.scene_file.clone();
+                self.ui_data.append_file = false;
+                self.scene_loading_state = SceneLoadingState::NeedsLoaded;
+            }
+            mode => {
+                if matches!(mode, GrapherSceneMode::Graph) {
+                    self.ui_data.function_string = String::new();
+                    self.ui_data.function_valid = true;
+                    if let Some(function_string) = &defaults.function_string {
+                        self.set_initial_function(function_string);
+                    }
+                }
+                self.grapher_scene = GrapherScene::Changed;
+            }
+        }
+
+        // Rebuild now, so the starting camera replaces the framing the
+        // scene sets up when it's created.
+        self.handle_scene_changes();
+        if self.grapher_scene.is_some() {
+            let camera_state = &mut self.grapher_state.camera_state;
+            camera_state.camera = Camera {
+                // the window may have been resized since
+                aspect: camera_state.camera.aspect,
+                ..defaults.camera.clone()
+            };
+            camera_state.update_view_matrix(&self.queue);
+        }
+        self.scene_defaults = Some(defaults);
+    }
+
+    /// Log why the picked file couldn't be used and show the load failure modal.
+    fn report_invalid_file(&mut self) {
+        match &self.ui_data.file_error {
+            Some(err) => log::error!("Failed to load \"{}\": {err}", self.ui_data.filename),
+            None => log::error!("Failed to load \"{}\".", self.ui_data.filename),
+        }
+        self.file_input_state = FileInputState::InvalidFile;
+    }
+
+    /// Save the scene as last rendered, without the GUI, to a new PNG file.
+    pub fn save_screenshot(&mut self) {
+        if !self.grapher_scene.is_some() {
+            log::warn!("No scene loaded to take a screenshot of.");
+            return;
+        }
+        let filename = screenshot::screenshot_filename();
+        match screenshot::save_screenshot(
+            &self.device,
+            &self.queue,
+            &self.surface_config,
+            &self.grapher_state.tone_map,
+            &filename,
+        ) {
+            Ok(()) => log::info!("Saved screenshot to {filename}"),
+            Err(err) => log::error!("{err}"),
+        }
+    }
+
     pub fn hide_file_input(&mut self) {
         self.file_input_state = FileInputState::Hidden;
         self.ui_data.show_file_input = false;
+        self.ui_data.append_file = false;
+        self.ui_data.pick_skybox = false;
     }
 
     pub fn show_file_input(&mut self) {
@@ -181,14 +440,152 @@ impl AppState {
     }
 }
 
+// -----------------------------------------------
+// Load files dropped onto the window by extension.
+
+impl AppState {
+    pub fn handle_dropped_file(&mut self, path: &Path) {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        self.hide_file_input();
+        self.ui_data.filename = path.to_string_lossy().to_string();
+
+        let scene_mode = match extension.as_str() {
+            "gltf" | "glb" | "obj" => GrapherSceneMode::Model,
+            "png" | "jpg" | "jpeg" => GrapherSceneMode::ImageViewer,
+            "csv" => GrapherSceneMode::DataPlot,
+            "hdr" => {
+                // Equirectangular HDR images are only used as skyboxes.
+                self.ui_data.pick_skybox = true;
+                self.file_input_state = FileInputState::NeedsChecked;
+                return;
+            }
+            _ => {
+                self.report_invalid_file();
+                return;
+            }
+        };
+        self.open_file_in_mode(scene_mode, path);
+    }
+
+    /// Load a scene of the given mode from a file over the next scene
+    /// change updates, switching modes without going through the file dialog.
+    pub fn open_file_in_mode(&mut self, scene_mode: GrapherSceneMode, path: &Path) {
+        self.hide_file_input();
+        self.ui_data.filename = path.to_string_lossy().to_string();
+
+        if scene_mode != self.scene_mode {
+            self.scene_mode = scene_mode;
+            self.ui_data.selected_scene_index = scene_mode.into();
+            self.grapher_scene = GrapherScene::None;
+        }
+        self.scene_loading_state = SceneLoadingState::NoData;
+        self.file_input_state = FileInputState::NeedsChecked;
+    }
+}
+
 // ---------------------
 // Mode change handlers.
 
 impl AppState {
+    /// Parse a function given at startup, to be graphed when the graph scene
+    /// is created. The default empty graph is used if it doesn't parse.
+    pub fn set_initial_function(&mut self, function_string: &str) {
+        if !matches!(self.scene_mode, GrapherSceneMode::Graph) {
+            log::warn!("Ignoring --function, which only applies in graph mode.");
+            return;
+        }
+        let Some(function) =
+            grapher::math::try_parse_function_string(function_string, &NoiseParameters::default())
+        else {
+            log::error!("Failed to parse --function \"{function_string}\"; using the default.");
+            return;
+        };
+        self.ui_data.function_string = function_string.to_string();
+        self.ui_data.function_valid = true;
+        self.initial_function = Some(function);
+    }
+
+    /// Set the workgroup size given at startup for the solver scene,
+    /// keeping the default if the device doesn't support it.
+    pub fn set_solver_workgroup_size(&mut self, size: u32) {
+        match check_workgroup_size(&self.device, size) {
+            Ok(()) => self.solver_workgroup_size = size,
+            Err(err) => {
+                log::error!("{err} Using {DEFAULT_WORKGROUP_SIZE}.");
+            }
+        }
+    }
+
+    /// Run one scene update and any compute passes, like a solver timestep.
+    /// These run one or more times for each drawn frame.
+    pub fn step_scene(&mut self) {
+        if !self.grapher_scene.is_some() {
+            return;
+        }
+        // Let scene run any of its own internal updates.
+        if !self.scene_updates_paused {
+            self.grapher_scene.update(
+                &self.device,
+                &self.surface_config,
+                &self.queue,
+                &self.grapher_state,
+            );
+        }
+        self.grapher_scene.compute(&self.device, &self.queue);
+    }
+
+    /// Switch to a demo scene through the usual scene change handlers,
+    /// dropping the current scene, and start the turntable.
+    pub fn show_demo_step(&mut self, step: &DemoStep) {
+        self.hide_file_input();
+        match step {
+            DemoStep::Graph(function) => {
+                self.set_scene_mode(GrapherSceneMode::Graph);
+                self.set_initial_function(function);
+            }
+            DemoStep::Scene(scene_mode) => {
+                self.set_scene_mode(*scene_mode);
+            }
+            DemoStep::Model(path) => {
+                // Loads over the next frames, as if the file were dropped.
+                self.handle_dropped_file(Path::new(path));
+            }
+        }
+        self.handle_scene_changes();
+
+        // A simulation is more interesting running, and on the 3D surface.
+        if let GrapherScene::Solver(data) = &mut self.grapher_scene {
+            data.updates_paused = false;
+            data.show_surface = true;
+        }
+        self.grapher_state.camera_state.controller.turntable = true;
+    }
+
+    pub fn set_scene_mode(&mut self, scene_mode: GrapherSceneMode) {
+        self.scene_mode = scene_mode;
+        self.ui_data.selected_scene_index = scene_mode.into();
+        self.grapher_scene = GrapherScene::Changed;
+    }
+
     pub fn handle_scene_changes(&mut self) {
+        if self.ui_data.reset_scene_requested {
+            self.reset_scene();
+        }
+        if self.ui_data.reset_camera_requested {
+            self.reset_camera();
+        }
         if self.ui_data.show_file_input {
             self.show_file_input();
         }
+        // Skybox picking is independent of the scene state machine.
+        if self.ui_data.pick_skybox {
+            self.load_picked_skybox();
+            return;
+        }
         match self.scene_mode {
             GrapherSceneMode::Graph => {
                 self.scene_change_graph();
@@ -202,9 +599,52 @@ impl AppState {
             GrapherSceneMode::Solver => {
                 self.scene_change_solver();
             }
+            GrapherSceneMode::Fractal => {
+                self.scene_change_fractal();
+            }
+            GrapherSceneMode::Heightmap => {
+                self.scene_change_heightmap();
+            }
+            GrapherSceneMode::DataPlot => {
+                self.scene_change_data_plot();
+            }
+            GrapherSceneMode::LinePlot => {
+                self.scene_change_line_plot();
+            }
+            GrapherSceneMode::ComplexDomain => {
+                self.scene_change_complex_domain();
+            }
+            GrapherSceneMode::Schrodinger => {
+                self.scene_change_schrodinger();
+            }
+            GrapherSceneMode::Sphere => {
+                self.scene_change_sphere();
+            }
+            GrapherSceneMode::Heat => {
+                self.scene_change_heat();
+            }
+            GrapherSceneMode::Wave => {
+                self.scene_change_wave();
+            }
         };
     }
 
+    fn load_picked_skybox(&mut self) {
+        if !matches!(self.file_input_state, FileInputState::NeedsChecked) {
+            return;
+        }
+        match Skybox::from_equirectangular(&self.device, &self.queue, &self.ui_data.filename) {
+            Ok(skybox) => {
+                self.grapher_state.skybox = Some(skybox);
+                self.hide_file_input();
+            }
+            Err(err) => {
+                self.ui_data.file_error = Some(err);
+                self.report_invalid_file();
+            }
+        }
+    }
+
     fn scene_change_graph(&mut self) {
         self.hide_file_input();
 
@@ -223,7 +663,14 @@ impl AppState {
                 let graph_scene = GraphScene::default();
                 self.grapher_scene =
                     GrapherScene::Graph(Box::from(graph_scene::GraphSceneData::new(graph_scene)));
+                let mut function_string = None;
+                if let Some(function) = self.initial_function.take() {
+                    self.grapher_scene
+                        .update_graph(&self.grapher_state, function);
+                    function_string = Some(self.ui_data.function_string.clone());
+                }
                 self.scene_loading_state = SceneLoadingState::Loaded;
+                self.capture_scene_defaults(function_string);
             }
 
             SceneLoadingState::NeedsLoaded => {
@@ -263,7 +710,6 @@ impl AppState {
                 let model_scene = grapher::scene::textured::model::model_scene(
                     &self.device,
                     &self.queue,
-                    &self.surface_config,
                     &mut self.grapher_state,
                     &self.ui_data.filename,
                 );
@@ -272,10 +718,12 @@ impl AppState {
                     self.grapher_scene =
                         GrapherScene::Model(model_scene::ModelSceneData::new(scene));
                     self.hide_file_input();
+                    self.scene_file = self.ui_data.filename.clone();
                     self.scene_loading_state = SceneLoadingState::Loaded;
+                    self.capture_scene_defaults(None);
                 } else {
                     self.grapher_scene = GrapherScene::None;
-                    self.file_input_state = FileInputState::InvalidFile;
+                    self.report_invalid_file();
                     self.scene_loading_state = SceneLoadingState::NoData;
                 }
             }
@@ -307,6 +755,27 @@ impl AppState {
                 _ => {}
             },
 
+            SceneLoadingState::NeedsLoaded if self.ui_data.append_file => {
+                let GrapherScene::ImageViewer(data) = &mut self.grapher_scene else {
+                    self.ui_data.append_file = false;
+                    return;
+                };
+                let result = data.image_viewer_scene.add_image(
+                    &self.device,
+                    &self.queue,
+                    &mut self.grapher_state,
+                    &self.ui_data.filename,
+                );
+
+                // Keep the current images if the new one fails to load.
+                self.scene_loading_state = SceneLoadingState::Loaded;
+                if result.is_ok() {
+                    self.hide_file_input();
+                } else {
+                    self.report_invalid_file();
+                }
+            }
+
             SceneLoadingState::NeedsLoaded => {
                 self.grapher_state
                     .camera_state
@@ -315,7 +784,6 @@ impl AppState {
                 let image_scene = grapher::scene::textured::image_viewer::image_viewer_scene(
                     &self.device,
                     &self.queue,
-                    &self.surface_config,
                     &mut self.grapher_state,
                     &self.ui_data.filename,
                 );
@@ -324,10 +792,12 @@ impl AppState {
                     self.grapher_scene =
                         GrapherScene::ImageViewer(image_scene::ImageViewerSceneData::new(scene));
                     self.hide_file_input();
+                    self.scene_file = self.ui_data.filename.clone();
                     self.scene_loading_state = SceneLoadingState::Loaded;
+                    self.capture_scene_defaults(None);
                 } else {
                     self.grapher_scene = GrapherScene::None;
-                    self.file_input_state = FileInputState::InvalidFile;
+                    self.report_invalid_file();
                     self.scene_loading_state = SceneLoadingState::NoData;
                 }
             }
@@ -341,15 +811,230 @@ impl AppState {
         }
     }
 
+    fn scene_change_heightmap(&mut self) {
+        // Detect change of mode.
+        if matches!(self.grapher_scene, GrapherScene::Changed) {
+            self.grapher_scene = GrapherScene::None;
+            self.scene_loading_state = SceneLoadingState::NoData;
+            self.ui_data.filename = "".into();
+            self.show_file_input();
+        }
+
+        #[allow(clippy::single_match)]
+        match self.scene_loading_state {
+            SceneLoadingState::NoData => match self.file_input_state {
+                FileInputState::NeedsChecked => {
+                    self.scene_loading_state = SceneLoadingState::NeedsLoaded;
+                }
+                _ => {}
+            },
+
+            SceneLoadingState::NeedsLoaded => {
+                self.grapher_state
+                    .camera_state
+                    .reset_camera(&self.queue, &self.surface_config);
+
+                let heightmap_scene = grapher::scene::solid::heightmap::HeightmapScene::new(
+                    &self.device,
+                    &self.surface_config,
+                    &self.grapher_state,
+                    &self.ui_data.filename,
+                );
+
+                match heightmap_scene {
+                    Ok(scene) => {
+                        self.grapher_scene =
+                            GrapherScene::Heightmap(HeightmapSceneData::new(scene));
+                        self.hide_file_input();
+                        self.scene_file = self.ui_data.filename.clone();
+                        self.scene_loading_state = SceneLoadingState::Loaded;
+                        self.capture_scene_defaults(None);
+                    }
+                    Err(err) => {
+                        self.grapher_scene = GrapherScene::None;
+                        self.ui_data.file_error = Some(err);
+                        self.report_invalid_file();
+                        self.scene_loading_state = SceneLoadingState::NoData;
+                    }
+                }
+            }
+
+            SceneLoadingState::Loaded => match self.file_input_state {
+                FileInputState::NeedsChecked => {
+                    self.scene_loading_state = SceneLoadingState::NeedsLoaded;
+                }
+                _ => {}
+            },
+        }
+    }
+
+    fn scene_change_data_plot(&mut self) {
+        // Detect change of mode.
+        if matches!(self.grapher_scene, GrapherScene::Changed) {
+            self.grapher_scene = GrapherScene::None;
+            self.scene_loading_state = SceneLoadingState::NoData;
+            self.ui_data.filename = "".into();
+            self.show_file_input();
+        }
+
+        #[allow(clippy::single_match)]
+        match self.scene_loading_state {
+            SceneLoadingState::NoData => match self.file_input_state {
+                FileInputState::NeedsChecked => {
+                    self.scene_loading_state = SceneLoadingState::NeedsLoaded;
+                }
+                _ => {}
+            },
+
+            SceneLoadingState::NeedsLoaded => {
+                self.grapher_state
+                    .camera_state
+                    .reset_camera(&self.queue, &self.surface_config);
+
+                let data_plot_scene = grapher::scene::data_plot::DataPlotScene::new(
+                    &self.device,
+                    &self.surface_config,
+                    &self.grapher_state,
+                    &self.ui_data.filename,
+                );
+
+                match data_plot_scene {
+                    Ok(scene) => {
+                        self.grapher_scene = GrapherScene::DataPlot(DataPlotSceneData::new(scene));
+                        self.hide_file_input();
+                        self.scene_file = self.ui_data.filename.clone();
+                        self.scene_loading_state = SceneLoadingState::Loaded;
+                        self.capture_scene_defaults(None);
+                    }
+                    Err(err) => {
+                        self.grapher_scene = GrapherScene::None;
+                        self.ui_data.file_error = Some(err);
+                        self.report_invalid_file();
+                        self.scene_loading_state = SceneLoadingState::NoData;
+                    }
+                }
+            }
+
+            SceneLoadingState::Loaded => match self.file_input_state {
+                FileInputState::NeedsChecked => {
+                    self.scene_loading_state = SceneLoadingState::NeedsLoaded;
+                }
+                _ => {}
+            },
+        }
+    }
+
     fn scene_change_solver(&mut self) {
         if matches!(self.grapher_scene, GrapherScene::Changed) {
             self.hide_file_input();
+            // The 3D surface view uses the usual perspective camera.
+            self.grapher_state
+                .camera_state
+                .reset_camera(&self.queue, &self.surface_config);
             self.grapher_scene = GrapherScene::Solver(SolverSceneData::new(
                 &self.device,
                 &self.queue,
                 &self.surface_config,
+                self.solver_workgroup_size,
+            ));
+            self.scene_loading_state = SceneLoadingState::Loaded;
+            self.capture_scene_defaults(None);
+        }
+    }
+
+    fn scene_change_line_plot(&mut self) {
+        if matches!(self.grapher_scene, GrapherScene::Changed) {
+            self.hide_file_input();
+            self.grapher_state
+                .camera_state
+                .reset_camera(&self.queue, &self.surface_config);
+            self.grapher_scene = GrapherScene::LinePlot(LinePlotSceneData::new(
+                &self.device,
+                &self.queue,
+                &mut self.grapher_state,
+            ));
+            self.scene_loading_state = SceneLoadingState::Loaded;
+            self.capture_scene_defaults(None);
+        }
+    }
+
+    fn scene_change_fractal(&mut self) {
+        if matches!(self.grapher_scene, GrapherScene::Changed) {
+            self.hide_file_input();
+            self.grapher_scene = GrapherScene::Fractal(FractalSceneData::new(
+                &self.device,
+                &self.queue,
+                &self.surface_config,
+                &mut self.grapher_state,
+            ));
+            self.scene_loading_state = SceneLoadingState::Loaded;
+            self.capture_scene_defaults(None);
+        }
+    }
+
+    fn scene_change_complex_domain(&mut self) {
+        if matches!(self.grapher_scene, GrapherScene::Changed) {
+            self.hide_file_input();
+            self.grapher_scene = GrapherScene::ComplexDomain(ComplexDomainSceneData::new(
+                &self.device,
+                &self.queue,
+                &self.surface_config,
+                &mut self.grapher_state,
+            ));
+            self.scene_loading_state = SceneLoadingState::Loaded;
+            self.capture_scene_defaults(None);
+        }
+    }
+
+    fn scene_change_schrodinger(&mut self) {
+        if matches!(self.grapher_scene, GrapherScene::Changed) {
+            self.hide_file_input();
+            self.grapher_scene = GrapherScene::Schrodinger(SchrodingerSceneData::new(
+                &self.device,
+                &self.surface_config,
+                &self.grapher_state,
+            ));
+            self.scene_loading_state = SceneLoadingState::Loaded;
+            self.capture_scene_defaults(None);
+        }
+    }
+
+    fn scene_change_sphere(&mut self) {
+        if matches!(self.grapher_scene, GrapherScene::Changed) {
+            self.hide_file_input();
+            self.grapher_scene = GrapherScene::Sphere(SphereSceneData::new(
+                &self.device,
+                &self.surface_config,
+                &self.grapher_state,
+            ));
+            self.scene_loading_state = SceneLoadingState::Loaded;
+            self.capture_scene_defaults(None);
+        }
+    }
+
+    fn scene_change_heat(&mut self) {
+        if matches!(self.grapher_scene, GrapherScene::Changed) {
+            self.hide_file_input();
+            self.grapher_scene = GrapherScene::Heat(HeatEquationSceneData::new(
+                &self.device,
+                &self.surface_config,
+                &self.grapher_state,
+            ));
+            self.scene_loading_state = SceneLoadingState::Loaded;
+            self.capture_scene_defaults(None);
+        }
+    }
+
+    fn scene_change_wave(&mut self) {
+        if matches!(self.grapher_scene, GrapherScene::Changed) {
+            self.hide_file_input();
+            self.grapher_scene = GrapherScene::Wave(WaveEquationSceneData::new(
+                &self.device,
+                &self.surface_config,
+                &self.grapher_state,
             ));
             self.scene_loading_state = SceneLoadingState::Loaded;
+            self.capture_scene_defaults(None);
         }
     }
 }
@@ -2,10 +2,12 @@
 
 #![allow(dead_code)]
 
-use std::f32;
+use std::{collections::VecDeque, f32};
 
 use egui::{Color32, Context, Ui};
 
+use crate::grapher::{math::colormap::Colormap, pipeline::texture::TextureFiltering};
+
 pub struct HasFocus(pub bool);
 
 impl HasFocus {
@@ -20,6 +22,7 @@ pub fn validated_text_input_window(
     input: &mut String,
     mut validate: impl FnMut(&String),
     is_valid: bool,
+    manual_apply: &mut bool,
 ) -> HasFocus {
     let mut text_has_focus = false;
     egui::Window::new(title)
@@ -41,10 +44,26 @@ pub fn validated_text_input_window(
                     .desired_rows(1),
             );
 
-            if response.lost_focus() || ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            let enter_pressed = ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let mut apply = if *manual_apply {
+                response.lost_focus() && enter_pressed
+            } else {
+                response.lost_focus() || enter_pressed
+            };
+            text_has_focus = response.has_focus();
+
+            ui.horizontal(|ui| {
+                ui.checkbox(manual_apply, "Manual apply").on_hover_text(
+                    "Apply only on Enter or the Apply button, not when the field loses focus.",
+                );
+                if *manual_apply {
+                    apply |= ui.button("Apply").clicked();
+                }
+            });
+
+            if apply {
                 validate(input);
             }
-            text_has_focus = response.has_focus();
         });
 
     HasFocus(text_has_focus)
@@ -76,3 +95,177 @@ pub fn float_edit_line(
 
     changed
 }
+
+/// Gradient bar for `colormap` with tick labels spanning \[min, max\],
+/// and a combo box to pick another colormap. Returns true if it changed.
+pub fn colormap_legend(colormap: &mut Colormap, min: f32, max: f32, ui: &mut Ui) -> bool {
+    const SEGMENTS: usize = 64;
+    const TICKS: usize = 5;
+    const BAR_HEIGHT: f32 = 16.0;
+
+    let previous = *colormap;
+    egui::ComboBox::from_label("Colormap")
+        .selected_text(colormap.name())
+        .show_ui(ui, |ui| {
+            for option in Colormap::ALL {
+                ui.selectable_value(colormap, option, option.name());
+            }
+        });
+
+    let width = ui.available_width();
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, BAR_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter();
+    let segment_width = rect.width() / SEGMENTS as f32;
+    for i in 0..SEGMENTS {
+        let [r, g, b] = colormap.color((i as f32 + 0.5) / SEGMENTS as f32);
+        let left = rect.left() + i as f32 * segment_width;
+        painter.rect_filled(
+            egui::Rect::from_min_max(
+                egui::pos2(left, rect.top()),
+                egui::pos2(left + segment_width, rect.bottom()),
+            ),
+            0.0,
+            // colormap colors are linear, like the vertex colors they become
+            Color32::from(egui::Rgba::from_rgb(r, g, b)),
+        );
+    }
+
+    // Equal-width columns, so each label sits under its tick.
+    ui.columns(TICKS, |columns| {
+        for (i, column) in columns.iter_mut().enumerate() {
+            let value = min + (max - min) * i as f32 / (TICKS - 1) as f32;
+            let layout = match i {
+                0 => egui::Layout::left_to_right(egui::Align::Center),
+                i if i == TICKS - 1 => egui::Layout::right_to_left(egui::Align::Center),
+                _ => egui::Layout::top_down(egui::Align::Center),
+            };
+            column.with_layout(layout, |ui| ui.label(format!("{value:.2}")));
+        }
+    });
+
+    *colormap != previous
+}
+
+/// Combo box choosing smooth or crisp texture sampling, with `id_salt`
+/// distinguishing it from others on screen. Returns true if it changed.
+pub fn texture_filtering_combo(
+    filtering: &mut TextureFiltering,
+    id_salt: &str,
+    ui: &mut Ui,
+) -> bool {
+    let previous = *filtering;
+    ui.horizontal(|ui| {
+        ui.label("Texture filtering");
+        egui::ComboBox::from_id_salt(id_salt)
+            .selected_text(filtering.label())
+            .show_ui(ui, |ui| {
+                for option in TextureFiltering::ALL {
+                    ui.selectable_value(filtering, option, option.label());
+                }
+            });
+    })
+    .response
+    .on_hover_text("Crisp shows individual pixels when zoomed in.");
+
+    *filtering != previous
+}
+
+/// Slider for a display-time multiplier of surface heights.
+pub fn vertical_exaggeration_slider(exaggeration: &mut f32, ui: &mut Ui) {
+    ui.add(
+        egui::Slider::new(exaggeration, 0.1..=10.0)
+            .logarithmic(true)
+            .text("Vertical exaggeration"),
+    )
+    .on_hover_text("Stretch heights for display, without rebuilding the mesh.");
+}
+
+/// Plot of recent frame times, oldest on the left, with a line marking the
+/// target frame time. `capacity` is the number of frames the width spans.
+pub fn frame_time_plot(frame_secs: &VecDeque<f32>, capacity: usize, target_ms: f32, ui: &mut Ui) {
+    const PLOT_HEIGHT: f32 = 60.0;
+
+    let width = ui.available_width();
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, PLOT_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    // Leave headroom above the target, and grow to fit spikes.
+    let max_ms = frame_secs
+        .iter()
+        .map(|secs| 1000.0 * secs)
+        .fold(2.0 * target_ms, f32::max);
+    let y_for = |ms: f32| rect.bottom() - rect.height() * ms / max_ms;
+    let x_step = rect.width() / capacity.saturating_sub(1).max(1) as f32;
+
+    let target_y = y_for(target_ms);
+    painter.hline(
+        rect.x_range(),
+        target_y,
+        egui::Stroke::new(1.0, Color32::from_rgb(200, 160, 60)),
+    );
+    painter.text(
+        egui::pos2(rect.right() - 2.0, target_y - 1.0),
+        egui::Align2::RIGHT_BOTTOM,
+        format!("{target_ms:.1} ms"),
+        egui::FontId::monospace(10.0),
+        Color32::from_rgb(200, 160, 60),
+    );
+
+    // Newest frame at the right edge.
+    let offset = capacity.saturating_sub(frame_secs.len());
+    let points: Vec<egui::Pos2> = frame_secs
+        .iter()
+        .enumerate()
+        .map(|(i, secs)| {
+            egui::pos2(
+                rect.left() + (offset + i) as f32 * x_step,
+                y_for(1000.0 * secs),
+            )
+        })
+        .collect();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.0, ui.visuals().text_color()),
+    ));
+
+    painter.text(
+        rect.left_top() + egui::vec2(2.0, 1.0),
+        egui::Align2::LEFT_TOP,
+        format!("{max_ms:.1} ms"),
+        egui::FontId::monospace(10.0),
+        ui.visuals().weak_text_color(),
+    );
+}
+
+/// Bar plot of histogram counts, lowest bin on the left, scaled so the
+/// tallest bar fills the height.
+pub fn histogram_plot(counts: &[u32], color: Color32, ui: &mut Ui) {
+    const PLOT_HEIGHT: f32 = 50.0;
+
+    let width = ui.available_width();
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, PLOT_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    let max_count = counts.iter().copied().max().unwrap_or(0);
+    if max_count == 0 {
+        return;
+    }
+    let bar_width = rect.width() / counts.len() as f32;
+    for (i, &count) in counts.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+        let left = rect.left() + i as f32 * bar_width;
+        let top = rect.bottom() - rect.height() * count as f32 / max_count as f32;
+        painter.rect_filled(
+            egui::Rect::from_min_max(
+                egui::pos2(left, top),
+                egui::pos2(left + bar_width, rect.bottom()),
+            ),
+            0.0,
+            color,
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! In-app console showing recent log messages.
+//!
+//! A global `log::Log` implementation keeps the most recent records in a
+//! ring buffer shared with the UI, and still echoes them to stdout.
+
+use egui::{Color32, Context, RichText};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, OnceLock},
+};
+
+// number of messages kept for display
+const CAPACITY: usize = 500;
+// target prefix of records logged by this crate
+const CRATE_NAME: &str = env!("CARGO_CRATE_NAME");
+
+pub struct LogEntry {
+    pub level: Level,
+    pub message: String,
+}
+
+struct ConsoleLogger {
+    entries: Mutex<VecDeque<LogEntry>>,
+}
+
+static LOGGER: OnceLock<ConsoleLogger> = OnceLock::new();
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Only warnings and errors from dependencies, like wgpu.
+        metadata.level() <= Level::Warn || metadata.target().starts_with(CRATE_NAME)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        println!("[{}] {message}", record.level());
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(LogEntry {
+            level: record.level(),
+            message,
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the console logger; later calls do nothing.
+pub fn install() {
+    let logger = LOGGER.get_or_init(|| ConsoleLogger {
+        entries: Mutex::new(VecDeque::with_capacity(CAPACITY)),
+    });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(LevelFilter::Info);
+    }
+}
+
+// -----------------
+// Console window UI.
+
+pub struct LogConsole {
+    pub open: bool,
+    // least severe level shown
+    pub min_level: Level,
+}
+
+impl Default for LogConsole {
+    fn default() -> Self {
+        Self {
+            open: false,
+            min_level: Level::Info,
+        }
+    }
+}
+
+impl LogConsole {
+    pub fn show(&mut self, context: &Context) {
+        let Some(logger) = LOGGER.get() else {
+            return;
+        };
+        let min_level = &mut self.min_level;
+
+        egui::Window::new("Log")
+            .open(&mut self.open)
+            .default_size([450.0, 200.0])
+            .default_pos([15.0, 450.0])
+            .resizable(true)
+            .show(context, |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label("Show")
+                        .selected_text(min_level.as_str())
+                        .show_ui(ui, |ui| {
+                            for level in [Level::Error, Level::Warn, Level::Info] {
+                                ui.selectable_value(min_level, level, level.as_str());
+                            }
+                        });
+                    if ui.button("Clear").clicked() {
+                        logger.entries.lock().unwrap().clear();
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .stick_to_bottom(true)
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        let entries = logger.entries.lock().unwrap();
+                        for entry in entries.iter().filter(|entry| entry.level <= *min_level) {
+                            let text = format!("[{}] {}", entry.level, entry.message);
+                            ui.label(
+                                RichText::new(text)
+                                    .monospace()
+                                    .color(level_color(entry.level)),
+                            );
+                        }
+                    });
+            });
+    }
+}
+
+fn level_color(level: Level) -> Color32 {
+    match level {
+        Level::Error => Color32::from_rgb(230, 90, 80),
+        Level::Warn => Color32::from_rgb(220, 180, 70),
+        _ => Color32::from_gray(200),
+    }
+}
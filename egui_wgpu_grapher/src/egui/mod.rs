@@ -1,3 +1,5 @@
 pub mod components;
 pub mod egui_tools;
+pub mod log_console;
+pub mod shortcuts;
 pub mod ui;
@@ -0,0 +1,94 @@
+//! Help overlay listing keyboard shortcuts and mouse interactions.
+//!
+//! New bindings should be added to the lists here so they show up in the app.
+
+use egui::{Context, Key, RichText};
+
+// (input, action) pairs shown in the overlay.
+pub const KEY_SHORTCUTS: &[(&str, &str)] = &[
+    ("W / Up", "rotate up"),
+    ("S / Down", "rotate down"),
+    ("A / Left", "rotate left"),
+    ("D / Right", "rotate right"),
+    ("Q / E", "roll left / right"),
+    ("L", "level horizon"),
+    ("K", "add camera path keyframe"),
+    ("Z", "zoom in"),
+    ("X", "zoom out"),
+    ("T", "translate up"),
+    ("G", "translate down"),
+    ("F", "translate left"),
+    ("H", "translate right"),
+    ("Shift + key", "increase speed"),
+    ("R", "reset view"),
+    ("[ / ]", "previous / next graph preset"),
+    ("F12", "save screenshot"),
+    ("Tab", "hide / show all windows"),
+    ("? / F1", "toggle this help"),
+    ("Esc", "exit"),
+];
+
+pub const MOUSE_SHORTCUTS: &[(&str, &str)] = &[
+    ("Drag", "rotate"),
+    ("Ctrl + drag", "translate"),
+    ("Wheel", "zoom"),
+    ("Drop file", "open model, image, or skybox"),
+];
+
+/// Toggle the overlay on its shortcut keys, unless a text field has focus.
+pub fn handle_toggle(context: &Context, show_shortcuts: &mut bool) {
+    if context.wants_keyboard_input() {
+        return;
+    }
+    if context.input(|i| i.key_pressed(Key::Questionmark) || i.key_pressed(Key::F1)) {
+        *show_shortcuts = !*show_shortcuts;
+    }
+}
+
+/// Hide or show every window on its shortcut key, unless a text field has focus.
+pub fn handle_hide_ui(context: &Context, show_ui: &mut bool) {
+    if context.wants_keyboard_input() {
+        return;
+    }
+    if context.input(|i| i.key_pressed(Key::Tab)) {
+        *show_ui = !*show_ui;
+    }
+}
+
+/// Request a screenshot on its shortcut key, unless a text field has focus.
+pub fn handle_screenshot(context: &Context, screenshot_requested: &mut bool) {
+    if context.wants_keyboard_input() {
+        return;
+    }
+    if context.input(|i| i.key_pressed(Key::F12)) {
+        *screenshot_requested = true;
+    }
+}
+
+pub fn shortcut_help_window(context: &Context, show_shortcuts: &mut bool) {
+    egui::Window::new("Shortcuts")
+        .open(show_shortcuts)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(context, |ui| {
+            shortcut_grid(ui, "keyboard shortcuts", "Keyboard", KEY_SHORTCUTS);
+            ui.add_space(5.0);
+            shortcut_grid(ui, "mouse shortcuts", "Mouse", MOUSE_SHORTCUTS);
+        });
+}
+
+fn shortcut_grid(ui: &mut egui::Ui, id: &str, heading: &str, shortcuts: &[(&str, &str)]) {
+    ui.label(RichText::new(heading).strong());
+    egui::Grid::new(id)
+        .num_columns(2)
+        .spacing([20.0, 2.0])
+        .striped(true)
+        .show(ui, |ui| {
+            for (input, action) in shortcuts {
+                ui.label(RichText::new(*input).monospace());
+                ui.label(*action);
+                ui.end_row();
+            }
+        });
+}
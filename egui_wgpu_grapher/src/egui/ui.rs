@@ -1,13 +1,32 @@
 use egui::{RichText, Ui};
+use egui_wgpu::wgpu::PresentMode;
+use std::{collections::VecDeque, ops::RangeInclusive};
 
-use crate::grapher_egui::{
-    GrapherScene, GrapherSceneMode, RenderState, RenderUiState, render_parameter_ui,
-    scene_selection_ui,
+use super::{components::frame_time_plot, log_console::LogConsole};
+
+use crate::{
+    grapher::{
+        pipeline::{
+            antialiasing::{self, Antialiasing},
+            texture,
+        },
+        render::RENDER_SCALE_RANGE,
+    },
+    grapher_egui::{
+        GrapherScene, GrapherSceneMode, RenderState, RenderUiState, render_parameter_ui,
+        scene_selection_ui, skybox_ui,
+    },
 };
 
 // ---------------------------------------
 // Store data modified by GUI interaction.
 
+/// Number of frames shown in the frame time plot.
+pub const FRAME_TIME_HISTORY_LEN: usize = 200;
+
+/// Allowed range of the UI scale factor, applied on top of the window's.
+pub const UI_SCALE_RANGE: RangeInclusive<f32> = 0.5..=3.0;
+
 #[derive(Default)]
 pub struct UiState {
     pub render_ui_state: RenderUiState,
@@ -16,7 +35,82 @@ pub struct UiState {
     pub filename: String,
     pub function_string: String,
     pub function_valid: bool,
+    // apply the function only on Enter or the Apply button
+    pub function_manual_apply: bool,
     pub show_file_input: bool,
+    // add picked file to current scene instead of replacing it
+    pub append_file: bool,
+    // use picked file as skybox instead of scene data
+    pub pick_skybox: bool,
+    pub show_shortcuts: bool,
+    // draw the egui windows over the scene; off for clean screenshots
+    pub show_ui: bool,
+    pub reset_camera_requested: bool,
+    // rebuild the current scene with its initial parameters
+    pub reset_scene_requested: bool,
+    // save the next rendered frame to a PNG
+    pub screenshot_requested: bool,
+    // details shown when a file fails to load
+    pub file_error: Option<String>,
+    // last cursor position over the window, in physical pixels
+    pub cursor_position: Option<[f32; 2]>,
+    pub frame_rate: FrameRateSettings,
+    pub demo: DemoSettings,
+    // latest average framerate and frame time percentiles
+    pub frame_time_summary: Option<String>,
+    // recent frame times in seconds; only recorded while the plot is shown
+    pub show_frame_time_plot: bool,
+    pub frame_time_history: VecDeque<f32>,
+    // present modes the surface supports
+    pub present_modes: Vec<PresentMode>,
+    // selected mode; MSAA changes apply on restart
+    pub antialiasing: Antialiasing,
+    // MSAA sample counts the adapter supports
+    pub supported_sample_counts: Vec<u32>,
+    // selected depth direction; applies on restart
+    pub reverse_z: bool,
+    // scene resolution relative to the window's; applied before the next frame
+    pub render_scale: f32,
+    pub log_console: LogConsole,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FrameRateSettings {
+    pub target_fps: u32,
+    // render as fast as possible, with vsync off, for benchmarking
+    pub uncapped: bool,
+    // used when not uncapped
+    pub present_mode: PresentMode,
+    // scene updates run before each drawn frame
+    pub sim_steps_per_frame: u32,
+}
+
+impl Default for FrameRateSettings {
+    fn default() -> Self {
+        Self {
+            target_fps: 60,
+            uncapped: false,
+            present_mode: PresentMode::AutoVsync,
+            sim_steps_per_frame: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DemoSettings {
+    // cycle through the demo scenes
+    pub enabled: bool,
+    // time each scene is shown
+    pub interval_secs: f32,
+}
+
+impl Default for DemoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 10.0,
+        }
+    }
 }
 
 // -----------------------------------
@@ -38,7 +132,20 @@ pub fn create_gui(
 
     if scene_selection_ui(scene_mode, ui_state, ui).changed() {
         *grapher_scene = GrapherScene::Changed;
+        // Picking a scene takes over from the demo.
+        ui_state.demo.enabled = false;
     }
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut ui_state.demo.enabled, "Demo")
+            .on_hover_text("Cycle through built-in scenes with the turntable on.");
+        if ui_state.demo.enabled {
+            ui.add(
+                egui::Slider::new(&mut ui_state.demo.interval_secs, 3.0..=60.0)
+                    .suffix(" s")
+                    .text("Per scene"),
+            );
+        }
+    });
 
     if grapher_scene.is_some() {
         ui.separator();
@@ -51,28 +158,183 @@ pub fn create_gui(
     ui.label(RichText::new("Render parameters").strong());
     ui.add_space(AFTER_LABEL_SPACE);
 
-    if !matches!(scene_mode, GrapherSceneMode::Solver) {
+    if !matches!(
+        scene_mode,
+        GrapherSceneMode::Solver
+            | GrapherSceneMode::Fractal
+            | GrapherSceneMode::LinePlot
+            | GrapherSceneMode::ComplexDomain
+    ) {
         render_parameter_ui(
             render_state,
             &mut ui_state.render_ui_state,
             grapher_scene,
             ui,
         );
+        skybox_ui(render_state, ui_state, ui);
+    }
+
+    antialiasing_ui(render_state, ui_state, ui);
+    render_scale_ui(render_state, ui_state, ui);
+    depth_ui(ui_state, ui);
+
+    ui.horizontal(|ui| {
+        if ui.button("Reset view (R)").clicked() {
+            ui_state.reset_camera_requested = true;
+        }
+        if ui.button("Screenshot (F12)").clicked() {
+            ui_state.screenshot_requested = true;
+        }
+    });
+    if grapher_scene.is_some()
+        && ui
+            .button("Reset scene to defaults")
+            .on_hover_text("Restore this scene's parameters and camera to how it started.")
+            .clicked()
+    {
+        ui_state.reset_scene_requested = true;
     }
 
     ui.separator();
     ui.label(RichText::new("UI settings").strong());
     ui.add_space(AFTER_LABEL_SPACE);
 
+    // Applied to the screen descriptor on the next frame.
     let scale_factor = &mut ui_state.scale_factor;
 
     ui.horizontal(|ui| {
-        ui.label(format!("Pixels per point: {pixels_per_point}"));
-        if ui.button("-").clicked() {
-            *scale_factor = (*scale_factor - 0.1).max(0.3);
+        ui.add(
+            egui::Slider::new(scale_factor, UI_SCALE_RANGE)
+                .step_by(0.05)
+                .text("UI scale"),
+        );
+        if ui.button("Reset").clicked() {
+            *scale_factor = 1.0;
+        }
+    });
+    ui.label(format!("Pixels per point: {pixels_per_point:.2}"));
+    ui.horizontal(|ui| {
+        if ui.button("Shortcuts (?)").clicked() {
+            ui_state.show_shortcuts = !ui_state.show_shortcuts;
         }
-        if ui.button("+").clicked() {
-            *scale_factor = (*scale_factor + 0.1).min(3.0);
+        if ui.button("Log").clicked() {
+            ui_state.log_console.open = !ui_state.log_console.open;
+        }
+        if ui.button("Hide UI (Tab)").clicked() {
+            ui_state.show_ui = false;
+        }
+    });
+
+    ui.separator();
+    ui.label(RichText::new("Frame rate").strong());
+    ui.add_space(AFTER_LABEL_SPACE);
+
+    let frame_rate = &mut ui_state.frame_rate;
+    ui.add_enabled(
+        !frame_rate.uncapped,
+        egui::Slider::new(&mut frame_rate.target_fps, 10..=240).text("Target fps"),
+    )
+    .on_hover_text("Capped at the display refresh rate while vsync is on.");
+    ui.add_enabled_ui(!frame_rate.uncapped, |ui| {
+        egui::ComboBox::from_label("Present mode")
+            .selected_text(format!("{:?}", frame_rate.present_mode))
+            .show_ui(ui, |ui| {
+                for mode in &ui_state.present_modes {
+                    ui.selectable_value(&mut frame_rate.present_mode, *mode, format!("{mode:?}"));
+                }
+            });
+    });
+    ui.checkbox(&mut frame_rate.uncapped, "Uncapped (benchmark)")
+        .on_hover_text("Disable vsync and the frame cap, and log frame times.");
+    ui.add(
+        egui::Slider::new(&mut frame_rate.sim_steps_per_frame, 1..=16).text("Sim steps per frame"),
+    )
+    .on_hover_text("Scene updates, like solver timesteps, run for each drawn frame.");
+    if let Some(summary) = &ui_state.frame_time_summary {
+        ui.label(summary);
+    }
+
+    if ui
+        .checkbox(&mut ui_state.show_frame_time_plot, "Frame time plot")
+        .changed()
+    {
+        ui_state.frame_time_history.clear();
+    }
+    if ui_state.show_frame_time_plot {
+        // The usual 60 fps budget when uncapped.
+        let target_fps = if frame_rate.uncapped {
+            60
+        } else {
+            frame_rate.target_fps
+        };
+        frame_time_plot(
+            &ui_state.frame_time_history,
+            FRAME_TIME_HISTORY_LEN,
+            1000.0 / target_fps as f32,
+            ui,
+        );
+    }
+}
+
+fn antialiasing_ui(render_state: &mut RenderState, ui_state: &mut UiState, ui: &mut Ui) {
+    let supported_counts = &ui_state.supported_sample_counts;
+    let selected = &mut ui_state.antialiasing;
+
+    egui::ComboBox::from_label("Antialiasing")
+        .selected_text(selected.label())
+        .show_ui(ui, |ui| {
+            for mode in Antialiasing::ALL {
+                let supported = supported_counts.contains(&mode.sample_count());
+                let response = ui
+                    .add_enabled(
+                        supported,
+                        egui::Button::selectable(*selected == mode, mode.label()),
+                    )
+                    .on_disabled_hover_text("Not supported by this GPU.");
+                if response.clicked() {
+                    *selected = mode;
+                }
+            }
+        });
+    render_state.fxaa.enabled = *selected == Antialiasing::Fxaa;
+
+    // Every scene pipeline is built for one sample count.
+    if selected.sample_count() != antialiasing::sample_count() {
+        ui.label("Restart to apply the MSAA change.");
+    }
+}
+
+fn render_scale_ui(render_state: &RenderState, ui_state: &mut UiState, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::Slider::new(&mut ui_state.render_scale, RENDER_SCALE_RANGE)
+                .step_by(0.05)
+                .text("Render scale"),
+        )
+        .on_hover_text(
+            "Scene resolution relative to the window. Lower is faster but blurrier; \
+             above one supersamples for smoother edges.",
+        );
+        if ui.button("Reset").clicked() {
+            ui_state.render_scale = 1.0;
         }
     });
+    let target = &render_state.offscreen_target.texture;
+    ui.label(format!(
+        "Scene resolution: {} x {}",
+        target.width(),
+        target.height()
+    ));
+}
+
+fn depth_ui(ui_state: &mut UiState, ui: &mut Ui) {
+    ui.checkbox(&mut ui_state.reverse_z, "Reverse-Z depth")
+        .on_hover_text(
+            "More depth precision far from the camera, reducing z-fighting in large scenes.",
+        );
+
+    // Every depth-tested pipeline is built for one depth direction.
+    if ui_state.reverse_z != texture::reverse_z() {
+        ui.label("Restart to apply the depth change.");
+    }
 }
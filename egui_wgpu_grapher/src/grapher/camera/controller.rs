@@ -9,9 +9,20 @@ use winit::{
 
 use std::f32::consts::PI;
 
+/// How mouse and key input moves the camera.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    // rotate the scene about the origin and zoom toward the target
+    #[default]
+    Orbit,
+    // turn the view with the mouse and move through the scene with the keys
+    Fly,
+}
+
 #[derive(Default)]
 pub struct CameraController {
     pub speed: f32,
+    pub mode: CameraMode,
 
     // rotation keys
     pub up_pressed: bool,
@@ -19,6 +30,14 @@ pub struct CameraController {
     pub left_pressed: bool,
     pub right_pressed: bool,
 
+    // roll keys
+    pub q_pressed: bool,
+    pub e_pressed: bool,
+    pub level_horizon_requested: bool,
+
+    // add the current view to the camera path
+    pub keyframe_requested: bool,
+
     // zoom keys
     pub z_pressed: bool,
     pub x_pressed: bool,
@@ -37,6 +56,10 @@ pub struct CameraController {
     pub left_down: bool,
     pub last_drag: Option<[f64; 2]>,
     pub last_mouse_scroll: Option<f32>,
+
+    // turntable auto-rotation, with speed in radians per second
+    pub turntable: bool,
+    pub turntable_speed: f32,
 }
 
 impl CameraController {
@@ -44,11 +67,62 @@ impl CameraController {
         Self {
             speed,
             left_down: false,
+            turntable_speed: Self::DEFAULT_TURNTABLE_SPEED,
             ..Default::default()
         }
     }
 
+    pub const DEFAULT_TURNTABLE_SPEED: f32 = 0.25;
+
+    /// Orbit the camera about the vertical axis when turntable mode is on,
+    /// pausing while the user drags the scene.
+    pub fn auto_rotate(&self, camera: &mut camera::Camera, elapsed_secs: f32) {
+        if self.turntable && !self.left_down {
+            camera.increment_user_rotation(self.turntable_speed * elapsed_secs, 0.0);
+        }
+    }
+
+    // keeps fly mode from looking straight up or down, where yaw is undefined
+    const MAX_PITCH: f32 = 89.0 * PI / 180.0;
+
     pub fn update_camera(&mut self, camera: &mut camera::Camera) {
+        match self.mode {
+            CameraMode::Orbit => self.update_orbit(camera),
+            CameraMode::Fly => self.update_fly(camera),
+        }
+
+        let roll_incr = self.speed * PI / 4.0;
+        if self.q_pressed {
+            camera.increment_roll(roll_incr);
+        }
+        if self.e_pressed {
+            camera.increment_roll(-roll_incr);
+        }
+        if std::mem::take(&mut self.level_horizon_requested) {
+            camera.level_horizon();
+        }
+
+        let trans_incr = if self.shift_pressed {
+            self.speed * 6.0
+        } else {
+            self.speed * 0.5
+        };
+
+        if self.t_pressed {
+            camera.translation_y += trans_incr / camera.ortho_scale;
+        }
+        if self.g_pressed {
+            camera.translation_y -= trans_incr / camera.ortho_scale;
+        }
+        if self.f_pressed {
+            camera.translation_x -= trans_incr / camera.ortho_scale;
+        }
+        if self.h_pressed {
+            camera.translation_x += trans_incr / camera.ortho_scale;
+        }
+    }
+
+    fn update_orbit(&mut self, camera: &mut camera::Camera) {
         let zoom_incr: f32 = if self.shift_pressed { 6.0 } else { 1.2 };
         let zoom_incr = zoom_incr * self.speed;
         const MOUSE_SCROLL_RATE: f32 = 5.0;
@@ -85,17 +159,13 @@ impl CameraController {
 
         if let Some(incr) = self.last_drag.take() {
             const MOUSE_ROTATION_RATE: f32 = 0.0125;
-            const MOUSE_TRANSLATION_RATE: f32 = 0.03125;
             if !self.ctrl_pressed {
                 camera.increment_user_rotation(
                     incr[0] as f32 * MOUSE_ROTATION_RATE,
                     incr[1] as f32 * MOUSE_ROTATION_RATE,
                 );
             } else {
-                camera.translation_x +=
-                    incr[0] as f32 * MOUSE_TRANSLATION_RATE / camera.ortho_scale;
-                camera.translation_y -=
-                    incr[1] as f32 * MOUSE_TRANSLATION_RATE / camera.ortho_scale;
+                Self::translate_by_drag(camera, incr);
             }
         }
 
@@ -115,25 +185,53 @@ impl CameraController {
                 camera.increment_user_rotation(0.0, -angle_incr);
             }
         }
+    }
 
-        let trans_incr = if self.shift_pressed {
-            self.speed * 6.0
-        } else {
-            self.speed * 0.5
-        };
+    /// Mouse drag turns the view, and the rotation and zoom keys
+    /// move the eye and target together.
+    fn update_fly(&mut self, camera: &mut camera::Camera) {
+        const MOUSE_LOOK_RATE: f32 = 0.004;
+        const MOUSE_SCROLL_RATE: f32 = 5.0;
 
-        if self.t_pressed {
-            camera.translation_y += trans_incr / camera.ortho_scale;
+        if let Some(incr) = self.last_drag.take() {
+            if !self.ctrl_pressed {
+                let (yaw, pitch) = camera.look_angles();
+                let yaw = yaw + incr[0] as f32 * MOUSE_LOOK_RATE;
+                let pitch = (pitch - incr[1] as f32 * MOUSE_LOOK_RATE)
+                    .clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+                camera.set_look_angles(yaw, pitch);
+            } else {
+                Self::translate_by_drag(camera, incr);
+            }
         }
-        if self.g_pressed {
-            camera.translation_y -= trans_incr / camera.ortho_scale;
+
+        let move_incr = if self.shift_pressed { 12.0 } else { 2.0 } * self.speed;
+        let mut forward = 0.0;
+        let mut right = 0.0;
+        if self.up_pressed || self.z_pressed {
+            forward += move_incr;
         }
-        if self.f_pressed {
-            camera.translation_x -= trans_incr / camera.ortho_scale;
+        if self.down_pressed || self.x_pressed {
+            forward -= move_incr;
         }
-        if self.h_pressed {
-            camera.translation_x += trans_incr / camera.ortho_scale;
+        if self.right_pressed {
+            right += move_incr;
+        }
+        if self.left_pressed {
+            right -= move_incr;
         }
+        if let Some(scroll) = self.last_mouse_scroll.take() {
+            forward += scroll * MOUSE_SCROLL_RATE * move_incr;
+        }
+        if forward != 0.0 || right != 0.0 {
+            camera.fly(forward, right);
+        }
+    }
+
+    fn translate_by_drag(camera: &mut camera::Camera, incr: [f64; 2]) {
+        const MOUSE_TRANSLATION_RATE: f32 = 0.03125;
+        camera.translation_x += incr[0] as f32 * MOUSE_TRANSLATION_RATE / camera.ortho_scale;
+        camera.translation_y -= incr[1] as f32 * MOUSE_TRANSLATION_RATE / camera.ortho_scale;
     }
 
     pub fn process_device_events(&mut self, event: &DeviceEvent) {
@@ -179,6 +277,26 @@ impl CameraController {
                         self.right_pressed = is_pressed;
                         true
                     }
+                    KeyCode::KeyQ => {
+                        self.q_pressed = is_pressed;
+                        true
+                    }
+                    KeyCode::KeyE => {
+                        self.e_pressed = is_pressed;
+                        true
+                    }
+                    KeyCode::KeyL => {
+                        if is_pressed {
+                            self.level_horizon_requested = true;
+                        }
+                        true
+                    }
+                    KeyCode::KeyK => {
+                        if is_pressed {
+                            self.keyframe_requested = true;
+                        }
+                        true
+                    }
                     KeyCode::KeyZ => {
                         self.z_pressed = is_pressed;
                         true
@@ -0,0 +1,210 @@
+//! Camera keyframes recorded at chosen viewpoints, played back as a
+//! fly-around: Catmull-Rom splines through the eye and target positions,
+//! and slerp between scene rotations.
+
+use super::Camera;
+
+use cgmath::{EuclideanSpace, InnerSpace, Matrix3, Matrix4, Point3, Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+pub const CAMERA_PATH_FILE: &str = "camera_path.json";
+
+/// Camera view at one point of a path.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub eye: [f32; 3],
+    pub target: [f32; 3],
+    pub up: [f32; 3],
+    // scene rotation as a quaternion, scalar part first
+    pub rotation: [f32; 4],
+    pub translation: [f32; 2],
+}
+
+impl Keyframe {
+    pub fn from_camera(camera: &Camera) -> Self {
+        let m = camera.rotation();
+        let rotation = Quaternion::from(Matrix3::from_cols(
+            m.x.truncate(),
+            m.y.truncate(),
+            m.z.truncate(),
+        ));
+        Self {
+            eye: camera.eye.into(),
+            target: camera.target.into(),
+            up: camera.up.into(),
+            rotation: [rotation.s, rotation.v.x, rotation.v.y, rotation.v.z],
+            translation: [camera.translation_x, camera.translation_y],
+        }
+    }
+
+    fn quaternion(&self) -> Quaternion<f32> {
+        let [s, x, y, z] = self.rotation;
+        Quaternion::new(s, x, y, z)
+    }
+}
+
+// playback progress, and the rotation mode to restore when it ends
+struct Playback {
+    elapsed: f32,
+    relative_rotation: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,
+    // seconds to play the whole path
+    pub duration: f32,
+
+    #[serde(skip)]
+    playback: Option<Playback>,
+}
+
+impl Default for CameraPath {
+    fn default() -> Self {
+        Self {
+            keyframes: vec![],
+            duration: Self::DEFAULT_DURATION,
+            playback: None,
+        }
+    }
+}
+
+impl CameraPath {
+    pub const DEFAULT_DURATION: f32 = 10.0;
+
+    pub fn add_keyframe(&mut self, camera: &Camera) {
+        self.keyframes.push(Keyframe::from_camera(camera));
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+    }
+
+    /// Playback needs at least two keyframes to move between.
+    pub fn can_play(&self) -> bool {
+        self.keyframes.len() >= 2 && self.duration > 0.0
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// Fraction of the path played so far, if playing.
+    pub fn progress(&self) -> Option<f32> {
+        let playback = self.playback.as_ref()?;
+        Some((playback.elapsed / self.duration).min(1.0))
+    }
+
+    /// Start playback from the first keyframe. The scene rotation is
+    /// interpolated as a matrix, so relative rotation is used meanwhile.
+    pub fn play(&mut self, camera: &mut Camera) {
+        if !self.can_play() {
+            return;
+        }
+        self.playback = Some(Playback {
+            elapsed: 0.0,
+            relative_rotation: camera.relative_rotation,
+        });
+        camera.relative_rotation = true;
+    }
+
+    /// Stop playback, leaving the camera where it is.
+    pub fn stop(&mut self, camera: &mut Camera) {
+        let Some(playback) = self.playback.take() else {
+            return;
+        };
+        if !playback.relative_rotation {
+            camera.relative_rotation = false;
+            camera.on_relative_rotation_change();
+        }
+    }
+
+    /// Advance playback, if playing, and move the camera along the path.
+    pub fn advance(&mut self, camera: &mut Camera, elapsed_secs: f32) {
+        // the keyframes may have been cleared while playing
+        if !self.can_play() {
+            self.stop(camera);
+            return;
+        }
+        let Some(playback) = &mut self.playback else {
+            return;
+        };
+        playback.elapsed += elapsed_secs;
+        let t = (playback.elapsed / self.duration).min(1.0);
+        self.apply(camera, t);
+        if t >= 1.0 {
+            self.stop(camera);
+        }
+    }
+
+    // Set the camera to the point a fraction `t` along the path, with
+    // the keyframes evenly spaced in time.
+    fn apply(&self, camera: &mut Camera, t: f32) {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len() - 1;
+        let s = t * last as f32;
+        let i = (s.floor() as usize).min(last - 1);
+        let u = s - i as f32;
+
+        // neighbors for the spline tangents, repeating the end keyframes
+        let k0 = &keyframes[i.saturating_sub(1)];
+        let k1 = &keyframes[i];
+        let k2 = &keyframes[i + 1];
+        let k3 = &keyframes[(i + 2).min(last)];
+        let spline = |f: fn(&Keyframe) -> [f32; 3]| {
+            catmull_rom(f(k0).into(), f(k1).into(), f(k2).into(), f(k3).into(), u)
+        };
+
+        camera.eye = Point3::from_vec(spline(|k| k.eye));
+        camera.target = Point3::from_vec(spline(|k| k.target));
+        let up = Vector3::from(k1.up) * (1.0 - u) + Vector3::from(k2.up) * u;
+        if up.magnitude() > 1e-6 {
+            camera.up = up.normalize();
+        }
+
+        // take the shorter way around
+        let q1 = k1.quaternion();
+        let mut q2 = k2.quaternion();
+        if q1.dot(q2) < 0.0 {
+            q2 = -q2;
+        }
+        camera.user_rotation = Matrix4::from(q1.slerp(q2, u));
+
+        camera.translation_x = k1.translation[0] * (1.0 - u) + k2.translation[0] * u;
+        camera.translation_y = k1.translation[1] * (1.0 - u) + k2.translation[1] * u;
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        std::fs::write(CAMERA_PATH_FILE, text).map_err(|err| err.to_string())
+    }
+
+    /// Replace the keyframes and duration with those saved, stopping
+    /// any playback.
+    pub fn load(&mut self, camera: &mut Camera) -> Result<(), String> {
+        let text = std::fs::read_to_string(CAMERA_PATH_FILE).map_err(|err| err.to_string())?;
+        let loaded: CameraPath = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+        self.stop(camera);
+        self.keyframes = loaded.keyframes;
+        self.duration = loaded.duration;
+        Ok(())
+    }
+}
+
+// Uniform Catmull-Rom spline from p1 to p2, for t in [0, 1].
+fn catmull_rom(
+    p0: Vector3<f32>,
+    p1: Vector3<f32>,
+    p2: Vector3<f32>,
+    p3: Vector3<f32>,
+    t: f32,
+) -> Vector3<f32> {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
@@ -0,0 +1,76 @@
+//! Read numeric tables from CSV files, for plotting data.
+//!
+//! Fields are split on commas by hand; quoted fields are not supported,
+//! since plotted tables hold only numbers. Blank lines and lines starting
+//! with `#` are skipped, and a first row that is not numeric is taken as
+//! the column names.
+
+use std::error::Error;
+
+pub struct CsvTable {
+    pub column_names: Vec<String>,
+    // each row has one value per column
+    pub rows: Vec<Vec<f64>>,
+}
+
+impl CsvTable {
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = contents
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .peekable();
+
+        let (_, first_line) = *lines.peek().ok_or("The file contains no data.")?;
+        let first_fields: Vec<&str> = first_line.split(',').map(str::trim).collect();
+
+        let column_names: Vec<String> = if first_fields
+            .iter()
+            .all(|field| field.parse::<f64>().is_ok())
+        {
+            (1..=first_fields.len())
+                .map(|column| format!("Column {column}"))
+                .collect()
+        } else {
+            lines.next();
+            first_fields.iter().map(|name| name.to_string()).collect()
+        };
+        let column_count = column_names.len();
+
+        let mut rows = vec![];
+        for (line_number, line) in lines {
+            let row = line
+                .split(',')
+                .map(|field| {
+                    field.trim().parse::<f64>().map_err(|_| {
+                        format!("Line {line_number}: \"{}\" is not a number.", field.trim())
+                    })
+                })
+                .collect::<Result<Vec<f64>, String>>()?;
+
+            if row.len() != column_count {
+                return Err(format!(
+                    "Line {line_number}: expected {column_count} values but found {}.",
+                    row.len()
+                )
+                .into());
+            }
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err("The file contains no data rows.".into());
+        }
+
+        Ok(Self { column_names, rows })
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.column_names.len()
+    }
+}
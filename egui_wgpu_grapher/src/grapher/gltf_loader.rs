@@ -6,16 +6,26 @@ use std::{cell::RefCell, error::Error, path::Path};
 use cgmath::{Matrix4, SquareMatrix, Zero};
 use egui_wgpu::wgpu::{Device, Queue};
 use gltf::{
-    Document, Mesh, Node, Primitive, buffer::Data, image::Source, mesh::Mode, scene::Transform,
+    Document, Mesh, Node, Primitive,
+    animation::{Interpolation, Property, util::ReadOutputs},
+    buffer::Data,
+    image::{self as gltf_image, Format, Source},
+    mesh::Mode,
+    scene::Transform,
 };
+use image::ImageBuffer;
 
 use crate::grapher::{
     matrix::Matrix,
     pipeline::texture::{Image, TextureData},
-    scene::{GpuVertex, textured::TexturedMeshData},
+    scene::{
+        GpuVertex,
+        textured::{TexturedMeshData, compute_tangents},
+    },
 };
 
-const DEFAULT_COLOR: [f32; 3] = [1.0, 0.0, 0.0];
+// Vertex color multiplies the texture color, so white leaves it unchanged.
+const DEFAULT_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
 
 const DEV_LOGGING: bool = false;
 
@@ -23,12 +33,102 @@ const DEV_LOGGING: bool = false;
 // Structure for glTF data.
 
 pub struct RenderMesh {
+    pub name: String,
     pub data: TexturedMeshData,
     pub matrix: Matrix,
+    pub morph: Option<MorphTarget>,
+}
+
+/// The first morph target of a mesh, as offsets from its vertices, which
+/// are blended in by the target's weight. Later targets are ignored.
+pub struct MorphTarget {
+    // one per vertex of the mesh, zero for primitives without the target
+    pub position_offsets: Vec<[f32; 3]>,
+    pub normal_offsets: Vec<[f32; 3]>,
+    // weight given in the file, used while not animating
+    pub default_weight: f32,
+    pub animation: Option<WeightAnimation>,
+}
+
+/// Keyframes of a morph target's weight, from a glTF animation channel.
+pub struct WeightAnimation {
+    // increasing keyframe times in seconds
+    times: Vec<f32>,
+    weights: Vec<f32>,
+    // hold each value until the next keyframe, instead of interpolating
+    step: bool,
+}
+
+impl WeightAnimation {
+    pub fn duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or_default()
+    }
+
+    /// Weight at time `t`, looping over the animation's duration.
+    /// Cubic spline keyframes are interpolated linearly.
+    pub fn weight_at(&self, t: f32) -> f32 {
+        let duration = self.duration();
+        let t = if duration > 0.0 { t % duration } else { 0.0 };
+        let next = self.times.partition_point(|&time| time <= t);
+        if next == 0 {
+            return self.weights[0];
+        }
+        if next == self.times.len() || self.step {
+            return self.weights[next - 1];
+        }
+        let (t_0, t_1) = (self.times[next - 1], self.times[next]);
+        let s = (t - t_0) / (t_1 - t_0);
+        self.weights[next - 1] * (1.0 - s) + self.weights[next] * s
+    }
+}
+
+/// A node of the scene hierarchy, with its local transform and the index
+/// in `RenderScene::meshes` of its mesh, if it has one.
+pub struct SceneNode {
+    pub index: usize,
+    pub name: String,
+    pub translation: [f32; 3],
+    // quaternion, scalar part last as in glTF
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    pub mesh: Option<usize>,
+    pub children: Vec<SceneNode>,
+    // hiding a node hides its whole subtree
+    pub visible: bool,
+}
+
+impl SceneNode {
+    /// A root node holding a single mesh, as for formats without a hierarchy.
+    pub fn mesh_node(index: usize, name: &str, mesh: usize) -> Self {
+        Self {
+            index,
+            name: name.to_string(),
+            translation: [0.0; 3],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0; 3],
+            mesh: Some(mesh),
+            children: vec![],
+            visible: true,
+        }
+    }
+
+    /// Call `f` with the mesh index and visibility of each mesh in the
+    /// subtree, where a mesh is visible if it and all its ancestors are.
+    pub fn for_each_mesh(&self, parent_visible: bool, f: &mut impl FnMut(usize, bool)) {
+        let visible = parent_visible && self.visible;
+        if let Some(mesh) = self.mesh {
+            f(mesh, visible);
+        }
+        for child in &self.children {
+            child.for_each_mesh(visible, f);
+        }
+    }
 }
 
 pub struct RenderScene {
     pub meshes: Vec<RenderMesh>,
+    // root nodes of the scene hierarchy
+    pub nodes: Vec<SceneNode>,
 
     pub min_x: f32,
     pub max_x: f32,
@@ -42,6 +142,7 @@ impl Default for RenderScene {
     fn default() -> Self {
         Self {
             meshes: vec![],
+            nodes: vec![],
             min_x: f32::MAX,
             max_x: f32::MIN,
             min_y: f32::MAX,
@@ -53,7 +154,26 @@ impl Default for RenderScene {
 }
 
 impl RenderScene {
-    fn normalize_position(&mut self) {
+    /// Add a mesh and grow the bounding box to contain it, returning
+    /// its index.
+    pub(crate) fn add_mesh(&mut self, mesh: RenderMesh) -> usize {
+        let matrix: cgmath::Matrix4<_> = mesh.matrix.into();
+        for vertex in &mesh.data.vertices {
+            let p = &vertex.position;
+            let world_p = matrix * cgmath::Vector4::from([p[0], p[1], p[2], 1.0_f32]);
+
+            self.min_x = self.min_x.min(world_p.x);
+            self.max_x = self.max_x.max(world_p.x);
+            self.min_y = self.min_y.min(world_p.y);
+            self.max_y = self.max_y.max(world_p.y);
+            self.min_z = self.min_z.min(world_p.z);
+            self.max_z = self.max_z.max(world_p.z);
+        }
+        self.meshes.push(mesh);
+        self.meshes.len() - 1
+    }
+
+    pub(crate) fn normalize_position(&mut self) {
         let mut scale_inv = (self.max_x - self.min_x)
             .max(self.max_y - self.min_y)
             .max(self.max_z - self.min_z);
@@ -80,6 +200,22 @@ impl RenderScene {
         self.meshes.iter_mut().for_each(|mesh| {
             mesh.matrix.mat4_left_mul(&normalizer);
         });
+
+        // Bounding box is now centered at the origin.
+        let half_x = scale[0][0] * (self.max_x - self.min_x) / 2.0;
+        let half_y = scale[1][1] * (self.max_y - self.min_y) / 2.0;
+        let half_z = scale[2][2] * (self.max_z - self.min_z) / 2.0;
+        (self.min_x, self.max_x) = (-half_x, half_x);
+        (self.min_y, self.max_y) = (-half_y, half_y);
+        (self.min_z, self.max_z) = (-half_z, half_z);
+    }
+
+    /// Bounding box corners, in world coordinates after normalization.
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        (
+            [self.min_x, self.min_y, self.min_z],
+            [self.max_x, self.max_y, self.max_z],
+        )
     }
 }
 
@@ -130,6 +266,9 @@ pub struct GltfLoader<'a> {
     path: String,
     document: Document,
     buffer_data: Vec<Data>,
+    // Images decoded by the importer, used for textures
+    // stored in buffer views, as is typical in `.glb` files.
+    image_data: Vec<gltf_image::Data>,
 
     // Wgpu handles for GPU updates.
     device: &'a Device,
@@ -145,11 +284,13 @@ impl<'a> GltfLoader<'a> {
         queue: &'a Queue,
         gltf_path: &str,
     ) -> Result<GltfLoader<'a>, Box<dyn Error>> {
-        let (document, buffer_data, _images) = gltf::import(gltf_path)?;
+        // Handles both `.gltf` and binary `.glb` files.
+        let (document, buffer_data, image_data) = gltf::import(gltf_path)?;
         Ok(GltfLoader {
             path: gltf_path.into(),
             document,
             buffer_data,
+            image_data,
             device,
             queue,
             render_scene: Default::default(),
@@ -163,9 +304,8 @@ impl GltfLoader<'_> {
         for scene in self.document.scenes() {
             // Traverse root nodes of scene.
             for node in scene.nodes() {
-                let matrix = root_matrix * node_matrix(&node);
-                self.add_node(&node, 1, &matrix)?;
-                self.traverse_children(&node, 2, &matrix)?;
+                let scene_node = self.traverse_node(&node, 1, &root_matrix)?;
+                self.render_scene.borrow_mut().nodes.push(scene_node);
             }
         }
         if DEV_LOGGING {
@@ -178,18 +318,35 @@ impl GltfLoader<'_> {
         Ok(self.render_scene.into_inner())
     }
 
-    fn traverse_children(
+    /// Add the meshes of a node and its descendants, and return the
+    /// node with its subtree.
+    fn traverse_node(
         &self,
         node: &Node,
         depth: usize,
         parent_matrix: &Matrix,
-    ) -> Result<(), Box<dyn Error>> {
-        for child in node.children() {
-            let matrix = *parent_matrix * node_matrix(&child);
-            self.add_node(&child, depth, &matrix)?;
-            self.traverse_children(&child, depth + 1, &matrix)?;
-        }
-        Ok(())
+    ) -> Result<SceneNode, Box<dyn Error>> {
+        let matrix = *parent_matrix * node_matrix(node);
+        let mesh = self.add_node(node, depth, &matrix)?;
+        let children = node
+            .children()
+            .map(|child| self.traverse_node(&child, depth + 1, &matrix))
+            .collect::<Result<_, _>>()?;
+
+        let (translation, rotation, scale) = node.transform().decomposed();
+        Ok(SceneNode {
+            index: node.index(),
+            name: match node.name() {
+                Some(name) => name.to_string(),
+                None => format!("Node {}", node.index()),
+            },
+            translation,
+            rotation,
+            scale,
+            mesh,
+            children,
+            visible: true,
+        })
     }
 
     fn indent(depth: usize) {
@@ -197,15 +354,20 @@ impl GltfLoader<'_> {
         print!("{}", " ".repeat(depth * INDENT));
     }
 
-    fn add_node(&self, node: &Node, depth: usize, matrix: &Matrix) -> Result<(), Box<dyn Error>> {
+    /// Add the node's mesh, if it has one, returning its index.
+    fn add_node(
+        &self,
+        node: &Node,
+        depth: usize,
+        matrix: &Matrix,
+    ) -> Result<Option<usize>, Box<dyn Error>> {
         if DEV_LOGGING {
             // Some logging.
             Self::log_node(node, depth);
         }
-        if let Some(mesh) = node.mesh() {
-            self.add_mesh(&mesh, depth + 1, matrix)?;
-        }
-        Ok(())
+        node.mesh()
+            .map(|mesh| self.add_mesh(node, &mesh, depth + 1, matrix))
+            .transpose()
     }
 
     fn log_node(node: &Node, depth: usize) {
@@ -245,7 +407,13 @@ impl GltfLoader<'_> {
         println!();
     }
 
-    fn add_mesh(&self, mesh: &Mesh, depth: usize, matrix: &Matrix) -> Result<(), Box<dyn Error>> {
+    fn add_mesh(
+        &self,
+        node: &Node,
+        mesh: &Mesh,
+        depth: usize,
+        matrix: &Matrix,
+    ) -> Result<usize, Box<dyn Error>> {
         if DEV_LOGGING {
             Self::indent(depth);
             println!("Node has mesh.");
@@ -254,12 +422,16 @@ impl GltfLoader<'_> {
         let mut vertices = vec![];
         let mut indices = vec![];
         let mut texture = None;
+        let mut position_offsets = vec![];
+        let mut normal_offsets = vec![];
+        let mut has_morph_target = false;
 
         for primitive in mesh.primitives() {
             if primitive.mode() != Mode::Triangles {
                 continue;
             }
             let reader = primitive.reader(|buff_idx| Some(&self.buffer_data[buff_idx.index()]));
+            let first_vertex = vertices.len();
 
             // Add position and normal coordinates.
             let iter = reader
@@ -279,6 +451,25 @@ impl GltfLoader<'_> {
                 });
             }
 
+            // Offsets of the first morph target, if the primitive has one.
+            position_offsets.resize(vertices.len(), [0.0; 3]);
+            normal_offsets.resize(vertices.len(), [0.0; 3]);
+            if let Some((positions, normals, _)) = reader.read_morph_targets().next() {
+                has_morph_target = true;
+                if let Some(positions) = positions {
+                    position_offsets[first_vertex..]
+                        .iter_mut()
+                        .zip(positions)
+                        .for_each(|(offset, value)| *offset = value);
+                }
+                if let Some(normals) = normals {
+                    normal_offsets[first_vertex..]
+                        .iter_mut()
+                        .zip(normals)
+                        .for_each(|(offset, value)| *offset = value);
+                }
+            }
+
             // Add indices.
             indices = reader
                 .read_indices()
@@ -290,22 +481,28 @@ impl GltfLoader<'_> {
             let model_path = Path::new(&self.path)
                 .parent()
                 .expect("Failed to get directory of glTF file.");
-            texture = read_texture(self.device, self.queue, &primitive, model_path)
-                .unwrap_or_else(|err| {
-                    println!("{err}");
-                    let base_color = primitive
-                        .material()
-                        .pbr_metallic_roughness()
-                        .base_color_factor();
-                    let base_color = [
-                        (255.0 * base_color[0]) as u8,
-                        (255.0 * base_color[1]) as u8,
-                        (255.0 * base_color[2]) as u8,
-                        255,
-                    ];
-                    TextureData::solid_color_texture(&base_color, self.device, self.queue)
-                })
-                .into();
+            texture = read_texture(
+                self.device,
+                self.queue,
+                &primitive,
+                model_path,
+                &self.image_data,
+            )
+            .unwrap_or_else(|err| {
+                log::warn!("{err}; using material base color.");
+                let base_color = primitive
+                    .material()
+                    .pbr_metallic_roughness()
+                    .base_color_factor();
+                let base_color = [
+                    (255.0 * base_color[0]) as u8,
+                    (255.0 * base_color[1]) as u8,
+                    (255.0 * base_color[2]) as u8,
+                    255,
+                ];
+                TextureData::solid_color_texture(&base_color, self.device, self.queue)
+            })
+            .into();
 
             // First set of texture coords is for vertex color.
             if let Some(iter) = reader.read_tex_coords(0) {
@@ -314,64 +511,103 @@ impl GltfLoader<'_> {
                     .zip(iter.into_f32())
                     .for_each(|(vertex, tex_coords)| vertex.tex_coords = tex_coords);
             }
+
+            // Use the file's tangents if it has them.
+            let primitive_vertices = &mut vertices[first_vertex..];
+            if let Some(iter) = reader.read_tangents() {
+                primitive_vertices
+                    .iter_mut()
+                    .zip(iter)
+                    .for_each(|(vertex, tangent)| vertex.tangent = tangent);
+            } else {
+                compute_tangents(primitive_vertices, &indices);
+            }
         }
 
         if DEV_LOGGING {
             println!();
         }
 
-        let mut render_scene = self.render_scene.borrow_mut();
-        render_scene.meshes.push(RenderMesh {
+        let name = match mesh.name() {
+            Some(name) => name.to_string(),
+            None => format!("Mesh {}", mesh.index()),
+        };
+        let morph = has_morph_target.then(|| MorphTarget {
+            position_offsets,
+            normal_offsets,
+            // node weights override the mesh's
+            default_weight: node
+                .weights()
+                .or(mesh.weights())
+                .and_then(|weights| weights.first().copied())
+                .unwrap_or_default(),
+            animation: self.read_weight_animation(node, mesh),
+        });
+        let index = self.render_scene.borrow_mut().add_mesh(RenderMesh {
+            name,
             data: TexturedMeshData {
                 vertices,
                 indices,
                 texture: texture.expect("Texture should have been assigned."),
             },
             matrix: *matrix,
+            morph,
         });
 
-        // For bounding box computation.
-        let mut min_x: f32 = f32::MAX;
-        let mut max_x: f32 = f32::MIN;
-        let mut min_y: f32 = f32::MAX;
-        let mut max_y: f32 = f32::MIN;
-        let mut min_z: f32 = f32::MAX;
-        let mut max_z: f32 = f32::MIN;
-
-        let new_verts = &render_scene
-            .meshes
-            .last()
-            .expect("A new mesh should have been added.")
-            .data
-            .vertices;
-        let matrix: cgmath::Matrix4<_> = render_scene
-            .meshes
-            .last()
-            .expect("A new mesh should have been added.")
-            .matrix
-            .into();
-        new_verts.iter().for_each(|vert| {
-            let p = &vert.position;
-            let vert = cgmath::Vector4::from([p[0], p[1], p[2], 1.0_f32]);
-            let world_p = matrix * vert;
-
-            min_x = min_x.min(world_p.x);
-            max_x = max_x.max(world_p.x);
-            min_y = min_y.min(world_p.y);
-            max_y = max_y.max(world_p.y);
-            min_z = min_z.min(world_p.z);
-            max_z = max_z.max(world_p.z);
-        });
+        Ok(index)
+    }
+}
 
-        // Done this way to avoid fighitng the borrow checker.
-        render_scene.min_x = render_scene.min_x.min(min_x);
-        render_scene.max_x = render_scene.max_x.max(max_x);
-        render_scene.min_y = render_scene.min_y.min(min_y);
-        render_scene.max_y = render_scene.max_y.max(max_y);
-        render_scene.min_z = render_scene.min_z.min(min_z);
-        render_scene.max_z = render_scene.max_z.max(max_z);
+impl GltfLoader<'_> {
+    /// Keyframes of the first morph target's weight, from the first
+    /// animation channel that targets the node's morph weights.
+    fn read_weight_animation(&self, node: &Node, mesh: &Mesh) -> Option<WeightAnimation> {
+        // each keyframe has a weight for every target
+        let target_count = mesh
+            .primitives()
+            .map(|primitive| primitive.morph_targets().count())
+            .max()?
+            .max(1);
+
+        let channel = self
+            .document
+            .animations()
+            .flat_map(|animation| animation.channels().collect::<Vec<_>>())
+            .find(|channel| {
+                channel.target().node().index() == node.index()
+                    && channel.target().property() == Property::MorphTargetWeights
+            })?;
+        let reader = channel.reader(|buffer| Some(&self.buffer_data[buffer.index()]));
+        let times: Vec<f32> = reader.read_inputs()?.collect();
+        let Some(ReadOutputs::MorphTargetWeights(outputs)) = reader.read_outputs() else {
+            return None;
+        };
+        let outputs: Vec<f32> = outputs.into_f32().collect();
+
+        // Cubic spline keyframes hold an in-tangent, value, and
+        // out-tangent for each target; only the values are used.
+        let interpolation = channel.sampler().interpolation();
+        let (stride, value_offset) = match interpolation {
+            Interpolation::CubicSpline => (3 * target_count, target_count),
+            _ => (target_count, 0),
+        };
+        let weights: Vec<f32> = outputs
+            .chunks_exact(stride)
+            .map(|keyframe| keyframe[value_offset])
+            .collect();
+        if times.is_empty() || weights.len() != times.len() {
+            log::warn!(
+                "Ignoring malformed morph weight animation on node {}.",
+                node.index()
+            );
+            return None;
+        }
 
-        Ok(())
+        Some(WeightAnimation {
+            times,
+            weights,
+            step: interpolation == Interpolation::Step,
+        })
     }
 }
 
@@ -380,10 +616,12 @@ pub fn read_texture(
     queue: &Queue,
     primitive: &Primitive<'_>,
     model_dir: &Path,
+    image_data: &[gltf_image::Data],
 ) -> Result<TextureData, Box<dyn Error>> {
     let pbr_metallic = primitive.material().pbr_metallic_roughness();
     if let Some(info) = pbr_metallic.base_color_texture() {
-        let image_source = info.texture().source().source();
+        let source = info.texture().source();
+        let image_source = source.source();
         match image_source {
             Source::Uri { uri, .. } => {
                 let img_path = model_dir.join(Path::new(uri));
@@ -398,9 +636,150 @@ pub fn read_texture(
                 return Ok(texture);
             }
             Source::View { .. } => {
-                println!("Warning: Buffer view texture will not be loaded.");
+                let data = image_data
+                    .get(source.index())
+                    .ok_or("Buffer view texture was not decoded.")?;
+                let image = image_from_gltf_data(data)?;
+                let texture = TextureData::from_image(&image, device, queue);
+                return Ok(texture);
             }
         }
     }
     Err("Mesh primitive contained no base metallic texture.".into())
 }
+
+/// Convert an image decoded by the glTF importer to RGBA bytes.
+fn image_from_gltf_data(data: &gltf_image::Data) -> Result<Image, Box<dyn Error>> {
+    let channels = match data.format {
+        Format::R8 => 1,
+        Format::R8G8 => 2,
+        Format::R8G8B8 => 3,
+        Format::R8G8B8A8 => 4,
+        format => return Err(format!("Unsupported texture format: {format:?}").into()),
+    };
+
+    let mut rgba = Vec::with_capacity(4 * (data.width * data.height) as usize);
+    for pixel in data.pixels.chunks_exact(channels) {
+        match channels {
+            // grayscale, possibly with alpha
+            1 => rgba.extend_from_slice(&[pixel[0], pixel[0], pixel[0], 255]),
+            2 => rgba.extend_from_slice(&[pixel[0], pixel[0], pixel[0], pixel[1]]),
+            3 => rgba.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]),
+            _ => rgba.extend_from_slice(pixel),
+        }
+    }
+
+    let image = ImageBuffer::from_raw(data.width, data.height, rgba)
+        .ok_or("Texture data did not match its dimensions.")?;
+    Ok(Image {
+        image,
+        dimensions: (data.width, data.height),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grapher::pipeline::test_device;
+    use std::io::Cursor;
+
+    // Two meshes sharing one triangle: the first textured by a 3x5 PNG
+    // stored in a buffer view, as `.glb` exporters embed images, and the
+    // second with no material.
+    fn glb_with_embedded_texture() -> Vec<u8> {
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(3, 5, image::Rgba([255, 0, 0, 255]))
+            .write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)
+            .unwrap();
+
+        let floats = |values: &[f32]| -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect()
+        };
+        let mut bin = floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        bin.extend(floats(&[0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0]));
+        bin.extend(floats(&[0.0, 0.0, 1.0, 0.0, 0.0, 1.0]));
+        bin.extend([0_u16, 1, 2].iter().flat_map(|index| index.to_le_bytes()));
+        bin.extend([0, 0]);
+        let png_offset = bin.len();
+        bin.extend(&png);
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let json = format!(
+            r#"{{
+                "asset": {{"version": "2.0"}},
+                "scene": 0,
+                "scenes": [{{"nodes": [0, 1]}}],
+                "nodes": [{{"mesh": 0}}, {{"mesh": 1, "translation": [2, 0, 0]}}],
+                "meshes": [
+                    {{"primitives": [{{"attributes": {{"POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2}},
+                        "indices": 3, "material": 0}}]}},
+                    {{"primitives": [{{"attributes": {{"POSITION": 0, "NORMAL": 1}}, "indices": 3}}]}}
+                ],
+                "materials": [{{"pbrMetallicRoughness": {{"baseColorTexture": {{"index": 0}}}}}}],
+                "textures": [{{"source": 0}}],
+                "images": [{{"bufferView": 4, "mimeType": "image/png"}}],
+                "accessors": [
+                    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                        "min": [0, 0, 0], "max": [1, 1, 0]}},
+                    {{"bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3"}},
+                    {{"bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC2"}},
+                    {{"bufferView": 3, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+                ],
+                "bufferViews": [
+                    {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+                    {{"buffer": 0, "byteOffset": 36, "byteLength": 36}},
+                    {{"buffer": 0, "byteOffset": 72, "byteLength": 24}},
+                    {{"buffer": 0, "byteOffset": 96, "byteLength": 6}},
+                    {{"buffer": 0, "byteOffset": {png_offset}, "byteLength": {}}}
+                ],
+                "buffers": [{{"byteLength": {}}}]
+            }}"#,
+            png.len(),
+            bin.len(),
+        );
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+
+        let total = 12 + 8 + json.len() + 8 + bin.len();
+        let mut glb = Vec::with_capacity(total);
+        glb.extend(b"glTF");
+        glb.extend(2_u32.to_le_bytes());
+        glb.extend((total as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(b"JSON");
+        glb.extend(json);
+        glb.extend((bin.len() as u32).to_le_bytes());
+        glb.extend(b"BIN\0");
+        glb.extend(bin);
+        glb
+    }
+
+    #[test]
+    fn glb_loads_meshes_and_embedded_texture() {
+        let Some((device, queue)) = test_device() else {
+            eprintln!("No graphics adapter; skipping the .glb loader test.");
+            return;
+        };
+        let path = std::env::temp_dir().join(format!("grapher_test_{}.glb", std::process::id()));
+        std::fs::write(&path, glb_with_embedded_texture()).unwrap();
+
+        let loaded = GltfLoader::create(&device, &queue, path.to_str().unwrap())
+            .and_then(|loader| loader.traverse());
+        std::fs::remove_file(&path).unwrap();
+        let scene = loaded.unwrap();
+
+        assert_eq!(scene.meshes.len(), 2);
+        assert_eq!(scene.nodes.len(), 2);
+        // the untextured mesh gets a 2x2 texture of its base color
+        let texture_sizes: Vec<_> = scene
+            .meshes
+            .iter()
+            .map(|mesh| mesh.data.texture.texture.size())
+            .map(|size| (size.width, size.height))
+            .collect();
+        assert_eq!(texture_sizes, [(3, 5), (2, 2)]);
+    }
+}
@@ -0,0 +1,145 @@
+//! Colormaps for coloring scalar fields, like the heat equation solution.
+//!
+//! The maps are defined in sRGB, so they interpolate like their usual
+//! versions, but colors are returned in linear RGB, which is what vertex
+//! colors are in.
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Colormap {
+    // red at the low end to yellow at the high end
+    #[default]
+    Heat,
+    Grayscale,
+    Viridis,
+    CoolWarm,
+}
+
+// Control points sampled evenly from matplotlib's viridis.
+const VIRIDIS: [[f32; 3]; 5] = [
+    [0.267, 0.005, 0.329],
+    [0.230, 0.322, 0.546],
+    [0.128, 0.567, 0.551],
+    [0.369, 0.789, 0.383],
+    [0.993, 0.906, 0.144],
+];
+
+const COOL_WARM: [[f32; 3]; 3] = [
+    [0.230, 0.299, 0.754],
+    [0.865, 0.865, 0.865],
+    [0.706, 0.016, 0.150],
+];
+
+impl Colormap {
+    pub const ALL: [Colormap; 4] = [
+        Colormap::Heat,
+        Colormap::Grayscale,
+        Colormap::Viridis,
+        Colormap::CoolWarm,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colormap::Heat => "heat",
+            Colormap::Grayscale => "grayscale",
+            Colormap::Viridis => "viridis",
+            Colormap::CoolWarm => "cool-warm",
+        }
+    }
+
+    /// Linear color for `t` in \[0, 1\]; values outside are clamped.
+    pub fn color(&self, t: f32) -> [f32; 3] {
+        let t = t.clamp(0.0, 1.0);
+        let srgb = match self {
+            Colormap::Heat => [1.0, t, 0.0],
+            Colormap::Grayscale => [t, t, t],
+            Colormap::Viridis => interpolate(&VIRIDIS, t),
+            Colormap::CoolWarm => interpolate(&COOL_WARM, t),
+        };
+        srgb.map(srgb_to_linear)
+    }
+
+    /// Color for `value` placed in the range \[min, max\].
+    pub fn color_in_range(&self, value: f32, min: f32, max: f32) -> [f32; 3] {
+        let t = if max > min {
+            (value - min) / (max - min)
+        } else {
+            0.5
+        };
+        self.color(t)
+    }
+}
+
+/// Linear color of full saturation for a hue in turns, with red at 0
+/// and 1, as for coloring by phase.
+pub fn hue_color(hue: f32) -> [f32; 3] {
+    let h = hue.rem_euclid(1.0) * 6.0;
+    let x = 1.0 - (h.rem_euclid(2.0) - 1.0).abs();
+    let srgb = match h as u32 {
+        0 => [1.0, x, 0.0],
+        1 => [x, 1.0, 0.0],
+        2 => [0.0, 1.0, x],
+        3 => [0.0, x, 1.0],
+        4 => [x, 0.0, 1.0],
+        _ => [1.0, 0.0, x],
+    };
+    srgb.map(srgb_to_linear)
+}
+
+/// Decode an sRGB channel value in \[0, 1\] to linear.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode a linear channel value in \[0, 1\] as sRGB.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Piecewise-linear interpolation between evenly spaced control points.
+fn interpolate(points: &[[f32; 3]], t: f32) -> [f32; 3] {
+    let scaled = t * (points.len() - 1) as f32;
+    let i = (scaled as usize).min(points.len() - 2);
+    let s = scaled - i as f32;
+    std::array::from_fn(|c| points[i][c] + s * (points[i + 1][c] - points[i][c]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    #[test]
+    fn mid_gray_decodes_to_linear() {
+        assert!((srgb_to_linear(0.5) - 0.214).abs() < 1e-3);
+        // sRGB 128 is about 21.6% linear, and encodes back to 128
+        let linear = srgb_to_linear(128.0 / 255.0);
+        assert!((linear - 0.2159).abs() < 1e-3);
+        assert_eq!((255.0 * linear_to_srgb(linear)).round() as u8, 128);
+    }
+
+    #[test]
+    fn grayscale_is_linear() {
+        let [r, g, b] = Colormap::Grayscale.color(0.5);
+        let expected = srgb_to_linear(0.5);
+        assert!([r, g, b].iter().all(|c| (c - expected).abs() < EPSILON));
+    }
+
+    #[test]
+    fn endpoints_are_unchanged() {
+        for value in [0.0, 1.0] {
+            assert!((srgb_to_linear(value) - value).abs() < EPSILON);
+            assert!((linear_to_srgb(value) - value).abs() < EPSILON);
+            let gray = Colormap::Grayscale.color(value);
+            assert!(gray.iter().all(|c| (c - value).abs() < EPSILON));
+        }
+    }
+}
@@ -0,0 +1,421 @@
+//! Complex numbers and a small parser for expressions in a complex
+//! variable `z`, for domain coloring.
+//!
+//! Expressions use `+ - * / ^`, parentheses, the constants `i`, `pi`
+//! and `e`, and the functions below in `Function::from_name`.
+
+use std::{
+    f64::consts::{E, PI},
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const I: Complex = Complex { re: 0.0, im: 1.0 };
+
+    pub const fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub const fn real(re: f64) -> Self {
+        Self { re, im: 0.0 }
+    }
+
+    pub fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// Argument in (-pi, pi].
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.re.is_finite() && self.im.is_finite()
+    }
+
+    pub fn exp(self) -> Self {
+        let r = self.re.exp();
+        Self::new(r * self.im.cos(), r * self.im.sin())
+    }
+
+    /// Principal branch of the logarithm.
+    pub fn ln(self) -> Self {
+        Self::new(self.abs().ln(), self.arg())
+    }
+
+    /// Principal square root.
+    pub fn sqrt(self) -> Self {
+        let r = self.abs();
+        let re = ((r + self.re) / 2.0).sqrt();
+        let im = ((r - self.re) / 2.0).sqrt();
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    pub fn sin(self) -> Self {
+        Self::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+
+    pub fn cos(self) -> Self {
+        Self::new(
+            self.re.cos() * self.im.cosh(),
+            -self.re.sin() * self.im.sinh(),
+        )
+    }
+
+    pub fn sinh(self) -> Self {
+        Self::new(
+            self.re.sinh() * self.im.cos(),
+            self.re.cosh() * self.im.sin(),
+        )
+    }
+
+    pub fn cosh(self) -> Self {
+        Self::new(
+            self.re.cosh() * self.im.cos(),
+            self.re.sinh() * self.im.sin(),
+        )
+    }
+
+    /// Integer powers by repeated squaring, which stay exact at zero.
+    pub fn powi(self, n: i32) -> Self {
+        let mut base = if n < 0 { Self::real(1.0) / self } else { self };
+        let mut n = n.unsigned_abs();
+        let mut result = Self::real(1.0);
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            n >>= 1;
+        }
+        result
+    }
+
+    /// Principal value of `self` to the power `w`.
+    pub fn pow(self, w: Self) -> Self {
+        // small integer exponents are common, and exp(w ln z) fails at zero
+        if w.im == 0.0 && w.re.fract() == 0.0 && w.re.abs() <= 64.0 {
+            return self.powi(w.re as i32);
+        }
+        if self == Self::default() {
+            return if w.re > 0.0 {
+                Self::default()
+            } else {
+                Self::new(f64::NAN, f64::NAN)
+            };
+        }
+        (w * self.ln()).exp()
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let denom = rhs.re * rhs.re + rhs.im * rhs.im;
+        Self::new(
+            (self.re * rhs.re + self.im * rhs.im) / denom,
+            (self.im * rhs.re - self.re * rhs.im) / denom,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
+
+// ------------------------------
+// Expressions in the variable z.
+
+#[derive(Debug, Clone, Copy)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Ln,
+    Sqrt,
+    Conj,
+    Abs,
+    Arg,
+    Re,
+    Im,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "sinh" => Function::Sinh,
+            "cosh" => Function::Cosh,
+            "tanh" => Function::Tanh,
+            "exp" => Function::Exp,
+            "ln" | "log" => Function::Ln,
+            "sqrt" => Function::Sqrt,
+            "conj" => Function::Conj,
+            "abs" => Function::Abs,
+            "arg" => Function::Arg,
+            "re" => Function::Re,
+            "im" => Function::Im,
+            _ => return None,
+        })
+    }
+
+    fn apply(self, z: Complex) -> Complex {
+        match self {
+            Function::Sin => z.sin(),
+            Function::Cos => z.cos(),
+            Function::Tan => z.sin() / z.cos(),
+            Function::Sinh => z.sinh(),
+            Function::Cosh => z.cosh(),
+            Function::Tanh => z.sinh() / z.cosh(),
+            Function::Exp => z.exp(),
+            Function::Ln => z.ln(),
+            Function::Sqrt => z.sqrt(),
+            Function::Conj => z.conj(),
+            Function::Abs => Complex::real(z.abs()),
+            Function::Arg => Complex::real(z.arg()),
+            Function::Re => Complex::real(z.re),
+            Function::Im => Complex::real(z.im),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Constant(Complex),
+    Z,
+    Neg(Box<Node>),
+    Add(Box<Node>, Box<Node>),
+    Sub(Box<Node>, Box<Node>),
+    Mul(Box<Node>, Box<Node>),
+    Div(Box<Node>, Box<Node>),
+    Pow(Box<Node>, Box<Node>),
+    Call(Function, Box<Node>),
+}
+
+impl Node {
+    fn eval(&self, z: Complex) -> Complex {
+        match self {
+            Node::Constant(c) => *c,
+            Node::Z => z,
+            Node::Neg(a) => -a.eval(z),
+            Node::Add(a, b) => a.eval(z) + b.eval(z),
+            Node::Sub(a, b) => a.eval(z) - b.eval(z),
+            Node::Mul(a, b) => a.eval(z) * b.eval(z),
+            Node::Div(a, b) => a.eval(z) / b.eval(z),
+            Node::Pow(a, b) => a.eval(z).pow(b.eval(z)),
+            Node::Call(f, a) => f.apply(a.eval(z)),
+        }
+    }
+}
+
+/// A parsed expression in the complex variable `z`.
+#[derive(Debug, Clone)]
+pub struct ComplexExpr {
+    root: Node,
+}
+
+impl ComplexExpr {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+        };
+        let root = parser.expr()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.peek() {
+            return Err(format!("unexpected '{c}' at position {}", parser.pos + 1));
+        }
+        Ok(Self { root })
+    }
+
+    pub fn eval(&self, z: Complex) -> Complex {
+        self.root.eval(z)
+    }
+}
+
+// Recursive descent parser; `^` binds tighter than unary minus and
+// is right associative, so `-z^2^3` is `-(z^(2^3))`.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    // next non-whitespace character, consumed if it's `c`
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Result<Node, String> {
+        let mut node = self.term()?;
+        loop {
+            if self.eat('+') {
+                node = Node::Add(Box::new(node), Box::new(self.term()?));
+            } else if self.eat('-') {
+                node = Node::Sub(Box::new(node), Box::new(self.term()?));
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        loop {
+            if self.eat('*') {
+                node = Node::Mul(Box::new(node), Box::new(self.unary()?));
+            } else if self.eat('/') {
+                node = Node::Div(Box::new(node), Box::new(self.unary()?));
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat('-') {
+            Ok(Node::Neg(Box::new(self.unary()?)))
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            Ok(Node::Pow(Box::new(base), Box::new(self.unary()?)))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let node = self.expr()?;
+                if !self.eat(')') {
+                    return Err(format!("missing ')' for '(' at position {}", start + 1));
+                }
+                Ok(node)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                // exponent, as in 1e-3
+                if self.peek().is_some_and(|c| c == 'e' || c == 'E')
+                    && self.chars.get(self.pos + 1).is_some_and(|c| {
+                        c.is_ascii_digit()
+                            || (matches!(c, '+' | '-')
+                                && self
+                                    .chars
+                                    .get(self.pos + 2)
+                                    .is_some_and(char::is_ascii_digit))
+                    })
+                {
+                    self.pos += 2;
+                    while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        self.pos += 1;
+                    }
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                text.parse::<f64>()
+                    .map(|value| Node::Constant(Complex::real(value)))
+                    .map_err(|_| format!("invalid number '{text}'"))
+            }
+            Some(c) if c.is_alphabetic() => {
+                while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                match name.as_str() {
+                    "z" => Ok(Node::Z),
+                    "i" => Ok(Node::Constant(Complex::I)),
+                    "pi" => Ok(Node::Constant(Complex::real(PI))),
+                    "e" => Ok(Node::Constant(Complex::real(E))),
+                    _ => {
+                        let function = Function::from_name(&name)
+                            .ok_or_else(|| format!("unknown name '{name}'"))?;
+                        if !self.eat('(') {
+                            return Err(format!("expected '(' after '{name}'"));
+                        }
+                        let argument = self.expr()?;
+                        if !self.eat(')') {
+                            return Err(format!("missing ')' after argument of '{name}'"));
+                        }
+                        Ok(Node::Call(function, Box::new(argument)))
+                    }
+                }
+            }
+            Some(c) => Err(format!("unexpected '{c}' at position {}", start + 1)),
+            None => Err("unexpected end of expression".into()),
+        }
+    }
+}
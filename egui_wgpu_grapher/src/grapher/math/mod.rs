@@ -1,10 +1,16 @@
 //! Code to build scenes containing mathematical objects.
 //! Currently used for building a 3D function graph scene.
 
+pub mod colormap;
+pub mod complex;
 pub mod graph;
+pub mod noise;
+pub mod presets;
+pub mod sphere;
 
 use graph::GraphableFunc;
-use meval::Expr;
+use meval::{Context, Expr};
+use noise::{NoiseParameters, Perlin};
 
 #[allow(dead_code)]
 pub mod pde;
@@ -36,14 +42,69 @@ impl GraphableFunc for FunctionHolder {
 // ----------------------------------------------
 // Try to create function object from user input.
 
-pub fn try_parse_function_string(function_string: &str) -> Option<FunctionHolder> {
-    let mut function = None;
-    if let Ok(expr) = function_string.parse::<Expr>()
-        && let Ok(func) = expr.bind2("x", "z")
-    {
-        function = Some(FunctionHolder { f: Box::from(func) });
+pub fn try_parse_function_string(
+    function_string: &str,
+    noise_parameters: &NoiseParameters,
+) -> Option<FunctionHolder> {
+    let result = function_string
+        .parse::<Expr>()
+        .and_then(|expr| expr.bind2_with_context(function_context(noise_parameters), "x", "z"));
+    match result {
+        Ok(func) => Some(FunctionHolder { f: Box::from(func) }),
+        Err(err) => {
+            log::warn!("Invalid function \"{function_string}\": {err}");
+            None
+        }
+    }
+}
+
+/// Parse a function of `x` alone, as for a line plot.
+pub fn try_parse_single_variable(function_string: &str) -> Option<Box<dyn Fn(f64) -> f64>> {
+    let result = function_string.parse::<Expr>().and_then(|expr| {
+        expr.bind_with_context(function_context(&NoiseParameters::default()), "x")
+    });
+    match result {
+        Ok(func) => Some(Box::from(func)),
+        Err(err) => {
+            log::warn!("Invalid function \"{function_string}\": {err}");
+            None
+        }
     }
-    function
+}
+
+/// Parse a function of `theta` and `phi`, as for a graph on the sphere.
+pub fn try_parse_sphere_function(function_string: &str) -> Option<FunctionHolder> {
+    let result = function_string.parse::<Expr>().and_then(|expr| {
+        expr.bind2_with_context(
+            function_context(&NoiseParameters::default()),
+            "theta",
+            "phi",
+        )
+    });
+    match result {
+        Ok(func) => Some(FunctionHolder { f: Box::from(func) }),
+        Err(err) => {
+            log::warn!("Invalid function \"{function_string}\": {err}");
+            None
+        }
+    }
+}
+
+/// Builtin expression context extended with our own functions.
+fn function_context(noise_parameters: &NoiseParameters) -> Context<'static> {
+    let mut context = Context::new();
+
+    let perlin = Perlin::new(noise_parameters.seed);
+    let frequency = noise_parameters.frequency;
+    context.func2("noise", move |x, z| {
+        perlin.eval(x * frequency, z * frequency)
+    });
+
+    let perlin = Perlin::new(noise_parameters.seed);
+    let params = *noise_parameters;
+    context.func2("fbm", move |x, z| perlin.fbm(x, z, &params));
+
+    context
 }
 
 // ----------------------------------------------
@@ -0,0 +1,147 @@
+//! Seeded 2D Perlin noise and fractional Brownian motion (fBm).
+//!
+//! These are registered as the `noise(x, z)` and `fbm(x, z)` functions
+//! in the expression context, so they can be used in graph functions,
+//! e.g. `2*noise(0.5*x, 0.5*z)`. The permutation table is generated from
+//! an explicit seed, so a given seed always produces the same surface.
+
+// --------------------------------------
+// User-adjustable noise parameters.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoiseParameters {
+    pub seed: u64,
+    // number of layers summed in fbm
+    pub octaves: u32,
+    // base frequency applied to inputs
+    pub frequency: f64,
+    // amplitude multiplier between octaves
+    pub persistence: f64,
+}
+
+impl Default for NoiseParameters {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            octaves: 4,
+            frequency: 1.0,
+            persistence: 0.5,
+        }
+    }
+}
+
+// -------------------------
+// Classic 2D Perlin noise.
+
+#[derive(Clone)]
+pub struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut values: [u8; 256] = std::array::from_fn(|i| i as u8);
+
+        // Fisher-Yates shuffle driven by splitmix64, which unlike the
+        // `rand` generators is guaranteed stable across crate versions.
+        let mut state = seed;
+        for i in (1..values.len()).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            values.swap(i, j);
+        }
+
+        let permutation = std::array::from_fn(|i| values[i & 255]);
+        Self { permutation }
+    }
+
+    /// Noise value in approximately \[-1, 1\].
+    pub fn eval(&self, x: f64, z: f64) -> f64 {
+        let x_floor = x.floor();
+        let z_floor = z.floor();
+        let x_frac = x - x_floor;
+        let z_frac = z - z_floor;
+
+        let xi = (x_floor as i64).rem_euclid(256) as usize;
+        let zi = (z_floor as i64).rem_euclid(256) as usize;
+
+        let p = &self.permutation;
+        let aa = p[p[xi] as usize + zi];
+        let ab = p[p[xi] as usize + zi + 1];
+        let ba = p[p[xi + 1] as usize + zi];
+        let bb = p[p[xi + 1] as usize + zi + 1];
+
+        let u = fade(x_frac);
+        let v = fade(z_frac);
+
+        lerp(
+            v,
+            lerp(
+                u,
+                gradient(aa, x_frac, z_frac),
+                gradient(ba, x_frac - 1.0, z_frac),
+            ),
+            lerp(
+                u,
+                gradient(ab, x_frac, z_frac - 1.0),
+                gradient(bb, x_frac - 1.0, z_frac - 1.0),
+            ),
+        )
+    }
+
+    /// Sum of octaves of noise with doubling frequency, normalized
+    /// so the result stays in approximately \[-1, 1\].
+    pub fn fbm(&self, x: f64, z: f64, params: &NoiseParameters) -> f64 {
+        let mut result = 0.0;
+        let mut amplitude = 1.0;
+        let mut total_amplitude = 0.0;
+        let mut frequency = params.frequency;
+
+        for _ in 0..params.octaves.max(1) {
+            result += amplitude * self.eval(x * frequency, z * frequency);
+            total_amplitude += amplitude;
+            amplitude *= params.persistence;
+            frequency *= 2.0;
+        }
+
+        result / total_amplitude
+    }
+}
+
+#[inline(always)]
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+#[inline(always)]
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+#[inline(always)]
+fn gradient(hash: u8, x: f64, z: f64) -> f64 {
+    match hash & 7 {
+        0 => x + z,
+        1 => -x + z,
+        2 => x - z,
+        3 => -x - z,
+        4 => x,
+        5 => -x,
+        6 => z,
+        _ => -z,
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// ----------------------------------------------
+// Check if a function string uses noise builtins.
+
+pub fn uses_noise(function_string: &str) -> bool {
+    function_string.contains("noise") || function_string.contains("fbm")
+}
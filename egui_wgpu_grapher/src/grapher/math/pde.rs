@@ -1,14 +1,26 @@
-//! Finite-difference solvers for the wave and heat equations.
+//! Finite-difference solvers for the wave, heat and Schrödinger equations.
 //!
-//! These are currently unused, but there is a GPU version of the
-//! wave equation solver in the `scene::solver` module.
-
-use rand::{Rng, rngs::ThreadRng};
+//! The wave and heat solvers drive the Wave and Heat scene modes, through
+//! `scene::solid::pde`. There is also a GPU version of the wave equation
+//! solver in the `scene::solver` module.
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 pub const X_SIZE: usize = 500;
 pub const Y_SIZE: usize = 500;
 
 const PROP_SPEED: f32 = 0.35;
+
+/// Largest `prop_speed` for which the wave update is stable. This is the
+/// 2D CFL condition c * dt / h <= 1 / sqrt(2), since `prop_speed` is the
+/// squared Courant number (c * dt / h)^2.
+pub const MAX_STABLE_PROP_SPEED: f32 = 0.5;
 const DAMPING_FACTOR: f32 = 0.995;
 
 const DISTURBANCE_PROB: f32 = 0.02;
@@ -24,14 +36,16 @@ pub struct WaveEquationData {
     // 2x previous data
     u_2: Vec<Vec<f32>>,
 
-    // random number generator
-    rng: ThreadRng,
+    // random number generator and the seed it started from
+    rng: StdRng,
+    seed: u64,
 
     // grid size
     pub x_size: usize,
     pub y_size: usize,
 
-    // parameters
+    // parameters; prop_speed is (c * dt / h)^2 for wave speed c,
+    // timestep dt and grid spacing h
     pub prop_speed: f32,
     pub damping_factor: f32,
     pub disturbance_prob: f32,
@@ -40,11 +54,19 @@ pub struct WaveEquationData {
 
 impl WaveEquationData {
     pub fn new(x_size: usize, y_size: usize) -> Self {
+        Self::with_seed(x_size, y_size, None)
+    }
+
+    /// Seed the disturbances so a run can be reproduced; with no seed
+    /// one is taken from the system time.
+    pub fn with_seed(x_size: usize, y_size: usize, seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(time_seed);
         Self {
             u_0: vec![vec![0.0; y_size]; x_size],
             u_1: vec![vec![0.0; y_size]; x_size],
             u_2: vec![vec![0.0; y_size]; x_size],
-            rng: rand::rng(),
+            rng: StdRng::seed_from_u64(seed),
+            seed,
             //
             x_size,
             y_size,
@@ -56,6 +78,48 @@ impl WaveEquationData {
         }
     }
 
+    /// Return to the zero initial state, keeping the current parameters.
+    /// The generator is reseeded, so the disturbances repeat.
+    pub fn reset(&mut self) {
+        *self = Self {
+            prop_speed: self.prop_speed,
+            damping_factor: self.damping_factor,
+            disturbance_prob: self.disturbance_prob,
+            disturbance_size: self.disturbance_size,
+            ..Self::with_seed(self.x_size, self.y_size, Some(self.seed))
+        };
+    }
+
+    /// Whether `prop_speed` satisfies the CFL condition; otherwise
+    /// the solution grows without bound and soon becomes NaN.
+    pub fn is_stable(&self) -> bool {
+        self.prop_speed <= MAX_STABLE_PROP_SPEED
+    }
+
+    /// Clamp `prop_speed` to the stable maximum, returning true if it changed.
+    pub fn clamp_prop_speed(&mut self) -> bool {
+        if self.is_stable() {
+            return false;
+        }
+        log::warn!(
+            "Wave equation prop_speed {} is unstable; clamping to {MAX_STABLE_PROP_SPEED}.",
+            self.prop_speed
+        );
+        self.prop_speed = MAX_STABLE_PROP_SPEED;
+        true
+    }
+
+    /// Seed of the disturbance generator, for reproducing this run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Save the current timestep `u_0` as an `x_size` by `y_size` array,
+    /// to a CSV or `.npy` file by the path's extension.
+    pub fn save_field(&self, path: &Path) -> Result<(), String> {
+        write_field(path, (self.x_size, self.y_size), |i, j| self.u_0[i][j])
+    }
+
     pub fn update(&mut self) {
         self.add_random_disturbance();
 
@@ -94,9 +158,9 @@ impl WaveEquationData {
             const B: usize = 5;
 
             // add random bump decaying like 1 / r^3
-            for i in B..self.y_size - B {
-                for j in B..self.x_size - B {
-                    let dist = ((j - x).pow(2) as f64 + (i - y).pow(2) as f64)
+            for i in B..self.x_size - B {
+                for j in B..self.y_size - B {
+                    let dist = (i.abs_diff(x).pow(2) as f64 + j.abs_diff(y).pow(2) as f64)
                         .powf(3.0 / 2.0)
                         .max(2.0) as f32;
                     self.u_0[i][j] += self.disturbance_size / dist;
@@ -106,6 +170,13 @@ impl WaveEquationData {
     }
 }
 
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
 // Finite-difference heat equation solver.
 
 pub struct HeatEquationData {
@@ -144,8 +215,8 @@ impl HeatEquationData {
             d: 1.0,
         };
 
-        // x, z width
-        let init_width = 150_usize;
+        // x, z width; must fit in the shorter side
+        let init_width = 150_usize.min(x_size.min(y_size) - 2);
         // y height
         let init_height = 10.0_f32;
 
@@ -167,6 +238,25 @@ impl HeatEquationData {
         new_self
     }
 
+    /// Save the current temperatures as a `y_size` by `x_size` array,
+    /// to a CSV or `.npy` file by the path's extension.
+    pub fn save_field(&self, path: &Path) -> Result<(), String> {
+        let t = self.current_index;
+        write_field(path, (self.y_size, self.x_size), |y, x| {
+            self.u[y * self.x_size + x][t]
+        })
+    }
+
+    /// Return to the initial condition, keeping the current parameters.
+    pub fn reset(&mut self) {
+        *self = Self {
+            k: self.k,
+            h: self.h,
+            d: self.d,
+            ..Self::new(self.x_size, self.y_size)
+        };
+    }
+
     pub fn update(&mut self) {
         // previous time index
         let t_0 = self.current_index;
@@ -194,3 +284,450 @@ impl HeatEquationData {
         self.current_index = t;
     }
 }
+
+// Export field data for offline analysis.
+
+/// File format for saved fields.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum FieldFormat {
+    // NumPy array of little-endian f32
+    #[default]
+    Npy,
+    // one line per row
+    Csv,
+}
+
+impl FieldFormat {
+    pub const ALL: [FieldFormat; 2] = [FieldFormat::Npy, FieldFormat::Csv];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FieldFormat::Npy => "NumPy (.npy)",
+            FieldFormat::Csv => "CSV",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FieldFormat::Npy => "npy",
+            FieldFormat::Csv => "csv",
+        }
+    }
+
+    fn from_path(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == extension)
+            .ok_or_else(|| {
+                format!(
+                    "Can't save a field as \"{}\"; use .npy or .csv.",
+                    path.display()
+                )
+            })
+    }
+}
+
+// Write a rows by cols array, with `value(i, j)` in row i and column j.
+// Values are written as they're read through a buffered writer, so large
+// grids aren't first collected in memory.
+fn write_field(
+    path: &Path,
+    (rows, cols): (usize, usize),
+    value: impl Fn(usize, usize) -> f32,
+) -> Result<(), String> {
+    let format = FieldFormat::from_path(path)?;
+    let file = File::create(path)
+        .map_err(|err| format!("Failed to create \"{}\": {err}", path.display()))?;
+    let mut out = BufWriter::new(file);
+
+    match format {
+        FieldFormat::Npy => write_npy(&mut out, rows, cols, &value),
+        FieldFormat::Csv => write_csv(&mut out, rows, cols, &value),
+    }
+    .and_then(|()| out.flush())
+    .map_err(|err| format!("Failed to write \"{}\": {err}", path.display()))
+}
+
+// NPY format version 1.0: magic string and version, then the header length
+// and a Python dict literal describing the array, padded with spaces so the
+// data starts on a 64-byte boundary, then the values in row-major order.
+fn write_npy(
+    out: &mut impl Write,
+    rows: usize,
+    cols: usize,
+    value: &impl Fn(usize, usize) -> f32,
+) -> io::Result<()> {
+    const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+    let mut header =
+        format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({rows}, {cols}), }}");
+    // the length field is two bytes, and the header ends in a newline
+    let unpadded = MAGIC.len() + 2 + header.len() + 1;
+    let padding = unpadded.next_multiple_of(64) - unpadded;
+    header.extend(std::iter::repeat_n(' ', padding));
+    header.push('\n');
+
+    out.write_all(MAGIC)?;
+    out.write_all(&(header.len() as u16).to_le_bytes())?;
+    out.write_all(header.as_bytes())?;
+    for i in 0..rows {
+        for j in 0..cols {
+            out.write_all(&value(i, j).to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn write_csv(
+    out: &mut impl Write,
+    rows: usize,
+    cols: usize,
+    value: &impl Fn(usize, usize) -> f32,
+) -> io::Result<()> {
+    for i in 0..rows {
+        for j in 0..cols {
+            if j > 0 {
+                out.write_all(b",")?;
+            }
+            write!(out, "{}", value(i, j))?;
+        }
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+// Finite-difference Schrödinger equation solver.
+
+/// Potential the wave packet moves in, scaled by `potential_strength`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Potential {
+    #[default]
+    Free,
+    // thin wall across the grid at x = 0
+    Barrier,
+    // V = strength * r^2 / 2 about the center
+    HarmonicWell,
+}
+
+impl Potential {
+    pub const ALL: [Potential; 3] = [Potential::Free, Potential::Barrier, Potential::HarmonicWell];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Potential::Free => "Free",
+            Potential::Barrier => "Barrier",
+            Potential::HarmonicWell => "Harmonic well",
+        }
+    }
+
+    // barrier height at strength 1, above the default packet's energy
+    const BARRIER_HEIGHT: f32 = 20.0;
+    const BARRIER_WIDTH: f32 = 0.4;
+
+    fn value(&self, strength: f32, x: f32, z: f32) -> f32 {
+        match self {
+            Potential::Free => 0.0,
+            Potential::Barrier if x.abs() < Self::BARRIER_WIDTH / 2.0 => {
+                strength * Self::BARRIER_HEIGHT
+            }
+            Potential::Barrier => 0.0,
+            Potential::HarmonicWell => strength * (x * x + z * z) / 2.0,
+        }
+    }
+}
+
+/// Gaussian wave packet the simulation starts from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WavePacket {
+    pub center: [f32; 2],
+    // standard deviation of the position density along each axis is width / sqrt(2)
+    pub width: f32,
+    // mean momentum, which is also the group velocity with m = 1
+    pub momentum: [f32; 2],
+}
+
+impl Default for WavePacket {
+    fn default() -> Self {
+        Self {
+            center: [-4.0, 0.0],
+            width: 1.0,
+            momentum: [5.0, 0.0],
+        }
+    }
+}
+
+pub struct SchrodingerData {
+    // Real part at whole timesteps and imaginary part half a step ahead,
+    // staggered as in Visscher's explicit scheme; row-major by z then x.
+    pub re: Vec<f32>,
+    pub im: Vec<f32>,
+    potential_values: Vec<f32>,
+
+    // points along each side of the square grid
+    pub size: usize,
+
+    // parameters, in units with hbar = m = 1
+
+    // space difference increment
+    pub h: f32,
+    // time difference increment
+    pub dt: f32,
+    // timesteps run per update
+    pub steps_per_update: u32,
+
+    pub potential: Potential,
+    pub potential_strength: f32,
+    pub packet: WavePacket,
+
+    // largest density at the start, for scaling the display
+    initial_peak_density: f32,
+}
+
+impl SchrodingerData {
+    pub fn new(size: usize) -> Self {
+        let mut new_self = Self {
+            re: vec![0.0; size * size],
+            im: vec![0.0; size * size],
+            potential_values: vec![0.0; size * size],
+            //
+            size,
+            //
+            h: 0.08,
+            dt: 0.002,
+            steps_per_update: 10,
+            //
+            potential: Potential::default(),
+            potential_strength: 1.0,
+            packet: WavePacket::default(),
+            //
+            initial_peak_density: 0.0,
+        };
+        new_self.reset();
+        new_self
+    }
+
+    /// Position of grid index `i` along either axis, with the origin
+    /// at the center of the grid.
+    pub fn coordinate(&self, i: usize) -> f32 {
+        (i as f32 - (self.size - 1) as f32 / 2.0) * self.h
+    }
+
+    /// Recompute the potential and restart from the wave packet,
+    /// keeping the current parameters.
+    pub fn reset(&mut self) {
+        let n = self.size;
+        let WavePacket {
+            center: [x_0, z_0],
+            width,
+            momentum: [k_x, k_z],
+        } = self.packet;
+
+        for i in 0..n {
+            let z = self.coordinate(i);
+            for j in 0..n {
+                let x = self.coordinate(j);
+                let k = i * n + j;
+                self.potential_values[k] = self.potential.value(self.potential_strength, x, z);
+
+                // boundary held at 0
+                if i == 0 || j == 0 || i == n - 1 || j == n - 1 {
+                    self.re[k] = 0.0;
+                    self.im[k] = 0.0;
+                    continue;
+                }
+                let r_2 = (x - x_0).powi(2) + (z - z_0).powi(2);
+                let amplitude = (-r_2 / (2.0 * width * width)).exp();
+                let phase = k_x * x + k_z * z;
+                self.re[k] = amplitude * phase.cos();
+                self.im[k] = amplitude * phase.sin();
+            }
+        }
+
+        // normalize total probability to 1
+        let norm = self.total_probability().sqrt();
+        if norm > 0.0 {
+            self.re.iter_mut().for_each(|v| *v /= norm);
+            self.im.iter_mut().for_each(|v| *v /= norm);
+        }
+        self.initial_peak_density = (0..n * n).map(|k| self.density(k)).fold(0.0, f32::max);
+
+        // start the imaginary part half a step ahead
+        advance(
+            &mut self.im,
+            &self.re,
+            &self.potential_values,
+            n,
+            self.h,
+            -self.dt / 2.0,
+        );
+    }
+
+    /// Largest timestep for which the scheme is stable, given the grid
+    /// spacing and potential. Larger steps grow without bound.
+    pub fn max_stable_dt(&self) -> f32 {
+        let max_potential = self.max_potential().max(0.0);
+        // largest eigenvalue of the discrete Hamiltonian
+        let max_energy = 4.0 / (self.h * self.h) + max_potential;
+        2.0 / max_energy
+    }
+
+    pub fn is_stable(&self) -> bool {
+        self.dt <= self.max_stable_dt()
+    }
+
+    pub fn max_potential(&self) -> f32 {
+        self.potential_values
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    pub fn potential_at(&self, k: usize) -> f32 {
+        self.potential_values[k]
+    }
+
+    /// Probability density at grid index `k`. The two parts are half a
+    /// step apart, which is close enough for display.
+    pub fn density(&self, k: usize) -> f32 {
+        self.re[k] * self.re[k] + self.im[k] * self.im[k]
+    }
+
+    /// Phase of the wavefunction at grid index `k`, in (-pi, pi].
+    pub fn phase(&self, k: usize) -> f32 {
+        self.im[k].atan2(self.re[k])
+    }
+
+    pub fn initial_peak_density(&self) -> f32 {
+        self.initial_peak_density
+    }
+
+    /// Integral of the density, which stays near 1 while stable.
+    pub fn total_probability(&self) -> f32 {
+        let sum: f32 = (0..self.re.len()).map(|k| self.density(k)).sum();
+        sum * self.h * self.h
+    }
+
+    pub fn update(&mut self) {
+        let n = self.size;
+        for _ in 0..self.steps_per_update {
+            // R(t + dt) = R(t) + dt * H I(t + dt/2)
+            advance(
+                &mut self.re,
+                &self.im,
+                &self.potential_values,
+                n,
+                self.h,
+                self.dt,
+            );
+            // I(t + 3dt/2) = I(t + dt/2) - dt * H R(t + dt)
+            advance(
+                &mut self.im,
+                &self.re,
+                &self.potential_values,
+                n,
+                self.h,
+                -self.dt,
+            );
+        }
+    }
+}
+
+// Add dt * H u to the interior points of `target`, for the discrete
+// Hamiltonian H = -laplacian / 2 + V on an n x n grid with spacing h.
+fn advance(target: &mut [f32], u: &[f32], potential: &[f32], n: usize, h: f32, dt: f32) {
+    let c = 0.5 / (h * h);
+    for i in 1..n - 1 {
+        for j in 1..n - 1 {
+            let k = i * n + j;
+            let laplacian = u[k - 1] + u[k + 1] + u[k - n] + u[k + n] - 4.0 * u[k];
+            target[k] += dt * (-c * laplacian + potential[k] * u[k]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // wider than tall and taller than wide, so a swapped index overruns
+    const GRID_SIZES: [(usize, usize); 2] = [(800, 400), (400, 800)];
+
+    #[test]
+    fn wave_updates_rectangular_grids() {
+        for (x_size, y_size) in GRID_SIZES {
+            let mut wave = WaveEquationData::with_seed(x_size, y_size, Some(1));
+            // disturb every step, so bumps land near each edge in turn
+            wave.disturbance_prob = 1.0;
+            for _ in 0..5 {
+                wave.update();
+            }
+            assert_eq!(wave.u_0.len(), x_size);
+            assert!(wave.u_0.iter().all(|column| column.len() == y_size));
+            assert!(wave.u_0.iter().flatten().all(|u| u.is_finite()));
+            assert!(wave.u_0.iter().flatten().any(|&u| u != 0.0));
+        }
+    }
+
+    #[test]
+    fn wave_blows_up_only_past_stable_speed() {
+        // largest value after some steps from a single bump, undamped;
+        // overflow ends in NaN, which max would skip
+        let peak_after_steps = |prop_speed: f32| {
+            let mut wave = WaveEquationData::with_seed(64, 64, Some(1));
+            wave.disturbance_prob = 0.0;
+            wave.damping_factor = 1.0;
+            wave.prop_speed = prop_speed;
+            assert_eq!(wave.is_stable(), prop_speed <= MAX_STABLE_PROP_SPEED);
+            wave.u_0[32][32] = 1.0;
+            for _ in 0..400 {
+                wave.update();
+            }
+            wave.u_0.iter().flatten().fold(0.0_f32, |max, u| {
+                if u.is_finite() {
+                    max.max(u.abs())
+                } else {
+                    f32::INFINITY
+                }
+            })
+        };
+
+        assert!(peak_after_steps(MAX_STABLE_PROP_SPEED * 0.9) <= 1.0);
+        assert!(peak_after_steps(MAX_STABLE_PROP_SPEED) <= 1.0);
+        assert!(peak_after_steps(MAX_STABLE_PROP_SPEED * 1.1) > 1e6);
+    }
+
+    #[test]
+    fn npy_header_pads_data_to_64_bytes() {
+        let mut out = Vec::new();
+        write_npy(&mut out, 2, 3, &|i, j| (3 * i + j) as f32).unwrap();
+
+        assert!(out.starts_with(b"\x93NUMPY\x01\x00"));
+        let header_len = u16::from_le_bytes([out[8], out[9]]) as usize;
+        let data_start = 10 + header_len;
+        assert_eq!(data_start % 64, 0);
+        let header = std::str::from_utf8(&out[10..data_start]).unwrap();
+        assert!(header.contains("'shape': (2, 3)"));
+        assert!(header.ends_with('\n'));
+
+        let values: Vec<f32> = out[data_start..]
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(values, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn heat_updates_rectangular_grids() {
+        for (x_size, y_size) in GRID_SIZES {
+            let mut heat = HeatEquationData::new(x_size, y_size);
+            for _ in 0..5 {
+                heat.update();
+            }
+            assert_eq!(heat.u.len(), x_size * y_size);
+            assert!(heat.u.iter().flatten().all(|u| u.is_finite()));
+        }
+    }
+}
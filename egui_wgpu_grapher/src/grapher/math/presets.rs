@@ -0,0 +1,67 @@
+//! Built-in example functions for the graph mode.
+//!
+//! To add a preset, append it to `FUNCTION_PRESETS`; the graph UI and the
+//! preset shortcut keys pick it up from there.
+
+use super::graph::Domain;
+
+/// An example function, with a domain and display height that suit it.
+pub struct FunctionPreset {
+    pub name: &'static str,
+    // function of x and z, in the syntax of the function input
+    pub expr: &'static str,
+    // width of the square domain centered at the origin
+    pub domain_width: f64,
+    pub vertical_exaggeration: f32,
+}
+
+impl FunctionPreset {
+    pub fn domain(&self) -> Domain {
+        Domain::centered(self.domain_width)
+    }
+}
+
+pub const FUNCTION_PRESETS: &[FunctionPreset] = &[
+    FunctionPreset {
+        name: "Ripple",
+        expr: "sin(3*sqrt(x^2 + z^2)) / (1 + sqrt(x^2 + z^2))",
+        domain_width: 12.0,
+        vertical_exaggeration: 2.0,
+    },
+    FunctionPreset {
+        name: "Saddle",
+        expr: "x^2 - z^2",
+        domain_width: 4.0,
+        vertical_exaggeration: 0.5,
+    },
+    FunctionPreset {
+        name: "Gaussian bump",
+        expr: "exp(-(x^2 + z^2))",
+        domain_width: 6.0,
+        vertical_exaggeration: 1.5,
+    },
+    FunctionPreset {
+        name: "Mexican hat",
+        expr: "(1 - (x^2 + z^2)) * exp(-(x^2 + z^2) / 2)",
+        domain_width: 8.0,
+        vertical_exaggeration: 1.5,
+    },
+    FunctionPreset {
+        name: "Egg crate",
+        expr: "sin(x) * cos(z)",
+        domain_width: 12.0,
+        vertical_exaggeration: 1.0,
+    },
+    FunctionPreset {
+        name: "Monkey saddle",
+        expr: "x^3 - 3*x*z^2",
+        domain_width: 3.0,
+        vertical_exaggeration: 0.3,
+    },
+    FunctionPreset {
+        name: "Terrain",
+        expr: "fbm(x, z)",
+        domain_width: 8.0,
+        vertical_exaggeration: 1.0,
+    },
+];
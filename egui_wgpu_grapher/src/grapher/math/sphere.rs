@@ -0,0 +1,158 @@
+//! Code to build a mesh for graphing a function on the sphere, r = f(θ, φ).
+//!
+//! θ is the polar angle from the +y axis, in [0, π], and φ is the azimuth
+//! about it, in [0, 2π), measured from +x toward +z. The surface is the
+//! unit sphere with each point moved to distance f(θ, φ) from the origin.
+//!
+//! Each pole is a single vertex and each ring of constant θ wraps around
+//! to its first vertex, so there are no cracks at the poles or the φ seam.
+
+use super::graph::GraphableFunc;
+use crate::grapher::scene::{GpuVertex, solid::MeshData};
+
+use std::f64::consts::{PI, TAU};
+
+type Vector = [f64; 3];
+
+/// Point on the unit sphere at the given angles.
+fn unit_vector(theta: f64, phi: f64) -> Vector {
+    [
+        theta.sin() * phi.cos(),
+        theta.cos(),
+        theta.sin() * phi.sin(),
+    ]
+}
+
+// Partial derivatives of the unit vector in θ and φ.
+fn unit_vector_derivatives(theta: f64, phi: f64) -> [Vector; 2] {
+    [
+        [
+            theta.cos() * phi.cos(),
+            -theta.sin(),
+            theta.cos() * phi.sin(),
+        ],
+        [-theta.sin() * phi.sin(), 0.0, theta.sin() * phi.cos()],
+    ]
+}
+
+fn cross(a: Vector, b: Vector) -> Vector {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalized(v: Vector) -> [f32; 3] {
+    let norm = (v[0].powi(2) + v[1].powi(2) + v[2].powi(2)).sqrt();
+    if norm > 0.0 {
+        [
+            (v[0] / norm) as f32,
+            (v[1] / norm) as f32,
+            (v[2] / norm) as f32,
+        ]
+    } else {
+        [0.0, 1.0, 0.0]
+    }
+}
+
+// Radius at the given angles, with non-finite values collapsed to the origin.
+fn radius<F: GraphableFunc>(f: &F, theta: f64, phi: f64) -> f64 {
+    let r = f.eval(theta, phi);
+    if r.is_finite() { r } else { 0.0 }
+}
+
+/// Unit normal of the surface at the given angles, from the partial
+/// derivatives of the parameterization p = f u. Points away from the
+/// origin where f is positive.
+fn normal_from_function<F: GraphableFunc>(f: &F, theta: f64, phi: f64) -> [f32; 3] {
+    const H: f64 = 1e-6;
+    let r = radius(f, theta, phi);
+    let dr_dtheta = (radius(f, theta + H, phi) - radius(f, theta - H, phi)) / (2.0 * H);
+    let dr_dphi = (radius(f, theta, phi + H) - radius(f, theta, phi - H)) / (2.0 * H);
+
+    let u = unit_vector(theta, phi);
+    let [du_dtheta, du_dphi] = unit_vector_derivatives(theta, phi);
+    let dp_dtheta: Vector = std::array::from_fn(|i| dr_dtheta * u[i] + r * du_dtheta[i]);
+    let dp_dphi: Vector = std::array::from_fn(|i| dr_dphi * u[i] + r * du_dphi[i]);
+
+    let normal = cross(dp_dphi, dp_dtheta);
+    normalized(normal.map(|c| c * r.signum()))
+}
+
+/// Mesh of the graph of f over a grid of `rings` steps in θ and twice as
+/// many in φ. Vertices where f is negative get `negative_color`.
+pub fn sphere_mesh_data<F: GraphableFunc>(
+    f: &F,
+    rings: u32,
+    color: [f32; 3],
+    negative_color: [f32; 3],
+) -> MeshData {
+    let rings = rings.max(2);
+    let segments = 2 * rings;
+    let vertex = |position: Vector, r: f64, normal: [f32; 3]| GpuVertex {
+        position: position.map(|c| c as f32),
+        color: if r < 0.0 { negative_color } else { color },
+        normal,
+        ..Default::default()
+    };
+
+    // Interior rings, then the poles. Each pole's radius and normal are
+    // averaged over the values f takes approaching it, which may depend
+    // on φ.
+    let mut vertices = Vec::with_capacity(((rings - 1) * segments + 2) as usize);
+    for ring in 1..rings {
+        let theta = PI * ring as f64 / rings as f64;
+        for segment in 0..segments {
+            let phi = TAU * segment as f64 / segments as f64;
+            let r = radius(f, theta, phi);
+            let u = unit_vector(theta, phi);
+            vertices.push(vertex(
+                u.map(|c| r * c),
+                r,
+                normal_from_function(f, theta, phi),
+            ));
+        }
+    }
+    for (theta, ring) in [(0.0, 0), (PI, rings - 2)] {
+        let ring_vertices = &vertices[(ring * segments) as usize..((ring + 1) * segments) as usize];
+        let r = (0..segments)
+            .map(|segment| radius(f, theta, TAU * segment as f64 / segments as f64))
+            .sum::<f64>()
+            / segments as f64;
+        let normal: Vector = std::array::from_fn(|i| {
+            ring_vertices
+                .iter()
+                .map(|vertex| vertex.normal[i] as f64)
+                .sum()
+        });
+        let position = unit_vector(theta, 0.0).map(|c| r * c);
+        vertices.push(vertex(position, r, normalized(normal)));
+    }
+
+    let ring_vertex = |ring: u32, segment: u32| (ring - 1) * segments + segment % segments;
+    let north = (rings - 1) * segments;
+    let south = north + 1;
+
+    // Triangles are counterclockwise seen from outside where f is positive.
+    let mut indices = Vec::with_capacity((6 * (rings - 1) * segments) as usize);
+    for segment in 0..segments {
+        indices.extend([north, ring_vertex(1, segment + 1), ring_vertex(1, segment)]);
+        indices.extend([
+            ring_vertex(rings - 1, segment),
+            ring_vertex(rings - 1, segment + 1),
+            south,
+        ]);
+    }
+    for ring in 1..rings - 1 {
+        for segment in 0..segments {
+            let a = ring_vertex(ring, segment);
+            let b = ring_vertex(ring, segment + 1);
+            let c = ring_vertex(ring + 1, segment);
+            let d = ring_vertex(ring + 1, segment + 1);
+            indices.extend([a, b, c, b, d, c]);
+        }
+    }
+
+    MeshData { vertices, indices }
+}
@@ -53,7 +53,6 @@ impl Mul for Matrix {
 }
 
 pub const X_AXIS: cgmath::Vector3<f32> = cgmath::Vector3::new(1.0, 0.0, 0.0);
-pub const Y_AXIS: cgmath::Vector3<f32> = cgmath::Vector3::new(0.0, 1.0, 0.0);
 
 impl Matrix {
     pub fn identity() -> Self {
@@ -80,6 +79,12 @@ impl Matrix {
         }
     }
 
+    pub fn y_scale(scale: f32) -> Self {
+        Self {
+            matrix: cgmath::Matrix4::from_nonuniform_scale(1.0, scale, 1.0).into(),
+        }
+    }
+
     pub fn x_rotation(degrees: f32) -> Self {
         Self {
             matrix: cgmath::Matrix4::from_axis_angle(X_AXIS, cgmath::Deg(degrees)).into(),
@@ -1,18 +1,26 @@
 //! Code for building, storing, and rendering 3D and 2D scenes using Wgpu.
 //!
 //! We currently have two main scene formats:
-//!  - A 3D scene with separate pipelines for textured and vertex-colored meshes.
-//!  - A 2D scene for running a finite-difference solver compute pipeline and
-//!    rendering the results to a fixed 2D canvas texture.
+//!  - A 3D scene with separate pipelines for textured and vertex-colored meshes,
+//!    and for unconnected points.
+//!  - 2D scenes that run a compute pipeline, either a finite-difference solver
+//!    or an escape-time fractal, and render the results to a fixed 2D canvas texture.
+//!    Domain coloring of complex functions is drawn the same way, from a texture
+//!    filled on the CPU.
 //!
 //! Within the 3D scene format there are several types, including a function
-//! grapher and a glTF model viewer.
+//! grapher, a glTF and OBJ model viewer, and a plotter for tabular data.
 
 mod camera;
+mod csv_loader;
 mod gltf_loader;
 mod matrix;
+mod obj_loader;
 
 pub mod math;
 pub mod pipeline;
 pub mod render;
 pub mod scene;
+
+pub use camera::{Camera, controller::CameraMode, path::CAMERA_PATH_FILE};
+pub use gltf_loader::SceneNode;
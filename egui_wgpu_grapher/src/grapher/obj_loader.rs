@@ -0,0 +1,174 @@
+//! Read scene data from a Wavefront OBJ file, with its MTL materials
+//! if present, using the `tobj` crate.
+
+use std::{error::Error, path::Path};
+
+use cgmath::{InnerSpace, Vector3};
+use egui_wgpu::wgpu::{Device, Queue};
+
+use crate::grapher::{
+    gltf_loader::{RenderMesh, RenderScene, SceneNode},
+    matrix::Matrix,
+    pipeline::texture::{Image, TextureData},
+    scene::{
+        GpuVertex,
+        textured::{TexturedMeshData, compute_tangents},
+    },
+};
+
+// Vertex color multiplies the texture color, so white leaves it unchanged.
+const DEFAULT_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+
+pub struct ObjLoader<'a> {
+    path: String,
+    models: Vec<tobj::Model>,
+    materials: Vec<tobj::Material>,
+
+    // Wgpu handles for GPU updates.
+    device: &'a Device,
+    queue: &'a Queue,
+}
+
+impl<'a> ObjLoader<'a> {
+    pub fn create(
+        device: &'a Device,
+        queue: &'a Queue,
+        obj_path: &str,
+    ) -> Result<ObjLoader<'a>, Box<dyn Error>> {
+        // A single index per vertex matches our vertex buffer layout.
+        let options = tobj::LoadOptions {
+            single_index: true,
+            triangulate: true,
+            ignore_points: true,
+            ignore_lines: true,
+        };
+        let (models, materials) = tobj::load_obj(obj_path, &options)?;
+
+        // The model is still usable without its materials.
+        let materials = materials.unwrap_or_else(|err| {
+            log::warn!("Failed to load OBJ materials: {err}");
+            vec![]
+        });
+
+        Ok(ObjLoader {
+            path: obj_path.into(),
+            models,
+            materials,
+            device,
+            queue,
+        })
+    }
+}
+
+impl ObjLoader<'_> {
+    pub fn traverse(self) -> Result<RenderScene, Box<dyn Error>> {
+        let model_dir = Path::new(&self.path)
+            .parent()
+            .ok_or("Failed to get directory of OBJ file.")?;
+
+        let mut render_scene = RenderScene::default();
+        for (index, model) in self.models.iter().enumerate() {
+            let mesh = &model.mesh;
+            if mesh.indices.is_empty() {
+                continue;
+            }
+
+            let mut vertices: Vec<GpuVertex> = mesh
+                .positions
+                .chunks_exact(3)
+                .map(|p| GpuVertex {
+                    position: [p[0], p[1], p[2]],
+                    color: DEFAULT_COLOR,
+                    ..Default::default()
+                })
+                .collect();
+
+            if mesh.normals.len() == mesh.positions.len() {
+                for (vertex, n) in vertices.iter_mut().zip(mesh.normals.chunks_exact(3)) {
+                    vertex.normal = [n[0], n[1], n[2]];
+                }
+            } else {
+                compute_normals(&mut vertices, &mesh.indices);
+            }
+
+            // OBJ texture coordinates have their origin at the bottom left.
+            for (vertex, t) in vertices.iter_mut().zip(mesh.texcoords.chunks_exact(2)) {
+                vertex.tex_coords = [t[0], 1.0 - t[1]];
+            }
+            compute_tangents(&mut vertices, &mesh.indices);
+
+            let material = mesh.material_id.and_then(|id| self.materials.get(id));
+            let texture = self.read_texture(material, model_dir);
+
+            let name = if model.name.is_empty() {
+                format!("Mesh {index}")
+            } else {
+                model.name.clone()
+            };
+            let mesh_index = render_scene.add_mesh(RenderMesh {
+                name: name.clone(),
+                data: TexturedMeshData {
+                    vertices,
+                    indices: mesh.indices.clone(),
+                    texture,
+                },
+                matrix: Matrix::identity(),
+                morph: None,
+            });
+            // OBJ has no hierarchy, so each object is a root node.
+            render_scene
+                .nodes
+                .push(SceneNode::mesh_node(index, &name, mesh_index));
+        }
+
+        if render_scene.meshes.is_empty() {
+            return Err("OBJ file contained no triangle meshes.".into());
+        }
+        render_scene.normalize_position();
+        Ok(render_scene)
+    }
+
+    /// Use the material's diffuse map if it has one, else its diffuse color.
+    fn read_texture(&self, material: Option<&tobj::Material>, model_dir: &Path) -> TextureData {
+        let Some(material) = material else {
+            return TextureData::solid_color_texture(&[255; 4], self.device, self.queue);
+        };
+
+        if let Some(texture_file) = &material.diffuse_texture {
+            let img_path = model_dir.join(texture_file);
+            match Image::from_file(&img_path.to_string_lossy()) {
+                Ok(image) => return TextureData::from_image(&image, self.device, self.queue),
+                Err(err) => log::warn!("{err} ({texture_file}); using material diffuse color."),
+            }
+        }
+
+        let diffuse = material.diffuse.unwrap_or([1.0, 1.0, 1.0]);
+        let color = [
+            (255.0 * diffuse[0]) as u8,
+            (255.0 * diffuse[1]) as u8,
+            (255.0 * diffuse[2]) as u8,
+            255,
+        ];
+        TextureData::solid_color_texture(&color, self.device, self.queue)
+    }
+}
+
+/// Smooth normals for a mesh that doesn't provide them: each vertex gets
+/// the area-weighted average of the normals of its adjacent triangles.
+fn compute_normals(vertices: &mut [GpuVertex], indices: &[u32]) {
+    let mut normals = vec![Vector3::new(0.0_f32, 0.0, 0.0); vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| Vector3::from(vertices[triangle[i] as usize].position));
+        // Unnormalized, so larger triangles contribute more.
+        let normal = (b - a).cross(c - a);
+        for &i in triangle {
+            normals[i as usize] += normal;
+        }
+    }
+
+    for (vertex, normal) in vertices.iter_mut().zip(normals) {
+        if normal.magnitude2() > 0.0 {
+            vertex.normal = normal.normalize().into();
+        }
+    }
+}
@@ -0,0 +1,212 @@
+// Antialiasing modes: MSAA sample count for scene render targets,
+// and an optional FXAA post-process over the resolved scene color.
+
+use super::texture::{DepthBuffer, OffscreenTarget};
+
+use egui_wgpu::wgpu::{
+    self, Adapter, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, CommandEncoder, Device, RenderPipeline, Sampler,
+    SurfaceConfiguration,
+};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Antialiasing {
+    Off,
+    Fxaa,
+    Msaa2x,
+    #[default]
+    Msaa4x,
+    Msaa8x,
+}
+
+impl Antialiasing {
+    pub const ALL: [Antialiasing; 5] = [
+        Antialiasing::Off,
+        Antialiasing::Fxaa,
+        Antialiasing::Msaa2x,
+        Antialiasing::Msaa4x,
+        Antialiasing::Msaa8x,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Antialiasing::Off => "Off",
+            Antialiasing::Fxaa => "FXAA",
+            Antialiasing::Msaa2x => "MSAA 2x",
+            Antialiasing::Msaa4x => "MSAA 4x",
+            Antialiasing::Msaa8x => "MSAA 8x",
+        }
+    }
+
+    /// MSAA samples per pixel this mode renders scenes with.
+    pub fn sample_count(&self) -> u32 {
+        match self {
+            Antialiasing::Off | Antialiasing::Fxaa => 1,
+            Antialiasing::Msaa2x => 2,
+            Antialiasing::Msaa4x => 4,
+            Antialiasing::Msaa8x => 8,
+        }
+    }
+
+    pub fn from_settings(samples: u32, fxaa: bool) -> Self {
+        match samples {
+            _ if fxaa => Antialiasing::Fxaa,
+            2 => Antialiasing::Msaa2x,
+            4 => Antialiasing::Msaa4x,
+            8 => Antialiasing::Msaa8x,
+            _ => Antialiasing::Off,
+        }
+    }
+}
+
+// Every scene pipeline and render target is built with this count, so it
+// is set once at startup, before the render state and scenes are created.
+static SAMPLE_COUNT: AtomicU32 = AtomicU32::new(4);
+
+pub fn sample_count() -> u32 {
+    SAMPLE_COUNT.load(Ordering::Relaxed)
+}
+
+pub fn set_sample_count(count: u32) {
+    SAMPLE_COUNT.store(count, Ordering::Relaxed);
+}
+
+/// Sample counts the adapter can render both the scene color and depth formats with.
+pub fn supported_sample_counts(adapter: &Adapter) -> Vec<u32> {
+    let color = adapter
+        .get_texture_format_features(OffscreenTarget::FORMAT)
+        .flags;
+    let depth = adapter
+        .get_texture_format_features(DepthBuffer::DEPTH_FORMAT)
+        .flags;
+    [1, 2, 4, 8]
+        .into_iter()
+        .filter(|&count| color.sample_count_supported(count) && depth.sample_count_supported(count))
+        .collect()
+}
+
+// -----------------
+// FXAA post-process.
+
+pub struct FxaaState {
+    pub enabled: bool,
+    // copy of the scene color, since the pass writes back into the target
+    source: OffscreenTarget,
+    sampler: Sampler,
+    bind_group_layout: BindGroupLayout,
+    // references the source copy, so is rebuilt on resize
+    bind_group: BindGroup,
+    pipeline: RenderPipeline,
+}
+
+impl FxaaState {
+    pub fn create(device: &Device, surface_config: &SurfaceConfiguration) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("fxaa bind group layout"),
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("fxaa sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let source = OffscreenTarget::create(surface_config, device);
+        let bind_group = Self::create_bind_group(device, &bind_group_layout, &sampler, &source);
+
+        let pipeline = super::create_fullscreen_pipeline(
+            device,
+            super::get_fxaa_shader(),
+            OffscreenTarget::FORMAT,
+            &[&bind_group_layout],
+        );
+
+        Self {
+            enabled: false,
+            source,
+            sampler,
+            bind_group_layout,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        source: &OffscreenTarget,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source.view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("fxaa bind group"),
+        })
+    }
+
+    pub fn handle_resize(&mut self, device: &Device, surface_config: &SurfaceConfiguration) {
+        self.source = OffscreenTarget::create(surface_config, device);
+        self.bind_group =
+            Self::create_bind_group(device, &self.bind_group_layout, &self.sampler, &self.source);
+    }
+
+    /// Antialias the scene in `target` in place.
+    pub fn render(&self, target: &OffscreenTarget, encoder: &mut CommandEncoder) {
+        if !self.enabled {
+            return;
+        }
+        encoder.copy_texture_to_texture(
+            target.texture.as_image_copy(),
+            self.source.texture.as_image_copy(),
+            target.texture.size(),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("fxaa pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
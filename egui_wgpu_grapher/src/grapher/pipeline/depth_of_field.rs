@@ -0,0 +1,215 @@
+// Optional post-process that blurs the 3D scene by distance from a focus plane.
+
+use super::{
+    antialiasing,
+    texture::{self, DepthBuffer, OffscreenTarget},
+};
+use crate::grapher::camera::Camera;
+
+use cgmath::SquareMatrix;
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, Buffer, CommandEncoder, Device, Queue, RenderPipeline,
+    SurfaceConfiguration, util::DeviceExt,
+};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DepthOfFieldUniform {
+    // inverse camera projection, to recover distance from the depth buffer
+    pub inverse_projection: [[f32; 4]; 4],
+    // distance from the eye that stays sharp
+    pub focus_distance: f32,
+    // blur radius in pixels per unit of relative defocus
+    pub aperture: f32,
+    _padding: [f32; 2],
+}
+
+pub struct DepthOfFieldState {
+    pub uniform: DepthOfFieldUniform,
+    pub enabled: bool,
+    buffer: Buffer,
+    // copy of the scene color, since the pass writes back into the target
+    source: OffscreenTarget,
+    bind_group_layout: BindGroupLayout,
+    // references the source copy and depth buffer, so is rebuilt on resize
+    bind_group: BindGroup,
+    // none where shaders can't read the depth buffer
+    pipeline: Option<RenderPipeline>,
+}
+
+impl DepthOfFieldState {
+    pub const DEFAULT_APERTURE: f32 = 4.0;
+
+    pub fn create(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        depth_buffer: &DepthBuffer,
+    ) -> Self {
+        let uniform = DepthOfFieldUniform {
+            inverse_projection: cgmath::Matrix4::identity().into(),
+            focus_distance: 5.0,
+            aperture: Self::DEFAULT_APERTURE,
+            _padding: [0.0; 2],
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Depth of Field UBO"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: antialiasing::sample_count() > 1,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Depth,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("depth of field bind group layout"),
+        });
+        let source = OffscreenTarget::create(surface_config, device);
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &buffer, &source, depth_buffer);
+
+        let pipeline = texture::depth_readable().then(|| {
+            super::create_fullscreen_pipeline(
+                device,
+                super::get_depth_of_field_shader(),
+                OffscreenTarget::FORMAT,
+                &[&bind_group_layout],
+            )
+        });
+
+        Self {
+            uniform,
+            enabled: false,
+            buffer,
+            source,
+            bind_group_layout,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        source: &OffscreenTarget,
+        depth_buffer: &DepthBuffer,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source.view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&depth_buffer.view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("depth of field bind group"),
+        })
+    }
+
+    /// Whether the blur can be applied on this backend.
+    pub fn supported(&self) -> bool {
+        self.pipeline.is_some()
+    }
+
+    pub fn handle_resize(
+        &mut self,
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        depth_buffer: &DepthBuffer,
+    ) {
+        self.source = OffscreenTarget::create(surface_config, device);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.buffer,
+            &self.source,
+            depth_buffer,
+        );
+    }
+
+    /// Match the camera projection and write the uniform.
+    pub fn update_uniform(&mut self, queue: &Queue, camera: &Camera) {
+        if !self.enabled {
+            return;
+        }
+        self.uniform.inverse_projection = camera
+            .get_projection_matrix()
+            .invert()
+            .unwrap_or_else(cgmath::Matrix4::identity)
+            .into();
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+    }
+
+    /// Blur the scene in `target` in place, using depth from the
+    /// depth buffer it was rendered with.
+    pub fn render(&self, target: &OffscreenTarget, encoder: &mut CommandEncoder) {
+        if !self.enabled {
+            return;
+        }
+        let Some(pipeline) = &self.pipeline else {
+            return;
+        };
+        encoder.copy_texture_to_texture(
+            target.texture.as_image_copy(),
+            self.source.texture.as_image_copy(),
+            target.texture.size(),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("depth of field pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
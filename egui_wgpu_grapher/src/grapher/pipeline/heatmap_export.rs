@@ -0,0 +1,232 @@
+// Save a graph seen from directly above, colored by height, to a PNG file.
+//
+// The mesh is drawn offscreen with an orthographic camera looking down
+// the y axis, at a size independent of the window. Each pixel holds the
+// height of the highest surface point over it, which is read back and
+// colored with a colormap on the CPU, so the colors are exact however
+// the heights are spread. Pixels the surface doesn't cover, like holes
+// where the function is undefined, are transparent.
+
+use super::texture::DepthBuffer;
+use crate::grapher::{
+    math::{
+        colormap::{Colormap, linear_to_srgb},
+        graph::Domain,
+    },
+    matrix::{self, Matrix, MatrixUniform},
+    scene::{GpuVertex, solid::MeshRenderData},
+};
+
+use egui_wgpu::wgpu::{
+    self, BindGroupLayoutDescriptor, Device, Origin3d, Queue, TexelCopyBufferLayout,
+    TexelCopyTextureInfo,
+};
+use image::{ImageBuffer, Rgba};
+
+// height and coverage of each pixel
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg32Float;
+
+/// File name for a new heatmap, distinguished by the time it was saved,
+/// in milliseconds, so quick repeated exports don't overwrite.
+pub fn heatmap_filename() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    format!("heatmap_{millis}.png")
+}
+
+/// Image size with `long_side` pixels along the longer side of the
+/// domain, so pixels are square, limited to the largest texture size.
+pub fn heatmap_size(device: &Device, domain: &Domain, long_side: u32) -> [u32; 2] {
+    let long_side = long_side.clamp(1, device.limits().max_texture_dimension_2d);
+    let (width, depth) = (domain.x_width(), domain.z_width());
+    let short_side = |ratio: f64| ((long_side as f64 * ratio).round() as u32).max(1);
+    if width >= depth {
+        [long_side, short_side(depth / width)]
+    } else {
+        [short_side(width / depth), long_side]
+    }
+}
+
+// Orthographic projection of the domain onto the image, with x to the
+// right and z up. Depth runs from the highest height, nearest, to the
+// lowest, kept just inside the clip range.
+fn top_down_matrix(domain: &Domain, (min_height, max_height): (f32, f32)) -> Matrix {
+    const DEPTH_SCALE: f32 = 0.98;
+    const DEPTH_OFFSET: f32 = 0.01;
+
+    let width = domain.x_width() as f32;
+    let depth = domain.z_width() as f32;
+    let range = if max_height > min_height {
+        max_height - min_height
+    } else {
+        1.0
+    };
+    // columns of the matrix
+    Matrix::from(cgmath::Matrix4::from([
+        [2.0 / width, 0.0, 0.0, 0.0],
+        [0.0, 0.0, -DEPTH_SCALE / range, 0.0],
+        [0.0, 2.0 / depth, 0.0, 0.0],
+        [
+            -2.0 * domain.x_min as f32 / width - 1.0,
+            -2.0 * domain.z_min as f32 / depth - 1.0,
+            DEPTH_SCALE * max_height / range + DEPTH_OFFSET,
+            1.0,
+        ],
+    ]))
+}
+
+/// Render the mesh from above at the given size and save its heights
+/// through the colormap, spanning the given height range. Blocks until
+/// the GPU has finished the copy.
+#[allow(clippy::too_many_arguments)]
+pub fn save_heatmap(
+    device: &Device,
+    queue: &Queue,
+    mesh: &MeshRenderData,
+    domain: &Domain,
+    height_range: (f32, f32),
+    colormap: Colormap,
+    [width, height]: [u32; 2],
+    filename: &str,
+) -> Result<(), String> {
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("heatmap texture"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("heatmap depth texture"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DepthBuffer::DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let camera_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        entries: &[*MatrixUniform::bind_group_layout_entry()],
+        label: Some("heatmap camera bind group layout"),
+    });
+    let camera = matrix::make_matrix_uniform(device, top_down_matrix(domain, height_range));
+    let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &camera_bind_group_layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: camera.buffer.as_entire_binding(),
+        }],
+        label: Some("heatmap camera bind group"),
+    });
+    let pipeline =
+        super::create_heatmap_pipeline::<GpuVertex>(device, &[&camera_bind_group_layout], FORMAT);
+
+    const TEXEL_SIZE: u32 = std::mem::size_of::<[f32; 2]>() as u32;
+    let unpadded_bytes_per_row = size.width * TEXEL_SIZE;
+    let padded_bytes_per_row =
+        unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Heatmap Staging Buffer"),
+        size: (padded_bytes_per_row * size.height) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("heatmap pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &camera_bind_group, &[]);
+        pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+    }
+    encoder.copy_texture_to_buffer(
+        TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &staging_buffer,
+            layout: TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(size.height),
+            },
+        },
+        size,
+    );
+    queue.submit(Some(encoder.finish()));
+
+    staging_buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |_| {});
+    device
+        .poll(wgpu::PollType::wait_indefinitely())
+        .map_err(|err| format!("Failed to read heatmap: {err}"))?;
+
+    let (min_height, max_height) = height_range;
+    let mapped_data = staging_buffer.slice(..).get_mapped_range();
+    let mut pixels = Vec::with_capacity((4 * size.width * size.height) as usize);
+    // drop the row padding
+    for row in mapped_data.chunks_exact(padded_bytes_per_row as usize) {
+        for texel in row[..unpadded_bytes_per_row as usize].chunks_exact(TEXEL_SIZE as usize) {
+            let height = f32::from_ne_bytes(texel[..4].try_into().unwrap());
+            let covered = f32::from_ne_bytes(texel[4..].try_into().unwrap());
+            if covered > 0.0 {
+                // colormap colors are linear, and PNG expects sRGB
+                let [r, g, b] = colormap
+                    .color_in_range(height, min_height, max_height)
+                    .map(|c| (255.0 * linear_to_srgb(c)).round() as u8);
+                pixels.extend_from_slice(&[r, g, b, 255]);
+            } else {
+                pixels.extend_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+    }
+    drop(mapped_data);
+    staging_buffer.unmap();
+
+    ImageBuffer::<Rgba<u8>, Vec<u8>>::from_vec(size.width, size.height, pixels)
+        .ok_or("Heatmap size doesn't match its data.")?
+        .save_with_format(filename, image::ImageFormat::Png)
+        .map_err(|err| format!("Failed to save {filename}: {err}"))
+}
@@ -1,4 +1,4 @@
-use cgmath::Matrix4;
+use cgmath::{InnerSpace, Matrix4, Transform};
 use egui_wgpu::wgpu::{
     self, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
     BindGroupLayoutDescriptor, Buffer, Device, Queue, util::DeviceExt,
@@ -30,25 +30,56 @@ pub struct LightState {
     pub camera_matrix_bind_group_layout: BindGroupLayout,
     pub camera_matrix_bind_group: BindGroup,
 
+    // min and max corners of the shadow casting meshes, which the
+    // shadow map is fit to; fixed bounds around the origin if none
+    shadow_bounds: Option<([f32; 3], [f32; 3])>,
+    // heights are scaled by this at display time, outside the bounds
+    vertical_scale: f32,
+
     // one-step light state save and restore
-    #[allow(unused)]
     pub previous_uniform: Option<LightUniform>,
 }
 
 impl LightState {
+    pub fn position(&self) -> [f32; 3] {
+        self.uniform.position
+    }
+
     pub fn set_position(&mut self, new_position: [f32; 3]) {
         self.uniform.position = new_position;
     }
 
+    /// Write the light uniform, and the shadow view matrix, which
+    /// follows the light position.
     pub fn update_uniform(&mut self, queue: &Queue) {
         queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+        self.camera_matrix
+            .uniform
+            .update_inner(Self::build_shadow_matrix(
+                &self.uniform.position,
+                self.scaled_shadow_bounds(),
+            ));
+        queue.write_buffer(
+            &self.camera_matrix.buffer,
+            0,
+            bytemuck::cast_slice(&[self.camera_matrix.uniform]),
+        );
     }
 }
 
 impl LightState {
-    const DEFAULT_LIGHT_POS: [f32; 3] = [3.0, 4.0, 0.0];
+    pub const DEFAULT_LIGHT_POS: [f32; 3] = [3.0, 4.0, 0.0];
 
     pub fn create(device: &Device) -> Self {
+        Self::with_shadow_bounds(device, None)
+    }
+
+    /// Light whose shadow map covers the given bounding box, as
+    /// min and max corners, or fixed bounds around the origin.
+    pub fn with_shadow_bounds(
+        device: &Device,
+        shadow_bounds: Option<([f32; 3], [f32; 3])>,
+    ) -> Self {
         let uniform = LightUniform {
             position: Self::DEFAULT_LIGHT_POS,
             _padding_1: 0_u32,
@@ -85,7 +116,7 @@ impl LightState {
         });
 
         // Create view matrix for use in shadow mapping.
-        let matrix = Self::build_shadow_matrix(&uniform.position);
+        let matrix = Self::build_shadow_matrix(&uniform.position, shadow_bounds);
         let matrix_uniform = Matrix::from(matrix);
         let camera_matrix = matrix::make_matrix_uniform(device, matrix_uniform);
 
@@ -103,9 +134,6 @@ impl LightState {
             label: Some("solid mesh matrix bind group"),
         });
 
-        // TODO: Later we'll want to be able to update the light
-        // during runtime, which will require updating this matrix.
-
         Self {
             uniform,
             buffer,
@@ -115,12 +143,46 @@ impl LightState {
             camera_matrix,
             camera_matrix_bind_group_layout,
             camera_matrix_bind_group,
+            shadow_bounds,
+            vertical_scale: 1.0,
             //
             previous_uniform: None,
         }
     }
 
-    fn build_shadow_matrix(position: &[f32; 3]) -> Matrix4<f32> {
+    /// Refit the shadow map to new mesh bounds, as min and max corners.
+    pub fn set_shadow_bounds(&mut self, queue: &Queue, bounds: Option<([f32; 3], [f32; 3])>) {
+        self.shadow_bounds = bounds;
+        self.update_uniform(queue);
+    }
+
+    /// Refit the shadow map after the meshes' heights are scaled by `scale`.
+    pub fn set_vertical_scale(&mut self, queue: &Queue, scale: f32) {
+        if scale != self.vertical_scale {
+            self.vertical_scale = scale;
+            self.update_uniform(queue);
+        }
+    }
+
+    fn scaled_shadow_bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        let (mut min, mut max) = self.shadow_bounds?;
+        // the scale may be negative in principle, flipping the bounds
+        let (low, high) = (min[1] * self.vertical_scale, max[1] * self.vertical_scale);
+        min[1] = low.min(high);
+        max[1] = low.max(high);
+        Some((min, max))
+    }
+
+    fn build_shadow_matrix(
+        position: &[f32; 3],
+        bounds: Option<([f32; 3], [f32; 3])>,
+    ) -> Matrix4<f32> {
+        if let Some(bounds) = bounds
+            && let Some(matrix) = Self::fitted_shadow_matrix(position, bounds)
+        {
+            return matrix;
+        }
+
         let view_target = cgmath::Point3::<f32>::from([0.0, 0.0, 0.0]);
         let view_origin = cgmath::Point3::<f32>::from(*position);
 
@@ -137,17 +199,73 @@ impl LightState {
         camera::OPENGL_TO_WGPU_MATRIX * projection * view
     }
 
+    /// Orthographic light view looking from the light at the center of the
+    /// bounding box, with the view volume fit tightly around the box, so
+    /// the whole shadow map covers the scene. None if the light is at the
+    /// center, where there's no direction to look in.
+    fn fitted_shadow_matrix(
+        position: &[f32; 3],
+        (min, max): ([f32; 3], [f32; 3]),
+    ) -> Option<Matrix4<f32>> {
+        let eye = cgmath::Point3::from(*position);
+        let center = cgmath::Point3::from(std::array::from_fn(|i| (min[i] + max[i]) / 2.0));
+        let direction = center - eye;
+        if direction.magnitude() < 1e-4 {
+            return None;
+        }
+        let up = if direction.x.abs() < 1e-4 && direction.z.abs() < 1e-4 {
+            cgmath::Vector3::unit_x()
+        } else {
+            cgmath::Vector3::unit_y()
+        };
+        let view = Matrix4::look_at_rh(eye, center, up);
+
+        // Light space extents of the box corners.
+        let mut low = [f32::INFINITY; 3];
+        let mut high = [f32::NEG_INFINITY; 3];
+        for i in 0..8 {
+            let corner = cgmath::Point3::new(
+                if i & 1 == 0 { min[0] } else { max[0] },
+                if i & 2 == 0 { min[1] } else { max[1] },
+                if i & 4 == 0 { min[2] } else { max[2] },
+            );
+            let light_space = view.transform_point(corner);
+            for axis in 0..3 {
+                low[axis] = low[axis].min(light_space[axis]);
+                high[axis] = high[axis].max(light_space[axis]);
+            }
+        }
+
+        // Pad so surfaces on the boundary, or a flat box seen edge on,
+        // stay inside the view volume.
+        let diagonal = (0..3)
+            .map(|i| (max[i] - min[i]).powi(2))
+            .sum::<f32>()
+            .sqrt();
+        let margin = 0.01 * diagonal + 1e-3;
+
+        // The view looks down the negative z-axis, so depth is -z.
+        let projection = cgmath::ortho(
+            low[0] - margin,
+            high[0] + margin,
+            low[1] - margin,
+            high[1] + margin,
+            -high[2] - margin,
+            -low[2] + margin,
+        );
+
+        Some(camera::OPENGL_TO_WGPU_MATRIX * projection * view)
+    }
+
     pub fn camera_view_matrix(&self) -> &MatrixUniform {
         &self.camera_matrix
     }
 
-    #[allow(unused)]
     pub fn save_light(&mut self) {
         self.previous_uniform = Some(self.uniform);
     }
 
     // Restores light uniform from previous state if one was saved.
-    #[allow(unused)]
     pub fn maybe_restore_light(&mut self, queue: &Queue) {
         if let Some(uniform) = self.previous_uniform.take() {
             self.uniform = uniform;
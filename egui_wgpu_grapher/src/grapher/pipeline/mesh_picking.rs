@@ -0,0 +1,346 @@
+// Find the mesh under the cursor by drawing mesh ids into an integer
+// texture and reading back the cursor's pixel.
+//
+// Only the cursor's pixel is drawn, using a scissor rect. The readback
+// is asynchronous: a pixel is copied and mapped in one update and read
+// in a later one, so the result lags the cursor by a frame or so but
+// never stalls rendering.
+
+use super::texture::DepthBuffer;
+use crate::grapher::{
+    matrix::Matrix,
+    scene::{GpuVertex, textured::InstancedMeshRenderData},
+};
+
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupLayout, BindGroupLayoutDescriptor, Buffer, Device, Queue,
+    RenderPipeline, SurfaceConfiguration, Texture, TextureView, util::DeviceExt,
+};
+use std::{
+    num::NonZeroU64,
+    sync::{Arc, Mutex},
+};
+
+// Progress of the pixel readback, shared with the buffer map callback.
+#[derive(Clone, Copy, PartialEq)]
+enum Readback {
+    Idle,
+    Mapping,
+    Mapped,
+}
+
+// Id and depth targets, resized to match the surface when picking.
+struct IdTarget {
+    texture: Texture,
+    view: TextureView,
+    depth_view: TextureView,
+}
+
+impl IdTarget {
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+
+    fn create(device: &Device, [width, height]: [u32; 2]) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("mesh id texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // The scene depth buffer may be multisampled, so the id pass has its own.
+        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("mesh id depth texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DepthBuffer::DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            view,
+            depth_view,
+        }
+    }
+}
+
+pub struct MeshPicker {
+    target: IdTarget,
+    pipeline: RenderPipeline,
+    // one id per mesh, each at a multiple of the uniform offset alignment
+    id_bind_group_layout: BindGroupLayout,
+    id_bind_group: BindGroup,
+    id_count: usize,
+    id_stride: u32,
+    // holds the picked pixel, padded to a full row
+    readback_buffer: Buffer,
+    readback: Arc<Mutex<Readback>>,
+    hovered: Option<usize>,
+}
+
+impl MeshPicker {
+    /// Takes the layouts of the camera and mesh matrix bind groups
+    /// that the instanced mesh pipeline uses.
+    pub fn create(
+        device: &Device,
+        bind_group_layouts: &[&BindGroupLayout],
+        cull_mode: Option<wgpu::Face>,
+    ) -> Self {
+        let id_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("mesh id bind group layout"),
+        });
+        let id_stride = device.limits().min_uniform_buffer_offset_alignment;
+        let id_bind_group = Self::create_id_bind_group(device, &id_bind_group_layout, id_stride, 0);
+        let pipeline =
+            Self::create_pipeline(device, bind_group_layouts, &id_bind_group_layout, cull_mode);
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("mesh id readback buffer"),
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            target: IdTarget::create(device, [1, 1]),
+            pipeline,
+            id_bind_group_layout,
+            id_bind_group,
+            id_count: 0,
+            id_stride,
+            readback_buffer,
+            readback: Arc::new(Mutex::new(Readback::Idle)),
+            hovered: None,
+        }
+    }
+
+    /// Recreate the pipeline after a change to the cull mode.
+    pub fn rebuild_pipeline(
+        &mut self,
+        device: &Device,
+        bind_group_layouts: &[&BindGroupLayout],
+        cull_mode: Option<wgpu::Face>,
+    ) {
+        self.pipeline = Self::create_pipeline(
+            device,
+            bind_group_layouts,
+            &self.id_bind_group_layout,
+            cull_mode,
+        );
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        bind_group_layouts: &[&BindGroupLayout],
+        id_bind_group_layout: &BindGroupLayout,
+        cull_mode: Option<wgpu::Face>,
+    ) -> RenderPipeline {
+        let mut layouts = bind_group_layouts.to_vec();
+        layouts.push(id_bind_group_layout);
+        super::create_mesh_id_pipeline::<GpuVertex, Matrix>(
+            device,
+            &layouts,
+            IdTarget::FORMAT,
+            cull_mode,
+        )
+    }
+
+    // Uniform buffer holding ids 1 through `count`, for meshes 0 through `count - 1`.
+    fn create_id_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        stride: u32,
+        count: usize,
+    ) -> BindGroup {
+        let mut contents = vec![0u8; stride as usize * count.max(1)];
+        for (i, slot) in contents.chunks_exact_mut(stride as usize).enumerate() {
+            slot[..4].copy_from_slice(&(i as u32 + 1).to_ne_bytes());
+        }
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mesh id UBO"),
+            contents: &contents,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(16),
+                }),
+            }],
+            label: Some("mesh id bind group"),
+        })
+    }
+
+    /// Index of the mesh under the cursor, as of the last finished readback.
+    pub fn hovered(&self) -> Option<usize> {
+        self.hovered
+    }
+
+    /// Read the result of a finished readback, then start a new one for
+    /// the pixel under the cursor if none is in flight. Without a cursor
+    /// nothing is hovered.
+    pub fn update(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        camera_bind_group: &BindGroup,
+        meshes: &[InstancedMeshRenderData],
+        cursor: Option<[f32; 2]>,
+    ) {
+        if let Err(err) = device.poll(wgpu::PollType::Poll) {
+            log::error!("Failed to poll for mesh id readback: {err}");
+        }
+        if cursor.is_none() {
+            self.hovered = None;
+        }
+        let readback = *self.readback.lock().unwrap();
+        match readback {
+            Readback::Mapping => return,
+            Readback::Mapped => {
+                let id = {
+                    let mapped_data = self.readback_buffer.slice(..).get_mapped_range();
+                    u32::from_ne_bytes(mapped_data[..4].try_into().unwrap())
+                };
+                self.readback_buffer.unmap();
+                *self.readback.lock().unwrap() = Readback::Idle;
+                self.hovered = cursor
+                    .and(id.checked_sub(1))
+                    .map(|index| index as usize)
+                    .filter(|index| *index < meshes.len());
+            }
+            Readback::Idle => {}
+        }
+
+        let size = [surface_config.width.max(1), surface_config.height.max(1)];
+        let Some(pixel) = cursor
+            .map(|[x, y]| [x.floor() as i64, y.floor() as i64])
+            .filter(|[x, y]| (0..size[0] as i64).contains(x) && (0..size[1] as i64).contains(y))
+            .map(|[x, y]| [x as u32, y as u32])
+        else {
+            self.hovered = None;
+            return;
+        };
+
+        if [self.target.texture.width(), self.target.texture.height()] != size {
+            self.target = IdTarget::create(device, size);
+        }
+        if meshes.len() > self.id_count {
+            self.id_bind_group = Self::create_id_bind_group(
+                device,
+                &self.id_bind_group_layout,
+                self.id_stride,
+                meshes.len(),
+            );
+            self.id_count = meshes.len();
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("mesh id encoder"),
+        });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("mesh id pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.target.view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.target.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(DepthBuffer::clear_value()),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_scissor_rect(pixel[0], pixel[1], 1, 1);
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, camera_bind_group, &[]);
+
+            for (index, instanced) in meshes.iter().enumerate() {
+                if !instanced.visible {
+                    continue;
+                }
+                let mesh = &instanced.mesh;
+                pass.set_bind_group(1, &mesh.matrix_bind_group, &[]);
+                pass.set_bind_group(2, &self.id_bind_group, &[index as u32 * self.id_stride]);
+                pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                pass.set_vertex_buffer(1, instanced.instance_buffer.slice(..));
+                pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                pass.draw_indexed(0..mesh.num_indices, 0, 0..instanced.num_instances);
+            }
+        }
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.target.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: pixel[0],
+                    y: pixel[1],
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(Some(encoder.finish()));
+
+        *self.readback.lock().unwrap() = Readback::Mapping;
+        let readback = self.readback.clone();
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *readback.lock().unwrap() = match result {
+                    Ok(()) => Readback::Mapped,
+                    Err(_) => Readback::Idle,
+                };
+            });
+    }
+}
@@ -1,16 +1,26 @@
+pub mod antialiasing;
+pub mod depth_of_field;
+pub mod heatmap_export;
 pub mod light;
+pub mod mesh_picking;
+pub mod reflection;
 pub mod render_preferences;
+pub mod screenshot;
+pub mod skybox;
+pub mod ssao;
+pub mod tone_map;
 
 #[allow(dead_code)]
 pub mod texture;
 
 use super::scene::Bufferable;
-use texture::DepthBuffer;
+use texture::{DepthBuffer, OffscreenTarget};
 
 use egui_wgpu::wgpu::{
     self, BindGroupLayout, ComputePipeline, Device, PipelineLayoutDescriptor, RenderPipeline,
-    ShaderSource, SurfaceConfiguration,
+    ShaderSource, TextureFormat,
 };
+use std::sync::{Arc, Mutex};
 
 // -------------------------------
 // Include shaders as static data.
@@ -19,6 +29,21 @@ pub fn get_shader() -> wgpu::ShaderSource<'static> {
     wgpu::ShaderSource::Wgsl(include_str!("shaders/shader.wgsl").into())
 }
 
+/// Solid shader with the body of its `surface_color` function replaced.
+/// The function takes the fragment's `in: VertexOutput` and returns its color.
+pub fn get_custom_surface_shader(body: &str) -> wgpu::ShaderSource<'static> {
+    const BEGIN: &str = "// surface_color begin";
+    const END: &str = "// surface_color end";
+
+    let template = include_str!("shaders/shader.wgsl");
+    let (head, rest) = template.split_once(BEGIN).unwrap();
+    let (_, tail) = rest.split_once(END).unwrap();
+    let source =
+        format!("{head}fn surface_color(in: VertexOutput) -> vec3<f32> {{\n{body}\n}}{tail}");
+
+    wgpu::ShaderSource::Wgsl(source.into())
+}
+
 pub fn get_shadow_shader() -> wgpu::ShaderSource<'static> {
     wgpu::ShaderSource::Wgsl(include_str!("shaders/shadow_shader.wgsl").into())
 }
@@ -35,16 +60,311 @@ pub fn get_solver_compute_shader() -> wgpu::ShaderSource<'static> {
     wgpu::ShaderSource::Wgsl(include_str!("shaders/solver.wgsl").into())
 }
 
+pub fn get_solver_surface_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/solver_surface_shader.wgsl").into())
+}
+
+pub fn get_fractal_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/fractal_shader.wgsl").into())
+}
+
+pub fn get_fractal_compute_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/fractal.wgsl").into())
+}
+
+pub fn get_complex_domain_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/complex_domain_shader.wgsl").into())
+}
+
+pub fn get_grid_normals_compute_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/grid_normals.wgsl").into())
+}
+
+pub fn get_image_filter_compute_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/image_filter.wgsl").into())
+}
+
+pub fn get_skybox_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/skybox.wgsl").into())
+}
+
+pub fn get_instanced_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/instanced_shader.wgsl").into())
+}
+
+pub fn get_mesh_id_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/mesh_id.wgsl").into())
+}
+
+pub fn get_heatmap_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/heatmap.wgsl").into())
+}
+
+pub fn get_points_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/points.wgsl").into())
+}
+
+pub fn get_lines_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/lines.wgsl").into())
+}
+
+pub fn get_tone_map_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/tone_map.wgsl").into())
+}
+
+pub fn get_depth_of_field_shader() -> wgpu::ShaderSource<'static> {
+    depth_reading_shader(include_str!("shaders/depth_of_field.wgsl"))
+}
+
+pub fn get_ssao_shader() -> wgpu::ShaderSource<'static> {
+    depth_reading_shader(include_str!("shaders/ssao.wgsl"))
+}
+
+/// Shaders that read the depth buffer declare it multisampled; without MSAA
+/// it is a plain depth texture. Their `textureLoad` calls work for either.
+/// They also declare the far plane depth, which is 0 with reverse-Z.
+fn depth_reading_shader(source: &'static str) -> wgpu::ShaderSource<'static> {
+    let mut source = source.to_string();
+    if antialiasing::sample_count() == 1 {
+        source = source.replace("texture_depth_multisampled_2d", "texture_depth_2d");
+    }
+    if texture::reverse_z() {
+        source = source.replace("const FAR_DEPTH: f32 = 1.0;", "const FAR_DEPTH: f32 = 0.0;");
+    }
+    wgpu::ShaderSource::Wgsl(source.into())
+}
+
+pub fn get_floor_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/floor.wgsl").into())
+}
+
+pub fn get_reference_plane_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/reference_plane.wgsl").into())
+}
+
+pub fn get_fxaa_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/fxaa.wgsl").into())
+}
+
+pub fn get_ssao_blur_shader() -> wgpu::ShaderSource<'static> {
+    wgpu::ShaderSource::Wgsl(include_str!("shaders/ssao_blur.wgsl").into())
+}
+
+// ----------------------------------------------------
+// Report validation errors, like WGSL compile failures.
+
+/// Log errors not caught by an error scope instead of panicking,
+/// which is wgpu's default. Repeats of the last error are skipped,
+/// since using an invalid object fails again on every frame.
+pub fn log_uncaptured_errors(device: &Device) {
+    let last_error = Mutex::new(String::new());
+    device.on_uncaptured_error(Arc::new(move |err: wgpu::Error| {
+        let message = err.to_string();
+        let mut last_error = last_error.lock().unwrap();
+        if *last_error != message {
+            log::error!("Wgpu error: {message}");
+            *last_error = message;
+        }
+    }));
+}
+
+/// Device and queue for tests, from any available adapter, including a
+/// software one. `None` if there is no adapter, so tests can be skipped.
+#[cfg(test)]
+pub(crate) fn test_device() -> Option<(Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .ok()?;
+    // as at startup, leave out what the adapter's shaders can't do
+    texture::set_depth_readable(texture::adapter_reads_depth(&adapter));
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).ok()
+}
+
+/// Run `create` in validation and internal error scopes, returning the error
+/// message if it failed. Shaders that can't be translated for the backend,
+/// as can happen on GL, give internal errors.
+pub fn with_error_scope<T>(device: &Device, create: impl FnOnce() -> T) -> Result<T, String> {
+    device.push_error_scope(wgpu::ErrorFilter::Internal);
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = create();
+    let validation = pollster::block_on(device.pop_error_scope());
+    let internal = pollster::block_on(device.pop_error_scope());
+    match validation.or(internal) {
+        Some(err) => Err(err.to_string()),
+        None => Ok(value),
+    }
+}
+
+/// Like `create_depth_mode_pipeline`, but returns shader and pipeline
+/// errors so the caller can keep using a previous pipeline.
+pub fn try_create_depth_mode_pipeline<Vertex: Bufferable>(
+    device: &Device,
+    shader: wgpu::ShaderSource<'static>,
+    bind_group_layouts: &[&BindGroupLayout],
+    polygon_mode: wgpu::PolygonMode,
+    cull_mode: Option<wgpu::Face>,
+    depth_mode: DepthMode,
+) -> Result<RenderPipeline, String> {
+    with_error_scope(device, || {
+        create_depth_mode_pipeline::<Vertex>(
+            device,
+            shader,
+            bind_group_layouts,
+            polygon_mode,
+            cull_mode,
+            depth_mode,
+        )
+    })
+}
+
+/// Like `create_compute_pipeline`, but returns shader and pipeline errors.
+pub fn try_create_compute_pipeline(
+    device: &Device,
+    shader_source: ShaderSource,
+    bind_group_layouts: &[&BindGroupLayout],
+) -> Result<ComputePipeline, String> {
+    with_error_scope(device, || {
+        create_compute_pipeline(device, shader_source, bind_group_layouts)
+    })
+}
+
 // -------------------------
 // Create a render pipeline.
 
+/// How a pipeline uses the depth buffer, which depends on whether
+/// the surfaces it draws are transparent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthMode {
+    // tested and written, for opaque surfaces
+    Opaque,
+    // tested but not written, so all layers blend, in draw order
+    Transparent,
+    // written without color, so a following transparent pass only
+    // draws the nearest layer
+    Prepass,
+}
+
 pub fn create_render_pipeline<Vertex: Bufferable>(
     device: &Device,
-    config: &SurfaceConfiguration,
     shader: wgpu::ShaderSource<'static>,
     bind_group_layouts: &[&BindGroupLayout],
     polygon_mode: wgpu::PolygonMode,
+    cull_mode: Option<wgpu::Face>,
+) -> RenderPipeline {
+    create_depth_mode_pipeline::<Vertex>(
+        device,
+        shader,
+        bind_group_layouts,
+        polygon_mode,
+        cull_mode,
+        DepthMode::Opaque,
+    )
+}
+
+pub fn create_depth_mode_pipeline<Vertex: Bufferable>(
+    device: &Device,
+    shader: wgpu::ShaderSource<'static>,
+    bind_group_layouts: &[&BindGroupLayout],
+    polygon_mode: wgpu::PolygonMode,
+    cull_mode: Option<wgpu::Face>,
+    depth_mode: DepthMode,
+) -> RenderPipeline {
+    render_pipeline_with_buffers(
+        device,
+        shader,
+        bind_group_layouts,
+        polygon_mode,
+        cull_mode,
+        &[Vertex::buffer_layout()],
+        depth_mode,
+        antialiasing::sample_count(),
+        false,
+    )
+}
+
+/// Opaque pipeline like `create_render_pipeline`, optionally with alpha
+/// to coverage, for shaders whose output alpha is a texture's cutout.
+/// Alpha to coverage needs MSAA, so it's ignored with one sample.
+pub fn create_cutout_render_pipeline<Vertex: Bufferable>(
+    device: &Device,
+    shader: wgpu::ShaderSource<'static>,
+    bind_group_layouts: &[&BindGroupLayout],
+    polygon_mode: wgpu::PolygonMode,
+    cull_mode: Option<wgpu::Face>,
+    alpha_to_coverage: bool,
+) -> RenderPipeline {
+    render_pipeline_with_buffers(
+        device,
+        shader,
+        bind_group_layouts,
+        polygon_mode,
+        cull_mode,
+        &[Vertex::buffer_layout()],
+        DepthMode::Opaque,
+        antialiasing::sample_count(),
+        alpha_to_coverage,
+    )
+}
+
+/// Opaque pipeline like `create_render_pipeline`, for rendering without
+/// MSAA into a single-sampled target, like a planar reflection.
+pub fn create_single_sample_pipeline<Vertex: Bufferable>(
+    device: &Device,
+    shader: wgpu::ShaderSource<'static>,
+    bind_group_layouts: &[&BindGroupLayout],
+) -> RenderPipeline {
+    render_pipeline_with_buffers(
+        device,
+        shader,
+        bind_group_layouts,
+        wgpu::PolygonMode::Fill,
+        None,
+        &[Vertex::buffer_layout()],
+        DepthMode::Opaque,
+        1,
+        false,
+    )
+}
+
+// Create a render pipeline whose second vertex buffer holds per-instance data,
+// optionally with alpha to coverage like `create_cutout_render_pipeline`.
+pub fn create_instanced_render_pipeline<Vertex: Bufferable, Instance: Bufferable>(
+    device: &Device,
+    shader: wgpu::ShaderSource<'static>,
+    bind_group_layouts: &[&BindGroupLayout],
+    polygon_mode: wgpu::PolygonMode,
+    cull_mode: Option<wgpu::Face>,
+    alpha_to_coverage: bool,
+) -> RenderPipeline {
+    render_pipeline_with_buffers(
+        device,
+        shader,
+        bind_group_layouts,
+        polygon_mode,
+        cull_mode,
+        &[Vertex::buffer_layout(), Instance::buffer_layout()],
+        DepthMode::Opaque,
+        antialiasing::sample_count(),
+        alpha_to_coverage,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_pipeline_with_buffers(
+    device: &Device,
+    shader: wgpu::ShaderSource<'static>,
+    bind_group_layouts: &[&BindGroupLayout],
+    polygon_mode: wgpu::PolygonMode,
+    cull_mode: Option<wgpu::Face>,
+    buffers: &[wgpu::VertexBufferLayout],
+    depth_mode: DepthMode,
+    sample_count: u32,
+    alpha_to_coverage: bool,
 ) -> RenderPipeline {
+    // Coverage from alpha replaces blending, and is only valid with MSAA.
+    let alpha_to_coverage = alpha_to_coverage && sample_count > 1;
+
     let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("a shader"),
         source: shader,
@@ -62,16 +382,19 @@ pub fn create_render_pipeline<Vertex: Bufferable>(
         vertex: wgpu::VertexState {
             module: &shader_module,
             entry_point: Some("vs_main"),
-            buffers: &[Vertex::buffer_layout()],
+            buffers,
             compilation_options: wgpu::PipelineCompilationOptions::default(),
         },
         fragment: Some(wgpu::FragmentState {
             module: &shader_module,
             entry_point: Some("fs_main"),
             targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
-                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                write_mask: wgpu::ColorWrites::ALL,
+                format: OffscreenTarget::FORMAT,
+                blend: (!alpha_to_coverage).then_some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: match depth_mode {
+                    DepthMode::Prepass => wgpu::ColorWrites::empty(),
+                    _ => wgpu::ColorWrites::ALL,
+                },
             })],
             compilation_options: wgpu::PipelineCompilationOptions::default(),
         }),
@@ -79,20 +402,281 @@ pub fn create_render_pipeline<Vertex: Bufferable>(
             topology: wgpu::PrimitiveTopology::TriangleList,
             strip_index_format: None,
             front_face: wgpu::FrontFace::Ccw,
-            cull_mode: Some(wgpu::Face::Back),
+            // Open surfaces like graphs are seen from both sides, so culling
+            // is off unless requested; the shaders flip the normals of back
+            // faces for lighting.
+            cull_mode,
             polygon_mode,
             unclipped_depth: false,
             conservative: false,
         },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DepthBuffer::DEPTH_FORMAT,
+            depth_write_enabled: depth_mode != DepthMode::Transparent,
+            // Equal passes where a prepass wrote this surface's depth.
+            depth_compare: DepthBuffer::compare(match depth_mode {
+                DepthMode::Transparent => wgpu::CompareFunction::LessEqual,
+                _ => wgpu::CompareFunction::Less,
+            }),
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: alpha_to_coverage,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+// -------------------------------------------------
+// Create pipeline that draws each vertex as a point.
+//
+// Points are drawn as instanced quads: the vertex buffer is stepped
+// once per instance, and each instance is expanded into two triangles
+// in the vertex shader, so points can have a size on every backend.
+
+pub fn create_point_pipeline<Vertex: Bufferable>(
+    device: &Device,
+    bind_group_layouts: &[&BindGroupLayout],
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("point shader"),
+        source: get_points_shader(),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("point pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    let mut instance_layout = Vertex::buffer_layout();
+    instance_layout.step_mode = wgpu::VertexStepMode::Instance;
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("point pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: Some("vs_main"),
+            buffers: &[instance_layout],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader_module,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: OffscreenTarget::FORMAT,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            // quads always face the camera
+            cull_mode: None,
+            ..Default::default()
+        },
         depth_stencil: Some(wgpu::DepthStencilState {
             format: DepthBuffer::DEPTH_FORMAT,
             depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
+            depth_compare: DepthBuffer::compare(wgpu::CompareFunction::Less),
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: antialiasing::sample_count(),
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+// -----------------------------------------------
+// Create pipeline that draws vertex pairs as lines.
+
+pub fn create_line_pipeline<Vertex: Bufferable>(
+    device: &Device,
+    bind_group_layouts: &[&BindGroupLayout],
+    always_on_top: bool,
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("line shader"),
+        source: get_lines_shader(),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("line pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("line pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::buffer_layout()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader_module,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: OffscreenTarget::FORMAT,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::LineList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DepthBuffer::DEPTH_FORMAT,
+            depth_write_enabled: !always_on_top,
+            depth_compare: if always_on_top {
+                wgpu::CompareFunction::Always
+            } else {
+                DepthBuffer::compare(wgpu::CompareFunction::LessEqual)
+            },
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: antialiasing::sample_count(),
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+// -------------------------------------------------
+// Create pipeline that draws the reflective floor.
+
+/// Depth-tested pipeline for the floor square, with vertex
+/// positions generated in the shader from the vertex index.
+pub fn create_floor_pipeline(
+    device: &Device,
+    bind_group_layouts: &[&BindGroupLayout],
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("floor shader"),
+        source: get_floor_shader(),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("floor pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("floor pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader_module,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: OffscreenTarget::FORMAT,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            // seen from above or below
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DepthBuffer::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: DepthBuffer::compare(wgpu::CompareFunction::Less),
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: antialiasing::sample_count(),
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+// ----------------------------------------------------
+// Create pipeline that draws a graph's reference plane.
+
+/// Alpha-blended pipeline for the reference plane, with vertex positions
+/// generated in the shader from the vertex index. It's depth-tested against
+/// the scene drawn before it, but doesn't write depth, so what it covers
+/// shows through.
+pub fn create_reference_plane_pipeline(
+    device: &Device,
+    bind_group_layouts: &[&BindGroupLayout],
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("reference plane shader"),
+        source: get_reference_plane_shader(),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("reference plane pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("reference plane pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader_module,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: OffscreenTarget::FORMAT,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            // seen from above or below
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DepthBuffer::DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: DepthBuffer::compare(wgpu::CompareFunction::LessEqual),
             stencil: wgpu::StencilState::default(),
             bias: wgpu::DepthBiasState::default(),
         }),
         multisample: wgpu::MultisampleState {
-            count: 4,
+            count: antialiasing::sample_count(),
             ..Default::default()
         },
         multiview: None,
@@ -132,7 +716,8 @@ pub fn create_shadow_pipeline<Vertex: Bufferable>(
         primitive: wgpu::PrimitiveState {
             topology: wgpu::PrimitiveTopology::TriangleList,
             front_face: wgpu::FrontFace::Ccw,
-            cull_mode: Some(wgpu::Face::Back),
+            // Surfaces cast shadows whichever side faces the light.
+            cull_mode: None,
             unclipped_depth: device
                 .features()
                 .contains(wgpu::Features::DEPTH_CLIP_CONTROL),
@@ -159,17 +744,190 @@ pub fn create_shadow_pipeline<Vertex: Bufferable>(
     })
 }
 
+/// Fill the scene depth buffer only, from the camera's point of view.
+/// Uses the shadow map shader, which needs only a matrix in group 0.
+pub fn create_depth_only_pipeline<Vertex: Bufferable>(
+    device: &Device,
+    bind_group_layouts: &[&BindGroupLayout],
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("depth only shader"),
+        source: get_shadow_shader(),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("depth only pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("depth only pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::buffer_layout()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: None,
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DepthBuffer::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: DepthBuffer::compare(wgpu::CompareFunction::Less),
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: antialiasing::sample_count(),
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Pipeline that draws instanced meshes into an integer target as mesh
+/// ids, read back to pick the mesh under the cursor. Unlike the scene
+/// pipelines it is never multisampled, as integer targets can't be resolved.
+pub fn create_mesh_id_pipeline<Vertex: Bufferable, Instance: Bufferable>(
+    device: &Device,
+    bind_group_layouts: &[&BindGroupLayout],
+    format: TextureFormat,
+    cull_mode: Option<wgpu::Face>,
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("mesh id shader"),
+        source: get_mesh_id_shader(),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("mesh id pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("mesh id pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::buffer_layout(), Instance::buffer_layout()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader_module,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DepthBuffer::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: DepthBuffer::compare(wgpu::CompareFunction::Less),
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Pipeline writing surface heights seen from above, for heatmap export.
+/// It has its own depth target, with near as less whatever the depth
+/// direction of the scene.
+pub fn create_heatmap_pipeline<Vertex: Bufferable>(
+    device: &Device,
+    bind_group_layouts: &[&BindGroupLayout],
+    format: TextureFormat,
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("heatmap shader"),
+        source: get_heatmap_shader(),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("heatmap pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("heatmap pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::buffer_layout()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader_module,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DepthBuffer::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
 // ---------------------------------------
 // Create pipeline setup for 2D rendering.
 
 pub fn create_solver_pipeline(
     device: &Device,
-    config: &SurfaceConfiguration,
+    bind_group_layouts: &[&BindGroupLayout],
+) -> RenderPipeline {
+    create_canvas_pipeline(device, get_solver_shader(), bind_group_layouts)
+}
+
+/// Pipeline for a shader that draws a textured quad on a fixed 2D canvas,
+/// with vertex positions generated in the shader from the vertex index.
+pub fn create_canvas_pipeline(
+    device: &Device,
+    shader: wgpu::ShaderSource<'static>,
     bind_group_layouts: &[&BindGroupLayout],
 ) -> RenderPipeline {
     let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("a shader"),
-        source: get_solver_shader(),
+        source: shader,
     });
 
     let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -191,7 +949,7 @@ pub fn create_solver_pipeline(
             module: &shader_module,
             entry_point: Some("fs_main"),
             targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
+                format: OffscreenTarget::FORMAT,
                 blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
@@ -208,7 +966,7 @@ pub fn create_solver_pipeline(
         },
         depth_stencil: None,
         multisample: wgpu::MultisampleState {
-            count: 4,
+            count: antialiasing::sample_count(),
             ..Default::default()
         },
         multiview: None,
@@ -216,6 +974,59 @@ pub fn create_solver_pipeline(
     })
 }
 
+// ----------------------------------------------
+// Create pipeline for a full-screen post pass.
+
+/// Pipeline for a shader that draws a single full-screen triangle,
+/// with vertex positions generated in the shader from the vertex index.
+pub fn create_fullscreen_pipeline(
+    device: &Device,
+    shader: wgpu::ShaderSource<'static>,
+    format: TextureFormat,
+    bind_group_layouts: &[&BindGroupLayout],
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("a full-screen shader"),
+        source: shader,
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("a full-screen pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("a full-screen pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader_module,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
 // --------------------------
 // Create a compute pipeline.
 
@@ -223,6 +1034,16 @@ pub fn create_compute_pipeline(
     device: &Device,
     shader_source: ShaderSource,
     bind_group_layouts: &[&BindGroupLayout],
+) -> ComputePipeline {
+    create_compute_pipeline_with_constants(device, shader_source, bind_group_layouts, &[])
+}
+
+/// Compute pipeline with values for the shader's `override` constants.
+pub fn create_compute_pipeline_with_constants(
+    device: &Device,
+    shader_source: ShaderSource,
+    bind_group_layouts: &[&BindGroupLayout],
+    constants: &[(&str, f64)],
 ) -> ComputePipeline {
     let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
         label: None,
@@ -238,7 +1059,96 @@ pub fn create_compute_pipeline(
         layout: Some(&pipeline_layout),
         module: &module,
         entry_point: Some("run"),
-        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        compilation_options: wgpu::PipelineCompilationOptions {
+            constants,
+            ..Default::default()
+        },
         cache: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shaders_compile() {
+        let Some((device, _queue)) = test_device() else {
+            eprintln!("no adapter, skipping");
+            return;
+        };
+        let shaders = [
+            ("shader", get_shader()),
+            (
+                "custom surface",
+                get_custom_surface_shader("return in.color;"),
+            ),
+            ("shadow", get_shadow_shader()),
+            ("textured", get_textured_shader()),
+            ("solver", get_solver_shader()),
+            ("solver compute", get_solver_compute_shader()),
+            ("solver surface", get_solver_surface_shader()),
+            ("fractal", get_fractal_shader()),
+            ("fractal compute", get_fractal_compute_shader()),
+            ("complex domain", get_complex_domain_shader()),
+            ("grid normals", get_grid_normals_compute_shader()),
+            ("image filter", get_image_filter_compute_shader()),
+            ("skybox", get_skybox_shader()),
+            ("instanced", get_instanced_shader()),
+            ("mesh id", get_mesh_id_shader()),
+            ("heatmap", get_heatmap_shader()),
+            ("points", get_points_shader()),
+            ("lines", get_lines_shader()),
+            ("tone map", get_tone_map_shader()),
+            ("depth of field", get_depth_of_field_shader()),
+            ("ssao", get_ssao_shader()),
+            ("floor", get_floor_shader()),
+            ("reference plane", get_reference_plane_shader()),
+            ("fxaa", get_fxaa_shader()),
+            ("ssao blur", get_ssao_blur_shader()),
+        ];
+        let failures: Vec<String> = shaders
+            .into_iter()
+            .filter_map(|(name, source)| {
+                with_error_scope(&device, || {
+                    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                        label: Some(name),
+                        source,
+                    })
+                })
+                .err()
+                .map(|err| format!("{name}: {err}"))
+            })
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    // Pipelines are checked when created, and on GL that is also when
+    // shaders are translated, so build the renderer's own pipelines.
+    #[test]
+    fn render_state_pipelines_build() {
+        let Some((device, _queue)) = test_device() else {
+            eprintln!("no adapter, skipping");
+            return;
+        };
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            width: 64,
+            height: 64,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+        let result = with_error_scope(&device, || {
+            pollster::block_on(crate::grapher::render::RenderState::new(
+                &device,
+                &surface_config,
+            ))
+        });
+        if let Err(err) = result {
+            panic!("{err}");
+        }
+    }
+}
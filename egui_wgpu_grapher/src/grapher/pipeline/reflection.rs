@@ -0,0 +1,393 @@
+// Planar reflection of a scene's solid meshes in a square floor below them.
+// The meshes are drawn with a camera mirrored across the floor plane into a
+// reflection target, which the floor samples at each of its pixels.
+
+use super::{
+    render_preferences::RenderPreferences,
+    ssao::SsaoState,
+    texture::{DepthBuffer, OffscreenTarget},
+};
+use crate::grapher::{
+    matrix::{self, Matrix, MatrixUniform},
+    scene::{GpuVertex, Scene3D, solid::MeshRenderData},
+};
+
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, Buffer, CommandEncoder, Device, Queue, RenderPass, RenderPipeline,
+    Sampler, SurfaceConfiguration, Texture, TextureView, util::DeviceExt,
+};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct FloorUniform {
+    color: [f32; 3],
+    // fraction of the floor color replaced by the reflection
+    reflectivity: f32,
+    height: f32,
+    half_width: f32,
+    _padding: [f32; 2],
+}
+
+// Color and depth targets the mirrored meshes are drawn into.
+struct ReflectionTarget {
+    _texture: Texture,
+    view: TextureView,
+    _depth_texture: Texture,
+    depth_view: TextureView,
+}
+
+impl ReflectionTarget {
+    fn create(config: &SurfaceConfiguration, device: &Device) -> Self {
+        let size = wgpu::Extent3d {
+            width: config.width.max(1),
+            height: config.height.max(1),
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("reflection color target"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: OffscreenTarget::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("reflection depth buffer"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DepthBuffer::DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        Self {
+            view: texture.create_view(&Default::default()),
+            _texture: texture,
+            depth_view: depth_texture.create_view(&Default::default()),
+            _depth_texture: depth_texture,
+        }
+    }
+}
+
+pub struct FloorReflection {
+    pub enabled: bool,
+    // fraction of the floor color replaced by the reflection
+    pub reflectivity: f32,
+    // height of the floor plane
+    pub height: f32,
+
+    // mirrored camera and preferences for the reflection pass, bound
+    // in place of the shared resources bind group
+    camera: MatrixUniform,
+    preferences_buffer: Buffer,
+    bind_group: BindGroup,
+
+    target: ReflectionTarget,
+    sampler: Sampler,
+    floor_buffer: Buffer,
+    // references the reflection target, so is rebuilt on resize
+    floor_bind_group_layout: BindGroupLayout,
+    floor_bind_group: BindGroup,
+    floor_pipeline: RenderPipeline,
+}
+
+impl FloorReflection {
+    // the floor spans the graph domain
+    pub const HALF_WIDTH: f32 = 3.0;
+    pub const DEFAULT_HEIGHT: f32 = -1.0;
+    pub const DEFAULT_REFLECTIVITY: f32 = 0.4;
+    const COLOR: [f32; 3] = [0.12, 0.12, 0.14];
+
+    pub fn create(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        shared_bind_group_layout: &BindGroupLayout,
+        render_preferences: &RenderPreferences,
+        ssao: &SsaoState,
+    ) -> Self {
+        let camera = matrix::make_matrix_uniform(device, Matrix::identity());
+        let preferences_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("reflection preferences UBO"),
+            contents: bytemuck::cast_slice(&[render_preferences.uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = Self::create_bind_group(
+            device,
+            shared_bind_group_layout,
+            &camera,
+            &preferences_buffer,
+            ssao,
+        );
+
+        let target = ReflectionTarget::create(surface_config, device);
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("reflection sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let floor_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("floor UBO"),
+            contents: bytemuck::cast_slice(&[Self::floor_uniform(
+                Self::DEFAULT_REFLECTIVITY,
+                Self::DEFAULT_HEIGHT,
+            )]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let floor_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("floor bind group layout"),
+        });
+        let floor_bind_group = Self::create_floor_bind_group(
+            device,
+            &floor_bind_group_layout,
+            &floor_buffer,
+            &target,
+            &sampler,
+        );
+        let floor_pipeline = super::create_floor_pipeline(
+            device,
+            &[shared_bind_group_layout, &floor_bind_group_layout],
+        );
+
+        Self {
+            enabled: false,
+            reflectivity: Self::DEFAULT_REFLECTIVITY,
+            height: Self::DEFAULT_HEIGHT,
+            camera,
+            preferences_buffer,
+            bind_group,
+            target,
+            sampler,
+            floor_buffer,
+            floor_bind_group_layout,
+            floor_bind_group,
+            floor_pipeline,
+        }
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        camera: &MatrixUniform,
+        preferences_buffer: &Buffer,
+        ssao: &SsaoState,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: camera.buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: preferences_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(ssao.view()),
+                },
+            ],
+            label: Some("reflection shared resources bind group"),
+        })
+    }
+
+    fn create_floor_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        floor_buffer: &Buffer,
+        target: &ReflectionTarget,
+        sampler: &Sampler,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: floor_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&target.view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("floor bind group"),
+        })
+    }
+
+    /// Pipeline that draws a scene's solid meshes into the reflection target.
+    /// It uses the scene's light and shadow layouts, so each scene makes its own.
+    pub fn create_reflection_pipeline(
+        device: &Device,
+        shared_bind_group_layout: &BindGroupLayout,
+        scene: &Scene3D,
+    ) -> Option<RenderPipeline> {
+        let shadow = scene.shadow.as_ref()?;
+        Some(super::create_single_sample_pipeline::<GpuVertex>(
+            device,
+            super::get_shader(),
+            &[
+                shared_bind_group_layout,
+                MeshRenderData::matrix_bgl(device),
+                &scene.light.bind_group_layout,
+                &shadow.render_pass_bind_group_layout,
+            ],
+        ))
+    }
+
+    /// Write the mirrored camera and the floor parameters.
+    pub fn update_uniforms(
+        &mut self,
+        queue: &Queue,
+        camera_matrix: Matrix,
+        render_preferences: &RenderPreferences,
+    ) {
+        // reflect across the plane y = height
+        let mirror = cgmath::Matrix4::from_translation(cgmath::Vector3::new(0.0, self.height, 0.0))
+            * cgmath::Matrix4::from_nonuniform_scale(1.0, -1.0, 1.0)
+            * cgmath::Matrix4::from_translation(cgmath::Vector3::new(0.0, -self.height, 0.0));
+        self.camera.uniform = camera_matrix * Matrix::from(mirror);
+        queue.write_buffer(
+            &self.camera.buffer,
+            0,
+            bytemuck::cast_slice(&[self.camera.uniform]),
+        );
+
+        let preferences = render_preferences.reflection_uniform(self.height);
+        queue.write_buffer(
+            &self.preferences_buffer,
+            0,
+            bytemuck::cast_slice(&[preferences]),
+        );
+
+        let floor = Self::floor_uniform(self.reflectivity, self.height);
+        queue.write_buffer(&self.floor_buffer, 0, bytemuck::cast_slice(&[floor]));
+    }
+
+    fn floor_uniform(reflectivity: f32, height: f32) -> FloorUniform {
+        FloorUniform {
+            color: Self::COLOR,
+            reflectivity,
+            height,
+            half_width: Self::HALF_WIDTH,
+            _padding: [0.0; 2],
+        }
+    }
+
+    pub fn handle_resize(
+        &mut self,
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        shared_bind_group_layout: &BindGroupLayout,
+        ssao: &SsaoState,
+    ) {
+        self.target = ReflectionTarget::create(surface_config, device);
+        self.floor_bind_group = Self::create_floor_bind_group(
+            device,
+            &self.floor_bind_group_layout,
+            &self.floor_buffer,
+            &self.target,
+            &self.sampler,
+        );
+        // the occlusion texture is recreated on resize too
+        self.bind_group = Self::create_bind_group(
+            device,
+            shared_bind_group_layout,
+            &self.camera,
+            &self.preferences_buffer,
+            ssao,
+        );
+    }
+
+    /// Draw the scene's solid meshes mirrored below the floor into the reflection target.
+    pub fn render_reflection(
+        &self,
+        encoder: &mut CommandEncoder,
+        scene: &Scene3D,
+        pipeline: &RenderPipeline,
+    ) {
+        let Some(shadow) = &scene.shadow else {
+            return;
+        };
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("reflection pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.target.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    // transparent where nothing is reflected
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.target.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(DepthBuffer::clear_value()),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_bind_group(2, &scene.light.bind_group, &[]);
+        render_pass.set_bind_group(3, &shadow.render_pass_bind_group, &[]);
+
+        for mesh in &scene.meshes {
+            render_pass.set_bind_group(1, &mesh.matrix_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+        }
+    }
+
+    /// Draw the floor into a render pass that already holds the scene.
+    pub fn draw_floor(&self, render_pass: &mut RenderPass, shared_bind_group: &BindGroup) {
+        render_pass.set_pipeline(&self.floor_pipeline);
+        render_pass.set_bind_group(0, shared_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.floor_bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+}
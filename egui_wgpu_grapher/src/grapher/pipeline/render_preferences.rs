@@ -1,5 +1,8 @@
 // Preferences passed to shaders as a uniform.
 
+use super::{DepthMode, antialiasing};
+use crate::grapher::{math::graph::Normals, render::CLEAR_COLOR};
+
 use egui_wgpu::wgpu::{
     self, BindGroupLayoutEntry, Buffer, Device, PolygonMode, Queue, util::DeviceExt,
 };
@@ -10,6 +13,16 @@ use egui_wgpu::wgpu::{
 pub struct ShaderPreferencesUniform {
     // see constants defined below
     pub flags: u32,
+    // exponential-squared fog falloff per unit of view depth
+    pub fog_density: f32,
+    // alpha of lit surfaces in the solid pipeline
+    pub opacity: f32,
+    _padding: u32,
+    // color fragments are blended toward with distance
+    pub fog_color: [f32; 3],
+    _padding_2: f32,
+    // plane normal and offset; fragments with dot(n, p) + d < 0 are discarded
+    pub clip_plane: [f32; 4],
 }
 
 pub struct RenderPreferences {
@@ -19,12 +32,28 @@ pub struct RenderPreferences {
     pub bind_group_layout_entry: BindGroupLayoutEntry,
     // render pipeline preferences
     pub polygon_mode: PolygonMode,
+    // faces left undrawn, none by default since surfaces are two-sided
+    pub cull_mode: Option<wgpu::Face>,
+    // draw only the nearest layer of transparent surfaces
+    pub depth_prepass: bool,
+    // how normals of tessellated surfaces are computed
+    pub shading: Normals,
 }
 
 // Preference bit meanings.
 const LIGHTING_BIT: u32 = 1_u32;
 const TEXTURE_BIT: u32 = 2_u32;
 const SHADOW_BIT: u32 = 4_u32;
+const FOG_BIT: u32 = 8_u32;
+const CLIP_BIT: u32 = 16_u32;
+const TWO_SIDED_BIT: u32 = 32_u32;
+const SSAO_BIT: u32 = 64_u32;
+// Textured shaders output texture alpha for alpha to coverage.
+const ALPHA_COVERAGE_BIT: u32 = 128_u32;
+// Textured shaders discard fragments with texture alpha below one half.
+const ALPHA_TEST_BIT: u32 = 256_u32;
+
+pub const DEFAULT_FOG_DENSITY: f32 = 0.05;
 
 impl RenderPreferences {
     pub fn lighting_enabled(&self) -> bool {
@@ -51,6 +80,100 @@ impl RenderPreferences {
         }
     }
 
+    pub fn fog_enabled(&self) -> bool {
+        self.uniform.flags & FOG_BIT > 0
+    }
+
+    pub fn set_fog_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.uniform.flags |= FOG_BIT;
+        } else {
+            self.uniform.flags &= !FOG_BIT;
+        }
+    }
+
+    pub fn clip_enabled(&self) -> bool {
+        self.uniform.flags & CLIP_BIT > 0
+    }
+
+    pub fn set_clip_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.uniform.flags |= CLIP_BIT;
+        } else {
+            self.uniform.flags &= !CLIP_BIT;
+        }
+    }
+
+    /// Clip against the plane normal to the given axis (0, 1, 2 for x, y, z)
+    /// through `offset` on it. Geometry below the offset is kept when flipped.
+    pub fn set_axis_clip_plane(&mut self, axis: usize, offset: f32, flipped: bool) {
+        let sign = if flipped { -1.0 } else { 1.0 };
+        let mut plane = [0.0; 4];
+        plane[axis.min(2)] = sign;
+        plane[3] = -sign * offset;
+        self.uniform.clip_plane = plane;
+    }
+
+    pub fn two_sided_lighting_enabled(&self) -> bool {
+        self.uniform.flags & TWO_SIDED_BIT > 0
+    }
+
+    pub fn set_two_sided_lighting(&mut self, enabled: bool) {
+        if enabled {
+            self.uniform.flags |= TWO_SIDED_BIT;
+        } else {
+            self.uniform.flags &= !TWO_SIDED_BIT;
+        }
+    }
+
+    pub fn ssao_enabled(&self) -> bool {
+        self.uniform.flags & SSAO_BIT > 0
+    }
+
+    pub fn set_ssao_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.uniform.flags |= SSAO_BIT;
+        } else {
+            self.uniform.flags &= !SSAO_BIT;
+        }
+    }
+
+    /// Whether textured scenes cut out transparent parts of their textures.
+    pub fn alpha_cutout_enabled(&self) -> bool {
+        self.uniform.flags & (ALPHA_COVERAGE_BIT | ALPHA_TEST_BIT) > 0
+    }
+
+    /// Cut out transparent parts of textures. Alpha to coverage needs more
+    /// than one sample per pixel, so without MSAA the cutout falls back to
+    /// a hard alpha test.
+    pub fn set_alpha_cutout(&mut self, enabled: bool) {
+        let multisampled = antialiasing::sample_count() > 1;
+        self.uniform.flags &= !(ALPHA_COVERAGE_BIT | ALPHA_TEST_BIT);
+        if enabled && multisampled {
+            self.uniform.flags |= ALPHA_COVERAGE_BIT;
+        } else if enabled {
+            self.uniform.flags |= ALPHA_TEST_BIT;
+        }
+    }
+
+    /// Whether textured pipelines should be built with alpha to coverage.
+    pub fn alpha_to_coverage(&self) -> bool {
+        self.uniform.flags & ALPHA_COVERAGE_BIT > 0
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.uniform.opacity < 1.0
+    }
+
+    /// Depth mode for the solid pipeline, and whether it needs a depth prepass.
+    pub fn depth_mode(&self) -> (DepthMode, bool) {
+        if self.is_transparent() {
+            (DepthMode::Transparent, self.depth_prepass)
+        } else {
+            (DepthMode::Opaque, false)
+        }
+    }
+
     pub fn set_use_texture(&mut self, enabled: bool) {
         if enabled {
             self.uniform.flags |= TEXTURE_BIT;
@@ -71,6 +194,24 @@ impl RenderPreferences {
         }
     }
 
+    pub fn backface_culling_enabled(&self) -> bool {
+        self.cull_mode == Some(wgpu::Face::Back)
+    }
+
+    pub fn set_backface_culling(&mut self, enabled: bool) {
+        self.cull_mode = enabled.then_some(wgpu::Face::Back);
+    }
+
+    /// Uniform for drawing meshes mirrored below a floor at `floor_height`:
+    /// geometry under the floor is clipped, and screen-space ambient occlusion
+    /// and two-sided lighting are off, since the mirror flips facing.
+    pub fn reflection_uniform(&self, floor_height: f32) -> ShaderPreferencesUniform {
+        let mut uniform = self.uniform;
+        uniform.flags = (uniform.flags | CLIP_BIT) & !(SSAO_BIT | TWO_SIDED_BIT);
+        uniform.clip_plane = [0.0, 1.0, 0.0, -floor_height];
+        uniform
+    }
+
     pub fn update_uniform(&mut self, queue: &Queue) {
         // update uniform buffer
         queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
@@ -84,8 +225,19 @@ impl RenderPreferences {
 
         // shader preferences
         let uniform = ShaderPreferencesUniform {
-            // only lighting enabled here by default
-            flags: 1_u32,
+            // only lighting and two-sided lighting enabled here by default
+            flags: LIGHTING_BIT | TWO_SIDED_BIT,
+            fog_density: DEFAULT_FOG_DENSITY,
+            opacity: 1.0,
+            _padding: 0,
+            // fade into the background by default
+            fog_color: [
+                CLEAR_COLOR.r as f32,
+                CLEAR_COLOR.g as f32,
+                CLEAR_COLOR.b as f32,
+            ],
+            _padding_2: 0.0,
+            clip_plane: [0.0; 4],
         };
 
         let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -110,6 +262,9 @@ impl RenderPreferences {
             buffer,
             bind_group_layout_entry,
             polygon_mode,
+            cull_mode: None,
+            depth_prepass: false,
+            shading: Normals::Smooth,
         }
     }
 
@@ -0,0 +1,202 @@
+// Save the rendered scene, without the GUI, to a PNG file.
+//
+// With MSAA the scene is drawn into a multisampled texture that is
+// resolved into the offscreen target, and multisampled textures can't be
+// copied to a buffer. The surface texture can't be copied either, so the
+// capture repeats the tone map pass from the resolved offscreen target
+// into a single-sampled texture matching the surface, and reads that back.
+
+use super::tone_map::ToneMapState;
+
+use egui_wgpu::wgpu::{
+    self, Device, Origin3d, Queue, SurfaceConfiguration, TexelCopyBufferLayout,
+    TexelCopyTextureInfo, TextureFormat,
+};
+use image::{ImageBuffer, Rgba};
+
+/// File name for a new screenshot, distinguished by the time it was
+/// taken, in milliseconds, so quick repeated captures don't overwrite.
+pub fn screenshot_filename() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    format!("screenshot_{millis}.png")
+}
+
+/// Tone map the offscreen target into a capture texture and save it.
+/// Blocks until the GPU has finished the copy.
+pub fn save_screenshot(
+    device: &Device,
+    queue: &Queue,
+    surface_config: &SurfaceConfiguration,
+    tone_map: &ToneMapState,
+    filename: &str,
+) -> Result<(), String> {
+    // The tone map pipeline targets the surface format.
+    let format = surface_config.format;
+    let swap_red_blue = match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+        _ => {
+            return Err(format!(
+                "Screenshots don't support surface format {format:?}"
+            ));
+        }
+    };
+    let size = wgpu::Extent3d {
+        width: surface_config.width.max(1),
+        height: surface_config.height.max(1),
+        depth_or_array_layers: 1,
+    };
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("screenshot texture"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    const TEXEL_SIZE: u32 = std::mem::size_of::<[u8; 4]>() as u32;
+    let unpadded_bytes_per_row = size.width * TEXEL_SIZE;
+    let padded_bytes_per_row =
+        unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Screenshot Staging Buffer"),
+        size: (padded_bytes_per_row * size.height) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    tone_map.render(&view, &mut encoder);
+    encoder.copy_texture_to_buffer(
+        TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &staging_buffer,
+            layout: TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(size.height),
+            },
+        },
+        size,
+    );
+    queue.submit(Some(encoder.finish()));
+
+    staging_buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |_| {});
+    device
+        .poll(wgpu::PollType::wait_indefinitely())
+        .map_err(|err| format!("Failed to read screenshot: {err}"))?;
+
+    let mapped_data = staging_buffer.slice(..).get_mapped_range();
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * size.height) as usize);
+    // drop the row padding
+    for row in mapped_data.chunks_exact(padded_bytes_per_row as usize) {
+        pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+    }
+    drop(mapped_data);
+    staging_buffer.unmap();
+
+    if swap_red_blue {
+        pixels
+            .chunks_exact_mut(4)
+            .for_each(|pixel| pixel.swap(0, 2));
+    }
+    // The texture is sRGB encoded, as PNG expects, and the scene is opaque.
+    pixels.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 255);
+
+    ImageBuffer::<Rgba<u8>, Vec<u8>>::from_vec(size.width, size.height, pixels)
+        .ok_or("Screenshot size doesn't match its data.")?
+        .save_with_format(filename, image::ImageFormat::Png)
+        .map_err(|err| format!("Failed to save {filename}: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grapher::pipeline::{test_device, texture::OffscreenTarget};
+
+    // Clear a 4x multisampled scene target to linear mid-gray, resolve it
+    // into the offscreen target as the scene pass does, and capture it. The
+    // width isn't a multiple of the row alignment, so rows are padded.
+    #[test]
+    fn screenshot_reads_back_resolved_msaa_target() {
+        let Some((device, queue)) = test_device() else {
+            eprintln!("No graphics adapter; skipping the screenshot test.");
+            return;
+        };
+        let surface_config = SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: TextureFormat::Bgra8UnormSrgb,
+            width: 70,
+            height: 30,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+        let target = OffscreenTarget::create(&surface_config, &device);
+        let multisampled = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: target.texture.size(),
+            mip_level_count: 1,
+            sample_count: 4,
+            dimension: wgpu::TextureDimension::D2,
+            format: OffscreenTarget::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        // sRGB 0.5, which the surface encodes back to 128
+        let mid_gray = crate::grapher::math::colormap::srgb_to_linear(0.5) as f64;
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &multisampled.create_view(&Default::default()),
+                resolve_target: Some(&target.view),
+                depth_slice: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: mid_gray,
+                        g: mid_gray,
+                        b: mid_gray,
+                        a: 1.0,
+                    }),
+                    store: wgpu::StoreOp::Discard,
+                },
+            })],
+            ..Default::default()
+        });
+        queue.submit(Some(encoder.finish()));
+
+        let tone_map = ToneMapState::create(&device, &surface_config, &target);
+        let path = std::env::temp_dir().join(format!("grapher_test_{}.png", std::process::id()));
+        let filename = path.to_str().unwrap();
+        save_screenshot(&device, &queue, &surface_config, &tone_map, filename).unwrap();
+        let image = image::open(&path).unwrap().into_rgba8();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.dimensions(), (70, 30));
+        for pixel in image.pixels() {
+            assert!(
+                pixel.0[..3].iter().all(|&c| c.abs_diff(128) <= 1),
+                "{pixel:?}"
+            );
+            assert_eq!(pixel.0[3], 255);
+        }
+    }
+}
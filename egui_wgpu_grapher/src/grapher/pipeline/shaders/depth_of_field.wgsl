@@ -0,0 +1,79 @@
+// Full-screen pass that blurs the scene color by each pixel's
+// distance from the focus plane, read from the depth buffer.
+
+struct DepthOfFieldUniform {
+    inverse_projection: mat4x4<f32>,
+    focus_distance: f32,
+    aperture: f32,
+}
+
+@group(0) @binding(0)
+var scene_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var depth_texture: texture_depth_multisampled_2d;
+
+@group(0) @binding(2)
+var<uniform> dof: DepthOfFieldUniform;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+// Vertex shader.
+
+// Single triangle covering the viewport; positions come from the index.
+@vertex
+fn vs_main(@builtin(vertex_index) in_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let uv = vec2<f32>(f32((in_index << 1u) & 2u), f32(in_index & 2u));
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+// Fragment shader.
+
+const MAX_RADIUS: f32 = 12.0;
+const SAMPLES: u32 = 48u;
+const GOLDEN_ANGLE: f32 = 2.39996323;
+// Distance from the eye along the view direction, undoing the projection.
+fn view_distance(buffer_depth: f32) -> f32 {
+    let position = dof.inverse_projection * vec4<f32>(0.0, 0.0, buffer_depth, 1.0);
+    return -position.z / position.w;
+}
+
+// Blur radius in pixels for the pixel at the given coordinates.
+fn blur_radius(coords: vec2<i32>) -> f32 {
+    let distance = view_distance(textureLoad(depth_texture, coords, 0));
+    let defocus = abs(distance - dof.focus_distance) / max(distance, 1e-3);
+    return min(dof.aperture * defocus, MAX_RADIUS);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(scene_texture));
+    let center = vec2<i32>(in.position.xy);
+    let radius = blur_radius(center);
+
+    var color = textureLoad(scene_texture, center, 0).rgb;
+    if radius < 0.5 {
+        return vec4<f32>(color, 1.0);
+    }
+
+    // Gather over a disk with a golden angle spiral. A sample only counts
+    // if its own blur reaches this pixel, so sharp surfaces stay crisp
+    // instead of bleeding into the blurred ones around them.
+    var total_weight = 1.0;
+    for (var i = 0u; i < SAMPLES; i++) {
+        let r = radius * sqrt((f32(i) + 0.5) / f32(SAMPLES));
+        let theta = f32(i) * GOLDEN_ANGLE;
+        let offset = vec2<i32>(round(r * vec2<f32>(cos(theta), sin(theta))));
+        let coords = clamp(center + offset, vec2<i32>(0), size - 1);
+
+        let weight = clamp(blur_radius(coords) - r + 1.0, 0.0, 1.0);
+        color += weight * textureLoad(scene_texture, coords, 0).rgb;
+        total_weight += weight;
+    }
+
+    return vec4<f32>(color / total_weight, 1.0);
+}
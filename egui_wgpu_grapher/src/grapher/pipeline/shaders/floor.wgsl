@@ -0,0 +1,60 @@
+// Square floor below a graph, blending its color with the scene
+// reflected below it, which is rendered to a texture beforehand.
+
+struct MatrixUniform {
+    matrix: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: MatrixUniform;
+
+struct FloorUniform {
+    color: vec3<f32>,
+    // fraction of the floor color replaced by the reflection
+    reflectivity: f32,
+    height: f32,
+    half_width: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> floor_params: FloorUniform;
+
+@group(1) @binding(1)
+var reflection: texture_2d<f32>;
+
+@group(1) @binding(2)
+var reflection_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // two triangles covering the square
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = floor_params.half_width * corners[index];
+
+    var out: VertexOutput;
+    out.clip_position = camera.matrix * vec4<f32>(corner.x, floor_params.height, corner.y, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The reflection was rendered from the same viewpoint, so
+    // it is sampled at this fragment's position on screen.
+    let uv = in.clip_position.xy / vec2<f32>(textureDimensions(reflection));
+    let reflected = textureSample(reflection, reflection_sampler, uv);
+
+    // Reflection alpha is zero where nothing was reflected.
+    let amount = floor_params.reflectivity * reflected.a;
+    return vec4<f32>(mix(floor_params.color, reflected.rgb, amount), 1.0);
+}
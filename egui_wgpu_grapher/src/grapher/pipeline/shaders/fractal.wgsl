@@ -0,0 +1,64 @@
+// Escape-time fractal compute shader. Computes the Mandelbrot set, or
+// a Julia set for a fixed constant, and writes colormapped output.
+
+@group(0) @binding(0) var output_texture: texture_storage_2d<rgba8unorm, write>;
+
+struct Uniform {
+    center: vec2<f32>,
+    scale: f32,
+    max_iterations: u32,
+    julia_constant: vec2<f32>,
+    is_julia: u32,
+    aspect_ratio: f32,
+};
+@group(1) @binding(0) var<uniform> params_uniform: Uniform;
+
+const ESCAPE_RADIUS_SQ: f32 = 4.0;
+const TWO_PI: f32 = 6.28318530718;
+
+// Cosine palette; maps [0, 1] smoothly through a cycle of hues.
+fn colormap(t: f32) -> vec3<f32> {
+    return 0.5 + 0.5 * cos(TWO_PI * (vec3<f32>(t) + vec3<f32>(0.0, 0.33, 0.67)));
+}
+
+@compute @workgroup_size(8, 8)
+fn run(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let texture_dims: vec2<u32> = textureDimensions(output_texture);
+    if global_id.x >= texture_dims.x || global_id.y >= texture_dims.y {
+        return;
+    }
+
+    // Texel position in [-1, 1] x [-1, 1], with y increasing upward.
+    let uv = vec2<f32>(global_id.xy) / vec2<f32>(texture_dims - 1u) * 2.0 - 1.0;
+    let point = params_uniform.center + vec2<f32>(uv.x, -uv.y) * params_uniform.scale;
+
+    var z: vec2<f32>;
+    var c: vec2<f32>;
+    if params_uniform.is_julia != 0u {
+        z = point;
+        c = params_uniform.julia_constant;
+    } else {
+        z = vec2<f32>(0.0, 0.0);
+        c = point;
+    }
+
+    var i: u32 = 0u;
+    loop {
+        if i >= params_uniform.max_iterations || dot(z, z) > ESCAPE_RADIUS_SQ {
+            break;
+        }
+        z = vec2<f32>(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+        i += 1u;
+    }
+
+    // Points that never escape are drawn black.
+    var color = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    if i < params_uniform.max_iterations {
+        // Fractional iteration count to avoid color banding.
+        let smooth_i = f32(i) + 1.0 - log2(0.5 * log2(dot(z, z)));
+        let t = clamp(smooth_i / f32(params_uniform.max_iterations), 0.0, 1.0);
+        color = vec4<f32>(colormap(t), 1.0);
+    }
+
+    textureStore(output_texture, global_id.xy, color);
+}
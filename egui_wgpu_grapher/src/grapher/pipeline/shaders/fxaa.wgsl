@@ -0,0 +1,185 @@
+// Full-screen FXAA pass over the resolved HDR scene color. Finds edges
+// from luma contrast and blends across them along the edge direction.
+
+@group(0) @binding(0)
+var scene_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var scene_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+// Vertex shader.
+
+// Single triangle covering the viewport; positions come from the index.
+@vertex
+fn vs_main(@builtin(vertex_index) in_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let uv = vec2<f32>(f32((in_index << 1u) & 2u), f32(in_index & 2u));
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+// Fragment shader.
+
+const EDGE_THRESHOLD_MIN: f32 = 0.0312;
+const EDGE_THRESHOLD_MAX: f32 = 0.125;
+const SUBPIXEL_QUALITY: f32 = 0.75;
+const SEARCH_STEPS: i32 = 12;
+
+// Compress HDR values first so contrast is judged roughly as it will look.
+fn luma(color: vec3<f32>) -> f32 {
+    let mapped = color / (1.0 + color);
+    return sqrt(dot(mapped, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+fn sample_color(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(scene_texture, scene_sampler, uv, 0.0);
+}
+
+fn sample_luma(uv: vec2<f32>) -> f32 {
+    return luma(sample_color(uv).rgb);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(scene_texture));
+    let uv = in.position.xy * texel;
+
+    let center = sample_color(uv);
+    let luma_center = luma(center.rgb);
+    let luma_up = sample_luma(uv + vec2<f32>(0.0, -texel.y));
+    let luma_down = sample_luma(uv + vec2<f32>(0.0, texel.y));
+    let luma_left = sample_luma(uv + vec2<f32>(-texel.x, 0.0));
+    let luma_right = sample_luma(uv + vec2<f32>(texel.x, 0.0));
+
+    let luma_min = min(luma_center, min(min(luma_up, luma_down), min(luma_left, luma_right)));
+    let luma_max = max(luma_center, max(max(luma_up, luma_down), max(luma_left, luma_right)));
+    let luma_range = luma_max - luma_min;
+
+    // not an edge, or too dark to notice
+    if luma_range < max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD_MAX) {
+        return center;
+    }
+
+    let luma_up_left = sample_luma(uv + vec2<f32>(-texel.x, -texel.y));
+    let luma_up_right = sample_luma(uv + vec2<f32>(texel.x, -texel.y));
+    let luma_down_left = sample_luma(uv + vec2<f32>(-texel.x, texel.y));
+    let luma_down_right = sample_luma(uv + vec2<f32>(texel.x, texel.y));
+
+    let luma_vertical = luma_up + luma_down;
+    let luma_horizontal = luma_left + luma_right;
+    let luma_left_corners = luma_up_left + luma_down_left;
+    let luma_right_corners = luma_up_right + luma_down_right;
+    let luma_up_corners = luma_up_left + luma_up_right;
+    let luma_down_corners = luma_down_left + luma_down_right;
+
+    // Compare gradient strength across rows and columns to get the edge direction.
+    let edge_horizontal = abs(-2.0 * luma_left + luma_left_corners)
+        + 2.0 * abs(-2.0 * luma_center + luma_vertical)
+        + abs(-2.0 * luma_right + luma_right_corners);
+    let edge_vertical = abs(-2.0 * luma_up + luma_up_corners)
+        + 2.0 * abs(-2.0 * luma_center + luma_horizontal)
+        + abs(-2.0 * luma_down + luma_down_corners);
+    let is_horizontal = edge_horizontal >= edge_vertical;
+
+    // Pick the side of the edge with the steeper gradient.
+    var luma_1 = luma_left;
+    var luma_2 = luma_right;
+    var step_length = texel.x;
+    if is_horizontal {
+        luma_1 = luma_up;
+        luma_2 = luma_down;
+        step_length = texel.y;
+    }
+    let gradient_1 = luma_1 - luma_center;
+    let gradient_2 = luma_2 - luma_center;
+    let is_1_steepest = abs(gradient_1) >= abs(gradient_2);
+    let gradient_scaled = 0.25 * max(abs(gradient_1), abs(gradient_2));
+
+    var luma_local_average: f32;
+    if is_1_steepest {
+        step_length = -step_length;
+        luma_local_average = 0.5 * (luma_1 + luma_center);
+    } else {
+        luma_local_average = 0.5 * (luma_2 + luma_center);
+    }
+
+    // Start half a texel onto the edge and search both ways along it for its ends.
+    var current_uv = uv;
+    var offset: vec2<f32>;
+    if is_horizontal {
+        current_uv.y += 0.5 * step_length;
+        offset = vec2<f32>(texel.x, 0.0);
+    } else {
+        current_uv.x += 0.5 * step_length;
+        offset = vec2<f32>(0.0, texel.y);
+    }
+
+    var uv_1 = current_uv - offset;
+    var uv_2 = current_uv + offset;
+    var luma_end_1 = sample_luma(uv_1) - luma_local_average;
+    var luma_end_2 = sample_luma(uv_2) - luma_local_average;
+    var reached_1 = abs(luma_end_1) >= gradient_scaled;
+    var reached_2 = abs(luma_end_2) >= gradient_scaled;
+
+    for (var i = 1; i < SEARCH_STEPS; i++) {
+        if reached_1 && reached_2 {
+            break;
+        }
+        if !reached_1 {
+            uv_1 -= offset;
+            luma_end_1 = sample_luma(uv_1) - luma_local_average;
+            reached_1 = abs(luma_end_1) >= gradient_scaled;
+        }
+        if !reached_2 {
+            uv_2 += offset;
+            luma_end_2 = sample_luma(uv_2) - luma_local_average;
+            reached_2 = abs(luma_end_2) >= gradient_scaled;
+        }
+    }
+
+    var distance_1: f32;
+    var distance_2: f32;
+    if is_horizontal {
+        distance_1 = uv.x - uv_1.x;
+        distance_2 = uv_2.x - uv.x;
+    } else {
+        distance_1 = uv.y - uv_1.y;
+        distance_2 = uv_2.y - uv.y;
+    }
+    let is_direction_1 = distance_1 < distance_2;
+    let distance_final = min(distance_1, distance_2);
+    let edge_length = distance_1 + distance_2;
+
+    // Only blend if the nearer end agrees with the center about which side is brighter.
+    let is_luma_center_smaller = luma_center < luma_local_average;
+    var correct_variation: bool;
+    if is_direction_1 {
+        correct_variation = (luma_end_1 < 0.0) != is_luma_center_smaller;
+    } else {
+        correct_variation = (luma_end_2 < 0.0) != is_luma_center_smaller;
+    }
+    var final_offset = 0.0;
+    if correct_variation {
+        final_offset = 0.5 - distance_final / edge_length;
+    }
+
+    // Sub-pixel aliasing from the 3x3 neighborhood average.
+    let luma_average = (2.0 * (luma_vertical + luma_horizontal)
+        + luma_left_corners + luma_right_corners) / 12.0;
+    let subpixel_1 = clamp(abs(luma_average - luma_center) / luma_range, 0.0, 1.0);
+    let subpixel_2 = (-2.0 * subpixel_1 + 3.0) * subpixel_1 * subpixel_1;
+    let subpixel_offset = subpixel_2 * subpixel_2 * SUBPIXEL_QUALITY;
+    final_offset = max(final_offset, subpixel_offset);
+
+    var final_uv = uv;
+    if is_horizontal {
+        final_uv.y += final_offset * step_length;
+    } else {
+        final_uv.x += final_offset * step_length;
+    }
+    return vec4<f32>(sample_color(final_uv).rgb, center.a);
+}
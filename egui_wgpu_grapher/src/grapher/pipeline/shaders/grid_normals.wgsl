@@ -0,0 +1,75 @@
+// Compute shader to set the heights and normals of a grid mesh from a buffer
+// of heights, writing them in place in the mesh's vertex buffer.
+//
+// Normals match the CPU flat normals of a grid whose squares are split
+// along "\" diagonals, as the adaptive split does for a flat grid: each
+// vertex takes the normal of the first triangle containing it, in the
+// order the squares are tesselated.
+
+struct GridUniform {
+    cols: u32,
+    rows: u32,
+    // distance between neighboring grid points
+    spacing: f32,
+    // size of a vertex in the vertex buffer, in floats
+    vertex_stride: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> grid: GridUniform;
+
+@group(0) @binding(1)
+var<storage, read> heights: array<f32>;
+
+@group(0) @binding(2)
+var<storage, read_write> vertices: array<f32>;
+
+// offsets within a vertex, in floats
+const POSITION_Y: u32 = 1u;
+const NORMAL: u32 = 6u;
+
+// Grid point position, relative to the first point.
+fn grid_point(x: u32, z: u32) -> vec3<f32> {
+    return vec3<f32>(f32(x) * grid.spacing, heights[z * grid.cols + x], f32(z) * grid.spacing);
+}
+
+@compute @workgroup_size(8, 8)
+fn run(@builtin(global_invocation_id) id: vec3<u32>) {
+    let x = id.x;
+    let z = id.y;
+    if x >= grid.cols || z >= grid.rows {
+        return;
+    }
+
+    // Corners of the triangle, counterclockwise seen from above.
+    var a: vec3<f32>;
+    var b: vec3<f32>;
+    var c: vec3<f32>;
+    if x > 0u && z > 0u {
+        // first triangle of the square behind and to the left
+        a = grid_point(x - 1u, z - 1u);
+        b = grid_point(x - 1u, z);
+        c = grid_point(x, z);
+    } else if x > 0u {
+        // second triangle of the square to the left
+        a = grid_point(x - 1u, 0u);
+        b = grid_point(x, 1u);
+        c = grid_point(x, 0u);
+    } else if z > 0u {
+        // first triangle of the square behind
+        a = grid_point(0u, z - 1u);
+        b = grid_point(0u, z);
+        c = grid_point(1u, z);
+    } else {
+        a = grid_point(0u, 0u);
+        b = grid_point(0u, 1u);
+        c = grid_point(1u, 1u);
+    }
+    let normal = normalize(cross(b - a, c - a));
+
+    let base = (z * grid.cols + x) * grid.vertex_stride;
+    vertices[base + POSITION_Y] = heights[z * grid.cols + x];
+    vertices[base + NORMAL] = normal.x;
+    vertices[base + NORMAL + 1u] = normal.y;
+    vertices[base + NORMAL + 2u] = normal.z;
+}
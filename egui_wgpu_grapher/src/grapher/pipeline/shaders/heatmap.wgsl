@@ -0,0 +1,41 @@
+// Shader to draw a graph seen from directly above, writing the height of
+// the surface at each pixel, for exporting it as a heatmap image. Colors
+// are applied when the heights are read back.
+
+struct MatrixUniform {
+    matrix: mat4x4<f32>,
+}
+
+// orthographic projection looking down the y axis
+@group(0) @binding(0)
+var<uniform> camera: MatrixUniform;
+
+// buffer structs
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) view_position: vec4<f32>,
+    @location(0) height: f32,
+}
+
+// vertex shader
+
+@vertex
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.view_position = camera.matrix * vec4<f32>(vertex.position, 1.0);
+    out.height = vertex.position.y;
+
+    return out;
+}
+
+// fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec2<f32> {
+    // the second channel marks pixels covered by the surface
+    return vec2<f32>(in.height, 1.0);
+}
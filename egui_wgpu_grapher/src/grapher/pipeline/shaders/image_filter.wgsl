@@ -0,0 +1,74 @@
+// Convolution filters for the image viewer. Reads the original image
+// and writes the filtered result to a separate storage texture.
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var filtered: texture_storage_2d<rgba16float, write>;
+
+struct Params {
+    // 1 = box blur, 2 = Gaussian blur, 3 = Laplacian edge detection
+    kind: u32,
+    radius: u32,
+};
+@group(0) @binding(2) var<uniform> params: Params;
+
+fn load_clamped(coords: vec2<i32>, dims: vec2<i32>) -> vec4<f32> {
+    return textureLoad(source, clamp(coords, vec2<i32>(0), dims - 1), 0);
+}
+
+fn blur(coords: vec2<i32>, dims: vec2<i32>, gaussian: bool) -> vec4<f32> {
+    let radius = i32(params.radius);
+    // sigma chosen so the kernel falls to ~1% at its edge
+    let sigma = max(f32(radius) / 3.0, 0.5);
+
+    var sum = vec4<f32>(0.0);
+    var weight_sum = 0.0;
+    for (var j = -radius; j <= radius; j++) {
+        for (var i = -radius; i <= radius; i++) {
+            var weight = 1.0;
+            if gaussian {
+                weight = exp(-f32(i * i + j * j) / (2.0 * sigma * sigma));
+            }
+            sum += weight * load_clamped(coords + vec2<i32>(i, j), dims);
+            weight_sum += weight;
+        }
+    }
+    return sum / weight_sum;
+}
+
+fn edges(coords: vec2<i32>, dims: vec2<i32>) -> vec4<f32> {
+    let center = load_clamped(coords, dims);
+    let laplacian = load_clamped(coords + vec2<i32>(1, 0), dims)
+        + load_clamped(coords + vec2<i32>(-1, 0), dims)
+        + load_clamped(coords + vec2<i32>(0, 1), dims)
+        + load_clamped(coords + vec2<i32>(0, -1), dims)
+        - 4.0 * center;
+
+    let luminance = dot(abs(laplacian.rgb), vec3<f32>(0.2126, 0.7152, 0.0722));
+    return vec4<f32>(vec3<f32>(clamp(4.0 * luminance, 0.0, 1.0)), center.a);
+}
+
+@compute @workgroup_size(8, 8)
+fn run(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let dims = vec2<i32>(textureDimensions(source));
+    let coords = vec2<i32>(global_id.xy);
+    if coords.x >= dims.x || coords.y >= dims.y {
+        return;
+    }
+
+    var color: vec4<f32>;
+    switch params.kind {
+        case 1u: {
+            color = blur(coords, dims, false);
+        }
+        case 2u: {
+            color = blur(coords, dims, true);
+        }
+        case 3u: {
+            color = edges(coords, dims);
+        }
+        default: {
+            color = textureLoad(source, coords, 0);
+        }
+    }
+    textureStore(filtered, coords, color);
+}
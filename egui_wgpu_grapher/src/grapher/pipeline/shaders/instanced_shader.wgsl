@@ -0,0 +1,180 @@
+// Shader to render instanced meshes with a texture sampler. Same as the
+// textured shader, except that each instance has its own model matrix,
+// passed as a vertex input and applied after the mesh matrix.
+
+struct MatrixUniform {
+    matrix: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: MatrixUniform;
+
+struct PreferencesUniform {
+    flags: u32,
+    fog_density: f32,
+    fog_color: vec3<f32>,
+    // plane normal and offset; fragments with dot(n, p) + d < 0 are clipped
+    clip_plane: vec4<f32>,
+}
+
+@group(0) @binding(1)
+var<uniform> preferences: PreferencesUniform;
+
+@group(1) @binding(0)
+var<uniform> model_matrix: MatrixUniform;
+
+struct LightUniform {
+    position: vec3<f32>,
+    color: vec3<f32>,
+}
+
+@group(2) @binding(0)
+var<uniform> light: LightUniform;
+
+// buffer structs
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) tex_coords: vec2<f32>,
+}
+
+struct InstanceInput {
+    @location(5) matrix_0: vec4<f32>,
+    @location(6) matrix_1: vec4<f32>,
+    @location(7) matrix_2: vec4<f32>,
+    @location(8) matrix_3: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) view_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) light_direction: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) tex_coords: vec2<f32>,
+    @location(4) view_depth: f32,
+    @location(5) world_position: vec4<f32>,
+}
+
+// vertex shader
+
+@vertex
+fn vs_main(
+    vertex: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let instance_matrix = mat4x4<f32>(
+        instance.matrix_0,
+        instance.matrix_1,
+        instance.matrix_2,
+        instance.matrix_3,
+    );
+    let world_matrix = instance_matrix * model_matrix.matrix;
+
+    var out: VertexOutput;
+    out.color = vertex.color;
+    out.tex_coords = vertex.tex_coords;
+
+    // World coordinates of vertex, after applying instance and model transformations.
+    out.world_position = world_matrix * vec4<f32>(vertex.position, 1.0);
+    // Position modified by camera transformation, for display.
+    out.view_position = camera.matrix * out.world_position;
+    // Clip w is the view-space depth under perspective projection.
+    out.view_depth = out.view_position.w;
+
+    // Rotate normal with body without translating.
+    out.normal = normalize((world_matrix * vec4<f32>(vertex.normal, 0.0)).xyz);
+
+    // Direction from point to light in world space.
+    out.light_direction = normalize(light.position - out.world_position.xyz);
+
+    return out;
+}
+
+// fragment shader
+
+@group(3) @binding(0)
+var diffuse_tex: texture_2d<f32>;
+
+@group(3) @binding(1)
+var diffuse_samp: sampler;
+
+const FOG_BIT: u32 = 8u;
+const CLIP_BIT: u32 = 16u;
+const TWO_SIDED_BIT: u32 = 32u;
+const ALPHA_COVERAGE_BIT: u32 = 128u;
+const ALPHA_TEST_BIT: u32 = 256u;
+
+// Discard fragments on the negative side of the clipping plane, if enabled.
+fn apply_clip_plane(world_position: vec3<f32>) {
+    if (preferences.flags & CLIP_BIT) == 0u {
+        return;
+    }
+    if dot(preferences.clip_plane.xyz, world_position) + preferences.clip_plane.w < 0.0 {
+        discard;
+    }
+}
+
+// Blend toward fog color with exponential-squared falloff in view depth.
+fn apply_fog(color: vec3<f32>, view_depth: f32) -> vec3<f32> {
+    if (preferences.flags & FOG_BIT) == 0u {
+        return color;
+    }
+    let fog_amount = preferences.fog_density * view_depth;
+    let visibility = clamp(exp(-fog_amount * fog_amount), 0.0, 1.0);
+    return mix(preferences.fog_color, color, visibility);
+}
+
+// Output alpha for a texel: its alpha with alpha to coverage, or opaque
+// after a hard alpha test without MSAA. Opaque unless cutouts are enabled.
+fn cutout_alpha(texture_alpha: f32) -> f32 {
+    if (preferences.flags & ALPHA_COVERAGE_BIT) != 0u {
+        return texture_alpha;
+    }
+    if (preferences.flags & ALPHA_TEST_BIT) != 0u && texture_alpha < 0.5 {
+        discard;
+    }
+    return 1.0;
+}
+
+// Normal facing the viewer, so back faces are lit as seen from their side.
+fn facing_normal(normal: vec3<f32>, front_facing: bool) -> vec3<f32> {
+    if front_facing || (preferences.flags & TWO_SIDED_BIT) == 0u {
+        return normal;
+    }
+    return -normal;
+}
+
+@fragment
+fn fs_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    apply_clip_plane(in.world_position.xyz);
+
+    let use_light = (preferences.flags & 1u) > 0u;
+    // TODO: Add correct handling for this in application.
+    let use_texture = (preferences.flags & 2u) > 0u;
+
+    var color: vec3<f32>;
+    var alpha = 1.0;
+    if use_texture {
+        let texel = textureSample(diffuse_tex, diffuse_samp, in.tex_coords);
+        // Vertex color tints the texture; it is white unless overridden.
+        color = in.color * texel.xyz;
+        alpha = cutout_alpha(texel.a);
+    } else {
+        color = in.color;
+    }
+
+    if use_light {
+        let ambient_strength = 0.05;
+        let normal = facing_normal(in.normal, front_facing);
+        let diffuse_strength = 0.95 * max(0.0, dot(in.light_direction, normal));
+        let out_color = light.color * color;
+
+        // Only ambient and diffuse lighting here for now.
+        return vec4<f32>(apply_fog((ambient_strength + diffuse_strength) * out_color, in.view_depth), alpha);
+    } else {
+
+        return vec4<f32>(apply_fog(color, in.view_depth), alpha);
+    }
+}
@@ -0,0 +1,36 @@
+// Shader to render line segments, unlit, with their vertex color.
+
+struct MatrixUniform {
+    matrix: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: MatrixUniform;
+
+@group(1) @binding(0)
+var<uniform> model_matrix: MatrixUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) view_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = vertex.color;
+    out.view_position = camera.matrix * model_matrix.matrix * vec4<f32>(vertex.position, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
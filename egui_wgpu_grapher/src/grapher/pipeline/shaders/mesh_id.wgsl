@@ -0,0 +1,84 @@
+// Shader to draw instanced meshes as flat mesh ids, for picking the mesh
+// under the cursor. Positions are computed as in the instanced shader.
+
+struct MatrixUniform {
+    matrix: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: MatrixUniform;
+
+struct PreferencesUniform {
+    flags: u32,
+    fog_density: f32,
+    fog_color: vec3<f32>,
+    // plane normal and offset; fragments with dot(n, p) + d < 0 are clipped
+    clip_plane: vec4<f32>,
+}
+
+@group(0) @binding(1)
+var<uniform> preferences: PreferencesUniform;
+
+@group(1) @binding(0)
+var<uniform> model_matrix: MatrixUniform;
+
+struct MeshIdUniform {
+    // mesh index plus one, so zero means no mesh
+    id: u32,
+}
+
+@group(2) @binding(0)
+var<uniform> mesh_id: MeshIdUniform;
+
+// buffer structs
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+}
+
+struct InstanceInput {
+    @location(5) matrix_0: vec4<f32>,
+    @location(6) matrix_1: vec4<f32>,
+    @location(7) matrix_2: vec4<f32>,
+    @location(8) matrix_3: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) view_position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+}
+
+// vertex shader
+
+@vertex
+fn vs_main(
+    vertex: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let instance_matrix = mat4x4<f32>(
+        instance.matrix_0,
+        instance.matrix_1,
+        instance.matrix_2,
+        instance.matrix_3,
+    );
+
+    var out: VertexOutput;
+    out.world_position = instance_matrix * model_matrix.matrix * vec4<f32>(vertex.position, 1.0);
+    out.view_position = camera.matrix * out.world_position;
+
+    return out;
+}
+
+// fragment shader
+
+const CLIP_BIT: u32 = 16u;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) u32 {
+    // Clipped fragments can't be picked, as they aren't drawn.
+    if (preferences.flags & CLIP_BIT) != 0u
+        && dot(preferences.clip_plane.xyz, in.world_position.xyz) + preferences.clip_plane.w < 0.0 {
+        discard;
+    }
+    return mesh_id.id;
+}
@@ -0,0 +1,97 @@
+// Shader to render each vertex as an unlit point with its vertex color.
+// Each vertex is an instance expanded into a screen-aligned quad,
+// and fragments outside the inscribed disc are discarded.
+
+struct MatrixUniform {
+    matrix: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: MatrixUniform;
+
+struct PreferencesUniform {
+    flags: u32,
+    fog_density: f32,
+    fog_color: vec3<f32>,
+    // plane normal and offset; fragments with dot(n, p) + d < 0 are clipped
+    clip_plane: vec4<f32>,
+}
+
+@group(0) @binding(1)
+var<uniform> preferences: PreferencesUniform;
+
+@group(1) @binding(0)
+var<uniform> model_matrix: MatrixUniform;
+
+struct PointUniform {
+    // diameter in pixels
+    size: f32,
+    // viewport size in pixels
+    viewport: vec2<f32>,
+}
+
+@group(1) @binding(1)
+var<uniform> point: PointUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) view_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) world_position: vec4<f32>,
+    @location(2) view_depth: f32,
+    // position within the quad, in [-1, 1]^2
+    @location(3) corner: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, vertex: VertexInput) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[index];
+
+    var out: VertexOutput;
+    out.color = vertex.color;
+    out.corner = corner;
+    out.world_position = model_matrix.matrix * vec4<f32>(vertex.position, 1.0);
+    out.view_position = camera.matrix * out.world_position;
+    out.view_depth = out.view_position.w;
+
+    // Offset in clip space, scaled by w so the size is constant in pixels.
+    let offset = corner * point.size / point.viewport;
+    out.view_position = vec4<f32>(out.view_position.xy + offset * out.view_position.w, out.view_position.zw);
+    return out;
+}
+
+const FOG_BIT: u32 = 8u;
+const CLIP_BIT: u32 = 16u;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if dot(in.corner, in.corner) > 1.0 {
+        discard;
+    }
+    if (preferences.flags & CLIP_BIT) != 0u
+        && dot(preferences.clip_plane.xyz, in.world_position.xyz) + preferences.clip_plane.w < 0.0 {
+        discard;
+    }
+
+    var color = in.color;
+    if (preferences.flags & FOG_BIT) != 0u {
+        let fog_amount = preferences.fog_density * in.view_depth;
+        let visibility = clamp(exp(-fog_amount * fog_amount), 0.0, 1.0);
+        color = mix(preferences.fog_color, color, visibility);
+    }
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,47 @@
+// Semi-transparent horizontal rectangle over a graph's domain, marking
+// a level the surface can be compared against.
+
+struct MatrixUniform {
+    matrix: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: MatrixUniform;
+
+struct PlaneUniform {
+    color: vec4<f32>,
+    // x and z of opposite corners
+    min_corner: vec2<f32>,
+    max_corner: vec2<f32>,
+    height: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> plane: PlaneUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // two triangles covering the rectangle
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = mix(plane.min_corner, plane.max_corner, corners[index]);
+
+    var out: VertexOutput;
+    out.clip_position = camera.matrix * vec4<f32>(corner.x, plane.height, corner.y, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return plane.color;
+}
@@ -13,11 +13,20 @@ var<uniform> camera: MatrixUniform;
 
 struct PreferencesUniform {
     flags: u32,
+    fog_density: f32,
+    opacity: f32,
+    fog_color: vec3<f32>,
+    // plane normal and offset; fragments with dot(n, p) + d < 0 are clipped
+    clip_plane: vec4<f32>,
 }
 
 @group(0) @binding(1)
 var<uniform> preferences: PreferencesUniform;
 
+// Ambient occlusion from the SSAO pass, one texel per screen pixel.
+@group(0) @binding(2)
+var ambient_occlusion: texture_2d<f32>;
+
 @group(1) @binding(0)
 var<uniform> model_matrix: MatrixUniform;
 
@@ -43,8 +52,8 @@ struct VertexOutput {
     @location(0) color: vec3<f32>,
     @location(1) light_direction: vec3<f32>,
     @location(2) normal: vec3<f32>,
-    @location(3) reflected_light: vec3<f32>,
-    @location(4) world_position: vec4<f32>,
+    @location(3) world_position: vec4<f32>,
+    @location(4) view_depth: f32,
 }
 
 // Vertex shader.
@@ -56,16 +65,24 @@ fn vs_main(vertex: VertexInput) -> VertexOutput {
 
     // Position modified by camera transformation, for display.
     out.view_position = camera.matrix * model_matrix.matrix * vec4<f32>(vertex.position, 1.0);
-
-    // Rotate normal with body without translating.
-    out.normal = normalize((model_matrix.matrix * vec4<f32>(vertex.normal, 0.0)).xyz);
+    // Clip w is the view-space depth under perspective projection.
+    out.view_depth = out.view_position.w;
+
+    // Transform normal by the cofactor matrix of the model matrix's linear part,
+    // which is proportional to its inverse transpose, so it stays perpendicular
+    // to the surface under non-uniform scaling.
+    let m = model_matrix.matrix;
+    let normal_matrix = mat3x3<f32>(
+        cross(m[1].xyz, m[2].xyz),
+        cross(m[2].xyz, m[0].xyz),
+        cross(m[0].xyz, m[1].xyz),
+    );
+    out.normal = normalize(normal_matrix * vertex.normal);
     // World coordinates of vertex, after applying model transformation.
     out.world_position = (model_matrix.matrix * vec4<f32>(vertex.position, 1.0));
 
     // Direction from point to light in world space.
     out.light_direction = normalize(light.position - out.world_position.xyz);
-    // Light reflected across normal for specular lighting.
-    out.reflected_light = reflect(-out.light_direction, out.normal);
 
     return out;
 }
@@ -113,25 +130,84 @@ fn get_shadow(world_position: vec4<f32>) -> f32 {
 
 const LIGHT_BIT: u32 = 1u;
 const SHADOW_BIT: u32 = 4u;
+const FOG_BIT: u32 = 8u;
+const CLIP_BIT: u32 = 16u;
+const TWO_SIDED_BIT: u32 = 32u;
+const SSAO_BIT: u32 = 64u;
+
+// Discard fragments on the negative side of the clipping plane, if enabled.
+fn apply_clip_plane(world_position: vec3<f32>) {
+    if (preferences.flags & CLIP_BIT) == 0u {
+        return;
+    }
+    if dot(preferences.clip_plane.xyz, world_position) + preferences.clip_plane.w < 0.0 {
+        discard;
+    }
+}
+
+// Blend toward fog color with exponential-squared falloff in view depth.
+fn apply_fog(color: vec3<f32>, view_depth: f32) -> vec3<f32> {
+    if (preferences.flags & FOG_BIT) == 0u {
+        return color;
+    }
+    let fog_amount = preferences.fog_density * view_depth;
+    let visibility = clamp(exp(-fog_amount * fog_amount), 0.0, 1.0);
+    return mix(preferences.fog_color, color, visibility);
+}
+
+// Fraction of ambient light reaching this pixel, if SSAO is enabled.
+fn get_ambient_occlusion(view_position: vec4<f32>) -> f32 {
+    if (preferences.flags & SSAO_BIT) == 0u {
+        return 1.0;
+    }
+    return textureLoad(ambient_occlusion, vec2<i32>(view_position.xy), 0).r;
+}
+
+// Normal facing the viewer, so back faces are lit as seen from their side.
+fn facing_normal(normal: vec3<f32>, front_facing: bool) -> vec3<f32> {
+    if front_facing || (preferences.flags & TWO_SIDED_BIT) == 0u {
+        return normal;
+    }
+    return -normal;
+}
+
+// Color of the surface before lighting and fog. The UI can replace the
+// body of this function, so keep the markers around it.
+// surface_color begin
+fn surface_color(in: VertexOutput) -> vec3<f32> {
+    return in.color;
+}
+// surface_color end
 
 @fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+fn fs_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    apply_clip_plane(in.world_position.xyz);
+    let base_color = surface_color(in);
+
     let use_light = (preferences.flags & LIGHT_BIT) > 0;
 
     if use_light {
+        let normal = facing_normal(normalize(in.normal), front_facing);
+        // Light reflected across normal for specular lighting.
+        let reflected_light = reflect(-in.light_direction, normal);
+
         let shadow = select(get_shadow(light_view.matrix * in.world_position), 1.0, (preferences.flags & SHADOW_BIT) == 0);
         let diffuse_strength = shadow *
-            LIGHT_SETTINGS.diffuse_v * max(0.0, dot(in.light_direction, in.normal));
+            LIGHT_SETTINGS.diffuse_v * max(0.0, dot(in.light_direction, normal));
         let specular_strength = shadow *
-            LIGHT_SETTINGS.speculr_v * pow(max(0.0, dot(in.reflected_light, in.normal)), LIGHT_SETTINGS.shininess);
+            LIGHT_SETTINGS.speculr_v * pow(max(0.0, dot(reflected_light, normal)), LIGHT_SETTINGS.shininess);
+
+        let out_color = light.color * base_color;
 
-        let out_color = light.color * in.color;
+        // Ambient light alone is faint, so occlusion also dims diffuse light.
+        let occlusion = get_ambient_occlusion(in.view_position);
 
         // Apply Phong illumination model.
-        return vec4<f32>((LIGHT_SETTINGS.ambient_v + diffuse_strength + specular_strength) * out_color, 1.0);
+        let lit_color = (occlusion * (LIGHT_SETTINGS.ambient_v + diffuse_strength) + specular_strength) * out_color;
+        return vec4<f32>(apply_fog(lit_color, in.view_depth), preferences.opacity);
     } else {
 
         // We're use alpha transparency when lighting is disabled; this is experimental.
-        return vec4<f32>(in.color, 0.8);
+        return vec4<f32>(apply_fog(base_color, in.view_depth), 0.8 * preferences.opacity);
     }
 }
@@ -1,4 +1,5 @@
-// A simple shader for filling the shadow map depth buffer.
+// A simple shader for filling the shadow map depth buffer,
+// also used for the camera depth prepass for SSAO.
 
 struct MatrixUniform {
     matrix: mat4x4<f32>,
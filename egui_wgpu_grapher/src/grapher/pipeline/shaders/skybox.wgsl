@@ -0,0 +1,42 @@
+// Background shader that samples a cube texture in the view direction.
+// Drawn as a full-screen triangle before the scene meshes.
+
+struct SkyboxUniform {
+    // maps clip coordinates to world-space view directions
+    inverse_view_projection: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> skybox: SkyboxUniform;
+
+@group(0) @binding(1)
+var sky_texture: texture_cube<f32>;
+
+@group(0) @binding(2)
+var sky_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) clip_position: vec2<f32>,
+}
+
+// Vertex shader.
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let uv = vec2<f32>(f32((in_index << 1u) & 2u), f32(in_index & 2u));
+    out.clip_position = uv * 2.0 - 1.0;
+    // Place at the far plane, behind everything else.
+    out.position = vec4<f32>(out.clip_position, 1.0, 1.0);
+    return out;
+}
+
+// Fragment shader.
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let world = skybox.inverse_view_projection * vec4<f32>(in.clip_position, 1.0, 1.0);
+    let direction = normalize(world.xyz / world.w);
+    return vec4<f32>(textureSample(sky_texture, sky_sampler, direction).rgb, 1.0);
+}
@@ -1,13 +1,22 @@
 @group(0) @binding(0) var eqn_data: texture_storage_2d<rgba32float, read_write>;
 
+// Must match UniformData in scene/solver/mod.rs; four 4-byte
+// scalars, so there is no padding.
 struct Uniform {
     timestep: u32,
+    aspect_ratio: f32,
+    // (c * dt / h)^2 for wave speed c
+    prop_speed: f32,
+    // fraction of the previous step's velocity that is kept
+    damping: f32,
 };
 @group(1) @binding(0) var<uniform> params_uniform: Uniform;
 
-const R: f32 = 0.35;
+// Width and height of a workgroup; set when the pipeline is created,
+// and must match the dispatch in SolverScene::solver_timestep.
+override WORKGROUP_SIZE: u32 = 8u;
 
-@compute @workgroup_size(8, 8)
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
 fn run(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let texture_dims: vec2<u32> = textureDimensions(eqn_data);
 
@@ -29,8 +38,13 @@ fn run(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let t_m1 = (params_uniform.timestep + 2) % 3;
     let t_m2 = (params_uniform.timestep + 1) % 3;
 
-    let x_new: f32 = R * (x_imj[t_m1] + x_ipj[t_m1] + x_ijm[t_m1] + x_ijp[t_m1] - 4.0 * x_ij[t_m1])
-                        + 2.0 * x_ij[t_m1] - x_ij[t_m2];
+    let r = params_uniform.prop_speed;
+    let laplacian = x_imj[t_m1] + x_ipj[t_m1] + x_ijm[t_m1] + x_ijp[t_m1] - 4.0 * x_ij[t_m1];
+
+    // Damp the velocity rather than the value, since the solution
+    // rests at a nonzero level; damping = 1 is the undamped update.
+    let velocity = x_ij[t_m1] - x_ij[t_m2];
+    let x_new: f32 = r * laplacian + x_ij[t_m1] + params_uniform.damping * velocity;
     x_ij[t] = x_new;
     textureStore(eqn_data, coords, x_ij);
 }
@@ -17,6 +17,8 @@ const QUAD_TEX_COORDS: array<vec2f, 4> = array(
 struct Uniform {
     timestep: u32,
     aspect_ratio: f32,
+    prop_speed: f32,
+    damping: f32,
 };
 
 @group(0) @binding(0) var<uniform> params_uniform: Uniform;
@@ -0,0 +1,162 @@
+// Shader to render the GPU wave equation solution as a 3D surface.
+// A flat grid mesh is displaced in the vertex shader by sampling the
+// solver's data texture at each vertex's texture coordinates.
+
+struct MatrixUniform {
+    matrix: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: MatrixUniform;
+
+struct PreferencesUniform {
+    flags: u32,
+    fog_density: f32,
+    fog_color: vec3<f32>,
+    // plane normal and offset; fragments with dot(n, p) + d < 0 are clipped
+    clip_plane: vec4<f32>,
+}
+
+@group(0) @binding(1)
+var<uniform> preferences: PreferencesUniform;
+
+@group(1) @binding(0)
+var<uniform> model_matrix: MatrixUniform;
+
+struct LightUniform {
+    position: vec3<f32>,
+    color: vec3<f32>,
+}
+
+@group(2) @binding(0)
+var<uniform> light: LightUniform;
+
+// Solver data and parameters.
+
+@group(3) @binding(0)
+var data_texture: texture_2d<f32>;
+
+@group(3) @binding(1)
+var data_sampler: sampler;
+
+struct SolverUniform {
+    timestep: u32,
+    aspect_ratio: f32,
+    prop_speed: f32,
+    damping: f32,
+}
+
+@group(3) @binding(2)
+var<uniform> solver: SolverUniform;
+
+struct SurfaceUniform {
+    height_scale: f32,
+    // distance between grid points in texture and world coordinates
+    texel_size: f32,
+    grid_spacing: f32,
+}
+
+@group(3) @binding(3)
+var<uniform> surface: SurfaceUniform;
+
+// buffer structs
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) view_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) light_direction: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) view_depth: f32,
+    @location(4) world_position: vec4<f32>,
+}
+
+const TEXTURE_MAX_VAL: f32 = 255.0;
+
+// Solution height at the current timestep, centered on the middle of the data range.
+fn height(tex_coords: vec2<f32>) -> f32 {
+    let t = solver.timestep % 3;
+    let value = textureSampleLevel(data_texture, data_sampler, tex_coords, 0.0)[t];
+    return surface.height_scale * (value / TEXTURE_MAX_VAL - 0.5);
+}
+
+// vertex shader
+
+@vertex
+fn vs_main(
+    vertex: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = vertex.color;
+
+    var position = vertex.position;
+    position.y = height(vertex.tex_coords);
+
+    // Central differences of neighboring heights give the normal.
+    let du = vec2<f32>(surface.texel_size, 0.0);
+    let dv = vec2<f32>(0.0, surface.texel_size);
+    let dh_dx = (height(vertex.tex_coords + du) - height(vertex.tex_coords - du)) / (2.0 * surface.grid_spacing);
+    let dh_dz = (height(vertex.tex_coords + dv) - height(vertex.tex_coords - dv)) / (2.0 * surface.grid_spacing);
+    let normal = normalize(vec3<f32>(-dh_dx, 1.0, -dh_dz));
+
+    // Position modified by camera transformation, for display.
+    out.view_position = camera.matrix * model_matrix.matrix * vec4<f32>(position, 1.0);
+    // Clip w is the view-space depth under perspective projection.
+    out.view_depth = out.view_position.w;
+
+    // Rotate normal with body without translating.
+    out.normal = normalize((model_matrix.matrix * vec4<f32>(normal, 0.0)).xyz);
+    // World coordinates of vertex, after applying model transformation.
+    out.world_position = (model_matrix.matrix * vec4<f32>(position, 1.0));
+
+    // Direction from point to light in world space.
+    out.light_direction = normalize(light.position - out.world_position.xyz);
+
+    return out;
+}
+
+// fragment shader
+
+const FOG_BIT: u32 = 8u;
+const CLIP_BIT: u32 = 16u;
+
+// Discard fragments on the negative side of the clipping plane, if enabled.
+fn apply_clip_plane(world_position: vec3<f32>) {
+    if (preferences.flags & CLIP_BIT) == 0u {
+        return;
+    }
+    if dot(preferences.clip_plane.xyz, world_position) + preferences.clip_plane.w < 0.0 {
+        discard;
+    }
+}
+
+// Blend toward fog color with exponential-squared falloff in view depth.
+fn apply_fog(color: vec3<f32>, view_depth: f32) -> vec3<f32> {
+    if (preferences.flags & FOG_BIT) == 0u {
+        return color;
+    }
+    let fog_amount = preferences.fog_density * view_depth;
+    let visibility = clamp(exp(-fog_amount * fog_amount), 0.0, 1.0);
+    return mix(preferences.fog_color, color, visibility);
+}
+
+@fragment
+fn fs_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    apply_clip_plane(in.world_position.xyz);
+
+    // Always lit, since the relief is hard to see otherwise and
+    // the render settings aren't shown in solver mode.
+    // The underside is lit as seen from below.
+    let normal = select(-1.0, 1.0, front_facing) * normalize(in.normal);
+    let ambient_strength = 0.1;
+    let diffuse_strength = 0.9 * max(0.0, dot(in.light_direction, normal));
+    let out_color = light.color * in.color;
+
+    return vec4<f32>(apply_fog((ambient_strength + diffuse_strength) * out_color, in.view_depth), 1.0);
+}
@@ -0,0 +1,122 @@
+// Full-screen pass estimating ambient occlusion from the scene depth
+// buffer: view-space positions and normals are reconstructed from depth,
+// and points in the hemisphere over each surface are tested against it.
+
+struct SsaoUniform {
+    projection: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    radius: f32,
+    intensity: f32,
+}
+
+@group(0) @binding(0)
+var depth_texture: texture_depth_multisampled_2d;
+
+@group(0) @binding(1)
+var<uniform> ssao: SsaoUniform;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+// Vertex shader.
+
+// Single triangle covering the viewport; positions come from the index.
+@vertex
+fn vs_main(@builtin(vertex_index) in_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let uv = vec2<f32>(f32((in_index << 1u) & 2u), f32(in_index & 2u));
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+// Fragment shader.
+
+const SAMPLES: u32 = 16u;
+// depth the buffer is cleared to; set when the shader is loaded
+const FAR_DEPTH: f32 = 1.0;
+// keeps surfaces from occluding themselves
+const BIAS: f32 = 0.025;
+
+fn load_depth(coords: vec2<i32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(depth_texture));
+    return textureLoad(depth_texture, clamp(coords, vec2<i32>(0), size - 1), 0);
+}
+
+// View-space position of the surface seen at the given pixel.
+fn view_position(coords: vec2<i32>) -> vec3<f32> {
+    let size = vec2<f32>(textureDimensions(depth_texture));
+    let uv = (vec2<f32>(coords) + 0.5) / size;
+    let ndc = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, load_depth(coords), 1.0);
+    let position = ssao.inverse_projection * ndc;
+    return position.xyz / position.w;
+}
+
+// Difference toward the neighbor on the same surface, avoiding edges.
+fn nearest_difference(center: vec3<f32>, a: vec3<f32>, b: vec3<f32>) -> vec3<f32> {
+    if abs(a.z - center.z) < abs(b.z - center.z) {
+        return a - center;
+    }
+    return center - b;
+}
+
+// Pseudo-random value in [0, 1) from an integer.
+fn hash(n: u32) -> f32 {
+    var x = n * 747796405u + 2891336453u;
+    x = ((x >> ((x >> 28u) + 4u)) ^ x) * 277803737u;
+    return f32((x >> 22u) ^ x) / 4294967296.0;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coords = vec2<i32>(in.position.xy);
+    // nothing was drawn here
+    if load_depth(coords) == FAR_DEPTH {
+        return vec4<f32>(1.0);
+    }
+
+    let center = view_position(coords);
+    let dx = nearest_difference(
+        center, view_position(coords + vec2<i32>(1, 0)), view_position(coords - vec2<i32>(1, 0)));
+    let dy = nearest_difference(
+        center, view_position(coords + vec2<i32>(0, 1)), view_position(coords - vec2<i32>(0, 1)));
+    var normal = normalize(cross(dy, dx));
+    // face the camera, which looks down -z in view space
+    if dot(normal, center) > 0.0 {
+        normal = -normal;
+    }
+
+    // Rotate the kernel by a 4x4 tiled angle; the blur pass averages it out.
+    let tile = vec2<u32>(coords) % 4u;
+    let angle = 6.2831853 * hash(tile.y * 4u + tile.x);
+    let random = vec3<f32>(cos(angle), sin(angle), 0.0);
+    let tangent = normalize(random - normal * dot(random, normal) + vec3<f32>(1e-4, 0.0, 0.0));
+    let bitangent = cross(normal, tangent);
+
+    let size = vec2<f32>(textureDimensions(depth_texture));
+    var occlusion = 0.0;
+    for (var i = 0u; i < SAMPLES; i++) {
+        // Points in the hemisphere, denser near the center.
+        var offset = vec3<f32>(
+            hash(3u * i) * 2.0 - 1.0,
+            hash(3u * i + 1u) * 2.0 - 1.0,
+            hash(3u * i + 2u),
+        );
+        let scale = mix(0.1, 1.0, pow(f32(i) / f32(SAMPLES), 2.0));
+        offset = normalize(offset) * scale * ssao.radius;
+        let sample_point = center + offset.x * tangent + offset.y * bitangent + offset.z * normal;
+
+        // Depth of the scene where the sample point projects.
+        let clip = ssao.projection * vec4<f32>(sample_point, 1.0);
+        let ndc = clip.xy / clip.w;
+        let sample_coords = vec2<i32>(vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5) * size);
+        let scene_z = view_position(sample_coords).z;
+
+        // Only count occluders within the radius of this point.
+        let in_range = smoothstep(0.0, 1.0, ssao.radius / abs(center.z - scene_z));
+        occlusion += select(0.0, 1.0, scene_z >= sample_point.z + BIAS) * in_range;
+    }
+
+    let ambient = clamp(1.0 - ssao.intensity * occlusion / f32(SAMPLES), 0.0, 1.0);
+    return vec4<f32>(ambient, 0.0, 0.0, 1.0);
+}
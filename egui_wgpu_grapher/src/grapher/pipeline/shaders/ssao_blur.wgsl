@@ -0,0 +1,38 @@
+// Full-screen pass averaging ambient occlusion over 4x4 blocks,
+// which removes the tiled noise from the occlusion pass.
+
+@group(0) @binding(0)
+var occlusion_texture: texture_2d<f32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+// Vertex shader.
+
+// Single triangle covering the viewport; positions come from the index.
+@vertex
+fn vs_main(@builtin(vertex_index) in_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let uv = vec2<f32>(f32((in_index << 1u) & 2u), f32(in_index & 2u));
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+// Fragment shader.
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(occlusion_texture));
+    let center = vec2<i32>(in.position.xy);
+
+    var total = 0.0;
+    for (var y = -2; y < 2; y++) {
+        for (var x = -2; x < 2; x++) {
+            let coords = clamp(center + vec2<i32>(x, y), vec2<i32>(0), size - 1);
+            total += textureLoad(occlusion_texture, coords, 0).r;
+        }
+    }
+
+    return vec4<f32>(total / 16.0, 0.0, 0.0, 1.0);
+}
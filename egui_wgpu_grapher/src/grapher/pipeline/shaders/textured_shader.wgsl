@@ -11,11 +11,19 @@ var<uniform> camera: MatrixUniform;
 
 struct PreferencesUniform {
     flags: u32,
+    fog_density: f32,
+    fog_color: vec3<f32>,
+    // plane normal and offset; fragments with dot(n, p) + d < 0 are clipped
+    clip_plane: vec4<f32>,
 }
 
 @group(0) @binding(1)
 var<uniform> preferences: PreferencesUniform;
 
+// Ambient occlusion from the SSAO pass, one texel per screen pixel.
+@group(0) @binding(2)
+var ambient_occlusion: texture_2d<f32>;
+
 @group(1) @binding(0)
 var<uniform> model_matrix: MatrixUniform;
 
@@ -34,6 +42,7 @@ struct VertexInput {
     @location(1) color: vec3<f32>,
     @location(2) normal: vec3<f32>,
     @location(3) tex_coords: vec2<f32>,
+    @location(4) tangent: vec4<f32>,
 }
 
 struct VertexOutput {
@@ -42,6 +51,10 @@ struct VertexOutput {
     @location(1) light_direction: vec3<f32>,
     @location(2) normal: vec3<f32>,
     @location(3) tex_coords: vec2<f32>,
+    @location(4) view_depth: f32,
+    @location(5) world_position: vec4<f32>,
+    // world space tangent, with bitangent handedness in w
+    @location(6) tangent: vec4<f32>,
 }
 
 // vertex shader
@@ -56,14 +69,20 @@ fn vs_main(
 
     // Position modified by camera transformation, for display.
     out.view_position = camera.matrix * model_matrix.matrix * vec4<f32>(vertex.position, 1.0);
+    // Clip w is the view-space depth under perspective projection.
+    out.view_depth = out.view_position.w;
 
     // Rotate normal with body without translating.
     out.normal = normalize((model_matrix.matrix * vec4<f32>(vertex.normal, 0.0)).xyz);
+    out.tangent = vec4<f32>(
+        normalize((model_matrix.matrix * vec4<f32>(vertex.tangent.xyz, 0.0)).xyz),
+        vertex.tangent.w,
+    );
     // World coordinates of vertex, after applying model transformation.
-    let world_position = (model_matrix.matrix * vec4<f32>(vertex.position, 1.0));
+    out.world_position = (model_matrix.matrix * vec4<f32>(vertex.position, 1.0));
 
     // Direction from point to light in world space.
-    out.light_direction = normalize(light.position - world_position.xyz);
+    out.light_direction = normalize(light.position - out.world_position.xyz);
 
     return out;
 }
@@ -76,28 +95,91 @@ var diffuse_tex: texture_2d<f32>;
 @group(3) @binding(1)
 var diffuse_samp: sampler;
 
+const FOG_BIT: u32 = 8u;
+const CLIP_BIT: u32 = 16u;
+const TWO_SIDED_BIT: u32 = 32u;
+const SSAO_BIT: u32 = 64u;
+const ALPHA_COVERAGE_BIT: u32 = 128u;
+const ALPHA_TEST_BIT: u32 = 256u;
+
+// Discard fragments on the negative side of the clipping plane, if enabled.
+fn apply_clip_plane(world_position: vec3<f32>) {
+    if (preferences.flags & CLIP_BIT) == 0u {
+        return;
+    }
+    if dot(preferences.clip_plane.xyz, world_position) + preferences.clip_plane.w < 0.0 {
+        discard;
+    }
+}
+
+// Blend toward fog color with exponential-squared falloff in view depth.
+fn apply_fog(color: vec3<f32>, view_depth: f32) -> vec3<f32> {
+    if (preferences.flags & FOG_BIT) == 0u {
+        return color;
+    }
+    let fog_amount = preferences.fog_density * view_depth;
+    let visibility = clamp(exp(-fog_amount * fog_amount), 0.0, 1.0);
+    return mix(preferences.fog_color, color, visibility);
+}
+
+// Fraction of ambient light reaching this pixel, if SSAO is enabled.
+fn get_ambient_occlusion(view_position: vec4<f32>) -> f32 {
+    if (preferences.flags & SSAO_BIT) == 0u {
+        return 1.0;
+    }
+    return textureLoad(ambient_occlusion, vec2<i32>(view_position.xy), 0).r;
+}
+
+// Output alpha for a texel: its alpha with alpha to coverage, or opaque
+// after a hard alpha test without MSAA. Opaque unless cutouts are enabled.
+fn cutout_alpha(texture_alpha: f32) -> f32 {
+    if (preferences.flags & ALPHA_COVERAGE_BIT) != 0u {
+        return texture_alpha;
+    }
+    if (preferences.flags & ALPHA_TEST_BIT) != 0u && texture_alpha < 0.5 {
+        discard;
+    }
+    return 1.0;
+}
+
+// Normal facing the viewer, so back faces are lit as seen from their side.
+fn facing_normal(normal: vec3<f32>, front_facing: bool) -> vec3<f32> {
+    if front_facing || (preferences.flags & TWO_SIDED_BIT) == 0u {
+        return normal;
+    }
+    return -normal;
+}
+
 @fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+fn fs_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    apply_clip_plane(in.world_position.xyz);
+
     let use_light = (preferences.flags & 1u) > 0u;
     // TODO: Add correct handling for this in application.
     let use_texture = (preferences.flags & 2u) > 0u;
 
     var color: vec3<f32>;
+    var alpha = 1.0;
     if use_texture {
-        color = textureSample(diffuse_tex, diffuse_samp, in.tex_coords).xyz;
+        let texel = textureSample(diffuse_tex, diffuse_samp, in.tex_coords);
+        // Vertex color tints the texture; it is white unless overridden.
+        color = in.color * texel.xyz;
+        alpha = cutout_alpha(texel.a);
     } else {
         color = in.color;
     }
 
     if use_light {
         let ambient_strength = 0.05;
-        let diffuse_strength = 0.95 * max(0.0, dot(in.light_direction, in.normal));
+        let normal = facing_normal(in.normal, front_facing);
+        let diffuse_strength = 0.95 * max(0.0, dot(in.light_direction, normal));
         let out_color = light.color * color;
+        let occlusion = get_ambient_occlusion(in.view_position);
 
         // Only ambient and diffuse lighting here for now.
-        return vec4<f32>((ambient_strength + diffuse_strength) * out_color, 1.0);
+        return vec4<f32>(apply_fog(occlusion * (ambient_strength + diffuse_strength) * out_color, in.view_depth), alpha);
     } else {
 
-        return vec4<f32>(color, 1.0);
+        return vec4<f32>(apply_fog(color, in.view_depth), alpha);
     }
 }
@@ -0,0 +1,77 @@
+// Final full-screen pass that maps the HDR offscreen scene
+// target to the display surface, with exposure and gamma. The
+// target is sampled, so it can be smaller or larger than the
+// surface when rendering at a render scale.
+
+struct ToneMapUniform {
+    exposure: f32,
+    gamma: f32,
+    // `operator` is reserved in WGSL
+    tone_operator: u32,
+    enabled: u32,
+}
+
+@group(0) @binding(0)
+var scene_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var<uniform> tone_map: ToneMapUniform;
+
+@group(0) @binding(2)
+var scene_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+// Vertex shader.
+
+// Single triangle covering the viewport; positions come from the index.
+@vertex
+fn vs_main(@builtin(vertex_index) in_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let uv = vec2<f32>(f32((in_index << 1u) & 2u), f32(in_index & 2u));
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    // Texture coordinates have y down.
+    out.tex_coords = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+// Fragment shader.
+
+const REINHARD: u32 = 0u;
+// The surface is sRGB, so encoding already applies this gamma.
+const SURFACE_GAMMA: f32 = 2.2;
+
+fn reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 + color);
+}
+
+// Narkowicz's fit of the ACES filmic curve.
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let numerator = color * (2.51 * color + 0.03);
+    let denominator = color * (2.43 * color + 0.59) + 0.14;
+    return clamp(numerator / denominator, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // At render scale one, pixel centers land on texel centers, so this
+    // matches a direct load. At two, each sample averages four texels.
+    let color = textureSampleLevel(scene_texture, scene_sampler, in.tex_coords, 0.0).rgb;
+
+    if tone_map.enabled == 0u {
+        return vec4<f32>(color, 1.0);
+    }
+
+    let exposed = max(color * tone_map.exposure, vec3<f32>(0.0));
+    var mapped: vec3<f32>;
+    if tone_map.tone_operator == REINHARD {
+        mapped = reinhard(exposed);
+    } else {
+        mapped = aces(exposed);
+    }
+
+    return vec4<f32>(pow(mapped, vec3<f32>(SURFACE_GAMMA / tone_map.gamma)), 1.0);
+}
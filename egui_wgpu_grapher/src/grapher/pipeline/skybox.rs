@@ -0,0 +1,279 @@
+// Cube texture background rendered behind 3D scenes.
+
+use super::{
+    antialiasing,
+    texture::{DepthBuffer, OffscreenTarget},
+};
+use crate::grapher::{
+    math::colormap::srgb_to_linear,
+    matrix::{self, Matrix, MatrixUniform},
+};
+
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, Device,
+    Extent3d, Origin3d, Queue, RenderPass, RenderPipeline, TexelCopyBufferLayout,
+    TexelCopyTextureInfo,
+};
+use image::DynamicImage;
+
+use std::f32::consts::PI;
+
+pub struct Skybox {
+    // inverse camera matrix used to find view directions
+    pub matrix: MatrixUniform,
+    pub bind_group: BindGroup,
+    pub pipeline: RenderPipeline,
+    // file the cube texture was built from
+    pub path: String,
+}
+
+impl Skybox {
+    // largest cube face edge we build from an equirectangular image
+    const MAX_FACE_SIZE: u32 = 512;
+
+    /// Build a skybox from an equirectangular (longitude/latitude) image.
+    pub fn from_equirectangular(
+        device: &Device,
+        queue: &Queue,
+        path: &str,
+    ) -> Result<Self, String> {
+        let Ok(image) = image::open(path) else {
+            return Err("Failed to load skybox image.".into());
+        };
+        let face_size = (image.width() / 4).clamp(1, Self::MAX_FACE_SIZE);
+        let faces = equirectangular_to_cube_faces(&image, face_size);
+
+        let size = Extent3d {
+            width: face_size,
+            height: face_size,
+            depth_or_array_layers: 6,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("skybox cube texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&faces),
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(face_size * std::mem::size_of::<[f32; 4]>() as u32),
+                rows_per_image: Some(face_size),
+            },
+            size,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("skybox cube view"),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("skybox sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let matrix = matrix::make_matrix_uniform(device, Matrix::identity());
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("skybox bind group layout"),
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: matrix.buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("skybox bind group"),
+        });
+
+        let pipeline = create_skybox_pipeline(device, &bind_group_layout);
+
+        Ok(Self {
+            matrix,
+            bind_group,
+            pipeline,
+            path: path.into(),
+        })
+    }
+
+    pub fn update_uniform(&mut self, queue: &Queue, inverse_view_projection: cgmath::Matrix4<f32>) {
+        self.matrix.uniform.update_inner(inverse_view_projection);
+        queue.write_buffer(
+            &self.matrix.buffer,
+            0,
+            bytemuck::cast_slice(&[self.matrix.uniform]),
+        );
+    }
+
+    /// Draw into a pass using the scene's MSAA color and depth targets.
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_skybox_pipeline(
+    device: &Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+) -> RenderPipeline {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("skybox shader"),
+        source: super::get_skybox_shader(),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("skybox pipeline layout"),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("skybox pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader_module,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader_module,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: OffscreenTarget::FORMAT,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        // Shares the scene depth buffer but never writes to it.
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DepthBuffer::DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: antialiasing::sample_count(),
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+// ------------------------------------------------
+// Resample an equirectangular image to cube faces.
+
+/// Returns the six faces in +X, -X, +Y, -Y, +Z, -Z order, as linear RGBA.
+fn equirectangular_to_cube_faces(image: &DynamicImage, face_size: u32) -> Vec<[f32; 4]> {
+    // HDR formats are already linear; 8-bit formats are sRGB encoded.
+    let is_linear = matches!(
+        image,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    );
+    let source = image.to_rgba32f();
+    let (width, height) = source.dimensions();
+
+    let mut faces = Vec::with_capacity((6 * face_size * face_size) as usize);
+    for face in 0..6 {
+        for y in 0..face_size {
+            for x in 0..face_size {
+                // Face coordinates in [-1, 1].
+                let s = 2.0 * (x as f32 + 0.5) / face_size as f32 - 1.0;
+                let t = 2.0 * (y as f32 + 0.5) / face_size as f32 - 1.0;
+                let direction = match face {
+                    0 => [1.0, -t, -s],
+                    1 => [-1.0, -t, s],
+                    2 => [s, 1.0, t],
+                    3 => [s, -1.0, -t],
+                    4 => [s, -t, 1.0],
+                    _ => [-s, -t, -1.0],
+                };
+                let length = (direction[0] * direction[0]
+                    + direction[1] * direction[1]
+                    + direction[2] * direction[2])
+                    .sqrt();
+
+                let longitude = direction[2].atan2(direction[0]);
+                let latitude = (direction[1] / length).acos();
+                let u = 0.5 + longitude / (2.0 * PI);
+                let v = latitude / PI;
+
+                let source_x = ((u * width as f32) as u32).min(width - 1);
+                let source_y = ((v * height as f32) as u32).min(height - 1);
+                let pixel = source.get_pixel(source_x, source_y).0;
+
+                if is_linear {
+                    faces.push([pixel[0], pixel[1], pixel[2], 1.0]);
+                } else {
+                    faces.push([
+                        srgb_to_linear(pixel[0]),
+                        srgb_to_linear(pixel[1]),
+                        srgb_to_linear(pixel[2]),
+                        1.0,
+                    ]);
+                }
+            }
+        }
+    }
+    faces
+}
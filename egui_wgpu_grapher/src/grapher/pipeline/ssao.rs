@@ -0,0 +1,307 @@
+// Screen-space ambient occlusion, computed from a depth prepass of the
+// 3D scene and sampled by the mesh shaders when shading.
+
+use super::{
+    antialiasing,
+    texture::{self, DepthBuffer},
+};
+use crate::grapher::{camera::Camera, scene::Bufferable};
+
+use cgmath::SquareMatrix;
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, Buffer, CommandEncoder, Device, Queue,
+    RenderPipeline, SurfaceConfiguration, Texture, TextureView, util::DeviceExt,
+};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SsaoUniform {
+    // camera projection, to move between view space and the depth buffer
+    pub projection: [[f32; 4]; 4],
+    pub inverse_projection: [[f32; 4]; 4],
+    // view-space radius of the sampled hemisphere
+    pub radius: f32,
+    // scales how much occluded samples darken
+    pub intensity: f32,
+    _padding: [f32; 2],
+}
+
+// Occlusion factor texture, written by a pass and read by later passes.
+struct OcclusionTarget {
+    _texture: Texture,
+    view: TextureView,
+}
+
+impl OcclusionTarget {
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+    fn create(config: &SurfaceConfiguration, device: &Device, label: &str) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: config.width.max(1),
+                height: config.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            _texture: texture,
+            view,
+        }
+    }
+}
+
+pub struct SsaoState {
+    pub uniform: SsaoUniform,
+    buffer: Buffer,
+    // noisy occlusion from the sampling pass
+    raw: OcclusionTarget,
+    // blurred occlusion that shading reads
+    blurred: OcclusionTarget,
+    // these reference the targets above and the depth buffer, so are rebuilt on resize
+    occlusion_bind_group_layout: BindGroupLayout,
+    occlusion_bind_group: BindGroup,
+    blur_bind_group_layout: BindGroupLayout,
+    blur_bind_group: BindGroup,
+    // none where shaders can't read the depth buffer
+    occlusion_pipeline: Option<RenderPipeline>,
+    blur_pipeline: RenderPipeline,
+    // fills the depth buffer before the occlusion pass
+    pub prepass_pipeline: RenderPipeline,
+}
+
+impl SsaoState {
+    pub const DEFAULT_RADIUS: f32 = 0.5;
+    pub const DEFAULT_INTENSITY: f32 = 1.0;
+
+    /// The mesh shaders read the occlusion at this binding of group 0.
+    pub fn bind_group_layout_entry(binding: u32) -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            },
+            count: None,
+        }
+    }
+
+    pub fn create<Vertex: Bufferable>(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        depth_buffer: &DepthBuffer,
+        prepass_bind_group_layouts: &[&BindGroupLayout],
+    ) -> Self {
+        let uniform = SsaoUniform {
+            projection: cgmath::Matrix4::identity().into(),
+            inverse_projection: cgmath::Matrix4::identity().into(),
+            radius: Self::DEFAULT_RADIUS,
+            intensity: Self::DEFAULT_INTENSITY,
+            _padding: [0.0; 2],
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("SSAO UBO"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let occlusion_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: antialiasing::sample_count() > 1,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Depth,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("ssao bind group layout"),
+            });
+        let blur_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[Self::bind_group_layout_entry(0)],
+            label: Some("ssao blur bind group layout"),
+        });
+
+        let raw = OcclusionTarget::create(surface_config, device, "ssao raw target");
+        let blurred = OcclusionTarget::create(surface_config, device, "ssao blurred target");
+        let occlusion_bind_group = Self::create_occlusion_bind_group(
+            device,
+            &occlusion_bind_group_layout,
+            &buffer,
+            depth_buffer,
+        );
+        let blur_bind_group = Self::create_blur_bind_group(device, &blur_bind_group_layout, &raw);
+
+        let occlusion_pipeline = texture::depth_readable().then(|| {
+            super::create_fullscreen_pipeline(
+                device,
+                super::get_ssao_shader(),
+                OcclusionTarget::FORMAT,
+                &[&occlusion_bind_group_layout],
+            )
+        });
+        let blur_pipeline = super::create_fullscreen_pipeline(
+            device,
+            super::get_ssao_blur_shader(),
+            OcclusionTarget::FORMAT,
+            &[&blur_bind_group_layout],
+        );
+        let prepass_pipeline =
+            super::create_depth_only_pipeline::<Vertex>(device, prepass_bind_group_layouts);
+
+        Self {
+            uniform,
+            buffer,
+            raw,
+            blurred,
+            occlusion_bind_group_layout,
+            occlusion_bind_group,
+            blur_bind_group_layout,
+            blur_bind_group,
+            occlusion_pipeline,
+            blur_pipeline,
+            prepass_pipeline,
+        }
+    }
+
+    fn create_occlusion_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        depth_buffer: &DepthBuffer,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&depth_buffer.view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("ssao bind group"),
+        })
+    }
+
+    fn create_blur_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        raw: &OcclusionTarget,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&raw.view),
+            }],
+            label: Some("ssao blur bind group"),
+        })
+    }
+
+    /// Whether occlusion can be computed on this backend.
+    pub fn supported(&self) -> bool {
+        self.occlusion_pipeline.is_some()
+    }
+
+    /// Blurred occlusion, for the shared bind group the mesh shaders use.
+    pub fn view(&self) -> &TextureView {
+        &self.blurred.view
+    }
+
+    pub fn handle_resize(
+        &mut self,
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        depth_buffer: &DepthBuffer,
+    ) {
+        self.raw = OcclusionTarget::create(surface_config, device, "ssao raw target");
+        self.blurred = OcclusionTarget::create(surface_config, device, "ssao blurred target");
+        self.occlusion_bind_group = Self::create_occlusion_bind_group(
+            device,
+            &self.occlusion_bind_group_layout,
+            &self.buffer,
+            depth_buffer,
+        );
+        self.blur_bind_group =
+            Self::create_blur_bind_group(device, &self.blur_bind_group_layout, &self.raw);
+    }
+
+    /// Match the camera projection and write the uniform.
+    pub fn update_uniform(&mut self, queue: &Queue, camera: &Camera) {
+        let projection = camera.get_projection_matrix();
+        self.uniform.projection = projection.into();
+        self.uniform.inverse_projection = projection
+            .invert()
+            .unwrap_or_else(cgmath::Matrix4::identity)
+            .into();
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+    }
+
+    /// Compute occlusion from the depth buffer filled by the prepass.
+    pub fn render(&self, encoder: &mut CommandEncoder) {
+        let Some(occlusion_pipeline) = &self.occlusion_pipeline else {
+            return;
+        };
+        for (label, target, pipeline, bind_group) in [
+            (
+                "ssao pass",
+                &self.raw,
+                occlusion_pipeline,
+                &self.occlusion_bind_group,
+            ),
+            (
+                "ssao blur pass",
+                &self.blurred,
+                &self.blur_pipeline,
+                &self.blur_bind_group,
+            ),
+        ] {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(label),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
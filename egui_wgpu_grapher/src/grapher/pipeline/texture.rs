@@ -1,6 +1,9 @@
 //! Code for building and representing textures and related data.
 
-use std::sync::OnceLock;
+use std::sync::{
+    OnceLock,
+    atomic::{AtomicBool, Ordering},
+};
 
 use egui_wgpu::wgpu::{
     self, BindGroupLayout, Device, Queue, SurfaceConfiguration, Texture, TextureView,
@@ -30,6 +33,48 @@ impl Image {
     }
 }
 
+// ---------------------------
+// Texture sampling filter mode.
+
+/// How textures are sampled when magnified or minified.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum TextureFiltering {
+    /// Linear interpolation between texels.
+    #[default]
+    Smooth,
+    /// Nearest texel, for pixel art and sharp data.
+    Crisp,
+}
+
+impl TextureFiltering {
+    pub const ALL: [TextureFiltering; 2] = [TextureFiltering::Smooth, TextureFiltering::Crisp];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TextureFiltering::Smooth => "Smooth",
+            TextureFiltering::Crisp => "Crisp",
+        }
+    }
+
+    /// Clamped sampler using this filter mode for both magnification
+    /// and minification.
+    pub fn create_sampler(self, device: &Device) -> wgpu::Sampler {
+        let filter = match self {
+            TextureFiltering::Smooth => wgpu::FilterMode::Linear,
+            TextureFiltering::Crisp => wgpu::FilterMode::Nearest,
+        };
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        })
+    }
+}
+
 // --------------------
 // Texture device data.
 
@@ -67,19 +112,28 @@ impl TextureData {
     }
 
     pub fn from_texture(texture: Texture, device: &Device) -> Self {
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = Self::create_bind_group(&texture, device, TextureFiltering::default());
 
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        Self {
+            bind_group,
+            texture,
+        }
+    }
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+    /// Recreate the bind group with a sampler using the given filtering.
+    pub fn set_filtering(&mut self, device: &Device, filtering: TextureFiltering) {
+        self.bind_group = Self::create_bind_group(&self.texture, device, filtering);
+    }
+
+    fn create_bind_group(
+        texture: &Texture,
+        device: &Device,
+        filtering: TextureFiltering,
+    ) -> wgpu::BindGroup {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = filtering.create_sampler(device);
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: Self::bind_group_layout(device),
             entries: &[
                 wgpu::BindGroupEntry {
@@ -92,12 +146,7 @@ impl TextureData {
                 },
             ],
             label: Some("texture bind group"),
-        });
-
-        Self {
-            bind_group,
-            texture,
-        }
+        })
     }
 
     pub fn from_image(image: &Image, device: &Device, queue: &Queue) -> Self {
@@ -208,6 +257,38 @@ pub fn texture_from_matrix(
 // -------------------------
 // Depth buffer device data.
 
+// Reverse-Z maps the near plane to depth 1 and the far plane to 0, which
+// with a float depth buffer spreads precision evenly over distance. Every
+// depth-tested scene pipeline is built for one direction, so like the MSAA
+// sample count it is set once at startup, before any exist.
+static REVERSE_Z: AtomicBool = AtomicBool::new(false);
+
+pub fn reverse_z() -> bool {
+    REVERSE_Z.load(Ordering::Relaxed)
+}
+
+pub fn set_reverse_z(enabled: bool) {
+    REVERSE_Z.store(enabled, Ordering::Relaxed);
+}
+
+// Post-processes that load from the depth buffer can't be translated to
+// GLSL, so on the GL backend they are left unbuilt and their options
+// disabled. Like the depth direction, this is set once at startup.
+static DEPTH_READABLE: AtomicBool = AtomicBool::new(true);
+
+pub fn depth_readable() -> bool {
+    DEPTH_READABLE.load(Ordering::Relaxed)
+}
+
+pub fn set_depth_readable(readable: bool) {
+    DEPTH_READABLE.store(readable, Ordering::Relaxed);
+}
+
+/// Whether the adapter's shaders can load from depth textures.
+pub fn adapter_reads_depth(adapter: &wgpu::Adapter) -> bool {
+    adapter.get_info().backend != wgpu::Backend::Gl
+}
+
 pub struct DepthBuffer {
     pub texture: Texture,
     pub view: TextureView,
@@ -216,6 +297,24 @@ pub struct DepthBuffer {
 impl DepthBuffer {
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
+    /// Depth of the far plane, which the buffer is cleared to.
+    pub fn clear_value() -> f32 {
+        if reverse_z() { 0.0 } else { 1.0 }
+    }
+
+    /// The given comparison, written for near-is-less depth, flipped
+    /// to match the depth direction in use.
+    pub fn compare(compare: wgpu::CompareFunction) -> wgpu::CompareFunction {
+        use wgpu::CompareFunction::*;
+        match compare {
+            Less if reverse_z() => Greater,
+            LessEqual if reverse_z() => GreaterEqual,
+            Greater if reverse_z() => Less,
+            GreaterEqual if reverse_z() => LessEqual,
+            _ => compare,
+        }
+    }
+
     pub fn create(config: &SurfaceConfiguration, device: &Device) -> Self {
         let size = wgpu::Extent3d {
             width: config.width.max(1),
@@ -227,7 +326,7 @@ impl DepthBuffer {
             label: Some("depth buffer"),
             size,
             mip_level_count: 1,
-            sample_count: 4,
+            sample_count: super::antialiasing::sample_count(),
             dimension: wgpu::TextureDimension::D2,
             format: Self::DEPTH_FORMAT,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
@@ -240,3 +339,43 @@ impl DepthBuffer {
         Self { texture, view }
     }
 }
+
+// ---------------------------------------------------
+// HDR color target that scenes are rendered into
+// before the final tone mapping pass to the surface.
+
+pub struct OffscreenTarget {
+    pub texture: Texture,
+    pub view: TextureView,
+}
+
+impl OffscreenTarget {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+    pub fn create(config: &SurfaceConfiguration, device: &Device) -> Self {
+        let size = wgpu::Extent3d {
+            width: config.width.max(1),
+            height: config.height.max(1),
+            depth_or_array_layers: 1,
+        };
+
+        let desc = wgpu::TextureDescriptor {
+            label: Some("offscreen color target"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        };
+        let texture = device.create_texture(&desc);
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self { texture, view }
+    }
+}
@@ -0,0 +1,213 @@
+// Final pass from the HDR offscreen target to the surface.
+
+use super::texture::OffscreenTarget;
+
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, Buffer, CommandEncoder, Device, Queue, RenderPipeline, Sampler,
+    SurfaceConfiguration, TextureView, util::DeviceExt,
+};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ToneMapUniform {
+    // linear scale applied before tone mapping
+    pub exposure: f32,
+    // display gamma; the sRGB surface default leaves color unchanged
+    pub gamma: f32,
+    // see `ToneMapOperator`
+    pub tone_operator: u32,
+    pub enabled: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToneMapOperator {
+    Reinhard = 0,
+    Aces = 1,
+}
+
+impl ToneMapOperator {
+    pub const ALL: [ToneMapOperator; 2] = [ToneMapOperator::Reinhard, ToneMapOperator::Aces];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ToneMapOperator::Reinhard => "Reinhard",
+            ToneMapOperator::Aces => "ACES",
+        }
+    }
+}
+
+pub struct ToneMapState {
+    pub uniform: ToneMapUniform,
+    pub buffer: Buffer,
+    pub bind_group_layout: BindGroupLayout,
+    // filters the offscreen target when its size differs from the surface's
+    sampler: Sampler,
+    // references the offscreen target, so is rebuilt on resize
+    pub bind_group: BindGroup,
+    pub pipeline: RenderPipeline,
+}
+
+impl ToneMapState {
+    pub const NEUTRAL_EXPOSURE: f32 = 1.0;
+    pub const NEUTRAL_GAMMA: f32 = 2.2;
+
+    pub fn create(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        target: &OffscreenTarget,
+    ) -> Self {
+        let uniform = ToneMapUniform {
+            exposure: Self::NEUTRAL_EXPOSURE,
+            gamma: Self::NEUTRAL_GAMMA,
+            tone_operator: ToneMapOperator::Aces as u32,
+            enabled: 0,
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tone Map UBO"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("tone map bind group layout"),
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("tone map sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &buffer, &sampler, target);
+
+        let pipeline = super::create_fullscreen_pipeline(
+            device,
+            super::get_tone_map_shader(),
+            surface_config.format,
+            &[&bind_group_layout],
+        );
+
+        Self {
+            uniform,
+            buffer,
+            bind_group_layout,
+            sampler,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        sampler: &Sampler,
+        target: &OffscreenTarget,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&target.view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("tone map bind group"),
+        })
+    }
+
+    pub fn handle_resize(&mut self, device: &Device, target: &OffscreenTarget) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.buffer,
+            &self.sampler,
+            target,
+        );
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.uniform.enabled != 0
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.uniform.enabled = enabled as u32;
+    }
+
+    pub fn operator(&self) -> ToneMapOperator {
+        if self.uniform.tone_operator == ToneMapOperator::Reinhard as u32 {
+            ToneMapOperator::Reinhard
+        } else {
+            ToneMapOperator::Aces
+        }
+    }
+
+    pub fn set_operator(&mut self, operator: ToneMapOperator) {
+        self.uniform.tone_operator = operator as u32;
+    }
+
+    pub fn update_uniform(&mut self, queue: &Queue) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+    }
+
+    /// Draw the offscreen target onto the given surface view.
+    pub fn render(&self, view: &TextureView, encoder: &mut CommandEncoder) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("tone map pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
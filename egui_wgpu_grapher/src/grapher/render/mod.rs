@@ -3,13 +3,18 @@
 mod state;
 pub use state::*;
 
-use super::scene::Scene3D;
-use crate::grapher::scene::solver::SolverScene;
+use super::{
+    pipeline::texture::DepthBuffer,
+    scene::{CANVAS_QUAD_INDICES, CanvasScene, Scene3D, reference_plane::ReferencePlane},
+};
 
 use egui_wgpu::wgpu::{
-    self, BindGroup, BufferSlice, Color, CommandEncoder, RenderPass, TextureView,
+    self, BindGroup, BufferSlice, Color, CommandEncoder, RenderPass, RenderPipeline, TextureView,
 };
 
+/// Background color for 3D scenes.
+pub const CLEAR_COLOR: Color = Color::BLACK;
+
 // -------------------------------
 // Main 3D scene rendering method.
 
@@ -46,29 +51,89 @@ impl RenderState {
             // Render pass ends on drop when it goes out of scope here.
         }
 
+        if self.render_preferences.ssao_enabled() {
+            self.render_ssao(encoder, scene);
+        }
+
         // want to clear depth & MSAA buffers on first render only
-        let mut load_op = wgpu::LoadOp::Clear(wgpu::Color {
-            r: 0.0,
-            g: 0.0,
-            b: 0.0,
-            a: 1.0,
-        });
-        let mut depth_load_op = wgpu::LoadOp::Clear(1.0);
+        let mut load_op = wgpu::LoadOp::Clear(CLEAR_COLOR);
+        let mut depth_load_op = wgpu::LoadOp::Clear(DepthBuffer::clear_value());
+
+        // Draw skybox background first; without one the clear color shows.
+        if let Some(skybox) = &self.skybox {
+            let color_attachment = self.color_attachment(view, load_op);
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("skybox pass"),
+                color_attachments: &[Some(color_attachment)],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_buffer.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: depth_load_op,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            skybox.draw(&mut render_pass);
+
+            load_op = wgpu::LoadOp::Load;
+            depth_load_op = wgpu::LoadOp::Load;
+        }
 
         // Render solid meshes if configured. Shadow always comes
         // with solid pipeline: these could be put in one struct.
         if let Some(pipeline) = &scene.pipeline
             && let Some(shadow) = &scene.shadow
         {
-            let color_attachment = wgpu::RenderPassColorAttachment {
-                view: &self.msaa_data.view,
-                resolve_target: Some(view),
-                ops: wgpu::Operations {
-                    load: load_op,
-                    store: wgpu::StoreOp::Store,
-                },
-                depth_slice: None,
-            };
+            let color_attachment = self.color_attachment(view, load_op);
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("render pass"),
+                color_attachments: &[Some(color_attachment)],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_buffer.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: depth_load_op,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            // With a depth prepass, transparent meshes only blend their
+            // nearest layer, which avoids depending on draw order.
+            let pipelines = scene.depth_prepass_pipeline.iter().chain([pipeline]);
+            for pipeline in pipelines {
+                render_pass.set_pipeline(pipeline);
+
+                for mesh in &scene.meshes {
+                    draw_mesh(
+                        &mut render_pass,
+                        mesh.vertex_buffer.slice(..),
+                        mesh.index_buffer.slice(..),
+                        mesh.num_indices,
+                        1,
+                        &[
+                            &self.bind_group,
+                            &mesh.matrix_bind_group,
+                            &scene.light.bind_group,
+                            &shadow.render_pass_bind_group,
+                        ],
+                    );
+                }
+            }
+
+            load_op = wgpu::LoadOp::Load;
+            depth_load_op = wgpu::LoadOp::Load;
+        }
+
+        // render textured meshes if configured
+        if let Some(pipeline) = &scene.textured_pipeline {
+            let color_attachment = self.color_attachment(view, load_op);
 
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("render pass"),
@@ -86,17 +151,18 @@ impl RenderState {
             });
             render_pass.set_pipeline(pipeline);
 
-            for mesh in &scene.meshes {
+            for mesh in &scene.textured_meshes {
                 draw_mesh(
                     &mut render_pass,
                     mesh.vertex_buffer.slice(..),
                     mesh.index_buffer.slice(..),
                     mesh.num_indices,
+                    1,
                     &[
                         &self.bind_group,
                         &mesh.matrix_bind_group,
                         &scene.light.bind_group,
-                        &shadow.render_pass_bind_group,
+                        &mesh.texture.bind_group,
                     ],
                 );
             }
@@ -105,20 +171,12 @@ impl RenderState {
             depth_load_op = wgpu::LoadOp::Load;
         }
 
-        // render textured meshes if configured
-        if let Some(pipeline) = &scene.textured_pipeline {
-            let color_attachment = wgpu::RenderPassColorAttachment {
-                view: &self.msaa_data.view,
-                resolve_target: Some(view),
-                ops: wgpu::Operations {
-                    load: load_op,
-                    store: wgpu::StoreOp::Store,
-                },
-                depth_slice: None,
-            };
+        // render instanced meshes if configured
+        if let Some(pipeline) = &scene.instanced_pipeline {
+            let color_attachment = self.color_attachment(view, load_op);
 
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("render pass"),
+                label: Some("instanced render pass"),
                 color_attachments: &[Some(color_attachment)],
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                     view: &self.depth_buffer.view,
@@ -133,12 +191,15 @@ impl RenderState {
             });
             render_pass.set_pipeline(pipeline);
 
-            for mesh in &scene.textured_meshes {
+            for instanced in scene.instanced_meshes.iter().filter(|mesh| mesh.visible) {
+                let mesh = &instanced.mesh;
+                render_pass.set_vertex_buffer(1, instanced.instance_buffer.slice(..));
                 draw_mesh(
                     &mut render_pass,
                     mesh.vertex_buffer.slice(..),
                     mesh.index_buffer.slice(..),
                     mesh.num_indices,
+                    instanced.num_instances,
                     &[
                         &self.bind_group,
                         &mesh.matrix_bind_group,
@@ -147,15 +208,255 @@ impl RenderState {
                     ],
                 );
             }
+
+            load_op = wgpu::LoadOp::Load;
+            depth_load_op = wgpu::LoadOp::Load;
+        }
+
+        // render points if configured
+        if let Some(pipeline) = &scene.point_pipeline {
+            let color_attachment = self.color_attachment(view, load_op);
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("point render pass"),
+                color_attachments: &[Some(color_attachment)],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_buffer.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: depth_load_op,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+
+            for points in &scene.points {
+                render_pass.set_bind_group(1, &points.bind_group, &[]);
+                render_pass.set_vertex_buffer(0, points.vertex_buffer.slice(..));
+                // one quad instance per point
+                render_pass.draw(0..6, 0..points.num_points);
+            }
+
+            load_op = wgpu::LoadOp::Load;
+            depth_load_op = wgpu::LoadOp::Load;
+        }
+
+        // render lines if configured
+        if let Some(pipeline) = &scene.line_pipeline {
+            let color_attachment = self.color_attachment(view, load_op);
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("line render pass"),
+                color_attachments: &[Some(color_attachment)],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_buffer.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: depth_load_op,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+
+            for lines in &scene.lines {
+                render_pass.set_bind_group(1, &lines.matrix_bind_group, &[]);
+                render_pass.set_vertex_buffer(0, lines.vertex_buffer.slice(..));
+                render_pass.draw(0..lines.num_vertices, 0..1);
+            }
         }
     }
 }
 
+impl RenderState {
+    /// Color attachment for scene passes: the MSAA texture resolving
+    /// into `view`, or `view` itself when multisampling is off.
+    pub fn color_attachment<'a>(
+        &'a self,
+        view: &'a TextureView,
+        load: wgpu::LoadOp<Color>,
+    ) -> wgpu::RenderPassColorAttachment<'a> {
+        let (view, resolve_target) = match &self.msaa_data {
+            Some(msaa_data) => (&msaa_data.view, Some(view)),
+            None => (view, None),
+        };
+        wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            },
+            depth_slice: None,
+        }
+    }
+
+    /// Fill the depth buffer with solid and textured meshes and compute
+    /// ambient occlusion from it. The main passes clear depth again.
+    fn render_ssao(&self, encoder: &mut CommandEncoder, scene: &Scene3D) {
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("ssao depth prepass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_buffer.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(DepthBuffer::clear_value()),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(&self.ssao.prepass_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+
+            let meshes = scene.meshes.iter().map(|mesh| {
+                (
+                    &mesh.vertex_buffer,
+                    &mesh.index_buffer,
+                    mesh.num_indices,
+                    &mesh.matrix_bind_group,
+                )
+            });
+            let textured_meshes = scene.textured_meshes.iter().map(|mesh| {
+                (
+                    &mesh.vertex_buffer,
+                    &mesh.index_buffer,
+                    mesh.num_indices,
+                    &mesh.matrix_bind_group,
+                )
+            });
+            for (vertex_buffer, index_buffer, num_indices, matrix_bind_group) in
+                meshes.chain(textured_meshes)
+            {
+                render_pass.set_bind_group(1, matrix_bind_group, &[]);
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..num_indices, 0, 0..1);
+            }
+        }
+
+        self.ssao.render(encoder);
+    }
+
+    /// Apply depth of field to a 3D scene already rendered to the offscreen target.
+    pub fn render_depth_of_field(&self, encoder: &mut CommandEncoder) {
+        self.depth_of_field.render(&self.offscreen_target, encoder);
+    }
+
+    /// Draw the camera target gizmo and light marker, if enabled,
+    /// over a 3D scene already rendered to `view`.
+    pub fn render_gizmo(&self, view: &TextureView, encoder: &mut CommandEncoder) {
+        let show_marker = self.light_placement.marker_visible();
+        if !self.gizmo.enabled && !show_marker {
+            return;
+        }
+
+        let color_attachment = self.color_attachment(view, wgpu::LoadOp::Load);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("gizmo pass"),
+            color_attachments: &[Some(color_attachment)],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_buffer.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        if self.gizmo.enabled {
+            self.gizmo.draw(&mut render_pass, &self.bind_group);
+        }
+        if show_marker {
+            self.light_placement
+                .draw(&mut render_pass, &self.bind_group);
+        }
+    }
+
+    /// Draw the scene's reflection below the floor, before the scene itself.
+    pub fn render_reflection(
+        &self,
+        encoder: &mut CommandEncoder,
+        scene: &Scene3D,
+        pipeline: &RenderPipeline,
+    ) {
+        if self.floor.enabled {
+            self.floor.render_reflection(encoder, scene, pipeline);
+        }
+    }
+
+    /// Draw the reflective floor into a 3D scene already rendered to `view`.
+    pub fn render_floor(&self, view: &TextureView, encoder: &mut CommandEncoder) {
+        if !self.floor.enabled {
+            return;
+        }
+
+        let color_attachment = self.color_attachment(view, wgpu::LoadOp::Load);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("floor pass"),
+            color_attachments: &[Some(color_attachment)],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_buffer.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        self.floor.draw_floor(&mut render_pass, &self.bind_group);
+    }
+
+    /// Draw a graph's reference plane over a 3D scene already rendered to `view`.
+    /// It's drawn last, so it blends over everything behind it.
+    pub fn render_reference_plane(
+        &self,
+        view: &TextureView,
+        encoder: &mut CommandEncoder,
+        plane: &ReferencePlane,
+    ) {
+        let color_attachment = self.color_attachment(view, wgpu::LoadOp::Load);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("reference plane pass"),
+            color_attachments: &[Some(color_attachment)],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_buffer.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        plane.draw(&mut render_pass, &self.bind_group);
+    }
+}
+
 fn draw_mesh(
     render_pass: &mut RenderPass,
     vertex_buffer: BufferSlice,
     index_buffer: BufferSlice,
     num_indices: u32,
+    num_instances: u32,
     bind_groups: &[&BindGroup],
 ) {
     for (index, bind_group) in bind_groups.iter().enumerate() {
@@ -163,7 +464,7 @@ fn draw_mesh(
     }
     render_pass.set_vertex_buffer(0, vertex_buffer);
     render_pass.set_index_buffer(index_buffer, wgpu::IndexFormat::Uint32);
-    render_pass.draw_indexed(0..num_indices, 0, 0..1);
+    render_pass.draw_indexed(0..num_indices, 0, 0..num_instances);
 }
 
 // ------------------------------
@@ -172,18 +473,10 @@ fn draw_mesh(
 pub fn render_2d(
     view: &TextureView,
     encoder: &mut CommandEncoder,
-    scene: &SolverScene,
+    scene: &impl CanvasScene,
     render_state: &RenderState,
 ) {
-    let color_attachment = wgpu::RenderPassColorAttachment {
-        view: &render_state.msaa_data.view,
-        resolve_target: Some(view),
-        ops: wgpu::Operations {
-            load: wgpu::LoadOp::Clear(Color::BLACK),
-            store: wgpu::StoreOp::Store,
-        },
-        depth_slice: None,
-    };
+    let color_attachment = render_state.color_attachment(view, wgpu::LoadOp::Clear(Color::BLACK));
     let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("render pass"),
         color_attachments: &[Some(color_attachment)],
@@ -191,9 +484,10 @@ pub fn render_2d(
         occlusion_query_set: None,
         timestamp_writes: None,
     });
-    render_pass.set_pipeline(&scene.render_pipeline);
-    render_pass.set_bind_group(0, &scene.uniform.render_bind_group, &[]);
-    render_pass.set_bind_group(1, &scene.data_texture.render_bind_group, &[]);
-    render_pass.set_index_buffer(scene.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-    render_pass.draw_indexed(0..6, 0, 0..1);
+    render_pass.set_pipeline(scene.render_pipeline());
+    for (index, bind_group) in scene.render_bind_groups().into_iter().enumerate() {
+        render_pass.set_bind_group(index as u32, bind_group, &[]);
+    }
+    render_pass.set_index_buffer(scene.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+    render_pass.draw_indexed(0..CANVAS_QUAD_INDICES.len() as u32, 0, 0..1);
 }
@@ -2,9 +2,20 @@ use crate::grapher::{
     camera::CameraState,
     matrix::MatrixUniform,
     pipeline::{
-        self, light::LightState, render_preferences::RenderPreferences, texture::DepthBuffer,
+        self,
+        antialiasing::{self, FxaaState},
+        depth_of_field::DepthOfFieldState,
+        light::LightState,
+        reflection::FloorReflection,
+        render_preferences::RenderPreferences,
+        skybox::Skybox,
+        ssao::SsaoState,
+        texture::{DepthBuffer, OffscreenTarget},
+        tone_map::ToneMapState,
+    },
+    scene::{
+        Bufferable, GpuVertex, gizmo::Gizmo, light_placement::LightPlacement, solid::MeshRenderData,
     },
-    scene::Bufferable,
 };
 
 use egui_wgpu::wgpu::{
@@ -12,8 +23,13 @@ use egui_wgpu::wgpu::{
     BindGroupLayoutDescriptor, Device, Extent3d, Queue, RenderPipeline, Sampler,
     SurfaceConfiguration, Texture, TextureDescriptor, TextureDimension, TextureUsages, TextureView,
 };
+use std::ops::RangeInclusive;
 use winit::event::{DeviceEvent, WindowEvent};
 
+/// Allowed range of the render scale, the scene resolution relative to
+/// the window's. Above two, the tone map's bilinear filter would skip texels.
+pub const RENDER_SCALE_RANGE: RangeInclusive<f32> = 0.25..=2.0;
+
 // State for global rendering environment.
 
 pub struct RenderState {
@@ -29,8 +45,28 @@ pub struct RenderState {
     pub depth_buffer: DepthBuffer,
     // running framerate
     pub framerate: f32,
-    // multisampling texture
-    pub msaa_data: MultisampleData,
+    // multisampling texture; none when MSAA is off
+    pub msaa_data: Option<MultisampleData>,
+    // HDR target scenes render into, sized by the render scale
+    pub offscreen_target: OffscreenTarget,
+    // scene resolution relative to the window's
+    pub render_scale: f32,
+    // final pass from offscreen target to surface
+    pub tone_map: ToneMapState,
+    // optional focus blur of 3D scenes, before tone mapping
+    pub depth_of_field: DepthOfFieldState,
+    // optional FXAA pass over any scene, before tone mapping
+    pub fxaa: FxaaState,
+    // ambient occlusion that 3D scene shading reads from the shared bind group
+    pub ssao: SsaoState,
+    // background drawn behind 3D scenes, if loaded
+    pub skybox: Option<Skybox>,
+    // crosshair at the camera target
+    pub gizmo: Gizmo,
+    // optional reflective floor under graphs
+    pub floor: FloorReflection,
+    // optional user-chosen light position, with its marker
+    pub light_placement: LightPlacement,
 }
 
 impl RenderState {
@@ -43,26 +79,40 @@ impl RenderState {
             entries: &[
                 *MatrixUniform::bind_group_layout_entry(),
                 shader_preferences.bind_group_layout_entry,
+                SsaoState::bind_group_layout_entry(2),
             ],
             label: Some("shared resources bind group layout"),
         });
-        let bind_group = device.create_bind_group(&BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[
-                BindGroupEntry {
-                    binding: 0,
-                    resource: camera_state.matrix.buffer.as_entire_binding(),
-                },
-                BindGroupEntry {
-                    binding: 1,
-                    resource: shader_preferences.buffer.as_entire_binding(),
-                },
-            ],
-            label: Some("shared resources bind group"),
-        });
 
         let depth_buffer = DepthBuffer::create(surface_config, device);
+        let ssao = SsaoState::create::<GpuVertex>(
+            device,
+            surface_config,
+            &depth_buffer,
+            &[&bind_group_layout, MeshRenderData::matrix_bgl(device)],
+        );
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &camera_state,
+            &shader_preferences,
+            &ssao,
+        );
+
         let msaa_texture = MultisampleData::create(surface_config, device);
+        let offscreen_target = OffscreenTarget::create(surface_config, device);
+        let tone_map = ToneMapState::create(device, surface_config, &offscreen_target);
+        let depth_of_field = DepthOfFieldState::create(device, surface_config, &depth_buffer);
+        let fxaa = FxaaState::create(device, surface_config);
+        let gizmo = Gizmo::create(device, &bind_group_layout);
+        let light_placement = LightPlacement::create(device, &bind_group_layout);
+        let floor = FloorReflection::create(
+            device,
+            surface_config,
+            &bind_group_layout,
+            &shader_preferences,
+            &ssao,
+        );
 
         Self {
             camera_state,
@@ -73,9 +123,46 @@ impl RenderState {
             // we target 60fps
             framerate: 60_f32,
             msaa_data: msaa_texture,
+            offscreen_target,
+            render_scale: 1.0,
+            tone_map,
+            depth_of_field,
+            fxaa,
+            ssao,
+            skybox: None,
+            gizmo,
+            floor,
+            light_placement,
         }
     }
 
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        camera_state: &CameraState,
+        render_preferences: &RenderPreferences,
+        ssao: &SsaoState,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: camera_state.matrix.buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: render_preferences.buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(ssao.view()),
+                },
+            ],
+            label: Some("shared resources bind group"),
+        })
+    }
+
     pub fn handle_user_input(&mut self, event: &WindowEvent) -> bool {
         // All currently handled events affect the camera.
         self.camera_state.controller.process_events(event)
@@ -91,19 +178,93 @@ impl RenderState {
         self.camera_state
             .controller
             .update_camera(&mut self.camera_state.camera);
+        self.camera_state
+            .controller
+            .auto_rotate(&mut self.camera_state.camera, 1.0 / self.framerate);
+        self.camera_state.update_path(1.0 / self.framerate);
         self.camera_state
             .matrix
             .uniform
             .update_inner(self.camera_state.camera.get_matrix());
         // we write the uniform every frame
         self.camera_state.update_uniform(queue);
+        if let Some(skybox) = &mut self.skybox {
+            skybox.update_uniform(queue, self.camera_state.camera.get_skybox_matrix());
+        }
+        self.gizmo.update_uniform(queue, &self.camera_state.camera);
+        self.light_placement
+            .update_uniform(queue, &self.camera_state.camera);
+        if self.floor.enabled {
+            self.floor.update_uniforms(
+                queue,
+                self.camera_state.matrix.uniform,
+                &self.render_preferences,
+            );
+        }
+        self.depth_of_field
+            .update_uniform(queue, &self.camera_state.camera);
+        if self.render_preferences.ssao_enabled() {
+            self.ssao.update_uniform(queue, &self.camera_state.camera);
+        }
+    }
+
+    /// Surface configuration with the size of the scene render targets:
+    /// the window size times the render scale, within the texture limit.
+    fn target_config(
+        &self,
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+    ) -> SurfaceConfiguration {
+        let max_size = device.limits().max_texture_dimension_2d;
+        let scaled =
+            |size: u32| ((size as f32 * self.render_scale).round() as u32).clamp(1, max_size);
+        SurfaceConfiguration {
+            width: scaled(surface_config.width),
+            height: scaled(surface_config.height),
+            ..surface_config.clone()
+        }
+    }
+
+    /// Render scenes at `scale` times the window resolution, recreating
+    /// the render targets at the new size. Tone mapping scales the result
+    /// to the surface.
+    pub fn set_render_scale(
+        &mut self,
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        scale: f32,
+    ) {
+        self.render_scale = scale;
+        self.handle_resize(device, surface_config);
     }
 
+    /// Recreate the render targets for the given window size.
     pub fn handle_resize(&mut self, device: &Device, surface_config: &SurfaceConfiguration) {
+        // Every target the scene passes share is sized by the render scale.
+        let surface_config = &self.target_config(device, surface_config);
+
         // Resize depth buffer texture.
         self.depth_buffer = DepthBuffer::create(surface_config, device);
         // Resize MSAA texture.
         self.msaa_data = MultisampleData::create(surface_config, device);
+        // Resize offscreen target and rebind it for tone mapping.
+        self.offscreen_target = OffscreenTarget::create(surface_config, device);
+        self.tone_map.handle_resize(device, &self.offscreen_target);
+        self.depth_of_field
+            .handle_resize(device, surface_config, &self.depth_buffer);
+        self.fxaa.handle_resize(device, surface_config);
+        // Resize occlusion targets and rebind them for shading.
+        self.ssao
+            .handle_resize(device, surface_config, &self.depth_buffer);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.camera_state,
+            &self.render_preferences,
+            &self.ssao,
+        );
+        self.floor
+            .handle_resize(device, surface_config, &self.bind_group_layout, &self.ssao);
     }
 }
 
@@ -115,7 +276,12 @@ pub struct MultisampleData {
 }
 
 impl MultisampleData {
-    pub fn create(surface_config: &SurfaceConfiguration, device: &Device) -> Self {
+    /// Returns none if the MSAA sample count is one.
+    pub fn create(surface_config: &SurfaceConfiguration, device: &Device) -> Option<Self> {
+        let sample_count = antialiasing::sample_count();
+        if sample_count == 1 {
+            return None;
+        }
         let msaa_texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("MSAA color texture"),
             size: Extent3d {
@@ -124,17 +290,17 @@ impl MultisampleData {
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
-            sample_count: 4,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
-            format: surface_config.format,
+            format: OffscreenTarget::FORMAT,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             view_formats: &[],
         });
         let msaa_view = msaa_texture.create_view(&Default::default());
-        Self {
+        Some(Self {
             _texture: msaa_texture,
             view: msaa_view,
-        }
+        })
     }
 }
 
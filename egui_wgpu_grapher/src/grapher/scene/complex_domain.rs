@@ -0,0 +1,349 @@
+//! A scene that colors the complex plane by the values of a function
+//! of `z`, with hue giving the argument and lightness the modulus, and
+//! renders the result on a fixed 2D canvas.
+
+use bytemuck::{Pod, Zeroable};
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupLayout, Buffer, Device, Extent3d, Origin3d, Queue, RenderPipeline,
+    SurfaceConfiguration, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture, util::DeviceExt,
+};
+
+use crate::grapher::{
+    camera::ProjectionType,
+    math::complex::{Complex, ComplexExpr},
+    pipeline::{create_canvas_pipeline, get_complex_domain_shader},
+    render::RenderState,
+    scene::{CANVAS_QUAD_INDICES, CanvasScene},
+};
+
+use std::f64::consts::PI;
+
+// ------------------------------
+// Uniform with the canvas shape.
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct CanvasUniform {
+    aspect_ratio: f32,
+    _padding: [f32; 3],
+}
+
+pub struct Uniform {
+    data: CanvasUniform,
+    buffer: Buffer,
+    pub bind_group: BindGroup,
+    pub bind_group_layout: BindGroupLayout,
+}
+
+impl Uniform {
+    pub fn new(device: &Device, surface_config: &SurfaceConfiguration) -> Self {
+        let data = CanvasUniform {
+            aspect_ratio: surface_config.height as f32 / surface_config.width as f32,
+            _padding: [0.0; 3],
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Complex Domain Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[data]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Complex Domain Uniform Bind Group Layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("Complex Domain Uniform Bind Group"),
+        });
+
+        Self {
+            data,
+            buffer,
+            bind_group,
+            bind_group_layout,
+        }
+    }
+}
+
+// -----------------------------------------
+// Texture holding the colors, written on CPU.
+
+pub struct ColorTexture {
+    texture: Texture,
+    pub bind_group: BindGroup,
+    pub bind_group_layout: BindGroupLayout,
+}
+
+const TEXTURE_WIDTH: u32 = 512;
+const TEXTURE_HEIGHT: u32 = 512;
+const TEXTURE_SIZE: Extent3d = Extent3d {
+    width: TEXTURE_WIDTH,
+    height: TEXTURE_HEIGHT,
+    depth_or_array_layers: 1,
+};
+
+impl ColorTexture {
+    pub fn new(device: &Device) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Complex Domain Texture"),
+            size: TEXTURE_SIZE,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Complex Domain Texture Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Complex Domain Texture Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            texture,
+            bind_group,
+            bind_group_layout,
+        }
+    }
+}
+
+// -------------------------------------
+// View of the plane and coloring style.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DomainView {
+    // complex plane point at the center of the canvas
+    pub center: [f64; 2],
+    // half-width of the visible region of the complex plane
+    pub scale: f64,
+    // darken bands between powers of two of the modulus
+    pub contours: bool,
+}
+
+pub const DEFAULT_CENTER: [f64; 2] = [0.0, 0.0];
+pub const DEFAULT_SCALE: f64 = 3.0;
+
+impl Default for DomainView {
+    fn default() -> Self {
+        Self {
+            center: DEFAULT_CENTER,
+            scale: DEFAULT_SCALE,
+            contours: false,
+        }
+    }
+}
+
+// --------------------------
+// Top-level scene structure.
+
+pub struct ComplexDomainScene {
+    pub render_pipeline: RenderPipeline,
+    pub index_buffer: Buffer,
+    pub uniform: Uniform,
+    pub color_texture: ColorTexture,
+    // view the texture was last colored for
+    colored_view: Option<DomainView>,
+}
+
+impl ComplexDomainScene {
+    /// Updates camera state to an orthographic one, so that
+    /// camera pan and zoom can drive the view of the plane.
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &mut RenderState,
+    ) -> Self {
+        Self::reset_camera(queue, surface_config, state);
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(&CANVAS_QUAD_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let uniform = Uniform::new(device, surface_config);
+        let color_texture = ColorTexture::new(device);
+
+        let render_pipeline = create_canvas_pipeline(
+            device,
+            get_complex_domain_shader(),
+            &[&uniform.bind_group_layout, &color_texture.bind_group_layout],
+        );
+
+        Self {
+            render_pipeline,
+            index_buffer,
+            uniform,
+            color_texture,
+            colored_view: None,
+        }
+    }
+
+    /// Orthographic camera whose pan and zoom drive the view of the plane.
+    pub fn reset_camera(
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &mut RenderState,
+    ) {
+        state.camera_state.reset_camera(queue, surface_config);
+        state.camera_state.camera.projection_type = ProjectionType::Orthographic;
+        state.camera_state.update_uniform(queue);
+    }
+
+    /// Force the next `color` call to recolor, as after the function changes.
+    pub fn invalidate(&mut self) {
+        self.colored_view = None;
+    }
+
+    /// Evaluate the function over the view and write the colors, if the
+    /// view changed since the last call.
+    pub fn color(&mut self, queue: &Queue, function: &ComplexExpr, view: DomainView) {
+        if self.colored_view == Some(view) {
+            return;
+        }
+        self.colored_view = Some(view);
+
+        let mut data = Vec::with_capacity((TEXTURE_WIDTH * TEXTURE_HEIGHT) as usize);
+        for i in 0..TEXTURE_HEIGHT {
+            // first row is the top of the canvas
+            let v = 1.0 - 2.0 * i as f64 / (TEXTURE_HEIGHT - 1) as f64;
+            for j in 0..TEXTURE_WIDTH {
+                let u = 2.0 * j as f64 / (TEXTURE_WIDTH - 1) as f64 - 1.0;
+                let z = Complex::new(
+                    view.center[0] + u * view.scale,
+                    view.center[1] + v * view.scale,
+                );
+                data.push(domain_color(function.eval(z), view.contours));
+            }
+        }
+
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &self.color_texture.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&data),
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(TEXTURE_WIDTH * std::mem::size_of::<[u8; 4]>() as u32),
+                rows_per_image: Some(TEXTURE_HEIGHT),
+            },
+            TEXTURE_SIZE,
+        );
+    }
+
+    pub fn update_aspect_ratio(&mut self, queue: &Queue, new_ratio: f32) {
+        self.uniform.data.aspect_ratio = new_ratio;
+        queue.write_buffer(
+            &self.uniform.buffer,
+            0,
+            bytemuck::bytes_of(&self.uniform.data),
+        );
+    }
+}
+
+impl CanvasScene for ComplexDomainScene {
+    fn render_pipeline(&self) -> &RenderPipeline {
+        &self.render_pipeline
+    }
+
+    fn render_bind_groups(&self) -> [&BindGroup; 2] {
+        [&self.uniform.bind_group, &self.color_texture.bind_group]
+    }
+
+    fn index_buffer(&self) -> &Buffer {
+        &self.index_buffer
+    }
+}
+
+// Color for one function value: hue from the argument, with red on the
+// positive reals, and lightness rising from black at zeros to white at
+// poles. Values that aren't finite, as at poles, are drawn white.
+fn domain_color(w: Complex, contours: bool) -> [u8; 4] {
+    if !w.is_finite() {
+        return [255, 255, 255, 255];
+    }
+    let modulus = w.abs();
+    let hue = w.arg().rem_euclid(2.0 * PI) / (2.0 * PI);
+    let mut lightness = 2.0 / PI * modulus.atan();
+    if contours && modulus > 0.0 {
+        // sawtooth in log modulus, so each band doubles the modulus
+        let band = modulus.log2().rem_euclid(1.0);
+        lightness *= 0.7 + 0.3 * band;
+    }
+    let [r, g, b] = hsl_to_rgb(hue, 1.0, lightness);
+    [to_byte(r), to_byte(g), to_byte(b), 255]
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [f64; 3] {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h = hue * 6.0;
+    let x = chroma * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+    let [r, g, b] = match h as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+    let m = lightness - chroma / 2.0;
+    [r + m, g + m, b + m]
+}
+
+fn to_byte(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,232 @@
+//! Build a scene plotting a table of numbers read from a CSV file.
+//!
+//! Rows can be read as scattered (x, y, z) points, drawn as points,
+//! or the whole table can be read as a grid of heights, drawn as a
+//! surface with the rows running along z and the columns along x.
+
+use super::{GpuVertex, RenderScene, Scene3D, points, solid};
+use crate::grapher::{
+    csv_loader::CsvTable,
+    math::graph::{Normals, SquareTesselation},
+    matrix::Matrix,
+    render::RenderState,
+};
+
+use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataLayout {
+    Points,
+    Grid,
+}
+
+pub struct DataPlotScene {
+    // all the data for rendering
+    pub scene: Option<Scene3D>,
+
+    pub layout: DataLayout,
+    // columns holding the coordinates of points
+    pub x_column: usize,
+    pub y_column: usize,
+    pub z_column: usize,
+    // diameter of points in pixels
+    pub point_size: f32,
+    // display-time height multiplier, applied through the mesh matrices
+    pub vertical_exaggeration: f32,
+    // have parameters changed that require mesh regen
+    pub needs_rebuild: bool,
+    // reason the last rebuild failed, if it did
+    pub error: Option<String>,
+
+    table: CsvTable,
+}
+
+impl DataPlotScene {
+    // width of the box the data is scaled to fit in
+    const WIDTH: f32 = 6.0;
+    // colors for the lowest and highest values
+    const LOW_COLOR: [f32; 3] = [0.2, 0.4, 1.0];
+    const HIGH_COLOR: [f32; 3] = [1.0, 0.3, 0.2];
+
+    pub fn new(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+        path: &str,
+    ) -> Result<Self, String> {
+        let table = CsvTable::from_file(path).map_err(|err| err.to_string())?;
+
+        // Tables of (x, z, y) triples are read as points by default.
+        let layout = if table.column_count() == 3 {
+            DataLayout::Points
+        } else {
+            DataLayout::Grid
+        };
+        let last_column = table.column_count() - 1;
+
+        let mut data_plot = Self {
+            scene: None,
+            layout,
+            x_column: 0,
+            y_column: 2.min(last_column),
+            z_column: 1.min(last_column),
+            point_size: points::DEFAULT_POINT_SIZE,
+            vertical_exaggeration: 1.0,
+            needs_rebuild: false,
+            error: None,
+            table,
+        };
+        data_plot.rebuild_scene(device, surface_config, state)?;
+
+        Ok(data_plot)
+    }
+
+    pub fn column_names(&self) -> &[String] {
+        &self.table.column_names
+    }
+
+    /// Rebuild the scene for the current layout, keeping
+    /// the previous scene if the table can't be plotted.
+    pub fn rebuild_scene(
+        &mut self,
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+    ) -> Result<(), String> {
+        let result = match self.layout {
+            DataLayout::Points => {
+                let vertices = self.point_vertices();
+                Ok(points::build_scene(
+                    device,
+                    surface_config,
+                    state,
+                    &vertices,
+                    self.point_size,
+                ))
+            }
+            DataLayout::Grid => self
+                .grid_mesh(state.render_preferences.shading)
+                .map(|mesh| {
+                    solid::build_scene(
+                        device,
+                        surface_config,
+                        state,
+                        vec![(mesh, Matrix::identity())],
+                    )
+                }),
+        };
+
+        match result {
+            Ok(scene) => {
+                self.scene = Some(scene);
+                self.error = None;
+                Ok(())
+            }
+            Err(err) => {
+                self.error = Some(err.clone());
+                Err(err)
+            }
+        }
+    }
+
+    pub fn handle_resize(&mut self, queue: &Queue, surface_config: &SurfaceConfiguration) {
+        if let Some(scene) = &mut self.scene {
+            for points in &mut scene.points {
+                points.handle_resize(queue, surface_config);
+            }
+        }
+    }
+
+    fn point_vertices(&self) -> Vec<GpuVertex> {
+        let columns = [self.x_column, self.y_column, self.z_column];
+        let points: Vec<[f32; 3]> = self
+            .table
+            .rows
+            .iter()
+            .map(|row| columns.map(|column| row[column] as f32))
+            .collect();
+
+        // Scale and center the bounding box to fit the plot box.
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for point in &points {
+            for i in 0..3 {
+                min[i] = min[i].min(point[i]);
+                max[i] = max[i].max(point[i]);
+            }
+        }
+        let extent = (0..3).map(|i| max[i] - min[i]).fold(0.0_f32, f32::max);
+        let scale = if extent > 0.0 {
+            Self::WIDTH / extent
+        } else {
+            1.0
+        };
+        let center: [f32; 3] = std::array::from_fn(|i| (min[i] + max[i]) / 2.0);
+
+        points
+            .iter()
+            .map(|point| GpuVertex {
+                position: std::array::from_fn(|i| scale * (point[i] - center[i])),
+                color: Self::height_color(point[1], min[1], max[1]),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn grid_mesh(&self, normals: Normals) -> Result<solid::MeshData, String> {
+        let rows = self.table.rows.len() as u32;
+        let cols = self.table.column_count() as u32;
+        if rows < 2 || cols < 2 {
+            return Err("A grid needs at least two rows and two columns.".into());
+        }
+
+        let values = self.table.rows.iter().flatten().map(|value| *value as f32);
+        let min = values.clone().fold(f32::MAX, f32::min);
+        let max = values.clone().fold(f32::MIN, f32::max);
+
+        // Heights span half the plot box width, centered at zero.
+        let range = max - min;
+        let scale = if range > 0.0 {
+            Self::WIDTH / 2.0 / range
+        } else {
+            1.0
+        };
+        let heights: Vec<f32> = values
+            .map(|value| scale * (value - min) - scale * range / 2.0)
+            .collect();
+
+        let spacing = Self::WIDTH as f64 / (cols.max(rows) - 1) as f64;
+        let mut mesh = SquareTesselation::from_heights(cols, rows, spacing, &heights)
+            .mesh_data(Self::LOW_COLOR, normals);
+        let (low, high) = (-scale * range / 2.0, scale * range / 2.0);
+        for vertex in &mut mesh.vertices {
+            vertex.color = Self::height_color(vertex.position[1], low, high);
+        }
+
+        Ok(mesh)
+    }
+
+    fn height_color(height: f32, min: f32, max: f32) -> [f32; 3] {
+        let t = if max > min {
+            (height - min) / (max - min)
+        } else {
+            0.5
+        };
+        std::array::from_fn(|i| Self::LOW_COLOR[i] + t * (Self::HIGH_COLOR[i] - Self::LOW_COLOR[i]))
+    }
+}
+
+impl RenderScene for DataPlotScene {
+    fn scene(&self) -> &Scene3D {
+        self.scene.as_ref().unwrap()
+    }
+
+    fn update(&mut self, queue: &Queue, _state: &RenderState) {
+        if let Some(scene) = &mut self.scene {
+            for points in &mut scene.points {
+                points.set_size(queue, self.point_size);
+            }
+            scene.set_vertical_exaggeration(queue, self.vertical_exaggeration);
+        }
+    }
+}
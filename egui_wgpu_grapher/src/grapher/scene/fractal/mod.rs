@@ -0,0 +1,376 @@
+//! A scene that runs a compute pipeline to evaluate a Mandelbrot or Julia
+//! set escape-time fractal and renders the result on a fixed 2D canvas.
+
+use bytemuck::{Pod, Zeroable};
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupLayout, Buffer, CommandEncoder, ComputePipeline, Device, Queue,
+    RenderPipeline, SurfaceConfiguration, Texture, util::DeviceExt,
+};
+
+use crate::grapher::{
+    camera::ProjectionType,
+    pipeline::{
+        create_canvas_pipeline, create_compute_pipeline, get_fractal_compute_shader,
+        get_fractal_shader,
+    },
+    render::RenderState,
+    scene::{CANVAS_QUAD_INDICES, CanvasScene},
+};
+
+// ---------------------------
+// Fractal scene uniform data.
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct FractalParameters {
+    // complex plane point at the center of the canvas
+    pub center: [f32; 2],
+    // half-width of the visible region of the complex plane
+    pub scale: f32,
+    pub max_iterations: u32,
+    pub julia_constant: [f32; 2],
+    // nonzero to render the Julia set for `julia_constant`
+    pub is_julia: u32,
+    aspect_ratio: f32,
+}
+
+pub const DEFAULT_CENTER: [f32; 2] = [-0.5, 0.0];
+pub const DEFAULT_SCALE: f32 = 1.5;
+
+impl Default for FractalParameters {
+    fn default() -> Self {
+        Self {
+            center: DEFAULT_CENTER,
+            scale: DEFAULT_SCALE,
+            max_iterations: 256,
+            julia_constant: [-0.8, 0.156],
+            is_julia: 0,
+            aspect_ratio: 1.0,
+        }
+    }
+}
+
+pub struct Uniform {
+    pub data: FractalParameters,
+    buffer: Buffer,
+    pub compute_bind_group: BindGroup,
+    pub compute_bind_group_layout: BindGroupLayout,
+    pub render_bind_group: BindGroup,
+    pub render_bind_group_layout: BindGroupLayout,
+}
+
+impl Uniform {
+    pub fn new(device: &Device, surface_config: &SurfaceConfiguration) -> Self {
+        let data = FractalParameters {
+            aspect_ratio: surface_config.height as f32 / surface_config.width as f32,
+            ..Default::default()
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Fractal Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[data]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let compute_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("Fractal Compute Bind Group Layout"),
+            });
+        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &compute_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("Fractal Uniform Bind Group"),
+        });
+
+        let render_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("Fractal Render Bind Group Layout"),
+            });
+        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &render_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("Fractal Uniform Bind Group"),
+        });
+
+        Self {
+            data,
+            buffer,
+            compute_bind_group,
+            compute_bind_group_layout,
+            render_bind_group,
+            render_bind_group_layout,
+        }
+    }
+}
+
+// ---------------------------------
+// Texture the compute pass writes.
+
+#[allow(dead_code)]
+pub struct OutputTexture {
+    texture: Texture,
+    pub compute_bind_group: BindGroup,
+    pub compute_bind_group_layout: BindGroupLayout,
+    pub render_bind_group: BindGroup,
+    pub render_bind_group_layout: BindGroupLayout,
+}
+
+const TEXTURE_WIDTH: u32 = 1024;
+const TEXTURE_HEIGHT: u32 = 1024;
+const TEXTURE_SIZE: wgpu::Extent3d = wgpu::Extent3d {
+    width: TEXTURE_WIDTH,
+    height: TEXTURE_HEIGHT,
+    depth_or_array_layers: 1,
+};
+
+impl OutputTexture {
+    pub fn new(device: &Device) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Fractal Output Texture"),
+            size: TEXTURE_SIZE,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Fractal Texture View"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            ..Default::default()
+        });
+
+        let compute_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Fractal Output Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba8Unorm,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                }],
+            });
+        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Fractal Output Compute Bind Group"),
+            layout: &compute_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let render_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Fractal Output Render Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Fractal Output Render Bind Group"),
+            layout: &render_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            texture,
+            compute_bind_group,
+            compute_bind_group_layout,
+            render_bind_group,
+            render_bind_group_layout,
+        }
+    }
+}
+
+// --------------------------
+// Top-level scene structure.
+
+pub struct FractalScene {
+    pub compute_pipeline: ComputePipeline,
+    pub render_pipeline: RenderPipeline,
+    pub index_buffer: Buffer,
+    pub uniform: Uniform,
+    pub output_texture: OutputTexture,
+    // set when parameters change and the texture is stale
+    pub needs_compute: bool,
+}
+
+impl FractalScene {
+    /// Updates camera state to an orthographic one, so that
+    /// camera pan and zoom can drive the fractal view.
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &mut RenderState,
+    ) -> Self {
+        Self::reset_camera(queue, surface_config, state);
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(&CANVAS_QUAD_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let uniform = Uniform::new(device, surface_config);
+        let output_texture = OutputTexture::new(device);
+
+        let compute_pipeline = create_compute_pipeline(
+            device,
+            get_fractal_compute_shader(),
+            &[
+                &output_texture.compute_bind_group_layout,
+                &uniform.compute_bind_group_layout,
+            ],
+        );
+        let render_pipeline = create_canvas_pipeline(
+            device,
+            get_fractal_shader(),
+            &[
+                &uniform.render_bind_group_layout,
+                &output_texture.render_bind_group_layout,
+            ],
+        );
+
+        Self {
+            compute_pipeline,
+            render_pipeline,
+            index_buffer,
+            uniform,
+            output_texture,
+            needs_compute: true,
+        }
+    }
+
+    /// Orthographic camera whose pan and zoom drive the fractal view.
+    pub fn reset_camera(
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &mut RenderState,
+    ) {
+        state.camera_state.reset_camera(queue, surface_config);
+        state.camera_state.camera.projection_type = ProjectionType::Orthographic;
+        state.camera_state.update_uniform(queue);
+    }
+
+    pub fn parameters(&self) -> FractalParameters {
+        self.uniform.data
+    }
+
+    /// Writes new parameters and marks the fractal for recompute if they changed.
+    pub fn set_parameters(&mut self, queue: &Queue, parameters: FractalParameters) {
+        let parameters = FractalParameters {
+            aspect_ratio: self.uniform.data.aspect_ratio,
+            ..parameters
+        };
+        if parameters != self.uniform.data {
+            self.uniform.data = parameters;
+            self.write_uniform(queue);
+            self.needs_compute = true;
+        }
+    }
+
+    pub fn update_aspect_ratio(&mut self, queue: &Queue, new_ratio: f32) {
+        self.uniform.data.aspect_ratio = new_ratio;
+        self.write_uniform(queue);
+    }
+
+    fn write_uniform(&self, queue: &Queue) {
+        queue.write_buffer(
+            &self.uniform.buffer,
+            0,
+            bytemuck::bytes_of(&self.uniform.data),
+        );
+    }
+
+    pub fn compute_fractal(&mut self, encoder: &mut CommandEncoder) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: None,
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.compute_pipeline);
+        compute_pass.set_bind_group(0, &self.output_texture.compute_bind_group, &[]);
+        compute_pass.set_bind_group(1, &self.uniform.compute_bind_group, &[]);
+
+        let workgroup_count_x = TEXTURE_WIDTH.div_ceil(8);
+        let workgroup_count_y = TEXTURE_HEIGHT.div_ceil(8);
+        compute_pass.dispatch_workgroups(workgroup_count_x, workgroup_count_y, 1);
+
+        self.needs_compute = false;
+    }
+}
+
+impl CanvasScene for FractalScene {
+    fn render_pipeline(&self) -> &RenderPipeline {
+        &self.render_pipeline
+    }
+
+    fn render_bind_groups(&self) -> [&BindGroup; 2] {
+        [
+            &self.uniform.render_bind_group,
+            &self.output_texture.render_bind_group,
+        ]
+    }
+
+    fn index_buffer(&self) -> &Buffer {
+        &self.index_buffer
+    }
+}
@@ -0,0 +1,98 @@
+//! Axis crosshair drawn at the camera target, to show the point the
+//! scene orbits around.
+
+use super::{GpuVertex, lines::LinesRenderData, solid::MeshRenderData};
+use crate::grapher::{camera::Camera, matrix::Matrix, pipeline};
+
+use cgmath::{EuclideanSpace, InnerSpace, Matrix as _, Vector3};
+use egui_wgpu::wgpu::{BindGroup, BindGroupLayout, Device, Queue, RenderPass, RenderPipeline};
+
+pub struct Gizmo {
+    lines: LinesRenderData,
+    // depth tested, so the scene can hide it
+    pipeline: RenderPipeline,
+    // drawn over the scene
+    on_top_pipeline: RenderPipeline,
+
+    pub enabled: bool,
+    pub always_on_top: bool,
+}
+
+impl Gizmo {
+    // fraction of the view height each axis spans
+    const SCREEN_SIZE: f32 = 0.1;
+
+    pub fn create(device: &Device, camera_bind_group_layout: &BindGroupLayout) -> Self {
+        // x, y, and z axes in red, green, and blue
+        let mut vertices = vec![];
+        for axis in 0..3 {
+            let mut color = [0.0; 3];
+            color[axis] = 1.0;
+            for sign in [-0.5, 0.5] {
+                let mut position = [0.0; 3];
+                position[axis] = sign;
+                vertices.push(GpuVertex {
+                    position,
+                    color,
+                    ..Default::default()
+                });
+            }
+        }
+        let lines = LinesRenderData::from_vertices(device, &vertices, Matrix::identity());
+
+        let bind_group_layouts = [camera_bind_group_layout, MeshRenderData::matrix_bgl(device)];
+        let pipeline =
+            pipeline::create_line_pipeline::<GpuVertex>(device, &bind_group_layouts, false);
+        let on_top_pipeline =
+            pipeline::create_line_pipeline::<GpuVertex>(device, &bind_group_layouts, true);
+
+        Self {
+            lines,
+            pipeline,
+            on_top_pipeline,
+            enabled: false,
+            always_on_top: false,
+        }
+    }
+
+    /// Place the gizmo at the camera target, scaled to a fixed size on screen.
+    pub fn update_uniform(&mut self, queue: &Queue, camera: &Camera) {
+        if !self.enabled {
+            return;
+        }
+
+        // The scene is rotated about the origin and then panned, so
+        // undo the rotation to find the target in world coordinates.
+        let rotation = camera.rotation();
+        let target = camera.target.to_vec();
+        let center = (rotation.transpose() * target.extend(1.0)).truncate();
+
+        let panned = target + Vector3::new(camera.translation_x, camera.translation_y, 0.0);
+        let forward = (camera.target - camera.eye).normalize();
+        let distance = (panned - camera.eye.to_vec())
+            .dot(forward)
+            .max(camera.znear);
+        let size = Self::SCREEN_SIZE * camera.view_height_at(distance);
+
+        let matrix = cgmath::Matrix4::from_translation(center) * cgmath::Matrix4::from_scale(size);
+        self.lines.matrix.uniform.update_inner(matrix);
+        queue.write_buffer(
+            &self.lines.matrix.buffer,
+            0,
+            bytemuck::cast_slice(&[self.lines.matrix.uniform]),
+        );
+    }
+
+    pub fn draw(&self, render_pass: &mut RenderPass, camera_bind_group: &BindGroup) {
+        let pipeline = if self.always_on_top {
+            &self.on_top_pipeline
+        } else {
+            &self.pipeline
+        };
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.lines.matrix_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.lines.vertex_buffer.slice(..));
+        render_pass.draw(0..self.lines.num_vertices, 0..1);
+    }
+}
@@ -0,0 +1,117 @@
+//! Manual placement of the scene light, with a marker drawn at its position.
+//!
+//! Scenes create their own light when built, so the chosen position is kept
+//! here and applied to whichever scene is current.
+
+use super::{GpuVertex, lines::LinesRenderData, solid::MeshRenderData};
+use crate::grapher::{
+    camera::{Camera, ProjectionType},
+    matrix::Matrix,
+    pipeline::{self, light::LightState},
+};
+
+use cgmath::{Point3, Vector4};
+use egui_wgpu::wgpu::{BindGroup, BindGroupLayout, Device, Queue, RenderPass, RenderPipeline};
+
+pub struct LightPlacement {
+    marker: LinesRenderData,
+    // drawn over the scene, so the light can be found when behind it
+    pipeline: RenderPipeline,
+
+    // use `position` in place of the scene's own light position
+    pub manual: bool,
+    // world space position of the light when placed manually
+    pub position: [f32; 3],
+    pub show_marker: bool,
+}
+
+impl LightPlacement {
+    // fraction of the view height the marker spans
+    const SCREEN_SIZE: f32 = 0.04;
+    const MARKER_COLOR: [f32; 3] = [1.0, 0.85, 0.2];
+
+    pub fn create(device: &Device, camera_bind_group_layout: &BindGroupLayout) -> Self {
+        // a six-pointed star along the axes
+        let mut vertices = vec![];
+        for axis in 0..3 {
+            for sign in [-0.5, 0.5] {
+                let mut position = [0.0; 3];
+                position[axis] = sign;
+                vertices.push(GpuVertex {
+                    position,
+                    color: Self::MARKER_COLOR,
+                    ..Default::default()
+                });
+            }
+        }
+        let marker = LinesRenderData::from_vertices(device, &vertices, Matrix::identity());
+
+        let bind_group_layouts = [camera_bind_group_layout, MeshRenderData::matrix_bgl(device)];
+        let pipeline =
+            pipeline::create_line_pipeline::<GpuVertex>(device, &bind_group_layouts, true);
+
+        Self {
+            marker,
+            pipeline,
+            manual: false,
+            position: LightState::DEFAULT_LIGHT_POS,
+            show_marker: true,
+        }
+    }
+
+    /// Move the scene's light to the manual position, saving its own position
+    /// the first time, or restore the saved position when placement is off.
+    pub fn apply(&self, queue: &Queue, light: &mut LightState) {
+        if !self.manual {
+            light.maybe_restore_light(queue);
+            return;
+        }
+        if light.position() == self.position {
+            return;
+        }
+        if light.previous_uniform.is_none() {
+            light.save_light();
+        }
+        light.set_position(self.position);
+        light.update_uniform(queue);
+    }
+
+    pub fn marker_visible(&self) -> bool {
+        self.manual && self.show_marker
+    }
+
+    /// Place the marker at the light, scaled to a fixed size on screen.
+    pub fn update_uniform(&mut self, queue: &Queue, camera: &Camera) {
+        if !self.marker_visible() {
+            return;
+        }
+
+        // Clip w is the distance along the view direction in perspective.
+        let distance = match camera.projection_type {
+            ProjectionType::Perspective => {
+                let point = Point3::from(self.position);
+                let clip = camera.get_matrix() * Vector4::new(point.x, point.y, point.z, 1.0);
+                clip.w.max(camera.znear)
+            }
+            ProjectionType::Orthographic => 1.0,
+        };
+        let size = Self::SCREEN_SIZE * camera.view_height_at(distance);
+
+        let matrix = cgmath::Matrix4::from_translation(self.position.into())
+            * cgmath::Matrix4::from_scale(size);
+        self.marker.matrix.uniform.update_inner(matrix);
+        queue.write_buffer(
+            &self.marker.matrix.buffer,
+            0,
+            bytemuck::cast_slice(&[self.marker.matrix.uniform]),
+        );
+    }
+
+    pub fn draw(&self, render_pass: &mut RenderPass, camera_bind_group: &BindGroup) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.marker.matrix_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.marker.vertex_buffer.slice(..));
+        render_pass.draw(0..self.marker.num_vertices, 0..1);
+    }
+}
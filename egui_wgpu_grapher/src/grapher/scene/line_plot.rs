@@ -0,0 +1,194 @@
+//! Build a scene plotting functions y = f(x) as curves in 2D.
+//!
+//! Each curve is sampled over the x range and drawn as line segments,
+//! with the y axis scaled to fit the values of all curves, inside a
+//! frame with axes through the origin when it's in view.
+
+use super::{GpuVertex, RenderScene, Scene3D, lines};
+use crate::grapher::{camera::ProjectionType, math, render::RenderState};
+
+use egui_wgpu::wgpu::{Device, Queue};
+
+// colors given to curves in the order they're added
+pub const CURVE_COLORS: [[f32; 3]; 6] = [
+    [0.95, 0.3, 0.25],
+    [0.3, 0.6, 1.0],
+    [0.35, 0.85, 0.4],
+    [1.0, 0.75, 0.2],
+    [0.8, 0.45, 0.95],
+    [0.3, 0.85, 0.85],
+];
+
+pub struct Curve {
+    pub expression: String,
+    pub color: [f32; 3],
+    // whether the expression parsed in the last rebuild
+    pub valid: bool,
+}
+
+impl Curve {
+    pub fn new(expression: &str, index: usize) -> Self {
+        Self {
+            expression: expression.into(),
+            color: CURVE_COLORS[index % CURVE_COLORS.len()],
+            valid: true,
+        }
+    }
+}
+
+pub struct LinePlotScene {
+    // all the data for rendering
+    pub scene: Option<Scene3D>,
+
+    pub curves: Vec<Curve>,
+    pub x_range: (f64, f64),
+    // have parameters changed that require a rebuild
+    pub needs_rebuild: bool,
+
+    // y range the curves were scaled to fit in the last rebuild
+    y_range: (f64, f64),
+}
+
+impl LinePlotScene {
+    // size of the frame the plot is scaled to fit in
+    const WIDTH: f32 = 6.0;
+    const HEIGHT: f32 = 4.0;
+    // number of samples along the x range
+    const SAMPLES: usize = 1000;
+
+    const FRAME_COLOR: [f32; 3] = [0.6, 0.6, 0.6];
+    const AXIS_COLOR: [f32; 3] = [0.35, 0.35, 0.35];
+
+    pub fn new(device: &Device, state: &RenderState) -> Self {
+        let mut line_plot = Self {
+            scene: None,
+            curves: vec![Curve::new("sin(x)", 0)],
+            x_range: (-2.0 * std::f64::consts::PI, 2.0 * std::f64::consts::PI),
+            needs_rebuild: false,
+            y_range: (-1.0, 1.0),
+        };
+        line_plot.rebuild_scene(device, state);
+
+        line_plot
+    }
+
+    pub fn y_range(&self) -> (f64, f64) {
+        self.y_range
+    }
+
+    pub fn add_curve(&mut self, expression: &str) {
+        self.curves.push(Curve::new(expression, self.curves.len()));
+        self.needs_rebuild = true;
+    }
+
+    /// Sample the curves, scale them to fit, and rebuild the line buffers.
+    /// Curves that don't parse are skipped and marked invalid.
+    pub fn rebuild_scene(&mut self, device: &Device, state: &RenderState) {
+        let (x_min, x_max) = self.x_range;
+        let step = (x_max - x_min) / (Self::SAMPLES - 1) as f64;
+
+        // Sample each curve, keeping non-finite values as gaps.
+        let mut samples: Vec<Option<Vec<f64>>> = vec![];
+        for curve in &mut self.curves {
+            let function = math::try_parse_single_variable(&curve.expression);
+            curve.valid = function.is_some();
+            samples.push(function.map(|f| {
+                (0..Self::SAMPLES)
+                    .map(|i| f(x_min + i as f64 * step))
+                    .collect()
+            }));
+        }
+
+        // Auto-scale the y axis to the range of all finite values.
+        let finite = samples.iter().flatten().flatten().filter(|y| y.is_finite());
+        let (y_min, y_max) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), y| {
+            (lo.min(*y), hi.max(*y))
+        });
+        self.y_range = if y_min > y_max {
+            (-1.0, 1.0)
+        } else if y_min == y_max {
+            (y_min - 1.0, y_max + 1.0)
+        } else {
+            (y_min, y_max)
+        };
+
+        let (y_min, y_max) = self.y_range;
+        let to_plot = |x: f64, y: f64| -> [f32; 2] {
+            [
+                Self::WIDTH * (((x - x_min) / (x_max - x_min)) as f32 - 0.5),
+                Self::HEIGHT * (((y - y_min) / (y_max - y_min)) as f32 - 0.5),
+            ]
+        };
+
+        let mut segments = vec![self.frame_and_axes(to_plot(0.0, 0.0))];
+        for (curve, ys) in self.curves.iter().zip(&samples) {
+            let Some(ys) = ys else {
+                continue;
+            };
+            let mut vertices = vec![];
+            for i in 1..ys.len() {
+                if ys[i - 1].is_finite() && ys[i].is_finite() {
+                    let x = x_min + i as f64 * step;
+                    // Curves sit slightly in front of the axes.
+                    for point in [to_plot(x - step, ys[i - 1]), to_plot(x, ys[i])] {
+                        vertices.push(vertex([point[0], point[1], 0.01], curve.color));
+                    }
+                }
+            }
+            segments.push(vertices);
+        }
+
+        self.scene = Some(lines::build_scene(device, state, &segments));
+    }
+
+    // Frame around the plot, with axes through the origin if it's inside.
+    fn frame_and_axes(&self, origin: [f32; 2]) -> Vec<GpuVertex> {
+        let (w, h) = (Self::WIDTH / 2.0, Self::HEIGHT / 2.0);
+        let corners = [[-w, -h], [w, -h], [w, h], [-w, h]];
+
+        let mut vertices = vec![];
+        for i in 0..corners.len() {
+            let [a, b] = [corners[i], corners[(i + 1) % corners.len()]];
+            vertices.push(vertex([a[0], a[1], 0.0], Self::FRAME_COLOR));
+            vertices.push(vertex([b[0], b[1], 0.0], Self::FRAME_COLOR));
+        }
+        if origin[1].abs() < h {
+            vertices.push(vertex([-w, origin[1], 0.0], Self::AXIS_COLOR));
+            vertices.push(vertex([w, origin[1], 0.0], Self::AXIS_COLOR));
+        }
+        if origin[0].abs() < w {
+            vertices.push(vertex([origin[0], -h, 0.0], Self::AXIS_COLOR));
+            vertices.push(vertex([origin[0], h, 0.0], Self::AXIS_COLOR));
+        }
+
+        vertices
+    }
+
+    /// Frame the plot with a front-facing orthographic camera.
+    pub fn frame_camera(&self, queue: &Queue, state: &mut RenderState) {
+        state.camera_state.set_from_z(52.0);
+        // a 2D view shouldn't spin
+        state.camera_state.controller.turntable = false;
+        let camera = &mut state.camera_state.camera;
+        camera.projection_type = ProjectionType::Orthographic;
+        // leave a margin around the frame
+        camera.ortho_scale = 0.9 * (camera.aspect / Self::WIDTH).min(1.0 / Self::HEIGHT);
+        state.camera_state.update_uniform(queue);
+    }
+}
+
+fn vertex(position: [f32; 3], color: [f32; 3]) -> GpuVertex {
+    GpuVertex {
+        position,
+        color,
+        ..Default::default()
+    }
+}
+
+impl RenderScene for LinePlotScene {
+    fn scene(&self) -> &Scene3D {
+        self.scene.as_ref().unwrap()
+    }
+
+    fn update(&mut self, _queue: &Queue, _state: &RenderState) {}
+}
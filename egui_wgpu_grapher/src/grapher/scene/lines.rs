@@ -0,0 +1,123 @@
+//! Code for scenes that draw line segments, like curves and axes.
+//!
+//! Vertices are taken in pairs, each pair giving one segment.
+
+use super::{GpuVertex, Scene3D, solid::MeshRenderData};
+use crate::grapher::{
+    matrix::{self, Matrix, MatrixUniform},
+    pipeline::{self, light},
+    render::RenderState,
+};
+
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupDescriptor, BindGroupEntry, Buffer, Device, util::DeviceExt,
+};
+
+// --------------------------------------
+// Render data for a set of line segments.
+
+pub struct LinesRenderData {
+    pub vertex_buffer: Buffer,
+    pub num_vertices: u32,
+
+    pub matrix: MatrixUniform,
+    pub matrix_bind_group: BindGroup,
+}
+
+impl LinesRenderData {
+    pub fn from_vertices(device: &Device, vertices: &[GpuVertex], matrix: Matrix) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("line vertex buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let matrix = matrix::make_matrix_uniform(device, matrix);
+        let matrix_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: MeshRenderData::matrix_bgl(device),
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: matrix.buffer.as_entire_binding(),
+            }],
+            label: Some("lines matrix bind group"),
+        });
+
+        Self {
+            vertex_buffer,
+            num_vertices: vertices.len() as u32,
+            //
+            matrix,
+            matrix_bind_group,
+        }
+    }
+}
+
+/// Segments from vertices along their normals, for a debug view of
+/// shading. Dense meshes are sampled down to about `max_lines` evenly
+/// spaced vertices. Segments are colored by direction, with each normal
+/// component mapped from [-1, 1] to [0, 1] as in normal maps.
+pub fn normal_lines(vertices: &[GpuVertex], length: f32, max_lines: usize) -> Vec<GpuVertex> {
+    let step = vertices.len().div_ceil(max_lines.max(1)).max(1);
+    vertices
+        .iter()
+        .step_by(step)
+        .filter(|vertex| {
+            vertex
+                .position
+                .iter()
+                .chain(&vertex.normal)
+                .all(|c| c.is_finite())
+        })
+        .flat_map(|vertex| {
+            let color = vertex.normal.map(|c| 0.5 * (c + 1.0));
+            let tip = std::array::from_fn(|k| vertex.position[k] + length * vertex.normal[k]);
+            [
+                GpuVertex {
+                    position: vertex.position,
+                    color,
+                    ..Default::default()
+                },
+                GpuVertex {
+                    position: tip,
+                    color,
+                    ..Default::default()
+                },
+            ]
+        })
+        .collect()
+}
+
+// -------------------------------------------------
+// Build scene from sets of segments, one per buffer.
+
+pub fn build_scene(device: &Device, state: &RenderState, segments: &[Vec<GpuVertex>]) -> Scene3D {
+    let lines = segments
+        .iter()
+        .map(|vertices| LinesRenderData::from_vertices(device, vertices, Matrix::identity()))
+        .collect();
+    let light = light::LightState::create(device);
+
+    let pipeline = pipeline::create_line_pipeline::<GpuVertex>(
+        device,
+        &[&state.bind_group_layout, MeshRenderData::matrix_bgl(device)],
+        false,
+    );
+
+    Scene3D {
+        pipeline: None,
+        textured_pipeline: None,
+        instanced_pipeline: None,
+        point_pipeline: None,
+        line_pipeline: Some(pipeline),
+        depth_prepass_pipeline: None,
+        //
+        meshes: vec![],
+        textured_meshes: vec![],
+        instanced_meshes: vec![],
+        points: vec![],
+        lines,
+        //
+        light,
+        shadow: None,
+    }
+}
@@ -1,24 +1,41 @@
 //! This module has code for building and representing scenes that we render.
 
+pub mod complex_domain;
+pub mod data_plot;
+pub mod fractal;
+pub mod gizmo;
+pub mod light_placement;
+pub mod line_plot;
+pub mod lines;
+pub mod points;
+pub mod reference_plane;
 pub mod solid;
 pub mod solver;
 pub mod textured;
 
 use super::render::RenderState;
-use crate::grapher::{pipeline::light::LightState, render::ShadowState};
+use crate::grapher::{matrix::Matrix, pipeline::light::LightState, render::ShadowState};
 
-use egui_wgpu::wgpu::{self, Queue, RenderPipeline};
+use egui_wgpu::wgpu::{self, BindGroup, Buffer, Queue, RenderPipeline};
 
 // -----------------------------------------
 // Pipelines and render data for a 3D scene.
 
 pub struct Scene3D {
-    // solid and textured render pipelines
+    // solid, textured, instanced, point and line render pipelines
     pub pipeline: Option<RenderPipeline>,
     pub textured_pipeline: Option<RenderPipeline>,
+    pub instanced_pipeline: Option<RenderPipeline>,
+    pub point_pipeline: Option<RenderPipeline>,
+    pub line_pipeline: Option<RenderPipeline>,
+    // writes depth of solid meshes before the solid pipeline, if transparent
+    pub depth_prepass_pipeline: Option<RenderPipeline>,
     // meshes
     pub meshes: Vec<solid::MeshRenderData>,
     pub textured_meshes: Vec<textured::TexturedMeshRenderData>,
+    pub instanced_meshes: Vec<textured::InstancedMeshRenderData>,
+    pub points: Vec<points::PointsRenderData>,
+    pub lines: Vec<lines::LinesRenderData>,
 
     // light
     pub light: LightState,
@@ -26,6 +43,26 @@ pub struct Scene3D {
     pub shadow: Option<ShadowState>,
 }
 
+impl Scene3D {
+    /// Scale the heights of the solid meshes, points, and lines by writing their
+    /// matrix uniforms, for scenes that are built with identity matrices. The
+    /// shadow map is refit to the scaled heights.
+    pub fn set_vertical_exaggeration(&mut self, queue: &Queue, scale: f32) {
+        self.light.set_vertical_scale(queue, scale);
+        let matrix = Matrix::y_scale(scale);
+        let uniforms = self
+            .meshes
+            .iter_mut()
+            .map(|mesh| &mut mesh.matrix)
+            .chain(self.points.iter_mut().map(|points| &mut points.matrix))
+            .chain(self.lines.iter_mut().map(|lines| &mut lines.matrix));
+        for uniform in uniforms {
+            uniform.uniform = matrix;
+            queue.write_buffer(&uniform.buffer, 0, bytemuck::cast_slice(&[matrix]));
+        }
+    }
+}
+
 // ------------------------------------------------
 // Trait to abstract scene behavior in render loop.
 
@@ -47,6 +84,22 @@ impl RenderScene for Scene3D {
     }
 }
 
+// --------------------------------------------------------
+// Trait for 2D scenes that render to a fixed canvas quad.
+
+pub trait CanvasScene {
+    /// pipeline that draws the canvas quad
+    fn render_pipeline(&self) -> &RenderPipeline;
+
+    /// bind groups in the order the render pipeline expects them
+    fn render_bind_groups(&self) -> [&BindGroup; 2];
+
+    /// index buffer holding `CANVAS_QUAD_INDICES`
+    fn index_buffer(&self) -> &Buffer;
+}
+
+pub const CANVAS_QUAD_INDICES: [u32; 6] = [0, 1, 2, 0, 2, 3];
+
 // ----------------------------------------------------------
 // Trait for structs that can provide a vertex buffer layout.
 
@@ -61,9 +114,13 @@ pub trait Bufferable {
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct GpuVertex {
     pub position: [f32; 3],
+    // linear RGB; the sRGB surface encodes it for display
     pub color: [f32; 3],
     pub normal: [f32; 3],
     pub tex_coords: [f32; 2],
+    // tangent along increasing u, with the handedness of the bitangent
+    // `cross(normal, tangent)` in w, for normal mapping
+    pub tangent: [f32; 4],
 }
 
 impl Bufferable for GpuVertex {
@@ -92,6 +149,11 @@ impl Bufferable for GpuVertex {
                     shader_location: 3,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 11]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ],
         }
     }
@@ -104,6 +166,7 @@ impl Default for GpuVertex {
             color: [1.0, 0.0, 1.0],
             normal: [0.0, 0.0, 0.0],
             tex_coords: [0.0, 0.0],
+            tangent: [1.0, 0.0, 0.0, 1.0],
         }
     }
 }
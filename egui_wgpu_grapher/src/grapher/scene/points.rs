@@ -0,0 +1,217 @@
+//! Code for scenes that draw vertices as unconnected points.
+//!
+//! Each point is drawn as a screen-aligned disc with a size in pixels,
+//! by instancing a quad over the vertex buffer: the vertices are read
+//! once per instance and the quad corners come from the vertex index.
+
+use super::{GpuVertex, RenderScene, Scene3D};
+use crate::grapher::{
+    matrix::{self, Matrix, MatrixUniform},
+    pipeline::{self, light},
+    render::RenderState,
+};
+
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, Buffer, Device, Queue, SurfaceConfiguration, util::DeviceExt,
+};
+use std::sync::OnceLock;
+
+// --------------------------------------
+// Uniform with point size and viewport.
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PointUniform {
+    // diameter of points in pixels
+    pub size: f32,
+    _padding: f32,
+    // viewport size in pixels, to convert size to clip space
+    pub viewport: [f32; 2],
+}
+
+pub const DEFAULT_POINT_SIZE: f32 = 4.0;
+
+// --------------------------------
+// Render data for a set of points.
+
+pub struct PointsRenderData {
+    pub vertex_buffer: Buffer,
+    pub num_points: u32,
+
+    pub matrix: MatrixUniform,
+    pub point_uniform: PointUniform,
+    pub point_buffer: Buffer,
+    pub bind_group: BindGroup,
+}
+
+impl PointsRenderData {
+    pub fn bind_group_layout(device: &Device) -> &'static BindGroupLayout {
+        static BGL: OnceLock<BindGroupLayout> = OnceLock::new();
+        BGL.get_or_init(|| {
+            let mut point_entry = *MatrixUniform::bind_group_layout_entry();
+            point_entry.binding = 1;
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[*MatrixUniform::bind_group_layout_entry(), point_entry],
+                label: Some("points bind group layout"),
+            })
+        })
+    }
+
+    pub fn from_vertices(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        vertices: &[GpuVertex],
+        matrix: Matrix,
+        size: f32,
+    ) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("point vertex buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let matrix = matrix::make_matrix_uniform(device, matrix);
+        let point_uniform = PointUniform {
+            size,
+            _padding: 0.0,
+            viewport: [surface_config.width as f32, surface_config.height as f32],
+        };
+        let point_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("point uniform buffer"),
+            contents: bytemuck::cast_slice(&[point_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: Self::bind_group_layout(device),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: matrix.buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: point_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("points bind group"),
+        });
+
+        Self {
+            vertex_buffer,
+            num_points: vertices.len() as u32,
+            //
+            matrix,
+            point_uniform,
+            point_buffer,
+            bind_group,
+        }
+    }
+
+    pub fn set_size(&mut self, queue: &Queue, size: f32) {
+        if size == self.point_uniform.size {
+            return;
+        }
+        self.point_uniform.size = size;
+        self.update_uniform(queue);
+    }
+
+    pub fn handle_resize(&mut self, queue: &Queue, surface_config: &SurfaceConfiguration) {
+        self.point_uniform.viewport = [surface_config.width as f32, surface_config.height as f32];
+        self.update_uniform(queue);
+    }
+
+    fn update_uniform(&self, queue: &Queue) {
+        queue.write_buffer(
+            &self.point_buffer,
+            0,
+            bytemuck::cast_slice(&[self.point_uniform]),
+        );
+    }
+}
+
+// ---------------------------------
+// Build scene from a set of points.
+
+pub fn build_scene(
+    device: &Device,
+    surface_config: &SurfaceConfiguration,
+    state: &RenderState,
+    vertices: &[GpuVertex],
+    size: f32,
+) -> Scene3D {
+    let points =
+        PointsRenderData::from_vertices(device, surface_config, vertices, Matrix::identity(), size);
+    let light = light::LightState::create(device);
+
+    let pipeline = pipeline::create_point_pipeline::<GpuVertex>(
+        device,
+        &[
+            &state.bind_group_layout,
+            PointsRenderData::bind_group_layout(device),
+        ],
+    );
+
+    Scene3D {
+        pipeline: None,
+        textured_pipeline: None,
+        instanced_pipeline: None,
+        point_pipeline: Some(pipeline),
+        line_pipeline: None,
+        depth_prepass_pipeline: None,
+        //
+        meshes: vec![],
+        textured_meshes: vec![],
+        instanced_meshes: vec![],
+        points: vec![points],
+        lines: vec![],
+        //
+        light,
+        shadow: None,
+    }
+}
+
+// ------------------------------------
+// Scene holding a single point cloud.
+
+pub struct PointCloudScene {
+    pub scene: Scene3D,
+    // point size currently written to the uniform
+    pub point_size: f32,
+}
+
+pub fn point_cloud_scene(
+    device: &Device,
+    surface_config: &SurfaceConfiguration,
+    state: &RenderState,
+    vertices: Vec<GpuVertex>,
+) -> PointCloudScene {
+    PointCloudScene {
+        scene: build_scene(device, surface_config, state, &vertices, DEFAULT_POINT_SIZE),
+        point_size: DEFAULT_POINT_SIZE,
+    }
+}
+
+impl PointCloudScene {
+    pub fn set_point_size(&mut self, queue: &Queue, size: f32) {
+        self.point_size = size;
+        for points in &mut self.scene.points {
+            points.set_size(queue, size);
+        }
+    }
+
+    pub fn handle_resize(&mut self, queue: &Queue, surface_config: &SurfaceConfiguration) {
+        for points in &mut self.scene.points {
+            points.handle_resize(queue, surface_config);
+        }
+    }
+}
+
+impl RenderScene for PointCloudScene {
+    fn scene(&self) -> &Scene3D {
+        &self.scene
+    }
+
+    fn update(&mut self, _queue: &Queue, _state: &RenderState) {}
+}
@@ -0,0 +1,92 @@
+//! Semi-transparent horizontal plane drawn over a graph at a chosen level,
+//! to show where the surface crosses it.
+
+use crate::grapher::{math::graph::Domain, pipeline};
+
+use bytemuck::Zeroable;
+use egui_wgpu::wgpu::{
+    self, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, Buffer, Device, Queue, RenderPass, RenderPipeline, util::DeviceExt,
+};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct PlaneUniform {
+    color: [f32; 4],
+    // x and z of opposite corners
+    min_corner: [f32; 2],
+    max_corner: [f32; 2],
+    height: f32,
+    _padding: [f32; 3],
+}
+
+pub struct ReferencePlane {
+    buffer: Buffer,
+    bind_group: BindGroup,
+    pipeline: RenderPipeline,
+}
+
+impl ReferencePlane {
+    // linear RGB
+    const COLOR: [f32; 3] = [0.15, 0.45, 0.9];
+
+    pub fn create(device: &Device, camera_bind_group_layout: &BindGroupLayout) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("reference plane UBO"),
+            contents: bytemuck::cast_slice(&[PlaneUniform::zeroed()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("reference plane bind group layout"),
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("reference plane bind group"),
+        });
+        let pipeline = pipeline::create_reference_plane_pipeline(
+            device,
+            &[camera_bind_group_layout, &bind_group_layout],
+        );
+
+        Self {
+            buffer,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    /// Span the domain at the given scene height, with the given opacity.
+    pub fn update_uniform(&self, queue: &Queue, domain: &Domain, height: f32, opacity: f32) {
+        let [r, g, b] = Self::COLOR;
+        let uniform = PlaneUniform {
+            color: [r, g, b, opacity],
+            min_corner: [domain.x_min as f32, domain.z_min as f32],
+            max_corner: [domain.x_max as f32, domain.z_max as f32],
+            height,
+            _padding: [0.0; 3],
+        };
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    /// Draw the plane into a render pass that already holds the scene.
+    pub fn draw(&self, render_pass: &mut RenderPass, camera_bind_group: &BindGroup) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+}
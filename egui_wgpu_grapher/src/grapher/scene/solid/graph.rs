@@ -1,18 +1,25 @@
 //! Structures and functions to build a 3D scene for a function graph.
 
-use super::build_scene;
+use super::{MeshData, MeshRenderData, build_scene, try_replace_shader};
 use crate::grapher::{
     math::{
         FunctionHolder, SmoothingFunctionWrapper,
-        graph::{self, GraphableFunc},
+        colormap::Colormap,
+        graph::{self, ColorBy, Diagonals, Domain, GraphableFunc, Normals, SurfaceMeasures},
     },
     matrix::Matrix,
+    pipeline::{self, DepthMode, heatmap_export, reflection::FloorReflection},
     render::RenderState,
-    scene::{RenderScene, Scene3D},
+    scene::{
+        GpuVertex, RenderScene, Scene3D,
+        lines::{self, LinesRenderData},
+        reference_plane::ReferencePlane,
+    },
 };
 
-use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
+use egui_wgpu::wgpu::{Device, Face, PolygonMode, Queue, RenderPipeline, SurfaceConfiguration};
 use meval::Expr;
+use std::time::{Duration, Instant};
 
 // -------------------------------------------
 // Function domain scale and shift parameters.
@@ -48,8 +55,8 @@ pub struct GraphScene {
     // all the data for rendering
     pub scene: Option<Scene3D>,
 
-    // size of rectangular domain of graph
-    pub width: f64,
+    // rectangular domain of graph
+    pub domain: Domain,
 
     // have parameters changed that require mesh regen
     pub needs_rebuild: bool,
@@ -57,18 +64,138 @@ pub struct GraphScene {
     // publicly adjustable parameters
     pub parameters: GraphParameters,
 
+    // shading the mesh normals were last computed for; follows
+    // the shading in the render preferences
+    normals: Normals,
+    // grid the mesh was built from, kept to recompute its normals
+    grid: Option<graph::SquareTesselation>,
+    // were smooth normals computed from the function itself
+    direct_normals: bool,
+
+    // how grid squares are split into triangles
+    pub diagonals: Diagonals,
+
+    // fit the function's output range to a fixed height on rebuild
+    pub auto_scale: bool,
+    // output shift and scale from the last fit, if any
+    output_fit: Option<(f64, f64)>,
+    // volume and area of the current mesh, computed once per rebuild
+    measures: Option<SurfaceMeasures>,
+    // display-time height multiplier, applied through the mesh matrix
+    pub vertical_exaggeration: f32,
+
+    // what the vertex colors show, and the colormap for height and slope
+    pub color_by: ColorBy,
+    pub colormap: Colormap,
+    // slope at the top of the colormap; steeper parts are clamped
+    pub slope_max: f32,
+    // function the vertex colors show when coloring by function; the
+    // graph is uniformly colored while there isn't one
+    pub color_function: Option<FunctionHolder>,
+    // values at the ends of the colormap as last applied, if the graph
+    // isn't uniformly colored
+    color_range: Option<(f32, f32)>,
+    // color settings changed, so the vertex colors need rewriting
+    pub needs_recolor: bool,
+
     // function to graph, if any
     pub function: Option<FunctionHolder>,
+
+    // user body for the shader's surface_color function, if any
+    surface_shader: Option<String>,
+
+    // draws the surface mirrored below the floor, if the floor is enabled
+    reflection_pipeline: Option<RenderPipeline>,
+
+    // rebuild in progress, replacing the scene when done
+    pending: Option<PendingBuild>,
+
+    // draw arrows for the function's gradient over the graph
+    pub show_gradient: bool,
+    // draw a segment along the normal at sampled vertices, to debug shading
+    pub show_normals: bool,
+    // a line setting or the mesh changed, so the lines need rebuilding
+    pub needs_lines_update: bool,
+    // normal segments for the current mesh, shown with show_normals
+    normal_lines: Vec<GpuVertex>,
+
+    // write rebuilt meshes of the same size into the existing buffers
+    pub reuse_buffers: bool,
+    // pipeline settings the current scene was built with
+    built_with: Option<PipelineSettings>,
+
+    // horizontal plane at a function value, to see where the graph crosses it
+    pub show_reference_plane: bool,
+    pub reference_level: f32,
+    pub reference_opacity: f32,
+    // created the first time it's shown
+    reference_plane: Option<ReferencePlane>,
+}
+
+// Render preferences the scene's pipelines depend on. The buffers can
+// only be reused while these are unchanged.
+#[derive(Clone, Copy, PartialEq)]
+struct PipelineSettings {
+    polygon_mode: PolygonMode,
+    cull_mode: Option<Face>,
+    depth_mode: (DepthMode, bool),
+    floor: bool,
+}
+
+impl PipelineSettings {
+    fn of(state: &RenderState) -> Self {
+        Self {
+            polygon_mode: state.render_preferences.polygon_mode,
+            cull_mode: state.render_preferences.cull_mode,
+            depth_mode: state.render_preferences.depth_mode(),
+            floor: state.floor.enabled,
+        }
+    }
+}
+
+// A rebuild spread over several frames, so large grids don't stall the UI.
+struct PendingBuild {
+    function: FunctionHolder,
+    sampler: graph::GridSampler,
+    // normals may come from the function itself, if it wasn't smoothed
+    direct_normals: bool,
 }
 
 impl Default for GraphScene {
     fn default() -> Self {
         Self {
             scene: None,
-            width: 6.0_f64,
+            domain: Domain::centered(6.0),
             needs_rebuild: false,
             parameters: Default::default(),
+            normals: Normals::Smooth,
+            grid: None,
+            direct_normals: false,
+            diagonals: Diagonals::default(),
+            auto_scale: false,
+            output_fit: None,
+            measures: None,
+            vertical_exaggeration: 1.0,
+            color_by: ColorBy::default(),
+            colormap: Colormap::default(),
+            slope_max: 2.0,
+            color_function: None,
+            color_range: None,
+            needs_recolor: false,
             function: None,
+            surface_shader: None,
+            reflection_pipeline: None,
+            pending: None,
+            show_gradient: false,
+            show_normals: false,
+            needs_lines_update: false,
+            normal_lines: vec![],
+            reuse_buffers: true,
+            built_with: None,
+            show_reference_plane: false,
+            reference_level: 0.0,
+            reference_opacity: 0.35,
+            reference_plane: None,
         }
     }
 }
@@ -78,21 +205,42 @@ impl RenderScene for GraphScene {
         self.scene.as_ref().unwrap()
     }
 
-    fn update(&mut self, _queue: &Queue, _state: &RenderState) {}
+    fn update(&mut self, queue: &Queue, state: &RenderState) {
+        let shading = state.render_preferences.shading;
+        let recolor = std::mem::take(&mut self.needs_recolor);
+        if shading != self.normals || recolor {
+            self.update_vertices(queue, shading);
+        }
+        if let Some(scene) = &mut self.scene {
+            scene.set_vertical_exaggeration(queue, self.vertical_exaggeration);
+        }
+    }
 }
 
 const GRAPH_SUBDIVISIONS: u32 = 750;
 
+// vertical extent of the graph when auto-scaled
+const AUTO_SCALE_HEIGHT: f64 = 2.0;
+
+// time per frame spent sampling the function for a rebuild
+const REBUILD_FRAME_BUDGET: Duration = Duration::from_millis(8);
+
 impl GraphScene {
-    pub fn try_rebuild_scene(
-        &mut self,
-        device: &Device,
-        surface_config: &SurfaceConfiguration,
-        state: &RenderState,
-        smoothing_scale: Option<f64>,
-    ) {
-        let Some(FunctionHolder { f }) = self.function.take() else {
+    /// Start rebuilding the mesh for the current function. The function is
+    /// sampled over calls to `continue_rebuild`, and the current mesh is
+    /// drawn until the new one is ready.
+    pub fn try_rebuild_scene(&mut self, state: &RenderState, smoothing_scale: Option<f64>) {
+        // a rebuild already in progress is restarted
+        let function = self
+            .function
+            .take()
+            .or_else(|| self.pending.take().map(|pending| pending.function));
+        let Some(FunctionHolder { f }) = function else {
             self.scene = None;
+            self.grid = None;
+            self.measures = None;
+            self.pending = None;
+            self.built_with = None;
             return;
         };
 
@@ -115,45 +263,584 @@ impl GraphScene {
             FunctionHolder::from(f)
         };
 
-        self.scene = Some(build_scene_for_graph(
+        // Slopes for smooth normals are sampled along with the heights.
+        let direct_normals = smoothing_scale.is_none();
+        let with_slopes = direct_normals && state.render_preferences.shading == Normals::Smooth;
+        self.pending = Some(PendingBuild {
+            function: f,
+            sampler: graph::GridSampler::new(GRAPH_SUBDIVISIONS, &self.domain, with_slopes),
+            direct_normals,
+        });
+    }
+
+    /// Fraction of a rebuild in progress that's done, if any.
+    pub fn build_progress(&self) -> Option<f32> {
+        self.pending
+            .as_ref()
+            .map(|pending| pending.sampler.progress())
+    }
+
+    /// Sample more of a rebuild in progress, for up to a frame's time
+    /// budget, and replace the scene once the sampling is done.
+    pub fn continue_rebuild(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+    ) {
+        let Some(pending) = &mut self.pending else {
+            return;
+        };
+        let start = Instant::now();
+        while !pending.sampler.is_done() && start.elapsed() < REBUILD_FRAME_BUDGET {
+            pending.sampler.sample_row(&pending.function);
+        }
+        if !pending.sampler.is_done() {
+            return;
+        }
+        let Some(PendingBuild {
+            function: f,
+            sampler,
+            direct_normals,
+        }) = self.pending.take()
+        else {
+            return;
+        };
+
+        // The original function is kept, so turning auto-scale off restores it.
+        self.output_fit = self
+            .auto_scale
+            .then(|| sampler.fit_output(AUTO_SCALE_HEIGHT))
+            .flatten();
+        let (shift, scale) = self.output_fit.unwrap_or((0.0, 1.0));
+
+        self.normals = state.render_preferences.shading;
+        self.direct_normals = direct_normals;
+        let mut grid = sampler.tesselation(shift, scale);
+        grid.set_diagonals(self.diagonals);
+        self.measures = Some(grid.measures(shift, scale));
+        let mut func_mesh = match sampler.normals(scale) {
+            Some(normals) if self.normals == Normals::Smooth => {
+                grid.mesh_data_with_normals(graph::SquareTesselation::FUNC_COLOR, normals)
+            }
+            // shading changed while sampling, or normals aren't from the function
+            _ => {
+                let fitted = graph::ClosureFunc(graph::shift_scale_output(
+                    |x, z| f.eval(x, z),
+                    shift,
+                    scale,
+                ));
+                graph_mesh(&grid, &fitted, self.normals, self.direct_normals)
+            }
+        };
+        self.color_mesh(&mut func_mesh);
+        self.normal_lines = self.compute_normal_lines(&func_mesh);
+        let mesh_data = vec![(func_mesh, Matrix::identity())];
+
+        // The grid size is fixed, so unless the pipelines need rebuilding
+        // the new mesh can usually be written over the old one.
+        let settings = PipelineSettings::of(state);
+        if self.reuse_buffers
+            && self.built_with == Some(settings)
+            && let Some(scene) = &mut self.scene
+            && scene.meshes[0].try_write_mesh_data(queue, &mesh_data[0].0)
+        {
+            scene
+                .light
+                .set_shadow_bounds(queue, MeshData::bounds(&mesh_data));
+            self.grid = Some(grid);
+            self.function = Some(f);
+            self.update_lines(device, state);
+            return;
+        }
+
+        let scene = build_scene(device, surface_config, state, mesh_data);
+        self.built_with = Some(settings);
+        self.reflection_pipeline = state
+            .floor
+            .enabled
+            .then(|| {
+                FloorReflection::create_reflection_pipeline(
+                    device,
+                    &state.bind_group_layout,
+                    &scene,
+                )
+            })
+            .flatten();
+        self.scene = Some(scene);
+        self.grid = Some(grid);
+        self.function = Some(f);
+        self.update_lines(device, state);
+
+        if let Some(body) = self.surface_shader.clone()
+            && let Err(err) = self.set_surface_shader(device, state, Some(body))
+        {
+            log::error!("Custom surface shader failed to compile: {err}");
+        }
+    }
+
+    pub fn reflection_pipeline(&self) -> Option<&RenderPipeline> {
+        self.reflection_pipeline.as_ref()
+    }
+
+    /// Rebuild the scene's lines: arrows for the gradient field, following
+    /// `show_gradient`, and the normals of the mesh, following `show_normals`.
+    pub fn update_lines(&mut self, device: &Device, state: &RenderState) {
+        self.needs_lines_update = false;
+        let (Some(scene), Some(f)) = (&mut self.scene, &self.function) else {
+            return;
+        };
+        scene.lines.clear();
+
+        let mut gradient = vec![];
+        if self.show_gradient {
+            let (shift, scale) = self.output_fit.unwrap_or((0.0, 1.0));
+            let fitted =
+                graph::ClosureFunc(graph::shift_scale_output(|x, z| f.eval(x, z), shift, scale));
+            gradient = gradient_arrows(&fitted, &self.domain);
+        }
+        let normals: &[GpuVertex] = if self.show_normals {
+            &self.normal_lines
+        } else {
+            &[]
+        };
+        for vertices in [gradient.as_slice(), normals] {
+            if vertices.is_empty() {
+                continue;
+            }
+            // the matrix is set to the vertical exaggeration on update
+            scene.lines.push(LinesRenderData::from_vertices(
+                device,
+                vertices,
+                Matrix::y_scale(self.vertical_exaggeration),
+            ));
+        }
+        if scene.lines.is_empty() {
+            scene.line_pipeline = None;
+            return;
+        }
+        scene.line_pipeline.get_or_insert_with(|| {
+            pipeline::create_line_pipeline::<GpuVertex>(
+                device,
+                &[&state.bind_group_layout, MeshRenderData::matrix_bgl(device)],
+                false,
+            )
+        });
+    }
+
+    /// Place the reference plane at the reference level, following the output
+    /// fit and vertical exaggeration, creating it the first time it's shown.
+    pub fn update_reference_plane(&mut self, device: &Device, queue: &Queue, state: &RenderState) {
+        if !self.show_reference_plane {
+            return;
+        }
+        let plane = self
+            .reference_plane
+            .get_or_insert_with(|| ReferencePlane::create(device, &state.bind_group_layout));
+        let (shift, scale) = self.output_fit.unwrap_or((0.0, 1.0));
+        let height = (self.reference_level as f64 * scale + shift) as f32;
+        plane.update_uniform(
+            queue,
+            &self.domain,
+            height * self.vertical_exaggeration,
+            self.reference_opacity,
+        );
+    }
+
+    pub fn reference_plane(&self) -> Option<&ReferencePlane> {
+        self.reference_plane
+            .as_ref()
+            .filter(|_| self.show_reference_plane)
+    }
+
+    /// Recompute the vertex normals for the given shading, and the colors, and
+    /// rewrite the vertex buffer, reusing the grid so the function isn't
+    /// evaluated again for heights.
+    fn update_vertices(&mut self, queue: &Queue, normals: Normals) {
+        self.normals = normals;
+        let (Some(grid), Some(f)) = (&self.grid, &self.function) else {
+            return;
+        };
+        let (shift, scale) = self.output_fit.unwrap_or((0.0, 1.0));
+        let mut mesh = {
+            let fitted =
+                graph::ClosureFunc(graph::shift_scale_output(|x, z| f.eval(x, z), shift, scale));
+            graph_mesh(grid, &fitted, normals, self.direct_normals)
+        };
+        self.color_mesh(&mut mesh);
+        self.normal_lines = self.compute_normal_lines(&mesh);
+        self.needs_lines_update |= self.show_normals;
+
+        let Some(scene) = &self.scene else {
+            return;
+        };
+        queue.write_buffer(
+            &scene.meshes[0].vertex_buffer,
+            0,
+            bytemuck::cast_slice(&mesh.vertices),
+        );
+    }
+
+    /// Normal segments at a sample of the mesh's vertices, with lengths
+    /// relative to the domain size.
+    fn compute_normal_lines(&self, mesh: &MeshData) -> Vec<GpuVertex> {
+        let width = self.domain.x_width().max(self.domain.z_width());
+        lines::normal_lines(
+            &mesh.vertices,
+            (NORMAL_LINE_LENGTH * width) as f32,
+            MAX_NORMAL_LINES,
+        )
+    }
+
+    /// Set the vertex colors for the coloring mode, from the heights or
+    /// normals already in the mesh, or the color function at each vertex.
+    /// Slope is of the surface as graphed, before vertical exaggeration.
+    fn color_mesh(&mut self, mesh: &mut MeshData) {
+        let vertices = &mut mesh.vertices;
+        let function_values = match self.color_by {
+            ColorBy::Function => self.color_function_values(vertices),
+            _ => None,
+        };
+        // no color function, or one with no finite value on the graph
+        let color_by = match (self.color_by, &function_values) {
+            (ColorBy::Function, None) => ColorBy::Uniform,
+            (color_by, _) => color_by,
+        };
+        match color_by {
+            ColorBy::Uniform => {
+                for vertex in vertices.iter_mut() {
+                    vertex.color = graph::SquareTesselation::FUNC_COLOR;
+                }
+                self.color_range = None;
+            }
+            ColorBy::Height => {
+                let (min, max) = vertices
+                    .iter()
+                    .map(|vertex| vertex.position[1])
+                    .filter(|y| y.is_finite())
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), y| {
+                        (min.min(y), max.max(y))
+                    });
+                for vertex in vertices.iter_mut() {
+                    vertex.color = self.colormap.color_in_range(vertex.position[1], min, max);
+                }
+                // legend shows the function's own values, before any fit
+                let (shift, scale) = self.output_fit.unwrap_or((0.0, 1.0));
+                let unfit = |y: f32| ((y as f64 - shift) / scale) as f32;
+                self.color_range = Some((unfit(min), unfit(max)));
+            }
+            ColorBy::Slope => {
+                for vertex in vertices.iter_mut() {
+                    let t = graph::normal_slope(vertex.normal) / self.slope_max;
+                    vertex.color = self.colormap.color(t);
+                }
+                self.color_range = Some((0.0, self.slope_max));
+            }
+            ColorBy::Function => {
+                let Some((values, (min, max))) = function_values else {
+                    return;
+                };
+                for (vertex, value) in vertices.iter_mut().zip(values) {
+                    // where g is undefined, as where f is
+                    vertex.color = if value.is_finite() {
+                        self.colormap.color_in_range(value, min, max)
+                    } else {
+                        graph::SquareTesselation::FUNC_COLOR
+                    };
+                }
+                self.color_range = Some((min, max));
+            }
+        }
+    }
+
+    /// Values of the color function at each vertex and the range of its
+    /// finite values, or `None` if there is no function or no finite value.
+    fn color_function_values(&self, vertices: &[GpuVertex]) -> Option<(Vec<f32>, (f32, f32))> {
+        let g = self.color_function.as_ref()?;
+        let values: Vec<f32> = vertices
+            .iter()
+            .map(|vertex| g.eval(vertex.position[0] as f64, vertex.position[2] as f64) as f32)
+            .collect();
+        let (min, max) = values
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            });
+        (min <= max).then_some((values, (min, max)))
+    }
+
+    /// Volume under and area of the graph, in the function's own units,
+    /// once a mesh has been built.
+    pub fn measures(&self) -> Option<SurfaceMeasures> {
+        self.measures
+    }
+
+    /// Save the graph seen from above, colored by height with the current
+    /// colormap, to a PNG file with `long_side` pixels along the longer
+    /// side of the domain.
+    pub fn export_heatmap(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        long_side: u32,
+        filename: &str,
+    ) -> Result<(), String> {
+        let (Some(scene), Some(grid)) = (&self.scene, &self.grid) else {
+            return Err("No graph to export.".into());
+        };
+        // the domain may already be changed for the next mesh
+        if self.pending.is_some() {
+            return Err("Wait for the graph to finish rebuilding before exporting.".into());
+        }
+        let height_range = grid
+            .height_range()
+            .ok_or("The graph has no finite heights to export.")?;
+        heatmap_export::save_heatmap(
             device,
-            surface_config,
-            state,
-            self.width,
-            &f,
-            smoothing_scale.is_none(),
+            queue,
+            &scene.meshes[0],
+            &self.domain,
+            height_range,
+            self.colormap,
+            heatmap_export::heatmap_size(device, &self.domain, long_side),
+            filename,
+        )
+    }
+
+    /// Values at the ends of the colormap, for its legend, or `None` when
+    /// the graph is uniformly colored.
+    pub fn color_range(&self) -> Option<(f32, f32)> {
+        self.color_range
+    }
+
+    /// Use the given body for the solid shader's `surface_color` function,
+    /// or the default shader for `None`. If the shader doesn't compile, the
+    /// error is returned and the current shader is kept.
+    pub fn set_surface_shader(
+        &mut self,
+        device: &Device,
+        state: &RenderState,
+        body: Option<String>,
+    ) -> Result<(), String> {
+        if let Some(scene) = &mut self.scene {
+            let shader = match &body {
+                Some(body) => pipeline::get_custom_surface_shader(body),
+                None => pipeline::get_shader(),
+            };
+            try_replace_shader(device, state, scene, shader)?;
+        }
+        self.surface_shader = body;
+        Ok(())
+    }
+}
+
+impl GraphScene {
+    // samples along the part of a ray over the graph domain
+    const RAY_MARCH_STEPS: usize = 1000;
+    const BISECTION_STEPS: usize = 24;
+
+    /// First intersection of a ray with the graph surface, snapped
+    /// to the nearest grid point of the tessellation.
+    pub fn ray_intersection(&self, origin: [f32; 3], direction: [f32; 3]) -> Option<[f64; 3]> {
+        let f = self.function.as_ref()?;
+        // height of the surface as drawn
+        let (shift, scale) = self.output_fit.unwrap_or((0.0, 1.0));
+        let exaggeration = self.vertical_exaggeration as f64;
+        let f = graph::ClosureFunc(graph::shift_scale_output(
+            |x, z| f.eval(x, z),
+            shift * exaggeration,
+            scale * exaggeration,
         ));
-        self.function = Some(f);
+        let origin = origin.map(f64::from);
+        let direction = direction.map(f64::from);
+        let domain = &self.domain;
+
+        // Clip ray to the vertical box over the rectangular domain.
+        let mut t_enter = 0.0_f64;
+        let mut t_exit = f64::MAX;
+        for (axis, min, max) in [
+            (0, domain.x_min, domain.x_max),
+            (2, domain.z_min, domain.z_max),
+        ] {
+            if direction[axis].abs() < f64::EPSILON {
+                if origin[axis] < min || origin[axis] > max {
+                    return None;
+                }
+                continue;
+            }
+            let t_a = (min - origin[axis]) / direction[axis];
+            let t_b = (max - origin[axis]) / direction[axis];
+            t_enter = t_enter.max(t_a.min(t_b));
+            t_exit = t_exit.min(t_a.max(t_b));
+        }
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        // Height of ray above surface, which changes sign at a crossing.
+        let height = |t: f64| {
+            let point: [f64; 3] = std::array::from_fn(|i| origin[i] + t * direction[i]);
+            point[1] - f.eval(point[0], point[2])
+        };
+
+        let step = (t_exit - t_enter) / Self::RAY_MARCH_STEPS as f64;
+        let mut t_prev = t_enter;
+        let mut h_prev = height(t_prev);
+        for i in 1..=Self::RAY_MARCH_STEPS {
+            let t = t_enter + i as f64 * step;
+            let h = height(t);
+            if h.is_finite() && h_prev.is_finite() && h.signum() != h_prev.signum() {
+                let (mut low, mut high) = (t_prev, t);
+                for _ in 0..Self::BISECTION_STEPS {
+                    let mid = (low + high) / 2.0;
+                    if height(mid).signum() == h_prev.signum() {
+                        low = mid;
+                    } else {
+                        high = mid;
+                    }
+                }
+                let t = (low + high) / 2.0;
+
+                let snap = |coord: f64, min: f64, width: f64| {
+                    let grid_step = width / GRAPH_SUBDIVISIONS as f64;
+                    ((coord - min) / grid_step).round() * grid_step + min
+                };
+                let x = snap(origin[0] + t * direction[0], domain.x_min, domain.x_width());
+                let z = snap(origin[2] + t * direction[2], domain.z_min, domain.z_width());
+
+                return Some([x, f.eval(x, z), z]);
+            }
+            t_prev = t;
+            h_prev = h;
+        }
+        None
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_scene_for_graph(
     device: &Device,
     surface_config: &SurfaceConfiguration,
     state: &RenderState,
-    width: f64,
+    domain: &Domain,
     f: &impl GraphableFunc,
+    normals: Normals,
+    diagonals: Diagonals,
     direct_normals: bool,
-) -> Scene3D {
+) -> (Scene3D, graph::SquareTesselation) {
     // TODO: Add GUI parameter for floor mesh.
     //
-    // let floor_mesh = graph::SquareTesselation::generate(SUBDIVISIONS, width)
+    // let floor_mesh = graph::SquareTesselation::generate(SUBDIVISIONS, domain)
     //     .mesh_data(graph::SquareTesselation::FLOOR_COLOR);
 
-    let grid = graph::SquareTesselation::generate(GRAPH_SUBDIVISIONS, width, f);
-
-    let func_mesh = if direct_normals {
-        grid.mesh_data_direct_normals(graph::SquareTesselation::FUNC_COLOR, f)
-    } else {
-        grid.mesh_data(graph::SquareTesselation::FUNC_COLOR)
-    };
+    let mut grid = graph::SquareTesselation::generate(GRAPH_SUBDIVISIONS, domain, f);
+    grid.set_diagonals(diagonals);
+    let func_mesh = graph_mesh(&grid, f, normals, direct_normals);
 
-    build_scene(
+    let scene = build_scene(
         device,
         surface_config,
         state,
         vec![(func_mesh, Matrix::identity())],
-    )
+    );
+    (scene, grid)
+}
+
+fn graph_mesh(
+    grid: &graph::SquareTesselation,
+    f: &impl GraphableFunc,
+    normals: Normals,
+    direct_normals: bool,
+) -> MeshData {
+    // Exact normals from the function are smoothest, but aren't
+    // available when the function has been smoothed numerically.
+    match normals {
+        Normals::Smooth if direct_normals => {
+            grid.mesh_data_direct_normals(graph::SquareTesselation::FUNC_COLOR, f)
+        }
+        _ => grid.mesh_data(graph::SquareTesselation::FUNC_COLOR, normals),
+    }
+}
+
+// -----------------------------------
+// Normals drawn over the graph.
+
+// normal segment length, relative to the wider side of the domain
+const NORMAL_LINE_LENGTH: f64 = 0.015;
+// most normals drawn, so dense meshes don't turn into a solid mat of lines
+const MAX_NORMAL_LINES: usize = 4096;
+
+// Gradient field drawn over the graph.
+
+// arrows along each side of the domain
+const GRADIENT_ARROWS: u32 = 24;
+// height of the arrows above the surface, so it doesn't hide them
+const GRADIENT_LIFT: f64 = 0.02;
+// arrows are colored by magnitude, independent of the surface colors
+const GRADIENT_COLORMAP: Colormap = Colormap::Viridis;
+
+/// Line segments for arrows showing the gradient of `f` at the centers of
+/// a grid over the domain. Each arrow starts just above the surface and
+/// follows it, pointing uphill, with the longest spanning most of a grid
+/// cell. Colors show the magnitude relative to the largest.
+fn gradient_arrows<F: GraphableFunc>(f: &F, domain: &Domain) -> Vec<GpuVertex> {
+    let dx = domain.x_width() / GRADIENT_ARROWS as f64;
+    let dz = domain.z_width() / GRADIENT_ARROWS as f64;
+    let mut samples = vec![];
+    for i in 0..GRADIENT_ARROWS {
+        for j in 0..GRADIENT_ARROWS {
+            let x = domain.x_min + (i as f64 + 0.5) * dx;
+            let z = domain.z_min + (j as f64 + 0.5) * dz;
+            let gradient = graph::slopes_from_function(x, z, f);
+            if gradient.iter().all(|g| g.is_finite()) && f.eval(x, z).is_finite() {
+                samples.push((x, z, gradient));
+            }
+        }
+    }
+    let max_magnitude = samples
+        .iter()
+        .map(|(_, _, [gx, gz])| gx.hypot(*gz))
+        .fold(0.0, f64::max);
+    if max_magnitude <= 0.0 {
+        return vec![];
+    }
+
+    let max_length = 0.9 * dx.min(dz);
+    let mut vertices = vec![];
+    for (x, z, [gx, gz]) in samples {
+        let magnitude = gx.hypot(gz);
+        let length = magnitude / max_magnitude * max_length;
+        if length < 1e-3 * max_length {
+            continue;
+        }
+        let (ux, uz) = (gx / magnitude, gz / magnitude);
+        let color = GRADIENT_COLORMAP.color((magnitude / max_magnitude) as f32);
+        let point = |x: f64, z: f64, y: f64| GpuVertex {
+            position: [x as f32, (y + GRADIENT_LIFT) as f32, z as f32],
+            color,
+            ..Default::default()
+        };
+
+        let tip_x = x + ux * length;
+        let tip_z = z + uz * length;
+        let tip_y = f.eval(tip_x, tip_z);
+        if !tip_y.is_finite() {
+            continue;
+        }
+        let tip = point(tip_x, tip_z, tip_y);
+        vertices.extend([point(x, z, f.eval(x, z)), tip]);
+
+        // two barbs for the head, level with the tip
+        let (back, side) = (0.3 * length, 0.15 * length);
+        for sign in [-1.0, 1.0] {
+            let barb_x = tip_x - ux * back - sign * uz * side;
+            let barb_z = tip_z - uz * back + sign * ux * side;
+            vertices.extend([tip, point(barb_x, barb_z, tip_y)]);
+        }
+    }
+    vertices
 }
 
 // ---------------
@@ -201,20 +888,27 @@ pub fn demo_graph_scene(
     }
 
     let mut scene = None;
+    let mut grid = None;
     if let Some(f) = function.as_ref() {
-        scene = Some(build_scene_for_graph(
+        let (graph_scene, graph_grid) = build_scene_for_graph(
             device,
             surface_config,
             state,
-            WIDTH,
+            &Domain::centered(WIDTH),
             f,
+            state.render_preferences.shading,
+            Diagonals::default(),
             false,
-        ));
+        );
+        scene = Some(graph_scene);
+        grid = Some(graph_grid);
     }
 
     GraphScene {
         scene,
-        width: WIDTH,
+        grid,
+        normals: state.render_preferences.shading,
+        domain: Domain::centered(WIDTH),
         parameters,
         function,
         ..Default::default()
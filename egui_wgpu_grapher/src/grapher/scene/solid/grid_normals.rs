@@ -0,0 +1,227 @@
+//! GPU recomputation of the heights and normals of animated grid meshes,
+//! so the CPU only uploads a buffer of heights each frame.
+
+use crate::grapher::{
+    pipeline::{get_grid_normals_compute_shader, try_create_compute_pipeline},
+    scene::GpuVertex,
+};
+
+use bytemuck::{Pod, Zeroable};
+use egui_wgpu::wgpu::{self, BindGroup, Buffer, ComputePipeline, Device, Queue, util::DeviceExt};
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct GridUniform {
+    cols: u32,
+    rows: u32,
+    spacing: f32,
+    vertex_stride: u32,
+}
+
+/// Compute pass that writes heights and flat normals into the vertex buffer
+/// of a grid mesh with one vertex per grid point, in row-major order.
+pub struct GridNormals {
+    // held to encode the compute pass from scene updates
+    device: Device,
+    pipeline: ComputePipeline,
+    bind_group: BindGroup,
+    heights_buffer: Buffer,
+    // heights gathered from the mesh vertices, reused between updates
+    heights: Vec<f32>,
+    size: (u32, u32),
+    // when off, scenes fall back to computing normals on the CPU
+    pub enabled: bool,
+}
+
+impl GridNormals {
+    /// Compute pass for a `cols` x `rows` grid mesh in the given vertex
+    /// buffer, which needs storage usage. Fails if the pipeline can't be
+    /// created, in which case scenes use the CPU path.
+    pub fn new(
+        device: &Device,
+        vertex_buffer: &Buffer,
+        (cols, rows): (usize, usize),
+        spacing: f32,
+    ) -> Result<Self, String> {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Grid Normals Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline = try_create_compute_pipeline(
+            device,
+            get_grid_normals_compute_shader(),
+            &[&bind_group_layout],
+        )?;
+
+        let uniform = GridUniform {
+            cols: cols as u32,
+            rows: rows as u32,
+            spacing,
+            vertex_stride: (std::mem::size_of::<GpuVertex>() / std::mem::size_of::<f32>()) as u32,
+        };
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grid Normals Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let heights_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Grid Heights Buffer"),
+            size: (cols * rows * std::mem::size_of::<f32>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Grid Normals Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: heights_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: vertex_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(Self {
+            device: device.clone(),
+            pipeline,
+            bind_group,
+            heights_buffer,
+            heights: Vec::with_capacity(cols * rows),
+            size: (cols as u32, rows as u32),
+            enabled: true,
+        })
+    }
+
+    /// Upload the heights of the given vertices and recompute the heights
+    /// and normals in the vertex buffer. Other vertex attributes are kept.
+    pub fn update(&mut self, queue: &Queue, vertices: &[GpuVertex]) {
+        self.heights.clear();
+        self.heights
+            .extend(vertices.iter().map(|vertex| vertex.position[1]));
+        queue.write_buffer(&self.heights_buffer, 0, bytemuck::cast_slice(&self.heights));
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Grid Normals Encoder"),
+            });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &self.bind_group, &[]);
+            let (cols, rows) = self.size;
+            compute_pass.dispatch_workgroups(cols.div_ceil(8), rows.div_ceil(8), 1);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grapher::{
+        math::graph::{Normals, SquareTesselation},
+        pipeline::test_device,
+    };
+
+    #[test]
+    fn gpu_normals_match_cpu_flat_normals() {
+        let Some((device, queue)) = test_device() else {
+            eprintln!("no adapter, skipping");
+            return;
+        };
+        // not square, so swapped rows and columns would show
+        let (cols, rows) = (23, 17);
+        let spacing = 0.25;
+        let grid = SquareTesselation::from_heights(
+            cols as u32,
+            rows as u32,
+            spacing,
+            &vec![0.0; cols * rows],
+        );
+        let mut mesh_data = grid.mesh_data(SquareTesselation::FUNC_COLOR, Normals::Flat);
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&mesh_data.vertices),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let mut grid_normals =
+            GridNormals::new(&device, &vertex_buffer, (cols, rows), spacing as f32).unwrap();
+
+        for (i, vertex) in mesh_data.vertices.iter_mut().enumerate() {
+            let (x, z) = ((i % cols) as f32, (i / cols) as f32);
+            vertex.position[1] = (0.7 * x).sin() * (0.4 * z).cos() + 0.05 * x * z / 10.0;
+        }
+        grid_normals.update(&queue, &mesh_data.vertices);
+        grid.update_normals(&mut mesh_data, Normals::Flat);
+
+        let size = vertex_buffer.size();
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(&vertex_buffer, 0, &staging_buffer, 0, size);
+        queue.submit(Some(encoder.finish()));
+        staging_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+        let gpu_vertices: Vec<GpuVertex> =
+            bytemuck::cast_slice(&staging_buffer.slice(..).get_mapped_range()).to_vec();
+
+        for (i, (gpu, cpu)) in gpu_vertices.iter().zip(&mesh_data.vertices).enumerate() {
+            assert_eq!(gpu.position, cpu.position, "position of vertex {i}");
+            for (g, c) in gpu.normal.iter().zip(cpu.normal) {
+                assert!(
+                    (g - c).abs() < 1e-5,
+                    "normal of vertex {i}: {gpu:?} vs {cpu:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Build a terrain surface from the intensities of a grayscale image.
+
+use super::{MeshData, build_scene};
+use crate::grapher::{
+    math::graph::{Normals, SquareTesselation},
+    matrix::Matrix,
+    pipeline::texture::Image,
+    render::RenderState,
+    scene::{RenderScene, Scene3D},
+};
+
+use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
+
+pub struct HeightmapScene {
+    // all the data for rendering
+    pub scene: Scene3D,
+
+    // number of grid points along the longer image side
+    pub resolution: u32,
+    // height of a fully white pixel
+    pub vertical_scale: f32,
+    // display-time height multiplier, applied through the mesh matrix
+    pub vertical_exaggeration: f32,
+    // have parameters changed that require mesh regen
+    pub needs_rebuild: bool,
+
+    // image intensities in [0, 1], row-major
+    intensities: Vec<f32>,
+    dimensions: (u32, u32),
+}
+
+impl HeightmapScene {
+    // width of the surface along the longer image side
+    const WIDTH: f64 = 6.0;
+    const COLOR: [f32; 3] = [0.55, 0.6, 0.45];
+
+    pub const DEFAULT_RESOLUTION: u32 = 256;
+    pub const DEFAULT_VERTICAL_SCALE: f32 = 1.0;
+
+    pub fn new(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+        image_path: &str,
+    ) -> Result<Self, String> {
+        let image = Image::from_file(image_path)?;
+
+        // Rec. 709 luma of each pixel.
+        let intensities = image
+            .image
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, _] = pixel.0.map(|c| c as f32 / 255.0);
+                0.2126 * r + 0.7152 * g + 0.0722 * b
+            })
+            .collect::<Vec<f32>>();
+
+        let resolution = Self::DEFAULT_RESOLUTION;
+        let vertical_scale = Self::DEFAULT_VERTICAL_SCALE;
+        let dimensions = image.dimensions;
+
+        let scene = build_scene(
+            device,
+            surface_config,
+            state,
+            vec![(
+                Self::mesh(
+                    &intensities,
+                    dimensions,
+                    resolution,
+                    vertical_scale,
+                    state.render_preferences.shading,
+                ),
+                Matrix::identity(),
+            )],
+        );
+
+        Ok(Self {
+            scene,
+            resolution,
+            vertical_scale,
+            vertical_exaggeration: 1.0,
+            needs_rebuild: false,
+            intensities,
+            dimensions,
+        })
+    }
+
+    pub fn rebuild_scene(
+        &mut self,
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+    ) {
+        let mesh = Self::mesh(
+            &self.intensities,
+            self.dimensions,
+            self.resolution,
+            self.vertical_scale,
+            state.render_preferences.shading,
+        );
+        self.scene = build_scene(
+            device,
+            surface_config,
+            state,
+            vec![(mesh, Matrix::identity())],
+        );
+    }
+
+    /// Downsample the image to a grid with `resolution` points along its
+    /// longer side, keeping its aspect ratio, and tessellate the heights.
+    fn mesh(
+        intensities: &[f32],
+        (width, height): (u32, u32),
+        resolution: u32,
+        vertical_scale: f32,
+        normals: Normals,
+    ) -> MeshData {
+        let longer_side = width.max(height);
+        let resolution = resolution.clamp(2, longer_side.max(2));
+        let grid_dim = |side: u32| {
+            ((side as f64 * resolution as f64 / longer_side as f64).round() as u32).max(2)
+        };
+        let (cols, rows) = (grid_dim(width), grid_dim(height));
+
+        // Bilinear sample of the image at fractional pixel coordinates.
+        let sample = |x: f64, y: f64| {
+            let (x_0, y_0) = (x.floor() as u32, y.floor() as u32);
+            let (x_1, y_1) = ((x_0 + 1).min(width - 1), (y_0 + 1).min(height - 1));
+            let (s, t) = ((x - x_0 as f64) as f32, (y - y_0 as f64) as f32);
+            let at = |x: u32, y: u32| intensities[(y * width + x) as usize];
+
+            let top = at(x_0, y_0) * (1.0 - s) + at(x_1, y_0) * s;
+            let bottom = at(x_0, y_1) * (1.0 - s) + at(x_1, y_1) * s;
+            top * (1.0 - t) + bottom * t
+        };
+
+        let mut heights = Vec::with_capacity((cols * rows) as usize);
+        for row in 0..rows {
+            let y = row as f64 * (height - 1) as f64 / (rows - 1) as f64;
+            for col in 0..cols {
+                let x = col as f64 * (width - 1) as f64 / (cols - 1) as f64;
+                heights.push(vertical_scale * sample(x, y));
+            }
+        }
+
+        let spacing = Self::WIDTH / (cols.max(rows) - 1) as f64;
+        SquareTesselation::from_heights(cols, rows, spacing, &heights)
+            .mesh_data(Self::COLOR, normals)
+    }
+}
+
+impl RenderScene for HeightmapScene {
+    fn scene(&self) -> &Scene3D {
+        &self.scene
+    }
+
+    fn update(&mut self, queue: &Queue, _state: &RenderState) {
+        self.scene
+            .set_vertical_exaggeration(queue, self.vertical_exaggeration);
+    }
+}
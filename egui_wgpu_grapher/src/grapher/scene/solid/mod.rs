@@ -1,19 +1,22 @@
 //! Code for meshes with color provided per-vertex.
 
 pub mod graph;
+pub mod grid_normals;
+pub mod heightmap;
 #[allow(dead_code)]
 pub mod pde;
+pub mod sphere;
 
 use super::{GpuVertex, Scene3D};
 use crate::grapher::{
     matrix::{self, Matrix, MatrixUniform},
-    pipeline::{self, light},
+    pipeline::{self, DepthMode, light},
     render::{RenderState, ShadowState},
 };
 
 use egui_wgpu::wgpu::{
     self, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
-    BindGroupLayoutDescriptor, Buffer, Device, SurfaceConfiguration, util::DeviceExt,
+    BindGroupLayoutDescriptor, Buffer, Device, Queue, SurfaceConfiguration, util::DeviceExt,
 };
 use std::sync::{LazyLock, OnceLock};
 
@@ -27,6 +30,29 @@ pub struct MeshData {
 }
 
 impl MeshData {
+    /// Min and max corners of the meshes after their matrices are applied,
+    /// ignoring non-finite positions, or none if there are no vertices.
+    pub fn bounds(mesh_data: &[(MeshData, Matrix)]) -> Option<([f32; 3], [f32; 3])> {
+        use cgmath::Transform;
+
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for (mesh, matrix) in mesh_data {
+            let matrix = cgmath::Matrix4::from(*matrix);
+            for vertex in &mesh.vertices {
+                let position = matrix.transform_point(cgmath::Point3::from(vertex.position));
+                if !(position.x.is_finite() && position.y.is_finite() && position.z.is_finite()) {
+                    continue;
+                }
+                for i in 0..3 {
+                    min[i] = min[i].min(position[i]);
+                    max[i] = max[i].max(position[i]);
+                }
+            }
+        }
+        (min[0] <= max[0]).then_some((min, max))
+    }
+
     pub fn set_uniform_color(&mut self, rgb: [f32; 3]) {
         for vertex in &mut self.vertices {
             vertex.color = rgb;
@@ -58,12 +84,15 @@ impl MeshRenderData {
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
             contents: bytemuck::cast_slice(mesh_data.vertices.as_slice()),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            // storage so animated grids can update normals in a compute pass
+            usage: wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::STORAGE,
         });
         let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Index Buffer"),
             contents: bytemuck::cast_slice(mesh_data.indices.as_slice()),
-            usage: wgpu::BufferUsages::INDEX,
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
         });
         let num_indices = mesh_data.indices.len() as u32;
 
@@ -86,6 +115,28 @@ impl MeshRenderData {
             matrix_bind_group,
         }
     }
+
+    /// Overwrite the vertex and index buffers in place, if the new mesh has
+    /// the same numbers of vertices and indices. Returns whether it did.
+    pub fn try_write_mesh_data(&self, queue: &Queue, mesh_data: &MeshData) -> bool {
+        let vertex_bytes = std::mem::size_of_val(mesh_data.vertices.as_slice());
+        if vertex_bytes as wgpu::BufferAddress != self.vertex_buffer.size()
+            || mesh_data.indices.len() != self.num_indices as usize
+        {
+            return false;
+        }
+        queue.write_buffer(
+            &self.vertex_buffer,
+            0,
+            bytemuck::cast_slice(mesh_data.vertices.as_slice()),
+        );
+        queue.write_buffer(
+            &self.index_buffer,
+            0,
+            bytemuck::cast_slice(mesh_data.indices.as_slice()),
+        );
+        true
+    }
 }
 
 // ---------------------------------------
@@ -97,41 +148,91 @@ pub fn build_scene(
     state: &RenderState,
     mesh_data: Vec<(MeshData, Matrix)>,
 ) -> Scene3D {
+    let shadow_bounds = MeshData::bounds(&mesh_data);
     let meshes: Vec<MeshRenderData> = mesh_data
         .into_iter()
         .map(|(mesh, matrix)| MeshRenderData::from_mesh_data(device, mesh, matrix))
         .collect();
 
     let matrix_bind_group_layout = MeshRenderData::matrix_bgl(device);
-    let light = light::LightState::create(device);
+    let light = light::LightState::with_shadow_bounds(device, shadow_bounds);
     let shadow =
         ShadowState::create::<GpuVertex>(surface_config, device, &light, matrix_bind_group_layout);
 
-    let pipeline = pipeline::create_render_pipeline::<GpuVertex>(
+    let bind_group_layouts = [
+        &state.bind_group_layout,
+        matrix_bind_group_layout,
+        &light.bind_group_layout,
+        &shadow.render_pass_bind_group_layout,
+    ];
+    let (depth_mode, depth_prepass) = state.render_preferences.depth_mode();
+    let pipeline = pipeline::create_depth_mode_pipeline::<GpuVertex>(
         device,
-        surface_config,
         pipeline::get_shader(),
-        &[
-            &state.bind_group_layout,
-            matrix_bind_group_layout,
-            &light.bind_group_layout,
-            &shadow.render_pass_bind_group_layout,
-        ],
+        &bind_group_layouts,
         state.render_preferences.polygon_mode,
+        state.render_preferences.cull_mode,
+        depth_mode,
     );
+    // The default shader is enough for depth, even with a custom surface color.
+    let depth_prepass_pipeline = depth_prepass.then(|| {
+        pipeline::create_depth_mode_pipeline::<GpuVertex>(
+            device,
+            pipeline::get_shader(),
+            &bind_group_layouts,
+            state.render_preferences.polygon_mode,
+            state.render_preferences.cull_mode,
+            DepthMode::Prepass,
+        )
+    });
 
     Scene3D {
         pipeline: Some(pipeline),
         textured_pipeline: None,
+        instanced_pipeline: None,
+        point_pipeline: None,
+        line_pipeline: None,
+        depth_prepass_pipeline,
         //
         meshes,
         textured_meshes: vec![],
+        instanced_meshes: vec![],
+        points: vec![],
+        lines: vec![],
         //
         light,
         shadow: Some(shadow),
     }
 }
 
+/// Replace the pipeline of a scene built by `build_scene` with one using the
+/// given shader, keeping the current pipeline if the shader doesn't compile.
+pub fn try_replace_shader(
+    device: &Device,
+    state: &RenderState,
+    scene: &mut Scene3D,
+    shader: wgpu::ShaderSource<'static>,
+) -> Result<(), String> {
+    let Some(shadow) = &scene.shadow else {
+        return Err("Scene has no solid mesh pipeline.".into());
+    };
+    let pipeline = pipeline::try_create_depth_mode_pipeline::<GpuVertex>(
+        device,
+        shader,
+        &[
+            &state.bind_group_layout,
+            MeshRenderData::matrix_bgl(device),
+            &scene.light.bind_group_layout,
+            &shadow.render_pass_bind_group_layout,
+        ],
+        state.render_preferences.polygon_mode,
+        state.render_preferences.cull_mode,
+        state.render_preferences.depth_mode().0,
+    )?;
+    scene.pipeline = Some(pipeline);
+    Ok(())
+}
+
 // --------------------------------------
 // Simple test scene for development use.
 
@@ -1,15 +1,57 @@
-//! Currently unused. Code to graph a "real-time" wave/heat equation simulation.
+//! Code to graph "real-time" PDE simulations, each backing its own mode.
 
 #[allow(dead_code)]
-use super::{MeshData, build_scene};
+use super::{MeshData, build_scene, grid_normals::GridNormals};
 use crate::grapher::{
-    math::{FunctionHolder, graph::SquareTesselation, pde},
+    math::{
+        colormap::{self, Colormap},
+        graph::{Normals, SquareTesselation},
+        pde::{self, FieldFormat},
+    },
     matrix::Matrix,
     render::RenderState,
     scene::{RenderScene, Scene3D},
 };
 use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
 
+/// Default wave equation grid size, in grid points along x and z.
+pub const WAVE_EQN_GRID: (usize, usize) = (600, 600);
+/// Default heat equation grid size, in grid points along x and z.
+pub const HEAT_EQN_GRID: (usize, usize) = (400, 400);
+/// Default Schrödinger equation grid size, in grid points along each side.
+pub const SCHRODINGER_GRID: usize = 256;
+
+// Distance between grid points when the grid spans `width` along the longer side.
+fn grid_spacing(cols: usize, rows: usize, width: f64) -> f64 {
+    width / (cols.max(rows) - 1) as f64
+}
+
+// Zero-height mesh with one vertex per grid point, spanning `width` along
+// the longer side; vertices are in row-major order from the back row.
+fn flat_grid(cols: usize, rows: usize, width: f64) -> SquareTesselation {
+    let spacing = grid_spacing(cols, rows, width);
+    SquareTesselation::from_heights(cols as u32, rows as u32, spacing, &vec![0.0; cols * rows])
+}
+
+// GPU normals for the scene's grid mesh, or none if the compute pipeline
+// can't be created.
+fn create_grid_normals(
+    device: &Device,
+    scene: &Scene3D,
+    (cols, rows): (usize, usize),
+    width: f64,
+) -> Option<GridNormals> {
+    let spacing = grid_spacing(cols, rows, width) as f32;
+    GridNormals::new(
+        device,
+        &scene.meshes[0].vertex_buffer,
+        (cols, rows),
+        spacing,
+    )
+    .inspect_err(|err| log::error!("Computing normals on the CPU: {err}"))
+    .ok()
+}
+
 // scene for simulating the wave equation
 
 pub struct WaveEquationScene {
@@ -18,21 +60,22 @@ pub struct WaveEquationScene {
     pub mesh_data: MeshData,
     pub wave_eqn: pde::WaveEquationData,
     pub display_scale: f64,
+    pub field_format: FieldFormat,
+    pub grid_normals: Option<GridNormals>,
 }
 
 pub fn wave_eqn_scene(
     device: &Device,
     surface_config: &SurfaceConfiguration,
     state: &RenderState,
+    (x_size, y_size): (usize, usize),
+    seed: Option<u64>,
 ) -> WaveEquationScene {
-    const WAVE_EQN_SUBDIV: usize = 600;
-    // number of squares is 1 less than number of gridpoints
-    const SUBDIVISIONS: u32 = WAVE_EQN_SUBDIV as u32 - 1;
-    const WIDTH: f64 = 1.0;
-
-    let func_mesh =
-        SquareTesselation::generate(SUBDIVISIONS, WIDTH, &FunctionHolder::from(|_, _| 0.0));
-    let mesh_data = func_mesh.mesh_data(SquareTesselation::FUNC_COLOR);
+    // spans the same width as the other 3D scenes
+    const WIDTH: f64 = 6.0;
+
+    let func_mesh = flat_grid(x_size, y_size, WIDTH);
+    let mesh_data = func_mesh.mesh_data(SquareTesselation::FUNC_COLOR, Normals::Flat);
     let matrix = Matrix::translation(&[
         (-WIDTH / 2.0_f64) as f32,
         0.1_f32,
@@ -45,14 +88,16 @@ pub fn wave_eqn_scene(
         state,
         vec![(mesh_data.clone(), matrix)],
     );
-    let mut wave_eqn = pde::WaveEquationData::new(WAVE_EQN_SUBDIV, WAVE_EQN_SUBDIV);
+    let mut wave_eqn = pde::WaveEquationData::with_seed(x_size, y_size, seed);
+    log::info!("Wave equation seed: {}", wave_eqn.seed());
 
     wave_eqn.disturbance_prob = 0.003;
     wave_eqn.disturbance_size = 2.0;
     wave_eqn.damping_factor = 0.998;
     wave_eqn.prop_speed = 0.15;
 
-    let display_scale: f64 = 0.075;
+    let display_scale: f64 = 0.45;
+    let grid_normals = create_grid_normals(device, &scene, (x_size, y_size), WIDTH);
 
     WaveEquationScene {
         scene,
@@ -60,32 +105,41 @@ pub fn wave_eqn_scene(
         mesh_data,
         wave_eqn,
         display_scale,
+        field_format: FieldFormat::default(),
+        grid_normals,
     }
 }
 
-impl RenderScene for WaveEquationScene {
-    fn scene(&self) -> &Scene3D {
-        &self.scene
+impl WaveEquationScene {
+    /// Restart the simulation from its initial state.
+    pub fn reset(&mut self, queue: &Queue, state: &RenderState) {
+        self.wave_eqn.reset();
+        self.write_mesh(queue, state);
     }
 
-    fn update(&mut self, queue: &Queue, state: &RenderState) {
-        // run next finite-difference timestep
-        self.wave_eqn.update();
-
-        let n = self.wave_eqn.x_size;
+    // copy solution heights into the mesh and upload it
+    fn write_mesh(&mut self, queue: &Queue, state: &RenderState) {
+        let (x_size, y_size) = (self.wave_eqn.x_size, self.wave_eqn.y_size);
         let b = 2_usize;
 
-        // update vertex y-coordinates
-        for i in b..n - b {
-            for j in b..n - b {
-                self.mesh_data.vertices[j + i * n].position[1] =
-                    (self.display_scale * self.wave_eqn.u_0[i][j] as f64) as f32;
+        // update vertex y-coordinates; mesh rows run along x
+        for x in b..x_size - b {
+            for y in b..y_size - b {
+                self.mesh_data.vertices[x + y * x_size].position[1] =
+                    (self.display_scale * self.wave_eqn.u_0[x][y] as f64) as f32;
             }
         }
 
+        // Only heights change, so only they are uploaded on the GPU path.
+        if let Some(grid_normals) = self.grid_normals.as_mut().filter(|g| g.enabled) {
+            grid_normals.update(queue, &self.mesh_data.vertices);
+            return;
+        }
+
         if state.render_preferences.lighting_enabled() {
             // update vertex normals
-            self.func_mesh.update_normals(&mut self.mesh_data);
+            self.func_mesh
+                .update_normals(&mut self.mesh_data, Normals::Flat);
         }
 
         // update vertex buffer
@@ -97,6 +151,18 @@ impl RenderScene for WaveEquationScene {
     }
 }
 
+impl RenderScene for WaveEquationScene {
+    fn scene(&self) -> &Scene3D {
+        &self.scene
+    }
+
+    fn update(&mut self, queue: &Queue, state: &RenderState) {
+        // run next finite-difference timestep
+        self.wave_eqn.update();
+        self.write_mesh(queue, state);
+    }
+}
+
 // scene for simulating the heat equation
 
 pub struct HeatEquationScene {
@@ -105,6 +171,12 @@ pub struct HeatEquationScene {
     pub mesh_data: MeshData,
     pub heat_eqn: pde::HeatEquationData,
     pub display_scale: f32,
+    pub colormap: Colormap,
+    pub field_format: FieldFormat,
+    pub grid_normals: Option<GridNormals>,
+
+    // min and max temperature shown in the last update
+    value_range: (f32, f32),
 
     // we don't update boundary each render, but
     // keep buffer area fixed to avoid flicker
@@ -115,19 +187,17 @@ pub fn heat_eqn_scene(
     device: &Device,
     surface_config: &SurfaceConfiguration,
     state: &RenderState,
+    (x_size, y_size): (usize, usize),
 ) -> HeatEquationScene {
     let b: usize = 5;
+    // spans the same width as the other 3D scenes
+    const WIDTH: f64 = 6.0;
 
-    static WAVE_EQN_SUBDIV: usize = 400;
-    // number of squares is 1 less than number of gridpoints
-    let subdivisions: u32 = WAVE_EQN_SUBDIV as u32 - 1 - (b as u32 * 2);
-    const WIDTH: f64 = 1.0;
+    // mesh covers the grid minus the fixed buffer area
+    let func_mesh = flat_grid(x_size - 2 * b, y_size - 2 * b, WIDTH);
+    let mut mesh_data = func_mesh.mesh_data(SquareTesselation::FUNC_COLOR, Normals::Flat);
 
-    let func_mesh =
-        SquareTesselation::generate(subdivisions, WIDTH, &FunctionHolder::from(|_, _| 0.0));
-    let mut mesh_data = func_mesh.mesh_data(SquareTesselation::FUNC_COLOR);
-
-    func_mesh.update_normals(&mut mesh_data);
+    func_mesh.update_normals(&mut mesh_data, Normals::Flat);
 
     let matrix = Matrix::translation(&[
         (-WIDTH / 2.0_f64) as f32,
@@ -141,8 +211,9 @@ pub fn heat_eqn_scene(
         vec![(mesh_data.clone(), matrix)],
     );
 
-    let heat_eqn = pde::HeatEquationData::new(WAVE_EQN_SUBDIV, WAVE_EQN_SUBDIV);
-    let display_scale: f32 = 0.015;
+    let heat_eqn = pde::HeatEquationData::new(x_size, y_size);
+    let display_scale: f32 = 0.09;
+    let grid_normals = create_grid_normals(device, &scene, (x_size - 2 * b, y_size - 2 * b), WIDTH);
 
     HeatEquationScene {
         scene,
@@ -150,10 +221,73 @@ pub fn heat_eqn_scene(
         mesh_data,
         heat_eqn,
         display_scale,
+        colormap: Colormap::default(),
+        field_format: FieldFormat::default(),
+        grid_normals,
+        value_range: (0.0, 0.0),
         b,
     }
 }
 
+impl HeatEquationScene {
+    /// Restart the simulation from its initial condition.
+    pub fn reset(&mut self, queue: &Queue, state: &RenderState) {
+        self.heat_eqn.reset();
+        self.write_mesh(queue, state);
+    }
+
+    /// Min and max temperature over the displayed grid, which the colormap spans.
+    pub fn value_range(&self) -> (f32, f32) {
+        self.value_range
+    }
+
+    // copy solution heights and colors into the mesh and upload it
+    fn write_mesh(&mut self, queue: &Queue, state: &RenderState) {
+        let n = self.heat_eqn.x_size;
+        // displayed columns and rows
+        let cols = n - self.b * 2;
+        let rows = self.heat_eqn.y_size - self.b * 2;
+        let value = |i: usize, j: usize| {
+            self.heat_eqn.u[(i + self.b) * n + (j + self.b)][self.heat_eqn.current_index]
+        };
+
+        let (mut min, mut max) = (f32::INFINITY, f32::NEG_INFINITY);
+        for i in 0..rows {
+            for j in 0..cols {
+                min = min.min(value(i, j));
+                max = max.max(value(i, j));
+            }
+        }
+
+        // update vertex y-coordinates and color
+        for i in 0..rows {
+            for j in 0..cols {
+                let vertex = &mut self.mesh_data.vertices[j + i * cols];
+                vertex.position[1] = self.display_scale * value(i, j);
+                vertex.color = self.colormap.color_in_range(value(i, j), min, max);
+            }
+        }
+        self.value_range = (min, max);
+
+        let gpu_normals = self.grid_normals.as_ref().is_some_and(|g| g.enabled);
+        if !gpu_normals && state.render_preferences.lighting_enabled() {
+            // update vertex normals
+            self.func_mesh
+                .update_normals(&mut self.mesh_data, Normals::Flat);
+        }
+
+        // update vertex buffer; colors change too, so all of it is uploaded
+        queue.write_buffer(
+            &self.scene.meshes[0].vertex_buffer,
+            0,
+            bytemuck::cast_slice(self.mesh_data.vertices.as_slice()),
+        );
+        if let Some(grid_normals) = self.grid_normals.as_mut().filter(|g| g.enabled) {
+            grid_normals.update(queue, &self.mesh_data.vertices);
+        }
+    }
+}
+
 impl RenderScene for HeatEquationScene {
     fn scene(&self) -> &Scene3D {
         &self.scene
@@ -162,36 +296,122 @@ impl RenderScene for HeatEquationScene {
     fn update(&mut self, queue: &Queue, state: &RenderState) {
         // run next finite-difference timestep
         self.heat_eqn.update();
+        self.write_mesh(queue, state);
+    }
+}
 
-        let n = self.heat_eqn.x_size;
-        let m = n - self.b * 2;
+// scene for simulating the Schrödinger equation
 
-        // update vertex y-coordinates and color
-        for i in 0..m {
-            for j in 0..m {
-                let new_height = self.display_scale
-                    * self.heat_eqn.u[(i + self.b) * n + (j + self.b)][self.heat_eqn.current_index];
-                let new_color: [f32; 3] = [
-                    255.0,
-                    (255.0 * new_height.abs().clamp(0.0, 10.0) / 10.0),
-                    0.0,
-                ];
-
-                self.mesh_data.vertices[j + i * m].position[1] = new_height;
-                self.mesh_data.vertices[j + i * m].color = new_color
-            }
+pub struct SchrodingerScene {
+    pub scene: Scene3D,
+    pub func_mesh: SquareTesselation,
+    pub mesh_data: MeshData,
+    pub schrodinger: pde::SchrodingerData,
+    // height of the initial peak density
+    pub display_height: f32,
+    pub grid_normals: Option<GridNormals>,
+
+    // parameters changed that need the simulation restarted
+    pub needs_reset: bool,
+    pub paused: bool,
+}
+
+impl SchrodingerScene {
+    const WIDTH: f64 = 6.0;
+
+    // surface color where the wavefunction vanishes, with no
+    // potential and at the largest potential
+    const FREE_COLOR: [f32; 3] = [0.5, 0.5, 0.5];
+    const WALL_COLOR: [f32; 3] = [0.08, 0.08, 0.1];
+
+    pub fn new(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+        size: usize,
+    ) -> Self {
+        let func_mesh = flat_grid(size, size, Self::WIDTH);
+        let mesh_data = func_mesh.mesh_data(SquareTesselation::FUNC_COLOR, Normals::Flat);
+        let matrix = Matrix::translation(&[
+            (-Self::WIDTH / 2.0) as f32,
+            0.0,
+            (-Self::WIDTH / 2.0) as f32,
+        ]);
+        let scene = build_scene(
+            device,
+            surface_config,
+            state,
+            vec![(mesh_data.clone(), matrix)],
+        );
+        let grid_normals = create_grid_normals(device, &scene, (size, size), Self::WIDTH);
+
+        Self {
+            scene,
+            func_mesh,
+            mesh_data,
+            schrodinger: pde::SchrodingerData::new(size),
+            display_height: 1.5,
+            grid_normals,
+            needs_reset: false,
+            paused: false,
         }
+    }
 
-        if state.render_preferences.lighting_enabled() {
+    // Copy the density into the mesh heights, and color it by phase, fading
+    // to a gray that darkens with the potential where the density is small.
+    fn write_mesh(&mut self, queue: &Queue, state: &RenderState) {
+        let schrodinger = &self.schrodinger;
+        let peak = schrodinger.initial_peak_density().max(f32::MIN_POSITIVE);
+        let max_potential = schrodinger.max_potential();
+
+        for (k, vertex) in self.mesh_data.vertices.iter_mut().enumerate() {
+            let density = schrodinger.density(k) / peak;
+            vertex.position[1] = self.display_height * density;
+
+            let wall = if max_potential > 0.0 {
+                schrodinger.potential_at(k) / max_potential
+            } else {
+                0.0
+            };
+            let hue = colormap::hue_color(schrodinger.phase(k) / std::f32::consts::TAU);
+            let t = density.sqrt().min(1.0);
+            vertex.color = std::array::from_fn(|c| {
+                let base = Self::FREE_COLOR[c] + wall * (Self::WALL_COLOR[c] - Self::FREE_COLOR[c]);
+                base + t * (hue[c] - base)
+            });
+        }
+
+        let gpu_normals = self.grid_normals.as_ref().is_some_and(|g| g.enabled);
+        if !gpu_normals && state.render_preferences.lighting_enabled() {
             // update vertex normals
-            self.func_mesh.update_normals(&mut self.mesh_data);
+            self.func_mesh
+                .update_normals(&mut self.mesh_data, Normals::Flat);
         }
 
-        // update vertex buffer
+        // update vertex buffer; colors change too, so all of it is uploaded
         queue.write_buffer(
             &self.scene.meshes[0].vertex_buffer,
             0,
             bytemuck::cast_slice(self.mesh_data.vertices.as_slice()),
         );
+        if let Some(grid_normals) = self.grid_normals.as_mut().filter(|g| g.enabled) {
+            grid_normals.update(queue, &self.mesh_data.vertices);
+        }
+    }
+}
+
+impl RenderScene for SchrodingerScene {
+    fn scene(&self) -> &Scene3D {
+        &self.scene
+    }
+
+    fn update(&mut self, queue: &Queue, state: &RenderState) {
+        if std::mem::take(&mut self.needs_reset) {
+            self.schrodinger.reset();
+        } else if !self.paused && self.schrodinger.is_stable() {
+            // run the next finite-difference timesteps
+            self.schrodinger.update();
+        }
+        self.write_mesh(queue, state);
     }
 }
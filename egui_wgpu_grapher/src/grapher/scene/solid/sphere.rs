@@ -0,0 +1,81 @@
+//! Build a deformed sphere graphing a function r = f(θ, φ).
+
+use super::{MeshData, build_scene};
+use crate::grapher::{
+    math::{FunctionHolder, sphere::sphere_mesh_data},
+    matrix::Matrix,
+    render::RenderState,
+    scene::{RenderScene, Scene3D},
+};
+
+use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
+
+pub struct SphereScene {
+    // all the data for rendering
+    pub scene: Scene3D,
+
+    pub function: FunctionHolder,
+    // number of steps in θ; φ has twice as many
+    pub resolution: u32,
+    // have parameters changed that require mesh regen
+    pub needs_rebuild: bool,
+}
+
+impl SphereScene {
+    const COLOR: [f32; 3] = [0.55, 0.6, 0.45];
+    // where f is negative, so signed functions show their sign
+    const NEGATIVE_COLOR: [f32; 3] = [0.7, 0.35, 0.3];
+
+    pub const DEFAULT_RESOLUTION: u32 = 96;
+
+    pub fn new(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+        function: FunctionHolder,
+    ) -> Self {
+        let resolution = Self::DEFAULT_RESOLUTION;
+        let scene = build_scene(
+            device,
+            surface_config,
+            state,
+            vec![(Self::mesh(&function, resolution), Matrix::identity())],
+        );
+
+        Self {
+            scene,
+            function,
+            resolution,
+            needs_rebuild: false,
+        }
+    }
+
+    pub fn rebuild_scene(
+        &mut self,
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+    ) {
+        let mesh = Self::mesh(&self.function, self.resolution);
+        self.scene = build_scene(
+            device,
+            surface_config,
+            state,
+            vec![(mesh, Matrix::identity())],
+        );
+    }
+
+    fn mesh(function: &FunctionHolder, resolution: u32) -> MeshData {
+        sphere_mesh_data(function, resolution, Self::COLOR, Self::NEGATIVE_COLOR)
+    }
+}
+
+impl RenderScene for SphereScene {
+    fn scene(&self) -> &Scene3D {
+        &self.scene
+    }
+
+    fn update(&mut self, _queue: &Queue, _state: &RenderState) {
+        // no-op; the mesh is only rebuilt when parameters change
+    }
+}
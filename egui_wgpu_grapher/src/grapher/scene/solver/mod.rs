@@ -1,7 +1,8 @@
 //! A scene that runs a finite-difference compute pipeline to solve a wave
-//! equation and renders the result to a texture on a fixed 2D canvas.
+//! equation and renders the result to a texture on a fixed 2D canvas,
+//! or as a 3D surface displaced by the solution.
 
-use std::sync::OnceLock;
+pub mod surface;
 
 use bytemuck::{Pod, Zeroable};
 use egui_wgpu::wgpu::{
@@ -9,19 +10,32 @@ use egui_wgpu::wgpu::{
     Origin3d, Queue, RenderPipeline, SurfaceConfiguration, TexelCopyBufferLayout,
     TexelCopyTextureInfo, Texture, util::DeviceExt,
 };
+use image::{ImageBuffer, Luma, Rgba};
+use rand::Rng;
+use std::time::{Duration, Instant};
 
-use crate::grapher::pipeline::{
-    create_compute_pipeline, create_solver_pipeline, get_solver_compute_shader,
+use crate::grapher::{
+    pipeline::{
+        create_compute_pipeline_with_constants, create_solver_pipeline, get_solver_compute_shader,
+        texture::TextureFiltering,
+    },
+    scene::{CANVAS_QUAD_INDICES, CanvasScene},
 };
 
 // --------------------------
 // Solver scene uniform data.
 
+pub const DEFAULT_PROP_SPEED: f32 = 0.35;
+pub const DEFAULT_DAMPING: f32 = 1.0;
+
 #[repr(C)]
 #[derive(Default, Copy, Clone, Pod, Zeroable)]
 pub struct UniformData {
     pub timestep: u32,
     aspect_ratio: f32,
+    // wave parameters; see solver.wgsl
+    pub prop_speed: f32,
+    pub damping: f32,
 }
 
 pub struct Uniform {
@@ -37,6 +51,8 @@ impl Uniform {
     pub fn new(device: &Device, surface_config: &SurfaceConfiguration) -> Self {
         let data = UniformData {
             aspect_ratio: surface_config.height as f32 / surface_config.width as f32,
+            prop_speed: DEFAULT_PROP_SPEED,
+            damping: DEFAULT_DAMPING,
             ..Default::default()
         };
         let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -105,13 +121,14 @@ impl Uniform {
 // -----------------------------
 // Texture to hold compute data.
 
-#[allow(dead_code)]
 pub struct DataTexture {
     texture: Texture,
     pub compute_bind_group: BindGroup,
     pub compute_bind_group_layout: BindGroupLayout,
     pub render_bind_group: BindGroup,
     pub render_bind_group_layout: BindGroupLayout,
+    // sampling used by the render bind group
+    filtering: TextureFiltering,
 }
 
 const TEXTURE_WIDTH: u32 = 1024;
@@ -141,7 +158,7 @@ impl DataTexture {
             dimension: Some(wgpu::TextureViewDimension::D2),
             ..Default::default()
         });
-        init_texture(queue, &texture, TEXTURE_SIZE);
+        init_texture(queue, &texture, TEXTURE_SIZE, InitialCondition::default());
 
         let compute_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -166,11 +183,6 @@ impl DataTexture {
             }],
         });
 
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        });
         let render_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Solver Data Render Group Layout"),
@@ -193,49 +205,147 @@ impl DataTexture {
                     },
                 ],
             });
-        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        let render_bind_group = Self::create_render_bind_group(
+            device,
+            &view,
+            &render_bind_group_layout,
+            TextureFiltering::default(),
+        );
+
+        Self {
+            texture,
+            compute_bind_group,
+            compute_bind_group_layout,
+            render_bind_group,
+            render_bind_group_layout,
+            filtering: TextureFiltering::default(),
+        }
+    }
+
+    pub fn filtering(&self) -> TextureFiltering {
+        self.filtering
+    }
+
+    /// Recreate the render bind group with a sampler using the given filtering.
+    pub fn set_filtering(&mut self, device: &Device, filtering: TextureFiltering) {
+        let view = self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Solver Texture View"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            ..Default::default()
+        });
+        self.render_bind_group = Self::create_render_bind_group(
+            device,
+            &view,
+            &self.render_bind_group_layout,
+            filtering,
+        );
+        self.filtering = filtering;
+    }
+
+    fn create_render_bind_group(
+        device: &Device,
+        view: &wgpu::TextureView,
+        layout: &BindGroupLayout,
+        filtering: TextureFiltering,
+    ) -> BindGroup {
+        let sampler = filtering.create_sampler(device);
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Solver Data Render Bind Group"),
-            layout: &render_bind_group_layout,
+            layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&view),
+                    resource: wgpu::BindingResource::TextureView(view),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
                     resource: wgpu::BindingResource::Sampler(&sampler),
                 },
             ],
-        });
+        })
+    }
+}
 
-        Self {
-            texture,
-            compute_bind_group,
-            compute_bind_group_layout,
-            render_bind_group,
-            render_bind_group_layout,
+// ------------------------------
+// Initial conditions for solver.
+
+// resting value of the solution, midway in the displayed range
+const BASE_VALUE: f32 = 64.0;
+const AMPLITUDE: f32 = 128.0;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum InitialCondition {
+    #[default]
+    CentralSquare,
+    CentralPulse,
+    RandomNoise,
+    TwoSources,
+    PlaneWave,
+}
+
+impl InitialCondition {
+    pub const ALL: [InitialCondition; 5] = [
+        InitialCondition::CentralSquare,
+        InitialCondition::CentralPulse,
+        InitialCondition::RandomNoise,
+        InitialCondition::TwoSources,
+        InitialCondition::PlaneWave,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InitialCondition::CentralSquare => "Central square",
+            InitialCondition::CentralPulse => "Central pulse",
+            InitialCondition::RandomNoise => "Random noise",
+            InitialCondition::TwoSources => "Two sources",
+            InitialCondition::PlaneWave => "Plane wave",
         }
     }
-}
 
-fn init_texture(queue: &Queue, texture: &Texture, texture_size: Extent3d) {
-    static INIT_DATA: OnceLock<Vec<[f32; 4]>> = OnceLock::new();
-
-    let init_data = INIT_DATA.get_or_init(|| {
-        let mut buffer = vec![
-            [64.0f32, 64.0f32, 64.0f32, 0.0f32];
-            TEXTURE_HEIGHT as usize * TEXTURE_WIDTH as usize
-        ];
-        for i in TEXTURE_HEIGHT / 4..TEXTURE_HEIGHT * 3 / 4 {
-            for j in TEXTURE_WIDTH / 4..TEXTURE_WIDTH * 3 / 4 {
-                let coord = i as usize * TEXTURE_WIDTH as usize + j as usize;
-                buffer[coord][0] = 192.0;
-                buffer[coord][1] = 192.0;
-                buffer[coord][2] = 192.0;
+    /// Displacement at texel `(i, j)`, where `i` is the row.
+    fn value(&self, i: u32, j: u32, rng: &mut impl Rng) -> f32 {
+        // coordinates in [-1, 1] with the origin at the texture center
+        let y = 2.0 * i as f32 / TEXTURE_HEIGHT as f32 - 1.0;
+        let x = 2.0 * j as f32 / TEXTURE_WIDTH as f32 - 1.0;
+        let bump = |x: f32, y: f32, width: f32| (-(x * x + y * y) / (width * width)).exp();
+
+        match self {
+            InitialCondition::CentralSquare => {
+                if x.abs() < 0.5 && y.abs() < 0.5 {
+                    BASE_VALUE + AMPLITUDE
+                } else {
+                    BASE_VALUE
+                }
+            }
+            InitialCondition::CentralPulse => BASE_VALUE + AMPLITUDE * bump(x, y, 0.08),
+            InitialCondition::RandomNoise => BASE_VALUE + rng.random_range(-0.25..0.25) * AMPLITUDE,
+            InitialCondition::TwoSources => {
+                // nearby pulses whose waves interfere where they overlap
+                BASE_VALUE + AMPLITUDE * (bump(x, y - 0.2, 0.04) + bump(x, y + 0.2, 0.04))
             }
+            InitialCondition::PlaneWave => {
+                // a ridge across the domain; it splits into two fronts moving left and right
+                BASE_VALUE + AMPLITUDE * bump(x + 0.5, 0.0, 0.05)
+            }
+        }
+    }
+}
+
+fn init_texture(
+    queue: &Queue,
+    texture: &Texture,
+    texture_size: Extent3d,
+    initial_condition: InitialCondition,
+) {
+    let mut rng = rand::rng();
+    let mut init_data = Vec::with_capacity(TEXTURE_HEIGHT as usize * TEXTURE_WIDTH as usize);
+    for i in 0..TEXTURE_HEIGHT {
+        for j in 0..TEXTURE_WIDTH {
+            // same value for all three time levels, so the solution starts at rest
+            let value = initial_condition.value(i, j, &mut rng);
+            init_data.push([value, value, value, 0.0f32]);
         }
-        buffer
-    });
+    }
     queue.write_texture(
         TexelCopyTextureInfo {
             texture,
@@ -243,7 +353,7 @@ fn init_texture(queue: &Queue, texture: &Texture, texture_size: Extent3d) {
             origin: Origin3d::ZERO,
             aspect: wgpu::TextureAspect::All,
         },
-        bytemuck::cast_slice(init_data),
+        bytemuck::cast_slice(&init_data),
         TexelCopyBufferLayout {
             offset: 0,
             bytes_per_row: Some(TEXTURE_WIDTH * std::mem::size_of::<[f32; 4]>() as u32),
@@ -262,12 +372,49 @@ pub struct SolverScene {
     pub index_buffer: Buffer,
     pub uniform: Uniform,
     pub data_texture: DataTexture,
+    // width and height of the compute workgroups
+    workgroup_size: u32,
 }
 
-const CANVAS_QUAD_INDICES: [u32; 6] = [0, 1, 2, 0, 2, 3];
+/// Workgroup widths offered for the solver, each used for the height too.
+pub const WORKGROUP_SIZES: [u32; 3] = [8, 16, 32];
+pub const DEFAULT_WORKGROUP_SIZE: u32 = 8;
+
+/// Check that square workgroups of the given width are offered and fit
+/// within the device's limits.
+pub fn check_workgroup_size(device: &Device, size: u32) -> Result<(), String> {
+    if !WORKGROUP_SIZES.contains(&size) {
+        return Err(format!(
+            "Workgroup size {size} isn't one of {WORKGROUP_SIZES:?}."
+        ));
+    }
+    let limits = device.limits();
+    let max_size = limits
+        .max_compute_workgroup_size_x
+        .min(limits.max_compute_workgroup_size_y);
+    if size > max_size {
+        return Err(format!(
+            "Workgroup size {size} exceeds the device maximum of {max_size}."
+        ));
+    }
+    let max_invocations = limits.max_compute_invocations_per_workgroup;
+    if size * size > max_invocations {
+        return Err(format!(
+            "Workgroup size {size}x{size} exceeds the device maximum of \
+             {max_invocations} invocations per workgroup."
+        ));
+    }
+    Ok(())
+}
 
 impl SolverScene {
-    pub fn new(device: &Device, queue: &Queue, surface_config: &SurfaceConfiguration) -> Self {
+    /// The workgroup size should have passed `check_workgroup_size`.
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        workgroup_size: u32,
+    ) -> Self {
         let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Index Buffer"),
             contents: bytemuck::cast_slice(&CANVAS_QUAD_INDICES),
@@ -276,17 +423,10 @@ impl SolverScene {
         let uniform = Uniform::new(device, surface_config);
         let data_texture = DataTexture::new(device, queue);
 
-        let compute_pipeline = create_compute_pipeline(
-            device,
-            get_solver_compute_shader(),
-            &[
-                &data_texture.compute_bind_group_layout,
-                &uniform.compute_bind_group_layout,
-            ],
-        );
+        let compute_pipeline =
+            Self::create_compute_pipeline(device, &data_texture, &uniform, workgroup_size);
         let render_pipeline = create_solver_pipeline(
             device,
-            surface_config,
             &[
                 &uniform.render_bind_group_layout,
                 &data_texture.render_bind_group_layout,
@@ -299,7 +439,56 @@ impl SolverScene {
             index_buffer,
             uniform,
             data_texture,
+            workgroup_size,
+        }
+    }
+
+    fn create_compute_pipeline(
+        device: &Device,
+        data_texture: &DataTexture,
+        uniform: &Uniform,
+        workgroup_size: u32,
+    ) -> ComputePipeline {
+        create_compute_pipeline_with_constants(
+            device,
+            get_solver_compute_shader(),
+            &[
+                &data_texture.compute_bind_group_layout,
+                &uniform.compute_bind_group_layout,
+            ],
+            &[("WORKGROUP_SIZE", workgroup_size as f64)],
+        )
+    }
+
+    pub fn workgroup_size(&self) -> u32 {
+        self.workgroup_size
+    }
+
+    /// Rebuild the compute pipeline for a new workgroup size, if the
+    /// device supports it; otherwise the current size is kept.
+    pub fn set_workgroup_size(&mut self, device: &Device, size: u32) -> Result<(), String> {
+        check_workgroup_size(device, size)?;
+        self.compute_pipeline =
+            Self::create_compute_pipeline(device, &self.data_texture, &self.uniform, size);
+        self.workgroup_size = size;
+        Ok(())
+    }
+
+    /// Run `steps` timesteps and wait for the GPU to finish them, returning
+    /// the elapsed time. The simulation advances by those steps.
+    pub fn benchmark(&mut self, device: &Device, queue: &Queue, steps: u32) -> Duration {
+        // finish any earlier work first, so it isn't counted
+        let _ = device.poll(wgpu::PollType::wait_indefinitely());
+
+        let start = Instant::now();
+        for _ in 0..steps {
+            let mut encoder = device.create_command_encoder(&Default::default());
+            self.increment_timestep(queue);
+            self.solver_timestep(&mut encoder);
+            queue.submit(Some(encoder.finish()));
         }
+        let _ = device.poll(wgpu::PollType::wait_indefinitely());
+        start.elapsed()
     }
 
     pub fn timestep(&self) -> u32 {
@@ -315,6 +504,33 @@ impl SolverScene {
         );
     }
 
+    /// Restart the simulation from the given initial condition at timestep zero.
+    pub fn reset(&mut self, queue: &Queue, initial_condition: InitialCondition) {
+        self.uniform.data.timestep = 0;
+        queue.write_buffer(
+            &self.uniform.buffer,
+            0,
+            bytemuck::bytes_of(&self.uniform.data),
+        );
+        init_texture(
+            queue,
+            &self.data_texture.texture,
+            TEXTURE_SIZE,
+            initial_condition,
+        );
+    }
+
+    /// Set the wave speed and damping used by the following timesteps.
+    pub fn set_wave_parameters(&mut self, queue: &Queue, prop_speed: f32, damping: f32) {
+        self.uniform.data.prop_speed = prop_speed;
+        self.uniform.data.damping = damping;
+        queue.write_buffer(
+            &self.uniform.buffer,
+            0,
+            bytemuck::bytes_of(&self.uniform.data),
+        );
+    }
+
     pub fn update_aspect_ratio(&mut self, queue: &Queue, new_ratio: f32) {
         self.uniform.data.aspect_ratio = new_ratio;
         queue.write_buffer(
@@ -333,8 +549,146 @@ impl SolverScene {
         compute_pass.set_bind_group(0, &self.data_texture.compute_bind_group, &[]);
         compute_pass.set_bind_group(1, &self.uniform.compute_bind_group, &[]);
 
-        let workgroup_count_x = TEXTURE_WIDTH.div_ceil(8);
-        let workgroup_count_y = TEXTURE_HEIGHT.div_ceil(8);
+        let workgroup_count_x = TEXTURE_WIDTH.div_ceil(self.workgroup_size);
+        let workgroup_count_y = TEXTURE_HEIGHT.div_ceil(self.workgroup_size);
         compute_pass.dispatch_workgroups(workgroup_count_x, workgroup_count_y, 1);
     }
 }
+
+// ----------------------
+// Exporting solver data.
+
+// same scale the solver shader uses to map values to brightness
+const TEXTURE_MAX_VAL: f32 = 255.0;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum FrameFormat {
+    // all four channels as 32-bit floats
+    #[default]
+    Exr,
+    // current time level as grayscale, scaled as it is on screen
+    Png,
+}
+
+impl FrameFormat {
+    pub const ALL: [FrameFormat; 2] = [FrameFormat::Exr, FrameFormat::Png];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FrameFormat::Exr => "EXR (float)",
+            FrameFormat::Png => "PNG",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FrameFormat::Exr => "exr",
+            FrameFormat::Png => "png",
+        }
+    }
+}
+
+impl SolverScene {
+    /// Copy the data texture back to the CPU, one texel per entry in row order.
+    /// Blocks until the GPU has finished the copy.
+    pub fn read_data(&self, device: &Device, queue: &Queue) -> Vec<[f32; 4]> {
+        const TEXEL_SIZE: u32 = std::mem::size_of::<[f32; 4]>() as u32;
+
+        let unpadded_bytes_per_row = TEXTURE_WIDTH * TEXEL_SIZE;
+        let padded_bytes_per_row =
+            unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Solver Staging Buffer"),
+            size: (padded_bytes_per_row * TEXTURE_HEIGHT) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_buffer(
+            TexelCopyTextureInfo {
+                texture: &self.data_texture.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging_buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(TEXTURE_HEIGHT),
+                },
+            },
+            TEXTURE_SIZE,
+        );
+        queue.submit(Some(encoder.finish()));
+
+        staging_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+
+        let mapped_data = staging_buffer.slice(..).get_mapped_range();
+        let mut data = Vec::with_capacity((TEXTURE_WIDTH * TEXTURE_HEIGHT) as usize);
+        // drop the row padding
+        for row in mapped_data.chunks_exact(padded_bytes_per_row as usize) {
+            let texels: &[[f32; 4]] = bytemuck::cast_slice(&row[..unpadded_bytes_per_row as usize]);
+            data.extend_from_slice(texels);
+        }
+        drop(mapped_data);
+        staging_buffer.unmap();
+
+        data
+    }
+
+    /// Write the current solver state to an image file.
+    pub fn save_frame(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        filename: &str,
+        format: FrameFormat,
+    ) -> Result<(), String> {
+        let data = self.read_data(device, queue);
+
+        let result = match format {
+            FrameFormat::Exr => {
+                let values: Vec<f32> = bytemuck::cast_slice(&data).to_vec();
+                ImageBuffer::<Rgba<f32>, Vec<f32>>::from_vec(TEXTURE_WIDTH, TEXTURE_HEIGHT, values)
+                    .unwrap()
+                    .save_with_format(filename, image::ImageFormat::OpenExr)
+            }
+            FrameFormat::Png => {
+                // channel holding the current time level, as in solver_shader.wgsl
+                let t = (self.timestep() % 3) as usize;
+                let values: Vec<u8> = data
+                    .iter()
+                    .map(|v| ((v[t] / TEXTURE_MAX_VAL).clamp(0.0, 1.0) * 255.0).round() as u8)
+                    .collect();
+                ImageBuffer::<Luma<u8>, Vec<u8>>::from_vec(TEXTURE_WIDTH, TEXTURE_HEIGHT, values)
+                    .unwrap()
+                    .save_with_format(filename, image::ImageFormat::Png)
+            }
+        };
+        result.map_err(|err| format!("Failed to save {filename}: {err}"))
+    }
+}
+
+impl CanvasScene for SolverScene {
+    fn render_pipeline(&self) -> &RenderPipeline {
+        &self.render_pipeline
+    }
+
+    fn render_bind_groups(&self) -> [&BindGroup; 2] {
+        [
+            &self.uniform.render_bind_group,
+            &self.data_texture.render_bind_group,
+        ]
+    }
+
+    fn index_buffer(&self) -> &Buffer {
+        &self.index_buffer
+    }
+}
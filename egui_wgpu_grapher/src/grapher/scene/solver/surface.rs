@@ -0,0 +1,185 @@
+//! A 3D surface displaced by the solver's data texture, so the GPU-computed
+//! wave can be viewed in relief without reading the solution back.
+
+use bytemuck::{Pod, Zeroable};
+use egui_wgpu::wgpu::{self, BindGroupLayout, Buffer, Device, Queue, util::DeviceExt};
+use std::sync::OnceLock;
+
+use super::SolverScene;
+use crate::grapher::{
+    math::{
+        FunctionHolder,
+        graph::{Domain, Normals, SquareTesselation},
+    },
+    matrix::Matrix,
+    pipeline::{self, texture::TextureData},
+    render::RenderState,
+    scene::{
+        Scene3D,
+        textured::{self, TexturedMeshData},
+    },
+};
+
+// number of squares along each side of the surface grid
+const SUBDIVISIONS: u32 = 255;
+const WIDTH: f64 = 4.0;
+const COLOR: [f32; 3] = [0.3, 0.55, 0.9];
+
+pub const DEFAULT_HEIGHT_SCALE: f32 = 1.0;
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Pod, Zeroable)]
+struct SurfaceUniformData {
+    height_scale: f32,
+    texel_size: f32,
+    grid_spacing: f32,
+    _padding: f32,
+}
+
+pub struct WaveSurface {
+    pub scene: Scene3D,
+    uniform: SurfaceUniformData,
+    uniform_buffer: Buffer,
+}
+
+impl WaveSurface {
+    fn bind_group_layout(device: &Device) -> &'static BindGroupLayout {
+        static BGL: OnceLock<BindGroupLayout> = OnceLock::new();
+        BGL.get_or_init(|| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Wave Surface Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            })
+        })
+    }
+
+    pub fn new(device: &Device, state: &RenderState, solver: &SolverScene) -> Self {
+        let uniform = SurfaceUniformData {
+            height_scale: DEFAULT_HEIGHT_SCALE,
+            texel_size: 1.0 / SUBDIVISIONS as f32,
+            grid_spacing: (WIDTH / SUBDIVISIONS as f64) as f32,
+            ..Default::default()
+        };
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Wave Surface Uniform Buffer"),
+            contents: bytemuck::bytes_of(&uniform),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let texture = &solver.data_texture.texture;
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Wave Surface Bind Group"),
+            layout: Self::bind_group_layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: solver.uniform.buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        // Flat grid; heights come from the data texture in the vertex shader.
+        let grid = SquareTesselation::generate(
+            SUBDIVISIONS,
+            &Domain::centered(WIDTH),
+            &FunctionHolder::from(|_, _| 0.0),
+        );
+        let mut mesh_data = grid.mesh_data(COLOR, Normals::Flat);
+        for vertex in &mut mesh_data.vertices {
+            vertex.tex_coords = [
+                (vertex.position[0] as f64 / WIDTH + 0.5) as f32,
+                (vertex.position[2] as f64 / WIDTH + 0.5) as f32,
+            ];
+        }
+        let mesh = TexturedMeshData {
+            vertices: mesh_data.vertices,
+            indices: mesh_data.indices,
+            texture: TextureData {
+                bind_group,
+                texture: texture.clone(),
+            },
+        };
+
+        let scene = textured::build_scene_with_shader(
+            device,
+            state,
+            vec![(mesh, Matrix::identity())],
+            pipeline::get_solver_surface_shader(),
+            Self::bind_group_layout(device),
+            wgpu::PolygonMode::Fill,
+        );
+
+        Self {
+            scene,
+            uniform,
+            uniform_buffer,
+        }
+    }
+
+    pub fn height_scale(&self) -> f32 {
+        self.uniform.height_scale
+    }
+
+    pub fn set_height_scale(&mut self, queue: &Queue, height_scale: f32) {
+        if self.uniform.height_scale == height_scale {
+            return;
+        }
+        self.uniform.height_scale = height_scale;
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&self.uniform));
+    }
+}
@@ -0,0 +1,190 @@
+//! GPU convolution filters applied to image viewer textures.
+
+use crate::grapher::pipeline::{
+    create_compute_pipeline, get_image_filter_compute_shader, texture::TextureData,
+};
+
+use bytemuck::{Pod, Zeroable};
+use egui_wgpu::wgpu::{
+    self, BindGroupLayout, Buffer, ComputePipeline, Device, Queue, Texture, util::DeviceExt,
+};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum ImageFilter {
+    #[default]
+    None,
+    BoxBlur,
+    GaussianBlur,
+    EdgeDetect,
+}
+
+impl ImageFilter {
+    pub const ALL: [ImageFilter; 4] = [
+        ImageFilter::None,
+        ImageFilter::BoxBlur,
+        ImageFilter::GaussianBlur,
+        ImageFilter::EdgeDetect,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImageFilter::None => "None",
+            ImageFilter::BoxBlur => "Box blur",
+            ImageFilter::GaussianBlur => "Gaussian blur",
+            ImageFilter::EdgeDetect => "Edge detection",
+        }
+    }
+
+    /// Whether the kernel radius affects this filter.
+    pub fn uses_radius(&self) -> bool {
+        matches!(self, ImageFilter::BoxBlur | ImageFilter::GaussianBlur)
+    }
+
+    // matches the switch in image_filter.wgsl
+    fn shader_index(&self) -> u32 {
+        match self {
+            ImageFilter::None => 0,
+            ImageFilter::BoxBlur => 1,
+            ImageFilter::GaussianBlur => 2,
+            ImageFilter::EdgeDetect => 3,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Pod, Zeroable)]
+struct FilterUniform {
+    kind: u32,
+    radius: u32,
+}
+
+pub struct ImageFilterPipeline {
+    pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+    buffer: Buffer,
+}
+
+impl ImageFilterPipeline {
+    pub const DEFAULT_RADIUS: u32 = 3;
+    pub const MAX_RADIUS: u32 = 16;
+
+    pub fn new(device: &Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Image Filter Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba16Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline = create_compute_pipeline(
+            device,
+            get_image_filter_compute_shader(),
+            &[&bind_group_layout],
+        );
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Image Filter Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[FilterUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            buffer,
+        }
+    }
+
+    /// Run the filter over `source` and return a new texture holding the result.
+    /// The source texture is left untouched so the filter can be turned off again.
+    pub fn apply(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        source: &Texture,
+        filter: ImageFilter,
+        radius: u32,
+    ) -> TextureData {
+        let uniform = FilterUniform {
+            kind: filter.shader_index(),
+            radius: radius.min(Self::MAX_RADIUS),
+        };
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+
+        let size = source.size();
+        let filtered = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Filtered Image Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba16Float,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let source_view = source.create_view(&wgpu::TextureViewDescriptor::default());
+        let filtered_view = filtered.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Image Filter Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&filtered_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Image Filter Encoder"),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(size.width.div_ceil(8), size.height.div_ceil(8), 1);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        TextureData::from_texture(filtered, device)
+    }
+}
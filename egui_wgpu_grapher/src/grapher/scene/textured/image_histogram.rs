@@ -0,0 +1,104 @@
+//! Per-channel histograms and statistics of a loaded image, computed once
+//! when the image is loaded.
+
+use image::{ImageBuffer, Rgba};
+
+pub const BINS: usize = 256;
+
+/// Channels with a histogram, in display order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HistogramChannel {
+    Red,
+    Green,
+    Blue,
+    Luminance,
+}
+
+impl HistogramChannel {
+    pub const ALL: [HistogramChannel; 4] = [
+        HistogramChannel::Red,
+        HistogramChannel::Green,
+        HistogramChannel::Blue,
+        HistogramChannel::Luminance,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HistogramChannel::Red => "Red",
+            HistogramChannel::Green => "Green",
+            HistogramChannel::Blue => "Blue",
+            HistogramChannel::Luminance => "Luminance",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Minimum, mean, and maximum of a channel's values, in 0-255.
+#[derive(Clone, Copy, Debug)]
+pub struct ChannelStats {
+    pub min: u8,
+    pub mean: f32,
+    pub max: u8,
+}
+
+pub struct ImageHistogram {
+    // pixel counts per value, one array per channel
+    counts: [[u32; BINS]; 4],
+    stats: [ChannelStats; 4],
+}
+
+impl ImageHistogram {
+    pub fn from_image(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Self {
+        let mut counts = [[0u32; BINS]; 4];
+        for pixel in image.pixels() {
+            let [r, g, b, _] = pixel.0;
+            counts[0][r as usize] += 1;
+            counts[1][g as usize] += 1;
+            counts[2][b as usize] += 1;
+            counts[3][luminance(r, g, b) as usize] += 1;
+        }
+
+        let stats = counts.map(|channel| channel_stats(&channel));
+        Self { counts, stats }
+    }
+
+    pub fn counts(&self, channel: HistogramChannel) -> &[u32; BINS] {
+        &self.counts[channel.index()]
+    }
+
+    pub fn stats(&self, channel: HistogramChannel) -> ChannelStats {
+        self.stats[channel.index()]
+    }
+}
+
+// Rec. 709 weights, applied to the stored (gamma-encoded) values.
+fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32).round() as u8
+}
+
+fn channel_stats(counts: &[u32; BINS]) -> ChannelStats {
+    let total: u64 = counts.iter().map(|&count| count as u64).sum();
+    if total == 0 {
+        return ChannelStats {
+            min: 0,
+            mean: 0.0,
+            max: 0,
+        };
+    }
+    let min = counts.iter().position(|&count| count > 0).unwrap_or(0);
+    let max = counts.iter().rposition(|&count| count > 0).unwrap_or(0);
+    let sum: u64 = counts
+        .iter()
+        .enumerate()
+        .map(|(value, &count)| value as u64 * count as u64)
+        .sum();
+
+    ChannelStats {
+        min: min as u8,
+        mean: (sum as f64 / total as f64) as f32,
+        max: max as u8,
+    }
+}
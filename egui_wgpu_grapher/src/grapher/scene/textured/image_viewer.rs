@@ -1,22 +1,27 @@
 //! Build a scene that renders an image as a texture on a rectangular canvas.
 
-use super::{SQUARE_INDICES, SQUARE_VERTICES_VERTICAL, TexturedMeshData, build_scene};
+use super::{
+    SQUARE_INDICES, SQUARE_VERTICES_VERTICAL, TexturedMeshData, TexturedMeshRenderData,
+    build_scene,
+    image_filter::{ImageFilter, ImageFilterPipeline},
+    image_histogram::ImageHistogram,
+    textured_pipeline,
+};
 use crate::grapher::{
     camera::ProjectionType,
     matrix::Matrix,
-    pipeline::texture::{Image, TextureData},
+    pipeline::texture::{Image, TextureData, TextureFiltering},
     render::RenderState,
     scene::{RenderScene, Scene3D},
 };
 
-use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
+use egui_wgpu::wgpu::{Device, Queue};
 
 /// Render the scene onto both sides of a square canvas.
 /// Updates camera state to one suited for viewing 2D image.
 pub fn image_viewer_scene(
     device: &Device,
     queue: &Queue,
-    surface_config: &SurfaceConfiguration,
     state: &mut RenderState,
     image_path: &str,
 ) -> Option<ImageViewerScene> {
@@ -28,27 +33,26 @@ pub fn image_viewer_scene(
     state.render_preferences.set_use_texture(true);
     state.render_preferences.update_uniform(queue);
 
-    // update camera settings
-    state.camera_state.set_from_z(52.0);
-    state.camera_state.camera.projection_type = ProjectionType::Orthographic;
-    state.camera_state.update_uniform(queue);
-
     // create textured canvas
-    let texture_data_front = TextureData::from_image(&image, device, queue);
-
-    let mut mesh_data_front = TexturedMeshData {
-        vertices: SQUARE_VERTICES_VERTICAL.clone(),
-        indices: Vec::from(SQUARE_INDICES),
-        texture: texture_data_front,
-    };
-    update_canvas_aspect_ratio(&mut mesh_data_front, image.dimensions.1, image.dimensions.0);
-
     let meshes: Vec<(TexturedMeshData, Matrix)> =
-        vec![(mesh_data_front, Matrix::translation(&[0.0, 0.0, 0.5]))];
+        vec![(canvas_mesh_data(&image, device, queue), Matrix::identity())];
 
     let mut image_scene = ImageViewerScene {
-        scene: build_scene(device, surface_config, state, meshes),
+        scene: build_scene(device, state, meshes),
+        image_paths: vec![image_path.into()],
+        histograms: vec![ImageHistogram::from_image(&image.image)],
+        filter: ImageFilter::None,
+        filter_radius: ImageFilterPipeline::DEFAULT_RADIUS,
+        needs_filter: false,
+        filter_pipeline: ImageFilterPipeline::new(device),
+        unfiltered_textures: vec![None],
+        texture_filtering: TextureFiltering::default(),
+        needs_resample: false,
+        needs_rebuild: false,
     };
+    // update camera settings
+    image_scene.reset_camera(queue, state);
+
     // update light position
     image_scene.scene.light.set_position([0.0, 0.0, 3.0]);
     image_scene.scene.light.update_uniform(queue);
@@ -56,6 +60,19 @@ pub fn image_viewer_scene(
     Some(image_scene)
 }
 
+fn canvas_mesh_data(image: &Image, device: &Device, queue: &Queue) -> TexturedMeshData {
+    let texture_data = TextureData::from_image(image, device, queue);
+
+    let mut mesh_data = TexturedMeshData {
+        vertices: SQUARE_VERTICES_VERTICAL.clone(),
+        indices: Vec::from(SQUARE_INDICES),
+        texture: texture_data,
+    };
+    update_canvas_aspect_ratio(&mut mesh_data, image.dimensions.1, image.dimensions.0);
+
+    mesh_data
+}
+
 fn update_canvas_aspect_ratio(mesh_data: &mut TexturedMeshData, height: u32, width: u32) {
     if width < height {
         let mult = width as f32 / height as f32;
@@ -72,6 +89,154 @@ fn update_canvas_aspect_ratio(mesh_data: &mut TexturedMeshData, height: u32, wid
 
 pub struct ImageViewerScene {
     pub scene: Scene3D,
+    // one path per canvas, in grid order
+    pub image_paths: Vec<String>,
+    // computed on load, in the same order as the paths
+    pub histograms: Vec<ImageHistogram>,
+
+    pub filter: ImageFilter,
+    pub filter_radius: u32,
+    // set by the UI when filter settings change
+    pub needs_filter: bool,
+    filter_pipeline: ImageFilterPipeline,
+    // original texture for each canvas while a filter is shown
+    unfiltered_textures: Vec<Option<TextureData>>,
+
+    pub texture_filtering: TextureFiltering,
+    // set by the UI when the texture filtering changes
+    pub needs_resample: bool,
+
+    // set by the UI when the polygon mode changes
+    pub needs_rebuild: bool,
+}
+
+impl ImageViewerScene {
+    // space between neighboring canvases
+    const GRID_GAP: f32 = 0.05;
+
+    /// Load another image onto a new canvas and add it to the grid.
+    pub fn add_image(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        state: &mut RenderState,
+        image_path: &str,
+    ) -> Result<(), String> {
+        let image = Image::from_file(image_path)?;
+        let mesh_data = canvas_mesh_data(&image, device, queue);
+
+        self.scene
+            .textured_meshes
+            .push(TexturedMeshRenderData::from_mesh_data(
+                device,
+                mesh_data,
+                Matrix::identity(),
+            ));
+        self.image_paths.push(image_path.into());
+        self.histograms
+            .push(ImageHistogram::from_image(&image.image));
+        self.unfiltered_textures.push(None);
+        self.layout_grid(queue, state);
+
+        if self.filter != ImageFilter::None {
+            self.apply_filter(device, queue);
+        } else {
+            self.apply_texture_filtering(device);
+        }
+
+        Ok(())
+    }
+
+    /// Recreate the pipeline so it picks up the current polygon mode.
+    pub fn rebuild_pipeline(&mut self, device: &Device, state: &RenderState) {
+        let pipeline = textured_pipeline(device, state, &self.scene.light);
+        self.scene.textured_pipeline = Some(pipeline);
+    }
+
+    /// Show each canvas with the current filter, or restore the original
+    /// images when the filter is off. Filters always run on the originals.
+    pub fn apply_filter(&mut self, device: &Device, queue: &Queue) {
+        let canvases = self
+            .scene
+            .textured_meshes
+            .iter_mut()
+            .zip(self.unfiltered_textures.iter_mut());
+
+        for (mesh, unfiltered) in canvases {
+            if let Some(original) = unfiltered.take() {
+                mesh.texture = original;
+            }
+            if self.filter == ImageFilter::None {
+                continue;
+            }
+            let filtered = self.filter_pipeline.apply(
+                device,
+                queue,
+                &mesh.texture.texture,
+                self.filter,
+                self.filter_radius,
+            );
+            *unfiltered = Some(std::mem::replace(&mut mesh.texture, filtered));
+        }
+        self.needs_filter = false;
+        self.apply_texture_filtering(device);
+    }
+
+    /// Sample every canvas texture, and the originals kept while a filter
+    /// is shown, with the current texture filtering.
+    pub fn apply_texture_filtering(&mut self, device: &Device) {
+        let filtering = self.texture_filtering;
+        for mesh in self.scene.textured_meshes.iter_mut() {
+            mesh.texture.set_filtering(device, filtering);
+        }
+        for original in self.unfiltered_textures.iter_mut().flatten() {
+            original.set_filtering(device, filtering);
+        }
+        self.needs_resample = false;
+    }
+
+    /// Frame the image grid with a front-facing orthographic camera.
+    pub fn reset_camera(&mut self, queue: &Queue, state: &mut RenderState) {
+        state.camera_state.set_from_z(52.0);
+        // a 2D view shouldn't spin
+        state.camera_state.controller.turntable = false;
+        state.camera_state.camera.projection_type = ProjectionType::Orthographic;
+        self.layout_grid(queue, state);
+        state.camera_state.update_uniform(queue);
+    }
+
+    /// Place canvases on a near-square grid, filling rows left to right
+    /// from the top, and zoom the camera so the whole grid is visible.
+    pub fn layout_grid(&mut self, queue: &Queue, state: &mut RenderState) {
+        let count = self.scene.textured_meshes.len().max(1);
+        let cols = (count as f32).sqrt().ceil() as usize;
+        let rows = count.div_ceil(cols);
+
+        // canvases are scaled to fit in a unit square
+        let cell = 1.0 + Self::GRID_GAP;
+
+        for (i, mesh) in self.scene.textured_meshes.iter_mut().enumerate() {
+            let col = (i % cols) as f32;
+            let row = (i / cols) as f32;
+            let x = (col - (cols - 1) as f32 / 2.0) * cell;
+            let y = ((rows - 1) as f32 / 2.0 - row) * cell;
+
+            mesh.matrix.uniform = Matrix::translation(&[x, y, 0.5]);
+            queue.write_buffer(
+                &mesh.matrix.buffer,
+                0,
+                bytemuck::cast_slice(&[mesh.matrix.uniform]),
+            );
+        }
+
+        let grid_width = cols as f32 * cell - Self::GRID_GAP;
+        let grid_height = rows as f32 * cell - Self::GRID_GAP;
+
+        let camera = &mut state.camera_state.camera;
+        camera.ortho_scale = (camera.aspect / grid_width).min(1.0 / grid_height);
+        camera.translation_x = 0.0;
+        camera.translation_y = 0.0;
+    }
 }
 
 impl RenderScene for ImageViewerScene {
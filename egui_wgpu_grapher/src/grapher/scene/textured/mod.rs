@@ -1,19 +1,22 @@
 //! Code for building textured mesh scenes.
 
+pub mod image_filter;
+pub mod image_histogram;
 pub mod image_viewer;
 pub mod model;
 pub mod pde_2d_cpu;
 
-use super::{GpuVertex, Scene3D};
+use super::{Bufferable, GpuVertex, Scene3D};
 use crate::grapher::{
     matrix::{self, Matrix, MatrixUniform},
     pipeline::{self, light, texture::TextureData},
     render::RenderState,
 };
 
+use cgmath::{InnerSpace, Vector3, Zero};
 use egui_wgpu::wgpu::{
     self, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
-    BindGroupLayoutDescriptor, Device, SurfaceConfiguration, util::DeviceExt,
+    BindGroupLayoutDescriptor, Device, Queue, RenderPipeline, util::DeviceExt,
 };
 use std::sync::{LazyLock, OnceLock};
 
@@ -88,14 +91,162 @@ impl TexturedMeshRenderData {
     }
 }
 
+// ------------------------------------------------
+// Tangent space from positions and texture coords.
+
+/// Per-vertex tangents for normal mapping: each vertex averages the
+/// tangents of its adjacent triangles, made orthogonal to its normal.
+/// Vertices whose triangles all have degenerate texture coordinates get
+/// an arbitrary tangent orthogonal to the normal.
+pub fn compute_tangents(vertices: &mut [GpuVertex], indices: &[u32]) {
+    let mut tangents = vec![Vector3::zero(); vertices.len()];
+    let mut bitangents = vec![Vector3::zero(); vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [v0, v1, v2] = [0, 1, 2].map(|i| &vertices[triangle[i] as usize]);
+        let edge_1 = Vector3::from(v1.position) - Vector3::from(v0.position);
+        let edge_2 = Vector3::from(v2.position) - Vector3::from(v0.position);
+        let [du_1, dv_1] = [0, 1].map(|i| v1.tex_coords[i] - v0.tex_coords[i]);
+        let [du_2, dv_2] = [0, 1].map(|i| v2.tex_coords[i] - v0.tex_coords[i]);
+
+        let determinant = du_1 * dv_2 - du_2 * dv_1;
+        if determinant.abs() < f32::EPSILON {
+            continue;
+        }
+        // Unnormalized, so larger triangles contribute more.
+        let tangent = (edge_1 * dv_2 - edge_2 * dv_1) / determinant;
+        let bitangent = (edge_2 * du_1 - edge_1 * du_2) / determinant;
+        for &i in triangle {
+            tangents[i as usize] += tangent;
+            bitangents[i as usize] += bitangent;
+        }
+    }
+
+    for ((vertex, tangent), bitangent) in vertices.iter_mut().zip(tangents).zip(bitangents) {
+        let mut normal = Vector3::from(vertex.normal);
+        if normal.magnitude2() > 0.0 {
+            normal = normal.normalize();
+        }
+        let tangent = tangent - normal * normal.dot(tangent);
+        vertex.tangent = if tangent.magnitude2() > f32::EPSILON {
+            let handedness = if normal.cross(tangent).dot(bitangent) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            tangent.normalize().extend(handedness).into()
+        } else {
+            orthogonal_unit(normal).extend(1.0).into()
+        };
+    }
+}
+
+// Unit vector orthogonal to `normal`, from the axis least aligned with it.
+fn orthogonal_unit(normal: Vector3<f32>) -> Vector3<f32> {
+    let axis = if normal.x.abs() < 0.9 {
+        Vector3::unit_x()
+    } else {
+        Vector3::unit_y()
+    };
+    let tangent = axis - normal * normal.dot(axis);
+    if tangent.magnitude2() > f32::EPSILON {
+        tangent.normalize()
+    } else {
+        Vector3::unit_x()
+    }
+}
+
+// ---------------------------------------------------------
+// Render data for a mesh drawn once per instance transform.
+
+pub struct InstancedMeshRenderData {
+    pub mesh: TexturedMeshRenderData,
+
+    // per-instance model matrices, applied before the mesh matrix
+    pub instance_buffer: wgpu::Buffer,
+    pub num_instances: u32,
+    // hidden meshes are skipped when rendering
+    pub visible: bool,
+}
+
+impl InstancedMeshRenderData {
+    fn from_mesh_data(
+        device: &Device,
+        mesh_data: TexturedMeshData,
+        matrix_uniform: Matrix,
+        instances: &[Matrix],
+    ) -> Self {
+        Self {
+            mesh: TexturedMeshRenderData::from_mesh_data(device, mesh_data, matrix_uniform),
+            instance_buffer: Self::make_instance_buffer(device, instances),
+            num_instances: instances.len() as u32,
+            visible: true,
+        }
+    }
+
+    fn make_instance_buffer(device: &Device, instances: &[Matrix]) -> wgpu::Buffer {
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("instance buffer"),
+            contents: bytemuck::cast_slice(instances),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        })
+    }
+
+    /// Replace the instance transforms, reallocating the buffer only if it must grow.
+    pub fn set_instances(&mut self, device: &Device, queue: &Queue, instances: &[Matrix]) {
+        let size = std::mem::size_of_val(instances) as wgpu::BufferAddress;
+        if size > self.instance_buffer.size() {
+            self.instance_buffer = Self::make_instance_buffer(device, instances);
+        } else {
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(instances));
+        }
+        self.num_instances = instances.len() as u32;
+    }
+}
+
+// Instance matrices are passed as four column vectors.
+impl Bufferable for Matrix {
+    fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+            5 => Float32x4,
+            6 => Float32x4,
+            7 => Float32x4,
+            8 => Float32x4
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Matrix>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
 // ---------------------------------------
 // Build scene from (mesh, matrix) vector.
 
 pub fn build_scene(
     device: &Device,
-    surface_config: &SurfaceConfiguration,
     state: &RenderState,
     mesh_data: Vec<(TexturedMeshData, Matrix)>,
+) -> Scene3D {
+    build_scene_with_shader(
+        device,
+        state,
+        mesh_data,
+        pipeline::get_textured_shader(),
+        TextureData::bind_group_layout(device),
+        state.render_preferences.polygon_mode,
+    )
+}
+
+/// Like `build_scene`, but with a custom shader whose group 3 bindings,
+/// taken from each mesh's texture bind group, have the given layout.
+pub fn build_scene_with_shader(
+    device: &Device,
+    state: &RenderState,
+    mesh_data: Vec<(TexturedMeshData, Matrix)>,
+    shader: wgpu::ShaderSource<'static>,
+    texture_bind_group_layout: &BindGroupLayout,
+    polygon_mode: wgpu::PolygonMode,
 ) -> Scene3D {
     let textured_meshes: Vec<TexturedMeshRenderData> = mesh_data
         .into_iter()
@@ -104,31 +255,121 @@ pub fn build_scene(
 
     let light = light::LightState::create(device);
 
-    let pipeline = pipeline::create_render_pipeline::<GpuVertex>(
+    let pipeline = pipeline::create_cutout_render_pipeline::<GpuVertex>(
         device,
-        surface_config,
-        pipeline::get_textured_shader(),
+        shader,
         &[
             &state.bind_group_layout,
             TexturedMeshRenderData::matrix_bgl(device),
             &light.bind_group_layout,
-            TextureData::bind_group_layout(device),
+            texture_bind_group_layout,
         ],
-        wgpu::PolygonMode::Fill,
+        polygon_mode,
+        None,
+        state.render_preferences.alpha_to_coverage(),
     );
 
     Scene3D {
         pipeline: None,
         textured_pipeline: Some(pipeline),
+        instanced_pipeline: None,
+        point_pipeline: None,
+        line_pipeline: None,
+        depth_prepass_pipeline: None,
         //
         meshes: vec![],
         textured_meshes,
+        instanced_meshes: vec![],
+        points: vec![],
+        lines: vec![],
         //
         light,
         shadow: None,
     }
 }
 
+// ------------------------------------------------------------
+// Build scene drawing each mesh once for every instance matrix.
+
+pub fn build_instanced_scene(
+    device: &Device,
+    state: &RenderState,
+    mesh_data: Vec<(TexturedMeshData, Matrix)>,
+    instances: &[Matrix],
+) -> Scene3D {
+    let instanced_meshes: Vec<InstancedMeshRenderData> = mesh_data
+        .into_iter()
+        .map(|(mesh, matrix)| {
+            InstancedMeshRenderData::from_mesh_data(device, mesh, matrix, instances)
+        })
+        .collect();
+
+    let light = light::LightState::create(device);
+    let pipeline = instanced_pipeline(device, state, &light);
+
+    Scene3D {
+        pipeline: None,
+        textured_pipeline: None,
+        instanced_pipeline: Some(pipeline),
+        point_pipeline: None,
+        line_pipeline: None,
+        depth_prepass_pipeline: None,
+        //
+        meshes: vec![],
+        textured_meshes: vec![],
+        instanced_meshes,
+        points: vec![],
+        lines: vec![],
+        //
+        light,
+        shadow: None,
+    }
+}
+
+/// Pipeline for textured scenes with the default textured shader, using
+/// the polygon mode and alpha cutout from the render preferences.
+pub fn textured_pipeline(
+    device: &Device,
+    state: &RenderState,
+    light: &light::LightState,
+) -> RenderPipeline {
+    pipeline::create_cutout_render_pipeline::<GpuVertex>(
+        device,
+        pipeline::get_textured_shader(),
+        &[
+            &state.bind_group_layout,
+            TexturedMeshRenderData::matrix_bgl(device),
+            &light.bind_group_layout,
+            TextureData::bind_group_layout(device),
+        ],
+        state.render_preferences.polygon_mode,
+        None,
+        state.render_preferences.alpha_to_coverage(),
+    )
+}
+
+/// Pipeline for instanced scenes, using the polygon and cull
+/// modes and alpha cutout from the render preferences.
+pub fn instanced_pipeline(
+    device: &Device,
+    state: &RenderState,
+    light: &light::LightState,
+) -> RenderPipeline {
+    pipeline::create_instanced_render_pipeline::<GpuVertex, Matrix>(
+        device,
+        pipeline::get_instanced_shader(),
+        &[
+            &state.bind_group_layout,
+            TexturedMeshRenderData::matrix_bgl(device),
+            &light.bind_group_layout,
+            TextureData::bind_group_layout(device),
+        ],
+        state.render_preferences.polygon_mode,
+        state.render_preferences.cull_mode,
+        state.render_preferences.alpha_to_coverage(),
+    )
+}
+
 // -------------------------------------
 // Mesh data for simple square canvases.
 
@@ -136,24 +377,28 @@ static SQUARE_VERTICES_VERTICAL: LazyLock<Vec<GpuVertex>> = LazyLock::new(|| {
     vec![
         GpuVertex {
             position: [-0.5, -0.5, 0.0],
+            color: [1.0, 1.0, 1.0],
             tex_coords: [0.0, 1.0],
             normal: [0.0, 0.0, 1.0],
             ..Default::default()
         },
         GpuVertex {
             position: [0.5, -0.5, 0.0],
+            color: [1.0, 1.0, 1.0],
             tex_coords: [1.0, 1.0],
             normal: [0.0, 0.0, 1.0],
             ..Default::default()
         },
         GpuVertex {
             position: [0.5, 0.5, 0.0],
+            color: [1.0, 1.0, 1.0],
             tex_coords: [1.0, 0.0],
             normal: [0.0, 0.0, 1.0],
             ..Default::default()
         },
         GpuVertex {
             position: [-0.5, 0.5, 0.0],
+            color: [1.0, 1.0, 1.0],
             tex_coords: [0.0, 0.0],
             normal: [0.0, 0.0, 1.0],
             ..Default::default()
@@ -165,21 +410,25 @@ static SQUARE_VERTICES_FLAT: LazyLock<Vec<GpuVertex>> = LazyLock::new(|| {
     vec![
         GpuVertex {
             position: [-0.5, 0.0, 0.5],
+            color: [1.0, 1.0, 1.0],
             tex_coords: [0.0, 1.0],
             ..Default::default()
         },
         GpuVertex {
             position: [0.5, 0.0, 0.5],
+            color: [1.0, 1.0, 1.0],
             tex_coords: [1.0, 1.0],
             ..Default::default()
         },
         GpuVertex {
             position: [0.5, 0.0, -0.5],
+            color: [1.0, 1.0, 1.0],
             tex_coords: [1.0, 0.0],
             ..Default::default()
         },
         GpuVertex {
             position: [-0.5, 0.0, -0.5],
+            color: [1.0, 1.0, 1.0],
             tex_coords: [0.0, 0.0],
             ..Default::default()
         },
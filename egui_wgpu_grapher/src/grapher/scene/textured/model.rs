@@ -1,34 +1,57 @@
-//! Code to build a scene from data imported from a glTF archive.
+//! Code to build a scene from data imported from a glTF archive or OBJ file.
 
-use super::build_scene;
+use super::{TexturedMeshRenderData, build_instanced_scene, instanced_pipeline};
 use crate::grapher::{
-    gltf_loader::{self},
+    gltf_loader::{self, MorphTarget, SceneNode},
+    matrix::Matrix,
+    obj_loader,
+    pipeline::{self, mesh_picking::MeshPicker},
     render::RenderState,
-    scene::{RenderScene, Scene3D},
+    scene::{
+        GpuVertex, RenderScene, Scene3D,
+        lines::{self, LinesRenderData},
+        solid::MeshRenderData,
+    },
 };
 
-use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
+use egui_wgpu::wgpu::{BindGroupLayout, Device, Queue, SurfaceConfiguration};
+use std::{borrow::Cow, error::Error, path::Path};
+
+// gap between copies in the instance grid, relative to model size
+const GRID_GAP: f32 = 0.25;
+
+// normal segment length, relative to the bounding box diagonal
+const NORMAL_LINE_LENGTH: f32 = 0.02;
+// most normals drawn over all meshes, shared in proportion to vertex count
+const MAX_NORMAL_LINES: usize = 8192;
 
 pub fn model_scene(
     device: &Device,
     queue: &Queue,
-    surface_config: &SurfaceConfiguration,
     state: &mut RenderState,
     path: &str,
 ) -> Option<ModelScene> {
-    let Ok(loader) = gltf_loader::GltfLoader::create(device, queue, path) else {
-        return None;
-    };
-
     let mut mesh_data = vec![];
-    match loader.traverse() {
+    let mut meshes = vec![];
+    let bounds;
+    let nodes;
+    match load_render_scene(device, queue, path) {
         Ok(render_scene) => {
+            bounds = render_scene.bounds();
+            nodes = render_scene.nodes;
             for render_mesh in render_scene.meshes {
+                meshes.push(ModelMesh {
+                    name: render_mesh.name,
+                    color: [1.0, 1.0, 1.0],
+                    vertices: render_mesh.data.vertices.clone(),
+                    morph: render_mesh.morph,
+                    morph_weight: 0.0,
+                });
                 mesh_data.push((render_mesh.data, render_mesh.matrix));
             }
         }
         Err(err) => {
-            println!("Error while reading glTF scene: {err:?}");
+            log::error!("Error while reading model scene: {err}");
             return None;
         }
     }
@@ -37,13 +60,327 @@ pub fn model_scene(
     state.render_preferences.set_use_texture(true);
     state.render_preferences.update_uniform(queue);
 
-    Some(ModelScene {
-        scene: build_scene(device, surface_config, state, mesh_data),
-    })
+    // A single identity instance until a larger grid is requested.
+    let model_scene = ModelScene {
+        scene: build_instanced_scene(device, state, mesh_data, &[Matrix::identity()]),
+        meshes,
+        nodes,
+        bounds,
+        grid_size: 1,
+        needs_rebuild: false,
+        show_normals: false,
+        normal_lines_stale: false,
+        picker: MeshPicker::create(
+            device,
+            &picking_bind_group_layouts(device, state),
+            state.render_preferences.cull_mode,
+        ),
+    };
+    model_scene.frame_camera(queue, state);
+
+    Some(model_scene)
+}
+
+// Layouts of the bind groups the mesh id pass shares with the instanced pipeline.
+fn picking_bind_group_layouts<'a>(
+    device: &Device,
+    state: &'a RenderState,
+) -> [&'a BindGroupLayout; 2] {
+    [
+        &state.bind_group_layout,
+        TexturedMeshRenderData::matrix_bgl(device),
+    ]
+}
+
+/// Read a scene with the loader for the file's extension.
+fn load_render_scene(
+    device: &Device,
+    queue: &Queue,
+    path: &str,
+) -> Result<gltf_loader::RenderScene, Box<dyn Error>> {
+    let extension = Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    match extension.as_deref() {
+        Some("obj") => obj_loader::ObjLoader::create(device, queue, path)?.traverse(),
+        _ => gltf_loader::GltfLoader::create(device, queue, path)?.traverse(),
+    }
+}
+
+/// CPU-side copy of a mesh's vertices, kept so its color can be rewritten
+/// and its morph target blended in.
+pub struct ModelMesh {
+    pub name: String,
+    pub color: [f32; 3],
+    // unmorphed vertices
+    vertices: Vec<GpuVertex>,
+    pub morph: Option<MorphTarget>,
+    // morph target weight of the uploaded vertices
+    morph_weight: f32,
+}
+
+impl ModelMesh {
+    /// Vertices with the morph target blended in by its current weight.
+    fn blended_vertices(&self) -> Cow<'_, [GpuVertex]> {
+        let Some(morph) = self.morph.as_ref().filter(|_| self.morph_weight != 0.0) else {
+            return Cow::Borrowed(&self.vertices);
+        };
+        let weight = self.morph_weight;
+        let blend = |base: [f32; 3], offset: [f32; 3]| {
+            std::array::from_fn(|k| base[k] + weight * offset[k])
+        };
+        let vertices = self
+            .vertices
+            .iter()
+            .zip(&morph.position_offsets)
+            .zip(&morph.normal_offsets)
+            .map(|((vertex, position_offset), normal_offset)| {
+                let normal: [f32; 3] = blend(vertex.normal, *normal_offset);
+                let norm = normal.iter().map(|c| c * c).sum::<f32>().sqrt();
+                GpuVertex {
+                    position: blend(vertex.position, *position_offset),
+                    normal: if norm > 0.0 {
+                        normal.map(|c| c / norm)
+                    } else {
+                        vertex.normal
+                    },
+                    ..*vertex
+                }
+            })
+            .collect();
+        Cow::Owned(vertices)
+    }
 }
 
 pub struct ModelScene {
     pub scene: Scene3D,
+    // in the same order as scene.instanced_meshes
+    pub meshes: Vec<ModelMesh>,
+    // root nodes of the model's scene hierarchy
+    pub nodes: Vec<SceneNode>,
+    // min and max corners of the model bounding box
+    bounds: ([f32; 3], [f32; 3]),
+    // copies of the model along each side of a square grid in the xz-plane
+    pub grid_size: u32,
+    // have render preferences changed that the pipeline is built with
+    pub needs_rebuild: bool,
+    // whether the scene's lines show vertex normals
+    show_normals: bool,
+    // vertices or visibility changed since the normal lines were built
+    normal_lines_stale: bool,
+    // finds the mesh under the cursor
+    picker: MeshPicker,
+}
+
+impl ModelScene {
+    /// Distance between neighboring copies in the instance grid.
+    fn grid_spacing(&self) -> f32 {
+        let (min, max) = self.bounds;
+        (1.0 + GRID_GAP) * (max[0] - min[0]).max(max[2] - min[2]).max(0.01)
+    }
+
+    /// Draw a `size` by `size` grid of copies of the model, centered on the original.
+    pub fn set_instance_grid(&mut self, device: &Device, queue: &Queue, size: u32) {
+        if size == self.grid_size {
+            return;
+        }
+        self.grid_size = size;
+
+        let spacing = self.grid_spacing();
+        let offset = |i: u32| spacing * (i as f32 - (size - 1) as f32 / 2.0);
+        let instances: Vec<Matrix> = (0..size * size)
+            .map(|i| Matrix::translation(&[offset(i % size), 0.0, offset(i / size)]))
+            .collect();
+
+        for instanced in &mut self.scene.instanced_meshes {
+            instanced.set_instances(device, queue, &instances);
+        }
+    }
+
+    /// Recreate the pipeline after a change to the render preferences.
+    pub fn rebuild_pipeline(&mut self, device: &Device, state: &RenderState) {
+        let pipeline = instanced_pipeline(device, state, &self.scene.light);
+        self.scene.instanced_pipeline = Some(pipeline);
+        self.picker.rebuild_pipeline(
+            device,
+            &picking_bind_group_layouts(device, state),
+            state.render_preferences.cull_mode,
+        );
+    }
+
+    /// Pick the mesh under the cursor, given in physical pixels.
+    /// The result is available from `hovered_mesh` a frame or so later.
+    pub fn update_hover(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+        cursor: Option<[f32; 2]>,
+    ) {
+        self.picker.update(
+            device,
+            queue,
+            surface_config,
+            &state.bind_group,
+            &self.scene.instanced_meshes,
+            cursor,
+        );
+    }
+
+    /// Index of the mesh under the cursor, if any.
+    pub fn hovered_mesh(&self) -> Option<usize> {
+        self.picker.hovered()
+    }
+
+    /// Bounding box of the whole instance grid.
+    fn grid_bounds(&self) -> ([f32; 3], [f32; 3]) {
+        let (mut min, mut max) = self.bounds;
+        let half_extent = self.grid_spacing() * (self.grid_size - 1) as f32 / 2.0;
+        for i in [0, 2] {
+            min[i] -= half_extent;
+            max[i] += half_extent;
+        }
+        (min, max)
+    }
+
+    /// Place the camera on the positive z side of the bounding box,
+    /// far enough that a sphere around the box fits in the view.
+    pub fn frame_camera(&self, queue: &Queue, state: &mut RenderState) {
+        let (min, max) = self.grid_bounds();
+        let center: [f32; 3] = std::array::from_fn(|i| (min[i] + max[i]) / 2.0);
+        let radius = (0..3)
+            .map(|i| (max[i] - min[i]) / 2.0)
+            .map(|half| half * half)
+            .sum::<f32>()
+            .sqrt()
+            .max(0.01);
+
+        let camera_state = &mut state.camera_state;
+        camera_state.set_from_z(0.0);
+
+        // Fit in the narrower of the vertical and horizontal fields of view.
+        let camera = &mut camera_state.camera;
+        let half_fovy = camera.fovy.to_radians() / 2.0;
+        let half_fovx = (camera.aspect * half_fovy.tan()).atan();
+        let distance = radius / half_fovy.min(half_fovx).sin();
+
+        camera.target = center.into();
+        camera.eye = (center[0], center[1], center[2] + distance).into();
+        camera.zfar = camera.zfar.max(distance + 2.0 * radius);
+
+        camera_state.update_view_matrix(queue);
+    }
+
+    /// Draw segments along the vertex normals of the visible meshes, at a
+    /// sample of their vertices, or remove them. Segments follow each mesh's
+    /// transform but not the instance grid, so they're drawn on the model
+    /// at its original position.
+    pub fn set_show_normals(&mut self, device: &Device, state: &RenderState, show: bool) {
+        if show == self.show_normals && !self.normal_lines_stale {
+            return;
+        }
+        self.show_normals = show;
+        self.normal_lines_stale = false;
+
+        let scene = &mut self.scene;
+        scene.lines.clear();
+        if !show {
+            scene.line_pipeline = None;
+            return;
+        }
+
+        let (min, max) = self.bounds;
+        let diagonal = (0..3)
+            .map(|i| (max[i] - min[i]).powi(2))
+            .sum::<f32>()
+            .sqrt();
+        let total_vertices: usize = self.meshes.iter().map(|mesh| mesh.vertices.len()).sum();
+        for (mesh, instanced) in self.meshes.iter().zip(&scene.instanced_meshes) {
+            if !instanced.visible {
+                continue;
+            }
+            let max_lines = MAX_NORMAL_LINES * mesh.vertices.len() / total_vertices.max(1);
+            let vertices = lines::normal_lines(
+                &mesh.blended_vertices(),
+                NORMAL_LINE_LENGTH * diagonal,
+                max_lines.max(1),
+            );
+            if !vertices.is_empty() {
+                scene.lines.push(LinesRenderData::from_vertices(
+                    device,
+                    &vertices,
+                    instanced.mesh.matrix.uniform,
+                ));
+            }
+        }
+        scene.line_pipeline.get_or_insert_with(|| {
+            pipeline::create_line_pipeline::<GpuVertex>(
+                device,
+                &[&state.bind_group_layout, MeshRenderData::matrix_bgl(device)],
+                false,
+            )
+        });
+    }
+
+    /// Show or hide each mesh according to the visibility of its node
+    /// and the node's ancestors.
+    pub fn apply_node_visibility(&mut self) {
+        let instanced_meshes = &mut self.scene.instanced_meshes;
+        for node in &self.nodes {
+            node.for_each_mesh(true, &mut |mesh, visible| {
+                if let Some(instanced) = instanced_meshes.get_mut(mesh) {
+                    instanced.visible = visible;
+                }
+            });
+        }
+        self.normal_lines_stale = true;
+    }
+
+    /// Overwrite the vertex color of one mesh and re-upload its vertex buffer.
+    /// Vertex color tints the mesh texture, so white restores the original.
+    pub fn set_mesh_color(&mut self, queue: &Queue, index: usize, rgb: [f32; 3]) {
+        let Some(mesh) = self.meshes.get_mut(index) else {
+            return;
+        };
+        if mesh.color == rgb {
+            return;
+        }
+        mesh.color = rgb;
+        for vertex in &mut mesh.vertices {
+            vertex.color = rgb;
+        }
+        self.write_vertices(queue, index);
+    }
+
+    /// Blend one mesh's morph target in by the given weight and re-upload
+    /// its vertex buffer. Meshes without a morph target are left alone.
+    pub fn set_morph_weight(&mut self, queue: &Queue, index: usize, weight: f32) {
+        let Some(mesh) = self.meshes.get_mut(index) else {
+            return;
+        };
+        if mesh.morph.is_none() || mesh.morph_weight == weight {
+            return;
+        }
+        mesh.morph_weight = weight;
+        self.write_vertices(queue, index);
+        self.normal_lines_stale = true;
+    }
+
+    fn write_vertices(&self, queue: &Queue, index: usize) {
+        let (Some(mesh), Some(render_data)) = (
+            self.meshes.get(index),
+            self.scene.instanced_meshes.get(index),
+        ) else {
+            return;
+        };
+        queue.write_buffer(
+            &render_data.mesh.vertex_buffer,
+            0,
+            bytemuck::cast_slice(&mesh.blended_vertices()),
+        );
+    }
 }
 
 impl RenderScene for ModelScene {
@@ -8,21 +8,23 @@ use super::{SQUARE_INDICES, SQUARE_VERTICES_FLAT, TexturedMeshData, build_scene}
 
 use crate::grapher::{
     math::pde,
-    matrix::Matrix,
+    matrix::{Matrix, X_AXIS},
     pipeline::texture::{TextureData, TextureMatrix},
     render::RenderState,
     scene::{RenderScene, Scene3D},
 };
 
-use egui_wgpu::wgpu::{self, Device, Queue, SurfaceConfiguration};
+use egui_wgpu::wgpu::{self, Device, Queue};
 
 pub fn wave_eqn_texture_scene(
     device: &Device,
     queue: &Queue,
-    surface_config: &SurfaceConfiguration,
     state: &RenderState,
+    (x_size, y_size): (usize, usize),
+    seed: Option<u64>,
 ) -> WaveEquationTextureScene {
-    let texture_dims: (u32, u32) = (pde::X_SIZE as u32, pde::Y_SIZE as u32);
+    // one texel per grid point
+    let texture_dims: (u32, u32) = (x_size as u32, y_size as u32);
 
     let mut texture_matrix = TextureMatrix::new(texture_dims.0, texture_dims.1);
 
@@ -43,10 +45,17 @@ pub fn wave_eqn_texture_scene(
         texture: texture_data,
     };
 
-    let meshes = vec![(mesh_data, Matrix::x_rotation(90.0))];
+    // stretch the unit square along x to the grid's aspect ratio
+    let aspect = x_size as f32 / y_size as f32;
+    let matrix = Matrix::from(
+        cgmath::Matrix4::from_axis_angle(X_AXIS, cgmath::Deg(90.0))
+            * cgmath::Matrix4::from_nonuniform_scale(aspect, 1.0, 1.0),
+    );
+    let meshes = vec![(mesh_data, matrix)];
 
-    let scene = build_scene(device, surface_config, state, meshes);
-    let mut wave_eqn = pde::WaveEquationData::new(1000, 1000);
+    let scene = build_scene(device, state, meshes);
+    let mut wave_eqn = pde::WaveEquationData::with_seed(x_size, y_size, seed);
+    log::info!("Wave equation seed: {}", wave_eqn.seed());
 
     // update solver properties
     wave_eqn.disturbance_prob = 0.01;
@@ -79,9 +88,9 @@ impl RenderScene for WaveEquationTextureScene {
         let matrix = &mut self.texture_matrix;
 
         // update vertex data
-        let n = matrix.dimensions.0;
-        for i in 0..n {
-            for j in 0..n {
+        let (x_dim, y_dim) = matrix.dimensions;
+        for i in 0..x_dim {
+            for j in 0..y_dim {
                 let new_val =
                     float_to_scaled_u8_color_pixel(self.wave_eqn.u_0[i as usize][j as usize]);
                 let entry = matrix.get(i, j);
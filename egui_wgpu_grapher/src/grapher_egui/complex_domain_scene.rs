@@ -0,0 +1,125 @@
+//! Scene to render domain coloring of a complex function on 2d canvas.
+
+use egui::Ui;
+use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
+
+use crate::grapher::{
+    math::complex::ComplexExpr,
+    render::RenderState,
+    scene::complex_domain::{ComplexDomainScene, DEFAULT_CENTER, DEFAULT_SCALE, DomainView},
+};
+
+// Complex-plane distance per unit of camera translation. The canvas quad
+// spans 0.9 clip units from its center, which covers `scale` in the plane.
+const TRANSLATION_TO_PLANE: f64 = DEFAULT_SCALE / 0.9;
+
+const DEFAULT_FUNCTION: &str = "(z^2 - 1)*(z - 2 - i)^2 / (z^2 + 2 + 2*i)";
+
+pub struct ComplexDomainSceneData {
+    pub scene: ComplexDomainScene,
+    pub view: DomainView,
+    function_text: String,
+    function: ComplexExpr,
+    function_error: Option<String>,
+    // camera translation and scale treated as the default view
+    view_origin: [f32; 3],
+    reset_view: bool,
+}
+
+impl ComplexDomainSceneData {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &mut RenderState,
+    ) -> Self {
+        let scene = ComplexDomainScene::new(device, queue, surface_config, state);
+        Self {
+            scene,
+            view: DomainView::default(),
+            function_text: DEFAULT_FUNCTION.to_string(),
+            function: ComplexExpr::parse(DEFAULT_FUNCTION).unwrap(),
+            function_error: None,
+            view_origin: [0.0, 0.0, 1.0],
+            reset_view: false,
+        }
+    }
+
+    /// Map camera pan and zoom onto the view center and scale, and
+    /// recolor if the view changed.
+    pub fn update(&mut self, queue: &Queue, state: &RenderState) {
+        let camera = &state.camera_state.camera;
+        if self.reset_view {
+            self.view_origin = [
+                camera.translation_x,
+                camera.translation_y,
+                camera.ortho_scale,
+            ];
+            self.reset_view = false;
+        }
+
+        let [origin_x, origin_y, origin_scale] = self.view_origin.map(f64::from);
+        self.view.center = [
+            DEFAULT_CENTER[0] - (camera.translation_x as f64 - origin_x) * TRANSLATION_TO_PLANE,
+            DEFAULT_CENTER[1] - (camera.translation_y as f64 - origin_y) * TRANSLATION_TO_PLANE,
+        ];
+        self.view.scale = DEFAULT_SCALE * origin_scale / camera.ortho_scale as f64;
+
+        self.scene.color(queue, &self.function, self.view);
+    }
+
+    pub fn reset_camera(
+        &mut self,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &mut RenderState,
+    ) {
+        ComplexDomainScene::reset_camera(queue, surface_config, state);
+        self.view_origin = [0.0, 0.0, 1.0];
+        self.reset_view = false;
+    }
+
+    pub fn handle_resize(&mut self, queue: &Queue, surface_config: &SurfaceConfiguration) {
+        let new_ratio = surface_config.height as f32 / surface_config.width as f32;
+        self.scene.update_aspect_ratio(queue, new_ratio);
+    }
+
+    // Keep the last valid function if the new text doesn't parse.
+    fn apply_function(&mut self) {
+        match ComplexExpr::parse(&self.function_text) {
+            Ok(function) => {
+                self.function = function;
+                self.function_error = None;
+                self.scene.invalidate();
+            }
+            Err(err) => {
+                log::warn!("Invalid complex function \"{}\": {err}", self.function_text);
+                self.function_error = Some(err);
+            }
+        }
+    }
+
+    pub fn parameter_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("f(z) = ");
+            let response = ui.text_edit_singleline(&mut self.function_text);
+            if response.lost_focus() {
+                self.apply_function();
+            }
+        });
+        if let Some(err) = &self.function_error {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+
+        ui.checkbox(&mut self.view.contours, "Modulus contours");
+
+        ui.add_space(2.5);
+        if ui.button("Reset view").clicked() {
+            self.reset_view = true;
+        }
+        ui.label(format!(
+            "Center: {:.6} + {:.6}i",
+            self.view.center[0], self.view.center[1]
+        ));
+    }
+}
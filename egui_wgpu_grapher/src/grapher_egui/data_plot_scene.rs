@@ -0,0 +1,63 @@
+//! UI specific to the data plot mode.
+
+use crate::{
+    egui::{components::vertical_exaggeration_slider, ui::UiState},
+    grapher::scene::data_plot::{DataLayout, DataPlotScene},
+};
+
+use egui::Ui;
+
+pub struct DataPlotSceneData {
+    pub data_plot_scene: DataPlotScene,
+}
+
+impl DataPlotSceneData {
+    pub fn new(data_plot_scene: DataPlotScene) -> Self {
+        Self { data_plot_scene }
+    }
+}
+
+pub fn parameter_ui_data_plot(data: &mut DataPlotSceneData, ui: &mut Ui, ui_state: &mut UiState) {
+    if ui.add(egui::Button::new("Change file")).clicked() {
+        ui_state.show_file_input = true;
+    }
+
+    let scene = &mut data.data_plot_scene;
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Layout");
+        changed |= ui
+            .radio_value(&mut scene.layout, DataLayout::Points, "Points")
+            .changed();
+        changed |= ui
+            .radio_value(&mut scene.layout, DataLayout::Grid, "Grid")
+            .changed();
+    });
+
+    if scene.layout == DataLayout::Points {
+        let column_names = scene.column_names().to_vec();
+        let columns = [
+            ("x column", &mut scene.x_column),
+            ("y column", &mut scene.y_column),
+            ("z column", &mut scene.z_column),
+        ];
+        for (label, column) in columns {
+            changed |= egui::ComboBox::from_label(label)
+                .show_index(ui, column, column_names.len(), |i| column_names[i].as_str())
+                .changed();
+        }
+
+        // Point size is a uniform, so it doesn't need a rebuild.
+        ui.add(egui::Slider::new(&mut scene.point_size, 1.0..=20.0).text("Point size"));
+    }
+    vertical_exaggeration_slider(&mut scene.vertical_exaggeration, ui);
+
+    if let Some(error) = &scene.error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+
+    if changed {
+        scene.needs_rebuild = true;
+    }
+}
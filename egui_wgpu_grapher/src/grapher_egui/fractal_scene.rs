@@ -0,0 +1,115 @@
+//! Scene to render an escape-time fractal on 2d canvas.
+
+use egui::Ui;
+use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
+
+use crate::grapher::{
+    render::RenderState,
+    scene::fractal::{DEFAULT_CENTER, DEFAULT_SCALE, FractalParameters, FractalScene},
+};
+
+// Complex-plane distance per unit of camera translation. The canvas quad
+// spans 0.9 clip units from its center, which covers `scale` in the plane.
+const TRANSLATION_TO_PLANE: f32 = DEFAULT_SCALE / 0.9;
+
+pub struct FractalSceneData {
+    pub scene: FractalScene,
+    pub parameters: FractalParameters,
+    // camera translation and scale treated as the default view
+    view_origin: [f32; 3],
+    reset_view: bool,
+}
+
+impl FractalSceneData {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &mut RenderState,
+    ) -> Self {
+        let scene = FractalScene::new(device, queue, surface_config, state);
+        Self {
+            parameters: scene.parameters(),
+            scene,
+            view_origin: [0.0, 0.0, 1.0],
+            reset_view: false,
+        }
+    }
+
+    /// Map camera pan and zoom onto the fractal center and scale.
+    pub fn update(&mut self, queue: &Queue, state: &RenderState) {
+        let camera = &state.camera_state.camera;
+        if self.reset_view {
+            self.view_origin = [
+                camera.translation_x,
+                camera.translation_y,
+                camera.ortho_scale,
+            ];
+            self.reset_view = false;
+        }
+
+        let [origin_x, origin_y, origin_scale] = self.view_origin;
+        self.parameters.center = [
+            DEFAULT_CENTER[0] - (camera.translation_x - origin_x) * TRANSLATION_TO_PLANE,
+            DEFAULT_CENTER[1] - (camera.translation_y - origin_y) * TRANSLATION_TO_PLANE,
+        ];
+        self.parameters.scale = DEFAULT_SCALE * origin_scale / camera.ortho_scale;
+
+        self.scene.set_parameters(queue, self.parameters);
+    }
+
+    pub fn reset_camera(
+        &mut self,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &mut RenderState,
+    ) {
+        FractalScene::reset_camera(queue, surface_config, state);
+        self.view_origin = [0.0, 0.0, 1.0];
+        self.reset_view = false;
+    }
+
+    pub fn handle_resize(&mut self, queue: &Queue, surface_config: &SurfaceConfiguration) {
+        let new_ratio = surface_config.height as f32 / surface_config.width as f32;
+        self.scene.update_aspect_ratio(queue, new_ratio);
+    }
+
+    pub fn run_compute(&mut self, device: &Device, queue: &Queue) {
+        if self.scene.needs_compute {
+            let mut encoder = device.create_command_encoder(&Default::default());
+            self.scene.compute_fractal(&mut encoder);
+            queue.submit(Some(encoder.finish()));
+        }
+    }
+
+    pub fn parameter_ui(&mut self, ui: &mut Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.parameters.max_iterations, 16..=4096)
+                .logarithmic(true)
+                .text("Max iterations"),
+        );
+
+        let mut is_julia = self.parameters.is_julia != 0;
+        if ui.checkbox(&mut is_julia, "Julia set").changed() {
+            self.parameters.is_julia = is_julia as u32;
+        }
+        if is_julia {
+            ui.horizontal(|ui| {
+                ui.label("c = ");
+                ui.add(egui::DragValue::new(&mut self.parameters.julia_constant[0]).speed(0.001));
+                ui.label("+");
+                ui.add(egui::DragValue::new(&mut self.parameters.julia_constant[1]).speed(0.001));
+                ui.label("i");
+            });
+        }
+
+        ui.add_space(2.5);
+        if ui.button("Reset view").clicked() {
+            self.reset_view = true;
+        }
+        ui.label(format!(
+            "Center: {:.6} + {:.6}i",
+            self.parameters.center[0], self.parameters.center[1]
+        ));
+    }
+}
@@ -1,9 +1,32 @@
 //! UI specific to the grapher mode.
 
-use super::GraphScene;
-use crate::egui::components::float_edit_line;
+use super::{GraphScene, RenderState};
+use crate::{
+    egui::{
+        components::{colormap_legend, float_edit_line, vertical_exaggeration_slider},
+        ui::UiState,
+    },
+    grapher::{
+        math::{
+            graph::{ColorBy, Diagonals, Domain},
+            noise::{self, NoiseParameters},
+            presets::FUNCTION_PRESETS,
+            try_parse_function_string,
+        },
+        pipeline::heatmap_export,
+    },
+};
 
-use egui::{Grid, Ui};
+use egui::{Context, Grid, Key, Ui};
+use egui_wgpu::wgpu::{Device, Queue};
+use std::time::{Duration, Instant};
+
+// wait after the last edit before recompiling the surface shader
+const SHADER_DEBOUNCE: Duration = Duration::from_millis(600);
+
+const DEFAULT_SHADER_BODY: &str = "    return in.color;";
+
+const DEFAULT_HEATMAP_SIZE: u32 = 2048;
 
 pub struct GraphSceneUiData {
     scale_x_text: String,
@@ -13,12 +36,36 @@ pub struct GraphSceneUiData {
     shift_x_text: String,
     shift_z_text: String,
     shift_y_text: String,
+
+    // domain as edited, applied to the scene once valid
+    domain: Domain,
+    // x min, x max, z min, z max
+    domain_text: [String; 4],
+
+    // custom surface_color function body
+    shader_enabled: bool,
+    shader_body: String,
+    // time of the last change not yet compiled
+    shader_edited: Option<Instant>,
+    shader_error: Option<String>,
+
+    // index into FUNCTION_PRESETS of the preset last applied
+    preset: Option<usize>,
+
+    // function of x and z to color the graph by, and whether it parsed
+    color_function_text: String,
+    color_function_valid: bool,
+
+    // pixels along the longer side of an exported heatmap
+    heatmap_size: u32,
+    heatmap_export_requested: bool,
 }
 
 pub struct GraphSceneData {
     pub graph_scene: GraphScene,
     pub ui_data: GraphSceneUiData,
     pub smoothing_scale: Option<f64>,
+    pub noise_parameters: NoiseParameters,
 }
 
 impl GraphSceneData {
@@ -31,6 +78,10 @@ impl GraphSceneData {
         let shift_z_text = graph_scene.parameters.shift_z.to_string();
         let shift_y_text = graph_scene.parameters.shift_y.to_string();
 
+        let domain = graph_scene.domain;
+        let domain_text =
+            [domain.x_min, domain.x_max, domain.z_min, domain.z_max].map(|value| value.to_string());
+
         Self {
             graph_scene,
             ui_data: GraphSceneUiData {
@@ -41,14 +92,79 @@ impl GraphSceneData {
                 shift_x_text,
                 shift_z_text,
                 shift_y_text,
+
+                domain,
+                domain_text,
+
+                shader_enabled: false,
+                shader_body: DEFAULT_SHADER_BODY.into(),
+                shader_edited: None,
+                shader_error: None,
+
+                preset: None,
+
+                color_function_text: String::new(),
+                color_function_valid: true,
+
+                heatmap_size: DEFAULT_HEATMAP_SIZE,
+                heatmap_export_requested: false,
             },
             smoothing_scale: None,
+            noise_parameters: NoiseParameters::default(),
+        }
+    }
+
+    /// Save a top-down heatmap of the graph, if one was requested.
+    pub fn export_heatmap(&mut self, device: &Device, queue: &Queue) {
+        if !std::mem::take(&mut self.ui_data.heatmap_export_requested) {
+            return;
+        }
+        let filename = heatmap_export::heatmap_filename();
+        match self
+            .graph_scene
+            .export_heatmap(device, queue, self.ui_data.heatmap_size, &filename)
+        {
+            Ok(()) => log::info!("Saved heatmap to {filename}"),
+            Err(err) => log::error!("{err}"),
+        }
+    }
+
+    /// Recompile the surface shader once edits have settled.
+    pub fn update_surface_shader(&mut self, device: &Device, state: &RenderState) {
+        let ui_data = &mut self.ui_data;
+        if ui_data
+            .shader_edited
+            .is_none_or(|edited| edited.elapsed() < SHADER_DEBOUNCE)
+        {
+            return;
+        }
+        ui_data.shader_edited = None;
+
+        let body = ui_data.shader_enabled.then(|| ui_data.shader_body.clone());
+        match self.graph_scene.set_surface_shader(device, state, body) {
+            Ok(()) => ui_data.shader_error = None,
+            Err(err) => {
+                log::error!("Custom surface shader failed to compile: {err}");
+                ui_data.shader_error = Some(err);
+            }
         }
     }
 }
 
 // graph-specific parameter ui
-pub fn parameter_ui_graph(data: &mut GraphSceneData, ui: &mut Ui) {
+pub fn parameter_ui_graph(data: &mut GraphSceneData, ui: &mut Ui, ui_state: &mut UiState) {
+    // the previous mesh is shown until a rebuild finishes
+    if let Some(progress) = data.graph_scene.build_progress() {
+        ui.add(
+            egui::ProgressBar::new(progress)
+                .desired_width(160.0)
+                .text("Building…"),
+        );
+    }
+
+    preset_ui(data, ui, ui_state);
+    measures_ui(data, ui);
+
     let scale_x = &mut data.graph_scene.parameters.scale_x;
     let scale_z = &mut data.graph_scene.parameters.scale_z;
     let scale_y = &mut data.graph_scene.parameters.scale_y;
@@ -97,6 +213,8 @@ pub fn parameter_ui_graph(data: &mut GraphSceneData, ui: &mut Ui) {
         });
     }
 
+    domain_ui(data, ui);
+
     let mut smoothing = data.smoothing_scale.unwrap_or_default();
     ui.label("Smoothing scale:");
     ui.add_space(2.5);
@@ -112,6 +230,64 @@ pub fn parameter_ui_graph(data: &mut GraphSceneData, ui: &mut Ui) {
         }
     }
 
+    ui.add_space(5.0);
+    if ui
+        .checkbox(&mut data.graph_scene.auto_scale, "Fit to view")
+        .on_hover_text("Scale the function's output to a fixed height.")
+        .changed()
+    {
+        data.graph_scene.needs_rebuild = true;
+    }
+
+    vertical_exaggeration_slider(&mut data.graph_scene.vertical_exaggeration, ui);
+
+    // Debugging aid for how the grid is triangulated.
+    let diagonals = &mut data.graph_scene.diagonals;
+    let previous = *diagonals;
+    ui.horizontal(|ui| {
+        ui.label("Diagonals");
+        egui::ComboBox::from_id_salt("graph diagonals")
+            .selected_text(diagonals.label())
+            .show_ui(ui, |ui| {
+                for alternative in Diagonals::ALL {
+                    ui.selectable_value(diagonals, alternative, alternative.label());
+                }
+            });
+    });
+    if *diagonals != previous {
+        data.graph_scene.needs_rebuild = true;
+    }
+    ui.checkbox(&mut data.graph_scene.reuse_buffers, "Reuse buffers")
+        .on_hover_text("Update the mesh in place on rebuild, instead of reallocating its buffers.");
+
+    color_by_ui(data, ui);
+    heatmap_export_ui(data, ui);
+
+    if ui
+        .checkbox(&mut data.graph_scene.show_gradient, "Gradient field")
+        .on_hover_text("Arrows pointing uphill, longer and brighter where the graph is steeper.")
+        .changed()
+    {
+        data.graph_scene.needs_lines_update = true;
+    }
+    if ui
+        .checkbox(&mut data.graph_scene.show_normals, "Show normals")
+        .on_hover_text(
+            "Segments along the vertex normals, colored by direction, at a sample of vertices.",
+        )
+        .changed()
+    {
+        data.graph_scene.needs_lines_update = true;
+    }
+
+    reference_plane_ui(data, ui);
+
+    if noise::uses_noise(&ui_state.function_string) {
+        noise_parameter_ui(data, ui, ui_state);
+    }
+
+    surface_shader_ui(data, ui);
+
     // TODO: Need to store function string for reuse;
     //       then we can implement this version.
     //
@@ -120,3 +296,311 @@ pub fn parameter_ui_graph(data: &mut GraphSceneData, ui: &mut Ui) {
     //     *needs_update = true;
     // }
 }
+
+fn reference_plane_ui(data: &mut GraphSceneData, ui: &mut Ui) {
+    let graph_scene = &mut data.graph_scene;
+    ui.checkbox(&mut graph_scene.show_reference_plane, "Reference plane")
+        .on_hover_text("Translucent plane at a function value, to see where the graph crosses it.");
+    if graph_scene.show_reference_plane {
+        ui.horizontal(|ui| {
+            // values past the range can be typed in
+            ui.add(
+                egui::Slider::new(&mut graph_scene.reference_level, -3.0..=3.0)
+                    .clamping(egui::SliderClamping::Never)
+                    .text("Level"),
+            );
+            if ui.button("Zero").clicked() {
+                graph_scene.reference_level = 0.0;
+            }
+        });
+        ui.add(
+            egui::Slider::new(&mut graph_scene.reference_opacity, 0.05..=0.9).text("Plane opacity"),
+        );
+    }
+}
+
+fn color_by_ui(data: &mut GraphSceneData, ui: &mut Ui) {
+    let graph_scene = &mut data.graph_scene;
+    let previous = graph_scene.color_by;
+    ui.horizontal(|ui| {
+        ui.label("Color by");
+        for alternative in ColorBy::ALL {
+            ui.radio_value(&mut graph_scene.color_by, alternative, alternative.label());
+        }
+    });
+    let mut recolor = graph_scene.color_by != previous;
+
+    if graph_scene.color_by == ColorBy::Slope {
+        let response = ui
+            .add(
+                egui::Slider::new(&mut graph_scene.slope_max, 0.1..=20.0)
+                    .logarithmic(true)
+                    .text("Max slope"),
+            )
+            .on_hover_text("Slope at the top of the colormap.");
+        // Recoloring recomputes the mesh, so wait for a drag to end.
+        recolor |= response.drag_stopped() || (response.changed() && !response.dragged());
+    }
+    if graph_scene.color_by == ColorBy::Function {
+        let ui_data = &mut data.ui_data;
+        ui.horizontal(|ui| {
+            ui.label("g(x, z) = ");
+            let response = ui.text_edit_singleline(&mut ui_data.color_function_text);
+            if response.lost_focus() {
+                // Without a valid function the graph is uniformly colored.
+                let text = ui_data.color_function_text.trim();
+                graph_scene.color_function = if text.is_empty() {
+                    None
+                } else {
+                    try_parse_function_string(text, &data.noise_parameters)
+                };
+                ui_data.color_function_valid =
+                    text.is_empty() || graph_scene.color_function.is_some();
+                recolor = true;
+            }
+        });
+        if !ui_data.color_function_valid {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                "Invalid function; using uniform color. See log.",
+            );
+        }
+    }
+    if let Some((min, max)) = graph_scene.color_range() {
+        recolor |= colormap_legend(&mut graph_scene.colormap, min, max, ui);
+    }
+    graph_scene.needs_recolor |= recolor;
+}
+
+fn heatmap_export_ui(data: &mut GraphSceneData, ui: &mut Ui) {
+    let ui_data = &mut data.ui_data;
+    ui.horizontal(|ui| {
+        if ui
+            .button("Export heatmap")
+            .on_hover_text(
+                "Save the graph seen from above, colored by height with the colormap, as a PNG.",
+            )
+            .clicked()
+        {
+            ui_data.heatmap_export_requested = true;
+        }
+        ui.add(
+            egui::DragValue::new(&mut ui_data.heatmap_size)
+                .range(64..=8192)
+                .suffix(" px"),
+        )
+        .on_hover_text("Size of the longer side of the image, independent of the window.");
+    });
+}
+
+fn measures_ui(data: &GraphSceneData, ui: &mut Ui) {
+    let Some(measures) = data.graph_scene.measures() else {
+        return;
+    };
+    Grid::new("graph measures").show(ui, |ui| {
+        ui.label("Volume");
+        ui.label(format!("{:.4}", measures.signed_volume))
+            .on_hover_text("Integral of the function over the domain; parts below zero subtract.");
+        ui.end_row();
+        ui.label("|Volume|");
+        ui.label(format!("{:.4}", measures.absolute_volume))
+            .on_hover_text("Volume between the surface and the y = 0 plane.");
+        ui.end_row();
+        ui.label("Surface area");
+        ui.label(format!("{:.4}", measures.area));
+        ui.end_row();
+    });
+    ui.add_space(5.0);
+}
+
+fn preset_ui(data: &mut GraphSceneData, ui: &mut Ui, ui_state: &mut UiState) {
+    let selected = current_preset(data, ui_state);
+    let mut choice = None;
+    ui.horizontal(|ui| {
+        ui.label("Preset");
+        egui::ComboBox::from_id_salt("graph preset")
+            .selected_text(selected.map_or("Custom", |index| FUNCTION_PRESETS[index].name))
+            .show_ui(ui, |ui| {
+                for (index, preset) in FUNCTION_PRESETS.iter().enumerate() {
+                    if ui
+                        .selectable_label(selected == Some(index), preset.name)
+                        .on_hover_text(preset.expr)
+                        .clicked()
+                    {
+                        choice = Some(index);
+                    }
+                }
+            });
+    });
+    if let Some(index) = choice {
+        apply_preset(data, ui_state, index);
+    }
+    ui.add_space(5.0);
+}
+
+/// Step through the presets with `[` and `]`, unless a text field has focus.
+pub fn handle_preset_keys(context: &Context, data: &mut GraphSceneData, ui_state: &mut UiState) {
+    if context.wants_keyboard_input() {
+        return;
+    }
+    let count = FUNCTION_PRESETS.len();
+    let (previous, next) = context.input(|i| {
+        (
+            i.key_pressed(Key::OpenBracket),
+            i.key_pressed(Key::CloseBracket),
+        )
+    });
+    // from a custom function, step to either end of the list
+    let index = match (current_preset(data, ui_state), previous, next) {
+        (Some(index), true, false) => (index + count - 1) % count,
+        (Some(index), false, true) => (index + 1) % count,
+        (None, true, false) => count - 1,
+        (None, false, true) => 0,
+        _ => return,
+    };
+    apply_preset(data, ui_state, index);
+}
+
+// The last preset applied, unless the function has since been edited.
+fn current_preset(data: &GraphSceneData, ui_state: &UiState) -> Option<usize> {
+    data.ui_data
+        .preset
+        .filter(|&index| FUNCTION_PRESETS[index].expr == ui_state.function_string)
+}
+
+// Graph a preset through the same parser as typed input, and set its
+// domain and vertical exaggeration.
+fn apply_preset(data: &mut GraphSceneData, ui_state: &mut UiState, index: usize) {
+    let preset = &FUNCTION_PRESETS[index];
+    let Some(function) = try_parse_function_string(preset.expr, &data.noise_parameters) else {
+        log::error!("Preset \"{}\" failed to parse.", preset.name);
+        return;
+    };
+    ui_state.function_string = preset.expr.to_string();
+    ui_state.function_valid = true;
+
+    let domain = preset.domain();
+    let ui_data = &mut data.ui_data;
+    ui_data.preset = Some(index);
+    ui_data.domain = domain;
+    ui_data.domain_text =
+        [domain.x_min, domain.x_max, domain.z_min, domain.z_max].map(|value| value.to_string());
+
+    let graph_scene = &mut data.graph_scene;
+    graph_scene.function = Some(function);
+    graph_scene.domain = domain;
+    graph_scene.vertical_exaggeration = preset.vertical_exaggeration;
+    graph_scene.needs_rebuild = true;
+}
+
+fn domain_ui(data: &mut GraphSceneData, ui: &mut Ui) {
+    let ui_data = &mut data.ui_data;
+    let domain = &mut ui_data.domain;
+    let [x_min_text, x_max_text, z_min_text, z_max_text] = &mut ui_data.domain_text;
+
+    ui.label("Domain:");
+    ui.add_space(2.5);
+
+    let mut changed = false;
+    Grid::new("graph domain input").show(ui, |ui| {
+        changed |= float_edit_line("x min", x_min_text, &mut domain.x_min, ui);
+        ui.end_row();
+        changed |= float_edit_line("x max", x_max_text, &mut domain.x_max, ui);
+        ui.end_row();
+        changed |= float_edit_line("z min", z_min_text, &mut domain.z_min, ui);
+        ui.end_row();
+        changed |= float_edit_line("z max", z_max_text, &mut domain.z_max, ui);
+        ui.end_row();
+    });
+
+    if !domain.is_valid() {
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            "Each min must be less than its max.",
+        );
+    } else if changed && *domain != data.graph_scene.domain {
+        data.graph_scene.domain = *domain;
+        data.graph_scene.needs_rebuild = true;
+    }
+    ui.add_space(5.0);
+}
+
+fn surface_shader_ui(data: &mut GraphSceneData, ui: &mut Ui) {
+    let ui_data = &mut data.ui_data;
+
+    ui.add_space(5.0);
+    egui::CollapsingHeader::new("Surface shader")
+        .default_open(false)
+        .show(ui, |ui| {
+            let mut changed = ui
+                .checkbox(&mut ui_data.shader_enabled, "Custom surface color")
+                .changed();
+
+            ui.label(
+                egui::RichText::new("fn surface_color(in: VertexOutput) -> vec3<f32> {")
+                    .monospace(),
+            );
+            changed |= ui
+                .add(
+                    egui::TextEdit::multiline(&mut ui_data.shader_body)
+                        .code_editor()
+                        .desired_rows(4)
+                        .desired_width(f32::INFINITY),
+                )
+                .changed();
+            ui.label(egui::RichText::new("}").monospace());
+            ui.label("Fields of in: color, normal, world_position, view_depth.");
+
+            if ui.button("Reset").clicked() {
+                ui_data.shader_body = DEFAULT_SHADER_BODY.into();
+                changed = true;
+            }
+            if let Some(error) = &ui_data.shader_error {
+                ui.colored_label(ui.visuals().error_fg_color, "Failed to compile; see log.")
+                    .on_hover_text(error);
+            }
+
+            if changed {
+                ui_data.shader_edited = Some(Instant::now());
+            }
+        });
+}
+
+fn noise_parameter_ui(data: &mut GraphSceneData, ui: &mut Ui, ui_state: &mut UiState) {
+    let params = &mut data.noise_parameters;
+    let mut changed = false;
+
+    ui.add_space(5.0);
+    ui.label("Noise:");
+    ui.add_space(2.5);
+
+    Grid::new("noise parameter input").show(ui, |ui| {
+        ui.label("Seed");
+        changed |= ui.add(egui::DragValue::new(&mut params.seed)).changed();
+        ui.end_row();
+
+        ui.label("Octaves");
+        changed |= ui
+            .add(egui::Slider::new(&mut params.octaves, 1..=8))
+            .changed();
+        ui.end_row();
+
+        ui.label("Frequency");
+        changed |= ui
+            .add(egui::Slider::new(&mut params.frequency, 0.1..=8.0))
+            .changed();
+        ui.end_row();
+
+        ui.label("Persistence");
+        changed |= ui
+            .add(egui::Slider::new(&mut params.persistence, 0.1..=1.0))
+            .changed();
+        ui.end_row();
+    });
+
+    if changed && let Some(function) = try_parse_function_string(&ui_state.function_string, params)
+    {
+        data.graph_scene.function = Some(function);
+        data.graph_scene.needs_rebuild = true;
+    }
+}
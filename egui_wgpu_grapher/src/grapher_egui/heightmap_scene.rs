@@ -0,0 +1,36 @@
+//! UI specific to the heightmap mode.
+
+use crate::{
+    egui::{components::vertical_exaggeration_slider, ui::UiState},
+    grapher::scene::solid::heightmap::HeightmapScene,
+};
+
+use egui::Ui;
+
+pub struct HeightmapSceneData {
+    pub heightmap_scene: HeightmapScene,
+}
+
+impl HeightmapSceneData {
+    pub fn new(heightmap_scene: HeightmapScene) -> Self {
+        Self { heightmap_scene }
+    }
+}
+
+pub fn parameter_ui_heightmap(data: &mut HeightmapSceneData, ui: &mut Ui, ui_state: &mut UiState) {
+    if ui.add(egui::Button::new("Change file")).clicked() {
+        ui_state.show_file_input = true;
+    }
+
+    let scene = &mut data.heightmap_scene;
+    let scale_changed = ui
+        .add(egui::Slider::new(&mut scene.vertical_scale, 0.0..=3.0).text("Vertical scale"))
+        .changed();
+    let resolution_changed = ui
+        .add(egui::Slider::new(&mut scene.resolution, 16..=1024).text("Resolution"))
+        .changed();
+    if scale_changed || resolution_changed {
+        scene.needs_rebuild = true;
+    }
+    vertical_exaggeration_slider(&mut scene.vertical_exaggeration, ui);
+}
@@ -1,31 +1,139 @@
 //! UI specific to the image viewer mode.
 
-use crate::{egui::ui::UiState, grapher::scene::textured::image_viewer::ImageViewerScene};
+use crate::{
+    egui::{
+        components::{histogram_plot, texture_filtering_combo},
+        ui::UiState,
+    },
+    grapher::scene::textured::{
+        image_filter::{ImageFilter, ImageFilterPipeline},
+        image_histogram::{HistogramChannel, ImageHistogram},
+        image_viewer::ImageViewerScene,
+    },
+};
 
-use egui::Ui;
+use egui::{Color32, Ui};
 
-pub struct ImageViewerSceneUiData;
+pub struct ImageViewerSceneUiData {
+    // which image's histogram is shown
+    histogram_image: usize,
+}
 
 pub struct ImageViewerSceneData {
     pub image_viewer_scene: ImageViewerScene,
-    pub _ui_data: ImageViewerSceneUiData,
+    pub ui_data: ImageViewerSceneUiData,
 }
 
 impl ImageViewerSceneData {
     pub fn new(image_viewer_scene: ImageViewerScene) -> Self {
         Self {
             image_viewer_scene,
-            _ui_data: ImageViewerSceneUiData {},
+            ui_data: ImageViewerSceneUiData { histogram_image: 0 },
         }
     }
 }
 
 pub fn parameter_ui_image_viewer(
-    _data: &mut ImageViewerSceneData,
+    data: &mut ImageViewerSceneData,
     ui: &mut Ui,
     ui_state: &mut UiState,
 ) {
-    if ui.add(egui::Button::new("Change file")).clicked() {
-        ui_state.show_file_input = true;
+    ui.horizontal(|ui| {
+        if ui.add(egui::Button::new("Change file")).clicked() {
+            ui_state.show_file_input = true;
+            ui_state.append_file = false;
+        }
+        if ui.add(egui::Button::new("Open another image")).clicked() {
+            ui_state.show_file_input = true;
+            ui_state.append_file = true;
+        }
+    });
+
+    let scene = &mut data.image_viewer_scene;
+    ui.add_space(2.5);
+    ui.horizontal(|ui| {
+        ui.label("Filter");
+        egui::ComboBox::from_id_salt("image_filter")
+            .selected_text(scene.filter.label())
+            .show_ui(ui, |ui| {
+                for filter in ImageFilter::ALL {
+                    if ui
+                        .selectable_value(&mut scene.filter, filter, filter.label())
+                        .changed()
+                    {
+                        scene.needs_filter = true;
+                    }
+                }
+            });
+    });
+    if scene.filter.uses_radius() {
+        let slider = egui::Slider::new(
+            &mut scene.filter_radius,
+            1..=ImageFilterPipeline::MAX_RADIUS,
+        )
+        .text("Kernel radius");
+        // rerun the filter once the slider is released, not on every drag step
+        let response = ui.add(slider);
+        if response.drag_stopped() || (response.changed() && !response.dragged()) {
+            scene.needs_filter = true;
+        }
+    }
+
+    if texture_filtering_combo(&mut scene.texture_filtering, "image_texture_filtering", ui) {
+        scene.needs_resample = true;
+    }
+
+    let image_paths = &scene.image_paths;
+    if image_paths.len() > 1 {
+        ui.add_space(2.5);
+        for (i, path) in image_paths.iter().enumerate() {
+            let name = std::path::Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+            ui.label(format!("{}: {name}", i + 1));
+        }
+    }
+
+    egui::CollapsingHeader::new("Histogram")
+        .default_open(false)
+        .show(ui, |ui| {
+            let image_count = scene.histograms.len();
+            let selected = &mut data.ui_data.histogram_image;
+            *selected = (*selected).min(image_count.saturating_sub(1));
+            if image_count > 1 {
+                // numbered like the list of image paths above
+                egui::ComboBox::from_label("Image")
+                    .selected_text(format!("{}", *selected + 1))
+                    .show_ui(ui, |ui| {
+                        for i in 0..image_count {
+                            ui.selectable_value(selected, i, format!("{}", i + 1));
+                        }
+                    });
+            }
+            if let Some(histogram) = scene.histograms.get(*selected) {
+                histogram_ui(histogram, ui);
+            }
+        });
+}
+
+fn histogram_ui(histogram: &ImageHistogram, ui: &mut Ui) {
+    for channel in HistogramChannel::ALL {
+        let color = match channel {
+            HistogramChannel::Red => Color32::from_rgb(220, 80, 80),
+            HistogramChannel::Green => Color32::from_rgb(80, 190, 80),
+            HistogramChannel::Blue => Color32::from_rgb(90, 130, 230),
+            HistogramChannel::Luminance => ui.visuals().text_color(),
+        };
+        let stats = histogram.stats(channel);
+        ui.label(format!(
+            "{}: min {}, mean {:.1}, max {}",
+            channel.label(),
+            stats.min,
+            stats.mean,
+            stats.max
+        ));
+        histogram_plot(histogram.counts(channel), color, ui);
+        ui.add_space(2.5);
     }
 }
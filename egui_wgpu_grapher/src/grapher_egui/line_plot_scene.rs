@@ -0,0 +1,83 @@
+//! UI specific to the 2D line plot mode.
+
+use crate::grapher::{render::RenderState, scene::line_plot::LinePlotScene};
+
+use egui::{Grid, Ui};
+use egui_wgpu::wgpu::{Device, Queue};
+
+pub struct LinePlotSceneData {
+    pub line_plot_scene: LinePlotScene,
+}
+
+impl LinePlotSceneData {
+    pub fn new(device: &Device, queue: &Queue, state: &mut RenderState) -> Self {
+        let line_plot_scene = LinePlotScene::new(device, state);
+        line_plot_scene.frame_camera(queue, state);
+        Self { line_plot_scene }
+    }
+}
+
+pub fn parameter_ui_line_plot(data: &mut LinePlotSceneData, ui: &mut Ui) {
+    let scene = &mut data.line_plot_scene;
+    let mut changed = false;
+    let mut removed = None;
+
+    ui.label("y = f(x):");
+    ui.add_space(2.5);
+
+    let can_remove = scene.curves.len() > 1;
+    Grid::new("line plot curves").show(ui, |ui| {
+        for (i, curve) in scene.curves.iter_mut().enumerate() {
+            // The color swatch doubles as the curve's legend entry.
+            changed |= ui.color_edit_button_rgb(&mut curve.color).changed();
+
+            let response = ui.text_edit_singleline(&mut curve.expression);
+            changed |= response.lost_focus();
+            if !curve.valid {
+                response.on_hover_text("Invalid function; see log.");
+            }
+
+            if can_remove && ui.small_button("✕").clicked() {
+                removed = Some(i);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(i) = removed {
+        scene.curves.remove(i);
+        changed = true;
+    }
+    if scene.curves.iter().any(|curve| !curve.valid) {
+        ui.colored_label(ui.visuals().error_fg_color, "Some functions are invalid.");
+    }
+    if ui.button("Add curve").clicked() {
+        scene.add_curve("x");
+    }
+
+    ui.add_space(5.0);
+    let (x_min, x_max) = &mut scene.x_range;
+    ui.horizontal(|ui| {
+        ui.label("x range");
+        changed |= ui
+            .add(
+                egui::DragValue::new(x_min)
+                    .speed(0.1)
+                    .range(f64::MIN..=*x_max - 0.01),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::DragValue::new(x_max)
+                    .speed(0.1)
+                    .range(*x_min + 0.01..=f64::MAX),
+            )
+            .changed();
+    });
+
+    let (y_min, y_max) = scene.y_range();
+    ui.label(format!("y range: {y_min:.3} to {y_max:.3}"));
+
+    if changed {
+        scene.needs_rebuild = true;
+    }
+}
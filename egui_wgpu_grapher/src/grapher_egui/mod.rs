@@ -2,31 +2,56 @@
 //! calls based on the current mode, and to map GUI-modified state to
 //! internal handler functions for the current mode.
 
+pub mod complex_domain_scene;
+pub mod data_plot_scene;
+pub mod fractal_scene;
 pub mod graph_scene;
+pub mod heightmap_scene;
 pub mod image_scene;
+pub mod line_plot_scene;
 pub mod model_scene;
+pub mod pde_scene;
+pub mod schrodinger_scene;
 pub mod solver_scene;
+pub mod sphere_scene;
 
 use crate::{
-    egui::ui::UiState,
+    egui::{components::float_edit_line, ui::UiState},
     grapher::{
-        math::FunctionHolder,
-        pipeline::render_preferences::RenderPreferences,
+        CAMERA_PATH_FILE, CameraMode,
+        math::{FunctionHolder, graph::Normals, noise::NoiseParameters},
+        pipeline::{
+            light::LightState,
+            render_preferences::RenderPreferences,
+            tone_map::{ToneMapOperator, ToneMapState},
+        },
         render::{ShadowState, render_2d},
         scene::{
-            GpuVertex, RenderScene,
+            GpuVertex, RenderScene, Scene3D,
             solid::{MeshRenderData, graph::GraphScene},
         },
     },
     grapher_egui::{
+        complex_domain_scene::ComplexDomainSceneData,
+        fractal_scene::FractalSceneData,
         image_scene::{ImageViewerSceneData, parameter_ui_image_viewer},
         solver_scene::SolverSceneData,
     },
 };
+use data_plot_scene::{DataPlotSceneData, parameter_ui_data_plot};
 use graph_scene::{GraphSceneData, parameter_ui_graph};
+use heightmap_scene::{HeightmapSceneData, parameter_ui_heightmap};
+use line_plot_scene::{LinePlotSceneData, parameter_ui_line_plot};
 use model_scene::{ModelSceneData, parameter_ui_model};
+use pde_scene::{
+    HeatEquationSceneData, WaveEquationSceneData, parameter_ui_heat_equation,
+    parameter_ui_wave_equation,
+};
+use schrodinger_scene::{SchrodingerSceneData, parameter_ui_schrodinger};
+use sphere_scene::{SphereSceneData, parameter_ui_sphere};
 
-use egui::Ui;
+use cgmath::{EuclideanSpace, InnerSpace, Point3};
+use egui::{Grid, Ui};
 use egui_wgpu::wgpu::{CommandEncoder, Device, Queue, SurfaceConfiguration, TextureView};
 
 pub use crate::grapher::render::RenderState;
@@ -34,13 +59,22 @@ pub use crate::grapher::render::RenderState;
 // --------------------------------
 // Grapher mode chosen by the user.
 
-#[derive(clap::ValueEnum, Debug, Default, Clone, Copy)]
+#[derive(clap::ValueEnum, Debug, Default, Clone, Copy, PartialEq)]
 pub enum GrapherSceneMode {
     #[default]
     Graph,
     Model,
     ImageViewer,
     Solver,
+    Fractal,
+    Heightmap,
+    DataPlot,
+    LinePlot,
+    ComplexDomain,
+    Schrodinger,
+    Sphere,
+    Heat,
+    Wave,
 }
 
 impl From<GrapherSceneMode> for usize {
@@ -50,6 +84,15 @@ impl From<GrapherSceneMode> for usize {
             GrapherSceneMode::Model => 1,
             GrapherSceneMode::ImageViewer => 2,
             GrapherSceneMode::Solver => 3,
+            GrapherSceneMode::Fractal => 4,
+            GrapherSceneMode::Heightmap => 5,
+            GrapherSceneMode::DataPlot => 6,
+            GrapherSceneMode::LinePlot => 7,
+            GrapherSceneMode::ComplexDomain => 8,
+            GrapherSceneMode::Schrodinger => 9,
+            GrapherSceneMode::Sphere => 10,
+            GrapherSceneMode::Heat => 11,
+            GrapherSceneMode::Wave => 12,
         }
     }
 }
@@ -61,6 +104,15 @@ impl From<usize> for GrapherSceneMode {
             1 => GrapherSceneMode::Model,
             2 => GrapherSceneMode::ImageViewer,
             3 => GrapherSceneMode::Solver,
+            4 => GrapherSceneMode::Fractal,
+            5 => GrapherSceneMode::Heightmap,
+            6 => GrapherSceneMode::DataPlot,
+            7 => GrapherSceneMode::LinePlot,
+            8 => GrapherSceneMode::ComplexDomain,
+            9 => GrapherSceneMode::Schrodinger,
+            10 => GrapherSceneMode::Sphere,
+            11 => GrapherSceneMode::Heat,
+            12 => GrapherSceneMode::Wave,
             _ => unimplemented!(),
         }
     }
@@ -79,7 +131,21 @@ pub fn scene_selection_ui(
     ui_state: &mut UiState,
     ui: &mut Ui,
 ) -> Changed {
-    let alternatives = ["graph", "model", "image", "solver"];
+    let alternatives = [
+        "graph",
+        "model",
+        "image",
+        "solver",
+        "fractal",
+        "heightmap",
+        "data",
+        "plot",
+        "complex",
+        "schrodinger",
+        "sphere",
+        "heat",
+        "wave",
+    ];
     let selected_scene_index = &mut ui_state.selected_scene_index;
     let response = egui::ComboBox::from_id_salt("select scene").show_index(
         ui,
@@ -108,6 +174,15 @@ pub enum GrapherScene {
     Model(ModelSceneData),
     ImageViewer(ImageViewerSceneData),
     Solver(SolverSceneData),
+    Fractal(FractalSceneData),
+    Heightmap(HeightmapSceneData),
+    DataPlot(DataPlotSceneData),
+    LinePlot(LinePlotSceneData),
+    ComplexDomain(ComplexDomainSceneData),
+    Schrodinger(SchrodingerSceneData),
+    Sphere(SphereSceneData),
+    Heat(HeatEquationSceneData),
+    Wave(WaveEquationSceneData),
 }
 
 impl GrapherScene {
@@ -124,16 +199,74 @@ impl GrapherScene {
         match self {
             GrapherScene::Graph(data) => {
                 if data.graph_scene.scene.is_some() {
-                    render_state.render(view, encoder, data.graph_scene.scene());
+                    let scene = data.graph_scene.scene();
+                    if let Some(pipeline) = data.graph_scene.reflection_pipeline() {
+                        render_state.render_reflection(encoder, scene, pipeline);
+                    }
+                    render_state.render(view, encoder, scene);
+                    render_state.render_floor(view, encoder);
+                    if let Some(plane) = data.graph_scene.reference_plane() {
+                        render_state.render_reference_plane(view, encoder, plane);
+                    }
+                    render_state.render_depth_of_field(encoder);
+                    render_state.render_gizmo(view, encoder);
                 }
             }
             GrapherScene::Model(data) => {
                 render_state.render(view, encoder, data.model_scene.scene());
+                render_state.render_depth_of_field(encoder);
+                render_state.render_gizmo(view, encoder);
             }
             GrapherScene::ImageViewer(data) => {
                 render_state.render(view, encoder, data.image_viewer_scene.scene());
             }
-            GrapherScene::Solver(data) => {
+            GrapherScene::Heightmap(data) => {
+                render_state.render(view, encoder, data.heightmap_scene.scene());
+                render_state.render_depth_of_field(encoder);
+                render_state.render_gizmo(view, encoder);
+            }
+            GrapherScene::Schrodinger(data) => {
+                render_state.render(view, encoder, data.schrodinger_scene.scene());
+                render_state.render_depth_of_field(encoder);
+                render_state.render_gizmo(view, encoder);
+            }
+            GrapherScene::Sphere(data) => {
+                render_state.render(view, encoder, data.sphere_scene.scene());
+                render_state.render_depth_of_field(encoder);
+                render_state.render_gizmo(view, encoder);
+            }
+            GrapherScene::Heat(data) => {
+                render_state.render(view, encoder, data.heat_scene.scene());
+                render_state.render_depth_of_field(encoder);
+                render_state.render_gizmo(view, encoder);
+            }
+            GrapherScene::Wave(data) => {
+                render_state.render(view, encoder, data.wave_scene.scene());
+                render_state.render_depth_of_field(encoder);
+                render_state.render_gizmo(view, encoder);
+            }
+            GrapherScene::DataPlot(data) => {
+                if data.data_plot_scene.scene.is_some() {
+                    render_state.render(view, encoder, data.data_plot_scene.scene());
+                    render_state.render_depth_of_field(encoder);
+                    render_state.render_gizmo(view, encoder);
+                }
+            }
+            GrapherScene::LinePlot(data) => {
+                if data.line_plot_scene.scene.is_some() {
+                    render_state.render(view, encoder, data.line_plot_scene.scene());
+                }
+            }
+            GrapherScene::Solver(data) => match &data.surface {
+                Some(surface) if data.show_surface => {
+                    render_state.render(view, encoder, &surface.scene);
+                }
+                _ => render_2d(view, encoder, &data.scene, render_state),
+            },
+            GrapherScene::Fractal(data) => {
+                render_2d(view, encoder, &data.scene, render_state);
+            }
+            GrapherScene::ComplexDomain(data) => {
                 render_2d(view, encoder, &data.scene, render_state);
             }
             _ => unimplemented!(),
@@ -141,8 +274,14 @@ impl GrapherScene {
     }
 
     pub fn compute(&mut self, device: &Device, queue: &Queue) {
-        if let GrapherScene::Solver(data) = self {
-            data.run_solver(device, queue);
+        match self {
+            GrapherScene::Solver(data) => {
+                data.run_solver(device, queue);
+            }
+            GrapherScene::Fractal(data) => {
+                data.run_compute(device, queue);
+            }
+            _ => {}
         }
     }
 
@@ -157,47 +296,137 @@ impl GrapherScene {
             GrapherScene::Graph(data) => {
                 // Rebuild scene if non-uniform parameters changed.
                 if data.graph_scene.needs_rebuild {
-                    data.graph_scene.try_rebuild_scene(
-                        device,
-                        surface_config,
-                        state,
-                        data.smoothing_scale,
-                    );
+                    data.graph_scene
+                        .try_rebuild_scene(state, data.smoothing_scale);
                     data.graph_scene.needs_rebuild = false;
                 }
+                data.graph_scene
+                    .continue_rebuild(device, queue, surface_config, state);
+                if data.graph_scene.needs_lines_update {
+                    data.graph_scene.update_lines(device, state);
+                }
+                data.graph_scene
+                    .update_reference_plane(device, queue, state);
+                data.update_surface_shader(device, state);
+                data.export_heatmap(device, queue);
                 data.graph_scene.update(queue, state);
             }
             GrapherScene::Model(data) => {
+                if data.model_scene.needs_rebuild {
+                    data.model_scene.rebuild_pipeline(device, state);
+                    data.model_scene.needs_rebuild = false;
+                }
+                data.update(device, queue, surface_config, state);
                 data.model_scene.update(queue, state);
             }
             GrapherScene::ImageViewer(data) => {
+                if data.image_viewer_scene.needs_rebuild {
+                    data.image_viewer_scene.rebuild_pipeline(device, state);
+                    data.image_viewer_scene.needs_rebuild = false;
+                }
+                if data.image_viewer_scene.needs_filter {
+                    data.image_viewer_scene.apply_filter(device, queue);
+                }
+                if data.image_viewer_scene.needs_resample {
+                    data.image_viewer_scene.apply_texture_filtering(device);
+                }
                 data.image_viewer_scene.update(queue, state);
             }
+            GrapherScene::Heightmap(data) => {
+                let heightmap_scene = &mut data.heightmap_scene;
+                if heightmap_scene.needs_rebuild {
+                    heightmap_scene.rebuild_scene(device, surface_config, state);
+                    heightmap_scene.needs_rebuild = false;
+                }
+                heightmap_scene.update(queue, state);
+            }
+            GrapherScene::Sphere(data) => {
+                let sphere_scene = &mut data.sphere_scene;
+                if sphere_scene.needs_rebuild {
+                    sphere_scene.rebuild_scene(device, surface_config, state);
+                    sphere_scene.needs_rebuild = false;
+                }
+                sphere_scene.update(queue, state);
+            }
+            GrapherScene::DataPlot(data) => {
+                let data_plot_scene = &mut data.data_plot_scene;
+                if data_plot_scene.needs_rebuild {
+                    // A failed rebuild keeps the previous plot and shows the error.
+                    let _ = data_plot_scene.rebuild_scene(device, surface_config, state);
+                    data_plot_scene.needs_rebuild = false;
+                }
+                data_plot_scene.update(queue, state);
+            }
+            GrapherScene::LinePlot(data) => {
+                let line_plot_scene = &mut data.line_plot_scene;
+                if line_plot_scene.needs_rebuild {
+                    line_plot_scene.rebuild_scene(device, state);
+                    line_plot_scene.needs_rebuild = false;
+                }
+            }
             GrapherScene::Solver(data) => {
-                data.update(queue);
+                data.update(device, queue, state);
+            }
+            GrapherScene::Fractal(data) => {
+                data.update(queue, state);
+            }
+            GrapherScene::ComplexDomain(data) => {
+                data.update(queue, state);
+            }
+            GrapherScene::Schrodinger(data) => {
+                data.schrodinger_scene.update(queue, state);
+            }
+            GrapherScene::Heat(data) => {
+                data.heat_scene.update(queue, state);
+            }
+            GrapherScene::Wave(data) => {
+                data.wave_scene.update(queue, state);
             }
             _ => unimplemented!(),
         }
+        if let Some(scene) = self.lit_scene_mut() {
+            state.light_placement.apply(queue, &mut scene.light);
+        }
     }
 
-    pub fn update_graph(
-        &mut self,
-        device: &Device,
-        surface_config: &SurfaceConfiguration,
-        state: &RenderState,
-        function: FunctionHolder,
-    ) {
+    pub fn update_graph(&mut self, state: &RenderState, function: FunctionHolder) {
         if let GrapherScene::Graph(data) = self {
             data.graph_scene.function = Some(function);
             data.graph_scene
-                .try_rebuild_scene(device, surface_config, state, data.smoothing_scale);
+                .try_rebuild_scene(state, data.smoothing_scale);
+        }
+    }
+
+    /// Graph point under the cursor, given in physical pixels.
+    pub fn graph_point_at_cursor(
+        &self,
+        state: &RenderState,
+        surface_config: &SurfaceConfiguration,
+        cursor: [f32; 2],
+    ) -> Option<[f64; 3]> {
+        let GrapherScene::Graph(data) = self else {
+            return None;
+        };
+        let ndc = [
+            2.0 * cursor[0] / surface_config.width as f32 - 1.0,
+            1.0 - 2.0 * cursor[1] / surface_config.height as f32,
+        ];
+        let (origin, direction) = state.camera_state.camera.cursor_ray(ndc)?;
+        data.graph_scene.ray_intersection(origin, direction)
+    }
+
+    pub fn noise_parameters(&self) -> NoiseParameters {
+        if let GrapherScene::Graph(data) = self {
+            data.noise_parameters
+        } else {
+            NoiseParameters::default()
         }
     }
 
     pub fn parameter_ui(&mut self, ui: &mut Ui, ui_state: &mut UiState) {
         match self {
             GrapherScene::Graph(data) => {
-                parameter_ui_graph(data, ui);
+                parameter_ui_graph(data, ui, ui_state);
             }
             GrapherScene::Model(data) => {
                 parameter_ui_model(data, ui, ui_state);
@@ -205,23 +434,95 @@ impl GrapherScene {
             GrapherScene::ImageViewer(data) => {
                 parameter_ui_image_viewer(data, ui, ui_state);
             }
+            GrapherScene::Heightmap(data) => {
+                parameter_ui_heightmap(data, ui, ui_state);
+            }
+            GrapherScene::DataPlot(data) => {
+                parameter_ui_data_plot(data, ui, ui_state);
+            }
+            GrapherScene::LinePlot(data) => {
+                parameter_ui_line_plot(data, ui);
+            }
             GrapherScene::Solver(data) => {
                 data.parameter_ui(ui);
             }
+            GrapherScene::Fractal(data) => {
+                data.parameter_ui(ui);
+            }
+            GrapherScene::ComplexDomain(data) => {
+                data.parameter_ui(ui);
+            }
+            GrapherScene::Schrodinger(data) => {
+                parameter_ui_schrodinger(data, ui);
+            }
+            GrapherScene::Sphere(data) => {
+                parameter_ui_sphere(data, ui);
+            }
+            GrapherScene::Heat(data) => {
+                parameter_ui_heat_equation(data, ui);
+            }
+            GrapherScene::Wave(data) => {
+                parameter_ui_wave_equation(data, ui);
+            }
             _ => {}
         }
     }
 
+    /// Return camera to the default framing for the current scene.
+    pub fn reset_camera(
+        &mut self,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &mut RenderState,
+    ) {
+        match self {
+            GrapherScene::ImageViewer(data) => {
+                state.camera_state.reset_camera(queue, surface_config);
+                data.image_viewer_scene.reset_camera(queue, state);
+            }
+            GrapherScene::Model(data) => {
+                state.camera_state.reset_camera(queue, surface_config);
+                data.model_scene.frame_camera(queue, state);
+            }
+            GrapherScene::Fractal(data) => {
+                data.reset_camera(queue, surface_config, state);
+            }
+            GrapherScene::ComplexDomain(data) => {
+                data.reset_camera(queue, surface_config, state);
+            }
+            GrapherScene::LinePlot(data) => {
+                state.camera_state.reset_camera(queue, surface_config);
+                data.line_plot_scene.frame_camera(queue, state);
+            }
+            _ => {
+                state.camera_state.reset_camera(queue, surface_config);
+            }
+        }
+        state.camera_state.update_view_matrix(queue);
+    }
+
     pub fn set_needs_rebuild(&mut self, needs_update: bool) {
         match self {
             GrapherScene::Graph(data) => {
                 data.graph_scene.needs_rebuild = needs_update;
             }
-            GrapherScene::Model(_data) => {
-                // no-op
+            GrapherScene::Model(data) => {
+                data.model_scene.needs_rebuild = needs_update;
+            }
+            GrapherScene::ImageViewer(data) => {
+                data.image_viewer_scene.needs_rebuild = needs_update;
+            }
+            GrapherScene::Heightmap(data) => {
+                data.heightmap_scene.needs_rebuild = needs_update;
+            }
+            GrapherScene::Sphere(data) => {
+                data.sphere_scene.needs_rebuild = needs_update;
             }
-            GrapherScene::ImageViewer(_data) => {
-                // no-op
+            GrapherScene::DataPlot(data) => {
+                data.data_plot_scene.needs_rebuild = needs_update;
+            }
+            GrapherScene::LinePlot(data) => {
+                data.line_plot_scene.needs_rebuild = needs_update;
             }
             _ => unimplemented!(),
         }
@@ -234,14 +535,41 @@ impl GrapherScene {
         surface_config: &SurfaceConfiguration,
     ) {
         self.rebuild_shadow_state(device, surface_config);
-        if let GrapherScene::Solver(data) = self {
-            data.handle_resize(queue, surface_config);
+        match self {
+            GrapherScene::Solver(data) => {
+                data.handle_resize(queue, surface_config);
+            }
+            GrapherScene::Fractal(data) => {
+                data.handle_resize(queue, surface_config);
+            }
+            GrapherScene::ComplexDomain(data) => {
+                data.handle_resize(queue, surface_config);
+            }
+            GrapherScene::DataPlot(data) => {
+                data.data_plot_scene.handle_resize(queue, surface_config);
+            }
+            _ => {}
+        }
+    }
+
+    /// The current scene, if it's a 3D scene lit by its own light.
+    fn lit_scene_mut(&mut self) -> Option<&mut Scene3D> {
+        match self {
+            GrapherScene::Graph(data) => data.graph_scene.scene.as_mut(),
+            GrapherScene::Model(data) => Some(&mut data.model_scene.scene),
+            GrapherScene::Heightmap(data) => Some(&mut data.heightmap_scene.scene),
+            GrapherScene::Schrodinger(data) => Some(&mut data.schrodinger_scene.scene),
+            GrapherScene::Sphere(data) => Some(&mut data.sphere_scene.scene),
+            GrapherScene::Heat(data) => Some(&mut data.heat_scene.scene),
+            GrapherScene::Wave(data) => Some(&mut data.wave_scene.scene),
+            GrapherScene::DataPlot(data) => data.data_plot_scene.scene.as_mut(),
+            _ => None,
         }
     }
 
     fn rebuild_shadow_state(&mut self, device: &Device, surface_config: &SurfaceConfiguration) {
-        if let GrapherScene::Graph(data) = self
-            && let Some(scene) = &mut data.graph_scene.scene
+        // Model scenes have only textured meshes, which cast no shadows.
+        if let Some(scene) = self.lit_scene_mut()
             && !scene.meshes.is_empty()
         {
             let shadow = ShadowState::create::<GpuVertex>(
@@ -262,17 +590,44 @@ impl GrapherScene {
 pub struct RenderUiState {
     pub lighting_enabled: bool,
     pub use_wireframe: bool,
+    pub backface_culling: bool,
+    pub alpha_cutout: bool,
     pub shadow_enabled: bool,
+    pub two_sided_enabled: bool,
+    pub ssao_enabled: bool,
+    pub fog_enabled: bool,
+    pub clip_enabled: bool,
+    // clipping plane is normal to this axis, 0, 1, 2 for x, y, z
+    pub clip_axis: usize,
+    pub clip_offset: f32,
+    pub clip_flipped: bool,
+    pub camera_edit: CameraEditState,
     pub needs_prefs_uniform_write: bool,
 }
 
+// Text for the camera position fields, kept in step with the camera.
+#[derive(Default)]
+pub struct CameraEditState {
+    // eye, target and up, in world space
+    values: [[f64; 3]; 3],
+    text: [[String; 3]; 3],
+    error: Option<&'static str>,
+}
+
 impl From<&RenderPreferences> for RenderUiState {
     fn from(render_prefs: &RenderPreferences) -> Self {
         Self {
             lighting_enabled: render_prefs.lighting_enabled(),
             use_wireframe: render_prefs.wireframe_enabled(),
+            backface_culling: render_prefs.backface_culling_enabled(),
+            alpha_cutout: render_prefs.alpha_cutout_enabled(),
             shadow_enabled: render_prefs.shadow_enabled(),
+            two_sided_enabled: render_prefs.two_sided_lighting_enabled(),
+            ssao_enabled: render_prefs.ssao_enabled(),
+            fog_enabled: render_prefs.fog_enabled(),
+            clip_enabled: render_prefs.clip_enabled(),
             needs_prefs_uniform_write: false,
+            ..Default::default()
         }
     }
 }
@@ -292,7 +647,15 @@ pub fn render_parameter_ui(
             render_ui_state.needs_prefs_uniform_write = true;
         }
 
-        if matches!(grapher_scene, GrapherScene::Graph(_)) {
+        if matches!(
+            grapher_scene,
+            GrapherScene::Graph(_)
+                | GrapherScene::Model(_)
+                | GrapherScene::ImageViewer(_)
+                | GrapherScene::Heightmap(_)
+                | GrapherScene::DataPlot(_)
+                | GrapherScene::Sphere(_)
+        ) {
             let response = ui.checkbox(&mut render_ui_state.use_wireframe, "Wireframe ");
             if response.changed() {
                 render_state
@@ -302,24 +665,530 @@ pub fn render_parameter_ui(
                 grapher_scene.set_needs_rebuild(true);
             }
         }
+        if matches!(
+            grapher_scene,
+            GrapherScene::Graph(_)
+                | GrapherScene::Model(_)
+                | GrapherScene::Heightmap(_)
+                | GrapherScene::DataPlot(_)
+                | GrapherScene::Sphere(_)
+        ) {
+            let response = ui
+                .checkbox(&mut render_ui_state.backface_culling, "Cull back faces")
+                .on_hover_text("Hide triangles facing away, to check a model's winding.");
+            if response.changed() {
+                render_state
+                    .render_preferences
+                    .set_backface_culling(render_ui_state.backface_culling);
+                grapher_scene.set_needs_rebuild(true);
+            }
+        }
     });
-    if matches!(grapher_scene, GrapherScene::Graph(_)) {
-        let response = ui.checkbox(&mut render_ui_state.shadow_enabled, "Shadow ");
+    if matches!(
+        grapher_scene,
+        GrapherScene::Model(_) | GrapherScene::ImageViewer(_)
+    ) {
+        let response = ui
+            .checkbox(&mut render_ui_state.alpha_cutout, "Alpha cutout")
+            .on_hover_text(
+                "Leave out transparent parts of textures. With MSAA, alpha to \
+                 coverage smooths their edges, more so with more samples; \
+                 without it, edges are hard.",
+            );
         if response.changed() {
             render_state
                 .render_preferences
-                .set_shadow_enabled(render_ui_state.shadow_enabled);
+                .set_alpha_cutout(render_ui_state.alpha_cutout);
             render_ui_state.needs_prefs_uniform_write = true;
+            // alpha to coverage is part of the pipeline
+            grapher_scene.set_needs_rebuild(true);
         }
     }
-    let response = ui.checkbox(
-        &mut render_state.camera_state.camera.relative_rotation,
-        "Relative rotation",
-    );
+    if matches!(
+        grapher_scene,
+        GrapherScene::Graph(_)
+            | GrapherScene::Heightmap(_)
+            | GrapherScene::DataPlot(_)
+            | GrapherScene::Sphere(_)
+    ) {
+        ui.horizontal(|ui| {
+            let response = ui.checkbox(&mut render_ui_state.shadow_enabled, "Shadow ");
+            if response.changed() {
+                render_state
+                    .render_preferences
+                    .set_shadow_enabled(render_ui_state.shadow_enabled);
+                render_ui_state.needs_prefs_uniform_write = true;
+            }
+
+            // Lights the underside of a surface as seen from below.
+            let response = ui.checkbox(&mut render_ui_state.two_sided_enabled, "Two-sided ");
+            if response.changed() {
+                render_state
+                    .render_preferences
+                    .set_two_sided_lighting(render_ui_state.two_sided_enabled);
+                render_ui_state.needs_prefs_uniform_write = true;
+            }
+        });
+        // Sphere normals always come from its parameterization.
+        if !matches!(grapher_scene, GrapherScene::Sphere(_)) {
+            ui.horizontal(|ui| {
+                ui.label("Shading");
+                let shading = &mut render_state.render_preferences.shading;
+                let flat = ui.radio_value(shading, Normals::Flat, "Flat").changed();
+                let smooth = ui.radio_value(shading, Normals::Smooth, "Smooth").changed();
+                // The graph updates its normals in place; other surfaces are rebuilt.
+                if (flat || smooth) && !matches!(grapher_scene, GrapherScene::Graph(_)) {
+                    grapher_scene.set_needs_rebuild(true);
+                }
+            });
+        }
+        opacity_ui(render_state, render_ui_state, grapher_scene, ui);
+    }
+    ui.horizontal(|ui| {
+        let response = ui.checkbox(&mut render_ui_state.fog_enabled, "Fog ");
+        if response.changed() {
+            render_state
+                .render_preferences
+                .set_fog_enabled(render_ui_state.fog_enabled);
+            render_ui_state.needs_prefs_uniform_write = true;
+        }
+        if render_ui_state.fog_enabled {
+            let uniform = &mut render_state.render_preferences.uniform;
+            let density_changed = ui
+                .add(egui::Slider::new(&mut uniform.fog_density, 0.0..=0.5).text("Density"))
+                .changed();
+            let color_changed = ui.color_edit_button_rgb(&mut uniform.fog_color).changed();
+            if density_changed || color_changed {
+                render_ui_state.needs_prefs_uniform_write = true;
+            }
+        }
+    });
+    clip_plane_ui(render_state, render_ui_state, ui);
+    tone_map_ui(&mut render_state.tone_map, render_ui_state, ui);
+    ui.horizontal(|ui| {
+        let response = ui.checkbox(
+            &mut render_state.camera_state.camera.relative_rotation,
+            "Relative rotation",
+        );
+        if response.changed() {
+            render_state
+                .camera_state
+                .camera
+                .on_relative_rotation_change();
+        }
+        if ui
+            .button("Level horizon")
+            .on_hover_text("Remove any camera roll (L).")
+            .clicked()
+        {
+            render_state.camera_state.camera.level_horizon();
+        }
+    });
+    if matches!(
+        grapher_scene,
+        GrapherScene::Graph(_)
+            | GrapherScene::Model(_)
+            | GrapherScene::Heightmap(_)
+            | GrapherScene::DataPlot(_)
+            | GrapherScene::Schrodinger(_)
+            | GrapherScene::Sphere(_)
+            | GrapherScene::Heat(_)
+            | GrapherScene::Wave(_)
+    ) {
+        let controller = &mut render_state.camera_state.controller;
+        ui.horizontal(|ui| {
+            ui.label("Camera");
+            ui.radio_value(&mut controller.mode, CameraMode::Orbit, "Orbit")
+                .on_hover_text("Drag to rotate the scene; keys rotate and zoom.");
+            ui.radio_value(&mut controller.mode, CameraMode::Fly, "Fly")
+                .on_hover_text("Drag to look around; W, A, S, D move through the scene.");
+        });
+        camera_position_ui(render_state, render_ui_state, ui);
+
+        let gizmo = &mut render_state.gizmo;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut gizmo.enabled, "Pivot gizmo ")
+                .on_hover_text("Show axes at the point the camera orbits around.");
+            if gizmo.enabled {
+                ui.checkbox(&mut gizmo.always_on_top, "On top");
+            }
+        });
+
+        let controller = &mut render_state.camera_state.controller;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut controller.turntable, "Turntable ")
+                .on_hover_text("Slowly orbit the scene about the vertical axis.");
+            if controller.turntable {
+                ui.add(
+                    egui::Slider::new(&mut controller.turntable_speed, -2.0..=2.0)
+                        .text("Speed")
+                        .suffix(" rad/s"),
+                );
+            }
+        });
+        camera_path_ui(render_state, ui);
+        ssao_ui(render_state, render_ui_state, ui);
+        depth_of_field_ui(render_state, ui);
+        light_placement_ui(render_state, ui);
+    }
+    if matches!(grapher_scene, GrapherScene::Graph(_)) {
+        floor_ui(render_state, grapher_scene, ui);
+    }
+}
+
+// Surface opacity, with a depth prepass option for transparent surfaces.
+fn opacity_ui(
+    render_state: &mut RenderState,
+    render_ui_state: &mut RenderUiState,
+    grapher_scene: &mut GrapherScene,
+    ui: &mut Ui,
+) {
+    let prefs = &mut render_state.render_preferences;
+    let was_transparent = prefs.is_transparent();
+
+    ui.horizontal(|ui| {
+        let response =
+            ui.add(egui::Slider::new(&mut prefs.uniform.opacity, 0.05..=1.0).text("Opacity"));
+        if response.changed() {
+            render_ui_state.needs_prefs_uniform_write = true;
+        }
+
+        if prefs.is_transparent() {
+            let response = ui
+                .checkbox(&mut prefs.depth_prepass, "Depth prepass")
+                .on_hover_text(
+                    "Blend only the nearest layer of each surface. Without it, \
+                     all layers blend in draw order, which can look wrong where \
+                     surfaces overlap.",
+                );
+            if response.changed() {
+                grapher_scene.set_needs_rebuild(true);
+            }
+        }
+    });
+
+    // Depth writes are turned off for transparent surfaces, which
+    // needs a new pipeline.
+    if prefs.is_transparent() != was_transparent {
+        grapher_scene.set_needs_rebuild(true);
+    }
+}
+
+// Exact eye, target and up entry, for reproducing a view.
+fn camera_position_ui(
+    render_state: &mut RenderState,
+    render_ui_state: &mut RenderUiState,
+    ui: &mut Ui,
+) {
+    const EPSILON: f32 = 1e-4;
+
+    let camera = &mut render_state.camera_state.camera;
+    let edit = &mut render_ui_state.camera_edit;
+
+    // follow the camera as it is moved interactively
+    let (eye, target, up) = camera.world_view();
+    let current: [[f32; 3]; 3] = [eye.into(), target.into(), up.into()];
+    for ((values, text), current) in edit.values.iter_mut().zip(&mut edit.text).zip(current) {
+        for ((value, text), current) in values.iter_mut().zip(text).zip(current) {
+            if *value as f32 != current {
+                *value = current as f64;
+                *text = format!("{current:.3}");
+            }
+        }
+    }
+
+    egui::CollapsingHeader::new("Camera position")
+        .default_open(false)
+        .show(ui, |ui| {
+            let [eye, target, up] =
+                current.map(|v| format!("({:.3}, {:.3}, {:.3})", v[0], v[1], v[2]));
+            ui.label(
+                egui::RichText::new(format!("eye {eye}\ntarget {target}\nup {up}")).monospace(),
+            );
+            ui.add_space(2.5);
+
+            let mut changed = false;
+            Grid::new("camera position input").show(ui, |ui| {
+                let vectors = edit.values.iter_mut().zip(&mut edit.text);
+                for (name, (values, text)) in ["eye", "target", "up"].into_iter().zip(vectors) {
+                    for (axis, (value, text)) in
+                        ["x", "y", "z"].into_iter().zip(values.iter_mut().zip(text))
+                    {
+                        changed |= float_edit_line(&format!("{name} {axis}"), text, value, ui);
+                        ui.end_row();
+                    }
+                }
+            });
+
+            if changed {
+                let [eye, target, up] = edit
+                    .values
+                    .map(|v| cgmath::Vector3::new(v[0] as f32, v[1] as f32, v[2] as f32));
+                let direction = target - eye;
+                edit.error = if direction.magnitude() < EPSILON {
+                    Some("The eye and target must differ.")
+                } else if up.magnitude() < EPSILON
+                    || direction.normalize().cross(up.normalize()).magnitude() < EPSILON
+                {
+                    Some("The up vector must not be along the view direction.")
+                } else {
+                    camera.set_world_view(Point3::from_vec(eye), Point3::from_vec(target), up);
+                    None
+                };
+            }
+            // rejected values are replaced by the camera's on the next frame
+            if let Some(error) = edit.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+}
+
+// Keyframes for a camera fly-around, which K also records.
+fn camera_path_ui(render_state: &mut RenderState, ui: &mut Ui) {
+    let camera_state = &mut render_state.camera_state;
+    let path = &mut camera_state.path;
+    let camera = &mut camera_state.camera;
+
+    egui::CollapsingHeader::new("Camera path")
+        .default_open(false)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("Keyframes: {} ", path.keyframes.len()));
+                if ui
+                    .button("Add")
+                    .on_hover_text("Add the current view (K).")
+                    .clicked()
+                {
+                    path.add_keyframe(camera);
+                }
+                if ui.button("Clear").clicked() {
+                    path.clear();
+                }
+            });
+            ui.add(
+                egui::Slider::new(&mut path.duration, 1.0..=120.0)
+                    .logarithmic(true)
+                    .text("Duration")
+                    .suffix(" s"),
+            );
+
+            ui.horizontal(|ui| {
+                if path.is_playing() {
+                    if ui.button("Stop").clicked() {
+                        path.stop(camera);
+                    }
+                } else if ui
+                    .add_enabled(path.can_play(), egui::Button::new("Play"))
+                    .on_disabled_hover_text("Add at least two keyframes.")
+                    .clicked()
+                {
+                    path.play(camera);
+                }
+
+                let save = ui
+                    .button("Save")
+                    .on_hover_text(format!("Write the keyframes to {CAMERA_PATH_FILE}."));
+                if save.clicked() {
+                    match path.save() {
+                        Ok(()) => log::info!("Saved camera path to {CAMERA_PATH_FILE}"),
+                        Err(err) => log::error!("Failed to save camera path: {err}"),
+                    }
+                }
+                let load = ui
+                    .button("Load")
+                    .on_hover_text(format!("Read the keyframes from {CAMERA_PATH_FILE}."));
+                if load.clicked()
+                    && let Err(err) = path.load(camera)
+                {
+                    log::error!("Failed to load camera path: {err}");
+                }
+            });
+            if let Some(progress) = path.progress() {
+                ui.add(egui::ProgressBar::new(progress).desired_width(160.0));
+            }
+        });
+}
+
+fn light_placement_ui(render_state: &mut RenderState, ui: &mut Ui) {
+    // half-width of the box the light can be placed in
+    const LIGHT_RANGE: f32 = 10.0;
+
+    let placement = &mut render_state.light_placement;
+    ui.checkbox(&mut placement.manual, "Place light")
+        .on_hover_text("Set the light position, which also aims the shadow map.");
+    if !placement.manual {
+        return;
+    }
+    for (coordinate, label) in placement.position.iter_mut().zip(["X", "Y", "Z"]) {
+        ui.add(egui::Slider::new(coordinate, -LIGHT_RANGE..=LIGHT_RANGE).text(label));
+    }
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut placement.show_marker, "Marker ");
+        if ui.button("Reset").clicked() {
+            placement.position = LightState::DEFAULT_LIGHT_POS;
+        }
+    });
+}
+
+fn clip_plane_ui(render_state: &mut RenderState, render_ui_state: &mut RenderUiState, ui: &mut Ui) {
+    // loaded models are normalized to fit in a box of this half-width
+    // TODO: Fit to the bounds of the current scene.
+    const CLIP_RANGE: f32 = 3.0;
+
+    let mut changed = ui
+        .checkbox(&mut render_ui_state.clip_enabled, "Clipping plane")
+        .changed();
+    if render_ui_state.clip_enabled {
+        ui.horizontal(|ui| {
+            for (axis, label) in ["X", "Y", "Z"].into_iter().enumerate() {
+                changed |= ui
+                    .radio_value(&mut render_ui_state.clip_axis, axis, label)
+                    .changed();
+            }
+            changed |= ui
+                .checkbox(&mut render_ui_state.clip_flipped, "Flip")
+                .changed();
+        });
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut render_ui_state.clip_offset, -CLIP_RANGE..=CLIP_RANGE)
+                    .text("Offset"),
+            )
+            .changed();
+    }
+    if changed {
+        let render_preferences = &mut render_state.render_preferences;
+        render_preferences.set_clip_enabled(render_ui_state.clip_enabled);
+        render_preferences.set_axis_clip_plane(
+            render_ui_state.clip_axis,
+            render_ui_state.clip_offset,
+            render_ui_state.clip_flipped,
+        );
+        render_ui_state.needs_prefs_uniform_write = true;
+    }
+}
+
+fn tone_map_ui(tone_map: &mut ToneMapState, render_ui_state: &mut RenderUiState, ui: &mut Ui) {
+    let mut enabled = tone_map.enabled();
+    if ui.checkbox(&mut enabled, "Tone mapping").changed() {
+        tone_map.set_enabled(enabled);
+        render_ui_state.needs_prefs_uniform_write = true;
+    }
+    if !enabled {
+        return;
+    }
+
+    let mut operator = tone_map.operator();
+    egui::ComboBox::from_id_salt("tone map operator")
+        .selected_text(operator.label())
+        .show_ui(ui, |ui| {
+            for alternative in ToneMapOperator::ALL {
+                ui.selectable_value(&mut operator, alternative, alternative.label());
+            }
+        });
+    if operator != tone_map.operator() {
+        tone_map.set_operator(operator);
+        render_ui_state.needs_prefs_uniform_write = true;
+    }
+
+    let exposure_changed = ui
+        .add(
+            egui::Slider::new(&mut tone_map.uniform.exposure, 0.05..=8.0)
+                .logarithmic(true)
+                .text("Exposure"),
+        )
+        .changed();
+    let gamma_changed = ui
+        .add(egui::Slider::new(&mut tone_map.uniform.gamma, 1.0..=3.0).text("Gamma"))
+        .changed();
+    if exposure_changed || gamma_changed {
+        render_ui_state.needs_prefs_uniform_write = true;
+    }
+    if ui.button("Reset to neutral").clicked() {
+        tone_map.uniform.exposure = ToneMapState::NEUTRAL_EXPOSURE;
+        tone_map.uniform.gamma = ToneMapState::NEUTRAL_GAMMA;
+        render_ui_state.needs_prefs_uniform_write = true;
+    }
+}
+
+// Ambient occlusion; its uniform is written with the camera each frame.
+fn ssao_ui(render_state: &mut RenderState, render_ui_state: &mut RenderUiState, ui: &mut Ui) {
+    let response = ui
+        .add_enabled(
+            render_state.ssao.supported(),
+            egui::Checkbox::new(&mut render_ui_state.ssao_enabled, "Ambient occlusion"),
+        )
+        .on_disabled_hover_text("Not supported by the GL backend, which can't read depth.");
     if response.changed() {
         render_state
-            .camera_state
-            .camera
-            .on_relative_rotation_change();
+            .render_preferences
+            .set_ssao_enabled(render_ui_state.ssao_enabled);
+        render_ui_state.needs_prefs_uniform_write = true;
+    }
+    if !render_ui_state.ssao_enabled {
+        return;
+    }
+
+    let uniform = &mut render_state.ssao.uniform;
+    ui.add(
+        egui::Slider::new(&mut uniform.radius, 0.05..=4.0)
+            .logarithmic(true)
+            .text("Radius"),
+    );
+    ui.add(egui::Slider::new(&mut uniform.intensity, 0.0..=2.0).text("Intensity"));
+}
+
+// Focus blur; off by default since it samples the scene many times per pixel.
+fn depth_of_field_ui(render_state: &mut RenderState, ui: &mut Ui) {
+    let depth_of_field = &mut render_state.depth_of_field;
+    let response = ui
+        .add_enabled(
+            depth_of_field.supported(),
+            egui::Checkbox::new(&mut depth_of_field.enabled, "Depth of field"),
+        )
+        .on_disabled_hover_text("Not supported by the GL backend, which can't read depth.");
+    if response.changed() && depth_of_field.enabled {
+        // start focused on the camera target
+        let camera = &render_state.camera_state.camera;
+        depth_of_field.uniform.focus_distance = (camera.target - camera.eye).magnitude();
+    }
+    if !depth_of_field.enabled {
+        return;
+    }
+
+    let uniform = &mut depth_of_field.uniform;
+    ui.add(
+        egui::Slider::new(&mut uniform.focus_distance, 0.1..=100.0)
+            .logarithmic(true)
+            .text("Focus distance"),
+    );
+    ui.add(egui::Slider::new(&mut uniform.aperture, 0.0..=32.0).text("Aperture"));
+}
+
+// Reflective floor under the graph; off by default since it adds a pass.
+fn floor_ui(render_state: &mut RenderState, grapher_scene: &mut GrapherScene, ui: &mut Ui) {
+    let floor = &mut render_state.floor;
+    let response = ui.checkbox(&mut floor.enabled, "Floor reflection");
+    if response.changed() && floor.enabled {
+        // the graph makes its reflection pipeline when rebuilt
+        grapher_scene.set_needs_rebuild(true);
+    }
+    if !floor.enabled {
+        return;
     }
+
+    ui.add(egui::Slider::new(&mut floor.reflectivity, 0.0..=1.0).text("Reflectivity"));
+    ui.add(egui::Slider::new(&mut floor.height, -3.0..=3.0).text("Floor height"));
+}
+
+pub fn skybox_ui(render_state: &mut RenderState, ui_state: &mut UiState, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        if ui.button("Load skybox").clicked() {
+            ui_state.show_file_input = true;
+            ui_state.pick_skybox = true;
+        }
+        if render_state.skybox.is_some() && ui.button("Clear skybox").clicked() {
+            render_state.skybox = None;
+        }
+    });
 }
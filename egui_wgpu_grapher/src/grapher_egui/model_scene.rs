@@ -1,28 +1,292 @@
 //! UI specific to the glTF viewer mode.
 
-use crate::{egui::ui::UiState, grapher::scene::textured::model::ModelScene};
+use crate::{
+    egui::ui::UiState,
+    grapher::{
+        SceneNode,
+        render::RenderState,
+        scene::textured::model::{ModelMesh, ModelScene},
+    },
+};
 
-use egui::Ui;
+use egui::{Context, RichText, Ui};
+use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
+use std::time::Instant;
 
-pub struct ModelSceneUiData;
+// Tint blended into the color of the mesh under the cursor.
+const HOVER_TINT: [f32; 3] = [1.0, 0.5, 0.0];
+
+pub struct ModelSceneUiData {
+    // color picked for each mesh, applied in update
+    pub mesh_colors: Vec<[f32; 3]>,
+    // copies of the model along each side of the instance grid
+    pub grid_size: u32,
+    // morph target weight for each mesh, applied in update
+    pub morph_weights: Vec<f32>,
+    // play the file's morph weight animations, overriding the weights
+    pub animate_morphs: bool,
+    // animation time in seconds, advanced while playing
+    morph_time: f32,
+    last_update: Option<Instant>,
+    // tint the mesh under the cursor and show its name
+    pub highlight_hovered: bool,
+    // cursor position in physical pixels, when over the scene
+    pub cursor_position: Option<[f32; 2]>,
+    // draw vertex normals as segments, to debug shading
+    pub show_normals: bool,
+}
 
 pub struct ModelSceneData {
     pub model_scene: ModelScene,
-    pub _ui_data: ModelSceneUiData,
+    pub ui_data: ModelSceneUiData,
 }
 
 impl ModelSceneData {
     pub fn new(model_scene: ModelScene) -> Self {
+        let mesh_colors = model_scene.meshes.iter().map(|mesh| mesh.color).collect();
+        let grid_size = model_scene.grid_size;
+        let morph_weights = model_scene
+            .meshes
+            .iter()
+            .map(|mesh| {
+                mesh.morph
+                    .as_ref()
+                    .map_or(0.0, |morph| morph.default_weight)
+            })
+            .collect();
+        let animate_morphs = model_scene.meshes.iter().any(is_animated);
         Self {
             model_scene,
-            _ui_data: ModelSceneUiData {},
+            ui_data: ModelSceneUiData {
+                mesh_colors,
+                grid_size,
+                morph_weights,
+                animate_morphs,
+                morph_time: 0.0,
+                last_update: None,
+                highlight_hovered: true,
+                cursor_position: None,
+                show_normals: false,
+            },
+        }
+    }
+
+    /// Re-upload vertices of any mesh whose picked color, hover highlight,
+    /// or morph weight changed, and instance transforms if the grid size changed.
+    pub fn update(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+    ) {
+        let ui_data = &mut self.ui_data;
+        self.model_scene
+            .set_instance_grid(device, queue, ui_data.grid_size);
+
+        let cursor = ui_data
+            .cursor_position
+            .filter(|_| ui_data.highlight_hovered);
+        self.model_scene
+            .update_hover(device, queue, surface_config, state, cursor);
+        let hovered = self.model_scene.hovered_mesh();
+        for (index, color) in ui_data.mesh_colors.iter().enumerate() {
+            let color = match hovered {
+                Some(hovered) if hovered == index => {
+                    std::array::from_fn(|i| 0.5 * (color[i] + HOVER_TINT[i]))
+                }
+                _ => *color,
+            };
+            self.model_scene.set_mesh_color(queue, index, color);
+        }
+
+        let now = Instant::now();
+        let last_update = ui_data.last_update.replace(now);
+        if ui_data.animate_morphs {
+            if let Some(last_update) = last_update {
+                ui_data.morph_time += (now - last_update).as_secs_f32();
+            }
+            for (mesh, weight) in self
+                .model_scene
+                .meshes
+                .iter()
+                .zip(ui_data.morph_weights.iter_mut())
+            {
+                if let Some(animation) = mesh.morph.as_ref().and_then(|m| m.animation.as_ref()) {
+                    *weight = animation.weight_at(ui_data.morph_time);
+                }
+            }
         }
+        for (index, weight) in ui_data.morph_weights.iter().enumerate() {
+            self.model_scene.set_morph_weight(queue, index, *weight);
+        }
+        self.model_scene
+            .set_show_normals(device, state, ui_data.show_normals);
     }
 }
 
 // model-specific parameter ui
-pub fn parameter_ui_model(_data: &mut ModelSceneData, ui: &mut Ui, ui_state: &mut UiState) {
+pub fn parameter_ui_model(data: &mut ModelSceneData, ui: &mut Ui, ui_state: &mut UiState) {
     if ui.add(egui::Button::new("Change file")).clicked() {
         ui_state.show_file_input = true;
     }
+    if ui.button("Frame model").clicked() {
+        ui_state.reset_camera_requested = true;
+    }
+
+    ui.separator();
+
+    // Copies are drawn with one instanced draw call per mesh.
+    ui.add(egui::Slider::new(&mut data.ui_data.grid_size, 1..=20).text("Instance grid"));
+    ui.checkbox(
+        &mut data.ui_data.highlight_hovered,
+        "Highlight mesh under cursor",
+    );
+    ui.checkbox(&mut data.ui_data.show_normals, "Show normals")
+        .on_hover_text(
+            "Segments along the vertex normals, colored by direction, at a sample of vertices.",
+        );
+
+    let hovered = data.model_scene.hovered_mesh();
+
+    egui::CollapsingHeader::new("Mesh colors")
+        .default_open(false)
+        .show(ui, |ui| {
+            for (index, (mesh, color)) in data
+                .model_scene
+                .meshes
+                .iter()
+                .zip(data.ui_data.mesh_colors.iter_mut())
+                .enumerate()
+            {
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_rgb(color);
+                    ui.label(hovered_text(ui, &mesh.name, hovered == Some(index)));
+                });
+            }
+            if ui.button("Reset colors").clicked() {
+                data.ui_data.mesh_colors.fill([1.0, 1.0, 1.0]);
+            }
+        });
+
+    if data
+        .model_scene
+        .meshes
+        .iter()
+        .any(|mesh| mesh.morph.is_some())
+    {
+        morph_ui(data, ui);
+    }
+
+    egui::CollapsingHeader::new("Scene tree")
+        .default_open(false)
+        .show(ui, |ui| {
+            let mut changed = false;
+            for node in &mut data.model_scene.nodes {
+                changed |= node_tree_ui(ui, node, hovered);
+            }
+            if changed {
+                data.model_scene.apply_node_visibility();
+            }
+        });
+}
+
+/// Show the name of the mesh under the cursor next to the cursor.
+pub fn hovered_mesh_label(context: &Context, data: &ModelSceneData) {
+    let (Some(index), Some(pointer)) =
+        (data.model_scene.hovered_mesh(), context.pointer_hover_pos())
+    else {
+        return;
+    };
+    let Some(mesh) = data.model_scene.meshes.get(index) else {
+        return;
+    };
+    egui::Area::new("hovered_mesh".into())
+        .fixed_pos(pointer + egui::vec2(16.0, 16.0))
+        .order(egui::Order::Tooltip)
+        .interactable(false)
+        .show(context, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(&mesh.name);
+            });
+        });
+}
+
+// Label text, emphasized for the mesh under the cursor.
+fn hovered_text(ui: &Ui, text: &str, hovered: bool) -> RichText {
+    let text = RichText::new(text);
+    if hovered {
+        text.strong().color(ui.visuals().selection.stroke.color)
+    } else {
+        text
+    }
+}
+
+fn is_animated(mesh: &ModelMesh) -> bool {
+    mesh.morph
+        .as_ref()
+        .is_some_and(|morph| morph.animation.is_some())
+}
+
+// Weight slider for each mesh with a morph target. Weights of animated
+// meshes follow the animation while it plays.
+fn morph_ui(data: &mut ModelSceneData, ui: &mut Ui) {
+    let ui_data = &mut data.ui_data;
+    egui::CollapsingHeader::new("Morph targets")
+        .default_open(false)
+        .show(ui, |ui| {
+            let meshes = &data.model_scene.meshes;
+            if meshes.iter().any(is_animated) {
+                ui.checkbox(&mut ui_data.animate_morphs, "Play animation");
+            }
+            for (mesh, weight) in meshes.iter().zip(ui_data.morph_weights.iter_mut()) {
+                if mesh.morph.is_none() {
+                    continue;
+                }
+                let enabled = !(ui_data.animate_morphs && is_animated(mesh));
+                ui.add_enabled(
+                    enabled,
+                    egui::Slider::new(weight, 0.0..=1.0).text(&mesh.name),
+                );
+            }
+        });
+}
+
+// Node visibility checkbox, with its children nested below it. The node
+// whose mesh is under the cursor is emphasized.
+// Returns whether the visibility of any node changed.
+fn node_tree_ui(ui: &mut Ui, node: &mut SceneNode, hovered: Option<usize>) -> bool {
+    let mut changed = false;
+    let label = match node.mesh {
+        Some(_) => format!("{} (mesh)", node.name),
+        None => node.name.clone(),
+    };
+    let label = hovered_text(ui, &label, node.mesh.is_some() && node.mesh == hovered);
+    let transform = format!(
+        "translation: {:.3?}\nrotation: {:.3?}\nscale: {:.3?}",
+        node.translation, node.rotation, node.scale
+    );
+
+    if node.children.is_empty() {
+        changed |= ui
+            .checkbox(&mut node.visible, label)
+            .on_hover_text(transform)
+            .changed();
+        return changed;
+    }
+
+    let id = ui.make_persistent_id(("model node", node.index));
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+        .show_header(ui, |ui| {
+            changed |= ui
+                .checkbox(&mut node.visible, label)
+                .on_hover_text(transform)
+                .changed();
+        })
+        .body(|ui| {
+            for child in &mut node.children {
+                changed |= node_tree_ui(ui, child, hovered);
+            }
+        });
+    changed
 }
@@ -0,0 +1,142 @@
+//! UI specific to the CPU wave and heat equation modes.
+
+use std::path::Path;
+
+use egui::{RichText, Ui};
+use egui_wgpu::wgpu::{Device, SurfaceConfiguration};
+
+use crate::{
+    egui::components::colormap_legend,
+    grapher::{
+        math::pde::{FieldFormat, MAX_STABLE_PROP_SPEED},
+        render::RenderState,
+        scene::solid::{
+            grid_normals::GridNormals,
+            pde::{
+                HEAT_EQN_GRID, HeatEquationScene, WAVE_EQN_GRID, WaveEquationScene, heat_eqn_scene,
+                wave_eqn_scene,
+            },
+        },
+    },
+};
+
+pub struct WaveEquationSceneData {
+    pub wave_scene: WaveEquationScene,
+}
+
+impl WaveEquationSceneData {
+    pub fn new(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+    ) -> Self {
+        Self {
+            wave_scene: wave_eqn_scene(device, surface_config, state, WAVE_EQN_GRID, None),
+        }
+    }
+}
+
+pub struct HeatEquationSceneData {
+    pub heat_scene: HeatEquationScene,
+}
+
+impl HeatEquationSceneData {
+    pub fn new(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+    ) -> Self {
+        Self {
+            heat_scene: heat_eqn_scene(device, surface_config, state, HEAT_EQN_GRID),
+        }
+    }
+}
+
+pub fn parameter_ui_wave_equation(data: &mut WaveEquationSceneData, ui: &mut Ui) {
+    let scene = &mut data.wave_scene;
+    let wave_eqn = &mut scene.wave_eqn;
+
+    // Range goes past the stable maximum so the warning can be seen.
+    let response =
+        ui.add(egui::Slider::new(&mut wave_eqn.prop_speed, 0.0..=1.0).text("Propagation speed"));
+    if response.drag_stopped() && !wave_eqn.is_stable() {
+        log::warn!(
+            "Wave equation prop_speed {} exceeds the stable maximum {MAX_STABLE_PROP_SPEED}.",
+            wave_eqn.prop_speed
+        );
+    }
+    ui.add(egui::Slider::new(&mut wave_eqn.damping_factor, 0.9..=1.0).text("Damping"));
+    gpu_normals_ui(&mut scene.grid_normals, ui);
+
+    if !wave_eqn.is_stable() {
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            format!("Unstable: propagation speed must be at most {MAX_STABLE_PROP_SPEED}."),
+        );
+        if ui.button("Clamp to stable").clicked() {
+            wave_eqn.clamp_prop_speed();
+        }
+    }
+
+    ui.separator();
+    let wave_eqn = &scene.wave_eqn;
+    save_field_ui("wave", &mut scene.field_format, ui, |path| {
+        wave_eqn.save_field(path)
+    });
+}
+
+pub fn parameter_ui_heat_equation(data: &mut HeatEquationSceneData, ui: &mut Ui) {
+    let scene = &mut data.heat_scene;
+    ui.label(RichText::new("Temperature").strong());
+    let (min, max) = scene.value_range();
+    colormap_legend(&mut scene.colormap, min, max, ui);
+    gpu_normals_ui(&mut scene.grid_normals, ui);
+
+    ui.separator();
+    let heat_eqn = &scene.heat_eqn;
+    save_field_ui("heat", &mut scene.field_format, ui, |path| {
+        heat_eqn.save_field(path)
+    });
+}
+
+/// Toggle between computing the surface normals of an animated grid on the
+/// GPU and the CPU. Hidden if the compute pipeline couldn't be created.
+pub fn gpu_normals_ui(grid_normals: &mut Option<GridNormals>, ui: &mut Ui) {
+    if let Some(grid_normals) = grid_normals {
+        ui.checkbox(&mut grid_normals.enabled, "GPU normals")
+            .on_hover_text(
+                "Compute heights and normals in a compute shader instead of on the CPU.",
+            );
+    }
+}
+
+// Format picker and button to save the current field to the working
+// directory, named like screenshots so repeated saves don't collide.
+fn save_field_ui(
+    name: &str,
+    format: &mut FieldFormat,
+    ui: &mut Ui,
+    save: impl FnOnce(&Path) -> Result<(), String>,
+) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt(("field_format", name))
+            .selected_text(format.label())
+            .show_ui(ui, |ui| {
+                for option in FieldFormat::ALL {
+                    ui.selectable_value(format, option, option.label());
+                }
+            });
+
+        if ui.button("Save field").clicked() {
+            let millis = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|duration| duration.as_millis())
+                .unwrap_or_default();
+            let filename = format!("{name}_field_{millis}.{}", format.extension());
+            match save(Path::new(&filename)) {
+                Ok(()) => log::info!("Saved {name} equation field to {filename}."),
+                Err(err) => log::error!("{err}"),
+            }
+        }
+    });
+}
@@ -0,0 +1,117 @@
+//! UI specific to the Schrödinger equation mode.
+
+use crate::{
+    grapher::{
+        math::pde::Potential,
+        render::RenderState,
+        scene::solid::pde::{SCHRODINGER_GRID, SchrodingerScene},
+    },
+    grapher_egui::pde_scene::gpu_normals_ui,
+};
+
+use egui::{RichText, Ui};
+use egui_wgpu::wgpu::{Device, SurfaceConfiguration};
+
+pub struct SchrodingerSceneData {
+    pub schrodinger_scene: SchrodingerScene,
+}
+
+impl SchrodingerSceneData {
+    pub fn new(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+    ) -> Self {
+        Self {
+            schrodinger_scene: SchrodingerScene::new(
+                device,
+                surface_config,
+                state,
+                SCHRODINGER_GRID,
+            ),
+        }
+    }
+}
+
+pub fn parameter_ui_schrodinger(data: &mut SchrodingerSceneData, ui: &mut Ui) {
+    let scene = &mut data.schrodinger_scene;
+    let schrodinger = &mut scene.schrodinger;
+    // Changes to the potential or packet restart the simulation.
+    let mut restart = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Potential");
+        egui::ComboBox::from_id_salt("schrodinger potential")
+            .selected_text(schrodinger.potential.label())
+            .show_ui(ui, |ui| {
+                for alternative in Potential::ALL {
+                    restart |= ui
+                        .selectable_value(
+                            &mut schrodinger.potential,
+                            alternative,
+                            alternative.label(),
+                        )
+                        .changed();
+                }
+            });
+    });
+    if schrodinger.potential != Potential::Free {
+        restart |= ui
+            .add(egui::Slider::new(&mut schrodinger.potential_strength, 0.0..=3.0).text("Strength"))
+            .changed();
+    }
+
+    ui.add_space(2.5);
+    ui.label(RichText::new("Wave packet").strong());
+    let packet = &mut schrodinger.packet;
+    restart |= ui
+        .add(egui::Slider::new(&mut packet.center[0], -8.0..=8.0).text("Center x"))
+        .changed();
+    restart |= ui
+        .add(egui::Slider::new(&mut packet.center[1], -8.0..=8.0).text("Center z"))
+        .changed();
+    restart |= ui
+        .add(egui::Slider::new(&mut packet.width, 0.3..=3.0).text("Width"))
+        .changed();
+    restart |= ui
+        .add(egui::Slider::new(&mut packet.momentum[0], -10.0..=10.0).text("Momentum x"))
+        .changed();
+    restart |= ui
+        .add(egui::Slider::new(&mut packet.momentum[1], -10.0..=10.0).text("Momentum z"))
+        .changed();
+
+    ui.add_space(2.5);
+    ui.label(RichText::new("Simulation").strong());
+    // Range goes past the stable maximum so the warning can be seen.
+    ui.add(
+        egui::Slider::new(&mut schrodinger.dt, 0.0005..=0.005)
+            .logarithmic(true)
+            .text("Timestep"),
+    );
+    ui.add(egui::Slider::new(&mut schrodinger.steps_per_update, 1..=50).text("Steps per frame"));
+    if !schrodinger.is_stable() {
+        let max_dt = schrodinger.max_stable_dt();
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            format!("Unstable: timestep must be at most {max_dt:.5}. Paused."),
+        );
+        if ui.button("Clamp to stable").clicked() {
+            schrodinger.dt = max_dt;
+        }
+    }
+    ui.label(format!(
+        "Total probability: {:.4}",
+        schrodinger.total_probability()
+    ));
+    ui.add(egui::Slider::new(&mut scene.display_height, 0.1..=5.0).text("Display height"));
+    gpu_normals_ui(&mut scene.grid_normals, ui);
+
+    ui.horizontal(|ui| {
+        let pause_label = if scene.paused { "Resume" } else { "Pause" };
+        if ui.button(pause_label).clicked() {
+            scene.paused = !scene.paused;
+        }
+        restart |= ui.button("Restart").clicked();
+    });
+    scene.needs_reset |= restart;
+}
@@ -2,23 +2,155 @@
 
 use egui::Ui;
 use egui_wgpu::wgpu::{Device, Queue, SurfaceConfiguration};
+use std::time::Duration;
 
-use crate::grapher::scene::solver::SolverScene;
+use crate::{
+    egui::components::texture_filtering_combo,
+    grapher::{
+        math::pde::MAX_STABLE_PROP_SPEED,
+        pipeline::texture::TextureFiltering,
+        render::RenderState,
+        scene::solver::{
+            DEFAULT_DAMPING, DEFAULT_PROP_SPEED, FrameFormat, InitialCondition, SolverScene,
+            WORKGROUP_SIZES,
+            surface::{DEFAULT_HEIGHT_SCALE, WaveSurface},
+        },
+    },
+};
 
 pub struct SolverSceneData {
     pub scene: SolverScene,
     pub updates_paused: bool,
+    // 3D surface view; built the first time it's shown
+    pub surface: Option<WaveSurface>,
+    pub show_surface: bool,
+    pub height_scale: f32,
+    pub prop_speed: f32,
+    pub damping: f32,
+    // set from the UI; applied on the next update since that has the queue
+    reset_requested: bool,
+    pub initial_condition: InitialCondition,
+    // sampling of the 2D display, applied on the next update
+    pub texture_filtering: TextureFiltering,
+    pub frame_format: FrameFormat,
+    save_requested: bool,
+    // result of the last save, shown under the button
+    save_status: Option<String>,
+    // compute workgroup width chosen in the UI, applied on the next update
+    pub workgroup_size: u32,
+    workgroup_error: Option<String>,
+    benchmark_requested: bool,
+    // time per timestep measured for each workgroup size, latest last
+    benchmark_results: Vec<(u32, Duration)>,
 }
 
+// timesteps run for one benchmark
+const BENCHMARK_STEPS: u32 = 500;
+
 impl SolverSceneData {
-    pub fn new(device: &Device, queue: &Queue, surface_config: &SurfaceConfiguration) -> Self {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        surface_config: &SurfaceConfiguration,
+        workgroup_size: u32,
+    ) -> Self {
         Self {
-            scene: SolverScene::new(device, queue, surface_config),
+            scene: SolverScene::new(device, queue, surface_config, workgroup_size),
             updates_paused: true,
+            surface: None,
+            show_surface: false,
+            height_scale: DEFAULT_HEIGHT_SCALE,
+            prop_speed: DEFAULT_PROP_SPEED,
+            damping: DEFAULT_DAMPING,
+            reset_requested: false,
+            initial_condition: InitialCondition::default(),
+            texture_filtering: TextureFiltering::default(),
+            frame_format: FrameFormat::default(),
+            save_requested: false,
+            save_status: None,
+            workgroup_size,
+            workgroup_error: None,
+            benchmark_requested: false,
+            benchmark_results: vec![],
+        }
+    }
+
+    pub fn update(&mut self, device: &Device, queue: &Queue, state: &RenderState) {
+        if self.reset_requested {
+            self.scene.reset(queue, self.initial_condition);
+            self.reset_requested = false;
+        }
+        let uniform = &self.scene.uniform.data;
+        if uniform.prop_speed != self.prop_speed || uniform.damping != self.damping {
+            self.scene
+                .set_wave_parameters(queue, self.prop_speed, self.damping);
+        }
+        let data_texture = &mut self.scene.data_texture;
+        if self.texture_filtering != data_texture.filtering() {
+            data_texture.set_filtering(device, self.texture_filtering);
+        }
+        if self.save_requested {
+            self.save_frame(device, queue);
+            self.save_requested = false;
+        }
+        if self.workgroup_size != self.scene.workgroup_size() {
+            match self.scene.set_workgroup_size(device, self.workgroup_size) {
+                Ok(()) => self.workgroup_error = None,
+                Err(err) => {
+                    log::error!("{err}");
+                    self.workgroup_error = Some(err);
+                    self.workgroup_size = self.scene.workgroup_size();
+                }
+            }
+        }
+        if self.benchmark_requested {
+            self.run_benchmark(device, queue);
+            self.benchmark_requested = false;
         }
+        if self.show_surface {
+            let surface = self
+                .surface
+                .get_or_insert_with(|| WaveSurface::new(device, state, &self.scene));
+            surface.set_height_scale(queue, self.height_scale);
+        }
+    }
+
+    fn save_frame(&mut self, device: &Device, queue: &Queue) {
+        let filename = format!(
+            "solver_frame_{}.{}",
+            self.scene.timestep(),
+            self.frame_format.extension()
+        );
+        let result = self
+            .scene
+            .save_frame(device, queue, &filename, self.frame_format);
+
+        self.save_status = Some(match result {
+            Ok(()) => {
+                log::info!("Saved solver frame to {filename}");
+                format!("Saved {filename}")
+            }
+            Err(err) => {
+                log::error!("{err}");
+                err
+            }
+        });
     }
 
-    pub fn update(&mut self, _: &Queue) {}
+    fn run_benchmark(&mut self, device: &Device, queue: &Queue) {
+        let size = self.scene.workgroup_size();
+        let elapsed = self.scene.benchmark(device, queue, BENCHMARK_STEPS);
+        let per_step = elapsed / BENCHMARK_STEPS;
+        log::info!(
+            "Solver benchmark: {BENCHMARK_STEPS} timesteps with {size}x{size} workgroups \
+             took {:.1} ms, {:.1} us per step",
+            elapsed.as_secs_f64() * 1e3,
+            per_step.as_secs_f64() * 1e6,
+        );
+        // keep only the latest result for each size
+        self.benchmark_results.retain(|(s, _)| *s != size);
+        self.benchmark_results.push((size, per_step));
+    }
 
     pub fn handle_resize(&mut self, queue: &Queue, surface_config: &SurfaceConfiguration) {
         let new_ratio = surface_config.height as f32 / surface_config.width as f32;
@@ -53,7 +185,100 @@ impl SolverSceneData {
         if clicked {
             self.updates_paused = !self.updates_paused;
         }
+        // Doesn't change the paused state, so a running simulation restarts.
+        if ui.button("Reset simulation").clicked() {
+            self.reset_requested = true;
+        }
+        // capped at the stable maximum, since the GPU solution blows up past it
+        ui.add(
+            egui::Slider::new(&mut self.prop_speed, 0.0..=MAX_STABLE_PROP_SPEED)
+                .text("Propagation speed"),
+        );
+        ui.add(egui::Slider::new(&mut self.damping, 0.9..=1.0).text("Damping"));
+
+        ui.horizontal(|ui| {
+            ui.label("Initial condition");
+            egui::ComboBox::from_id_salt("solver_initial_condition")
+                .selected_text(self.initial_condition.label())
+                .show_ui(ui, |ui| {
+                    for condition in InitialCondition::ALL {
+                        // switching presets restarts from the new state
+                        if ui
+                            .selectable_value(
+                                &mut self.initial_condition,
+                                condition,
+                                condition.label(),
+                            )
+                            .changed()
+                        {
+                            self.reset_requested = true;
+                        }
+                    }
+                });
+        });
+        texture_filtering_combo(&mut self.texture_filtering, "solver_texture_filtering", ui);
         ui.add_space(2.5);
         ui.label(format!("Timestep: {}", self.scene.timestep()));
+
+        ui.horizontal(|ui| {
+            if ui.button("Save frame").clicked() {
+                self.save_requested = true;
+            }
+            egui::ComboBox::from_id_salt("solver_frame_format")
+                .selected_text(self.frame_format.label())
+                .show_ui(ui, |ui| {
+                    for format in FrameFormat::ALL {
+                        ui.selectable_value(&mut self.frame_format, format, format.label());
+                    }
+                });
+        });
+        if let Some(status) = &self.save_status {
+            ui.label(status);
+        }
+        ui.add_space(2.5);
+
+        self.workgroup_ui(ui);
+        ui.add_space(2.5);
+
+        ui.checkbox(&mut self.show_surface, "3D surface");
+        if self.show_surface {
+            ui.add(egui::Slider::new(&mut self.height_scale, 0.0..=4.0).text("Height scale"));
+        }
+    }
+
+    // Workgroup size choice and timing, for comparing GPUs.
+    fn workgroup_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Workgroup size");
+            egui::ComboBox::from_id_salt("solver_workgroup_size")
+                .selected_text(format!("{0}x{0}", self.workgroup_size))
+                .show_ui(ui, |ui| {
+                    for size in WORKGROUP_SIZES {
+                        ui.selectable_value(
+                            &mut self.workgroup_size,
+                            size,
+                            format!("{size}x{size}"),
+                        );
+                    }
+                });
+            if ui
+                .button("Benchmark")
+                .on_hover_text(format!(
+                    "Time {BENCHMARK_STEPS} timesteps, which advances the simulation."
+                ))
+                .clicked()
+            {
+                self.benchmark_requested = true;
+            }
+        });
+        if let Some(err) = &self.workgroup_error {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+        for (size, per_step) in &self.benchmark_results {
+            ui.label(format!(
+                "{size}x{size}: {:.1} us per step",
+                per_step.as_secs_f64() * 1e6
+            ));
+        }
     }
 }
@@ -0,0 +1,64 @@
+//! UI specific to the sphere graph mode.
+
+use crate::grapher::{
+    math::try_parse_sphere_function, render::RenderState, scene::solid::sphere::SphereScene,
+};
+
+use egui::Ui;
+use egui_wgpu::wgpu::{Device, SurfaceConfiguration};
+
+// Shape of the real spherical harmonic Y_2^0.
+const DEFAULT_FUNCTION: &str = "abs(3*cos(theta)^2 - 1)";
+
+pub struct SphereSceneData {
+    pub sphere_scene: SphereScene,
+    function_text: String,
+    function_valid: bool,
+}
+
+impl SphereSceneData {
+    pub fn new(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        state: &RenderState,
+    ) -> Self {
+        let function = try_parse_sphere_function(DEFAULT_FUNCTION).unwrap();
+        Self {
+            sphere_scene: SphereScene::new(device, surface_config, state, function),
+            function_text: DEFAULT_FUNCTION.into(),
+            function_valid: true,
+        }
+    }
+}
+
+pub fn parameter_ui_sphere(data: &mut SphereSceneData, ui: &mut Ui) {
+    let scene = &mut data.sphere_scene;
+
+    ui.horizontal(|ui| {
+        ui.label("r = f(theta, phi) = ");
+        let response = ui.text_edit_singleline(&mut data.function_text);
+        // Keep the last valid function if the new text doesn't parse.
+        if response.lost_focus() {
+            match try_parse_sphere_function(&data.function_text) {
+                Some(function) => {
+                    scene.function = function;
+                    scene.needs_rebuild = true;
+                    data.function_valid = true;
+                }
+                None => data.function_valid = false,
+            }
+        }
+    });
+    if !data.function_valid {
+        ui.colored_label(ui.visuals().error_fg_color, "Invalid function; see log.");
+    }
+    ui.label("theta is the angle from the vertical axis and phi the angle around it.");
+
+    if ui
+        .add(egui::Slider::new(&mut scene.resolution, 8..=512).text("Resolution"))
+        .on_hover_text("Steps in theta; phi has twice as many.")
+        .changed()
+    {
+        scene.needs_rebuild = true;
+    }
+}
@@ -1,12 +1,96 @@
-use egui_wgpu_grapher::{app, grapher_egui};
+use egui_wgpu_grapher::{
+    app,
+    egui::{
+        log_console,
+        ui::{DemoSettings, FrameRateSettings},
+    },
+    grapher_egui,
+};
 
 use clap::Parser;
+use egui_wgpu::wgpu::{PowerPreference, PresentMode};
 use winit::event_loop::{ControlFlow, EventLoop};
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum PowerArg {
+    High,
+    Low,
+}
+
+impl From<PowerArg> for PowerPreference {
+    fn from(value: PowerArg) -> Self {
+        match value {
+            PowerArg::High => PowerPreference::HighPerformance,
+            PowerArg::Low => PowerPreference::LowPower,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default)]
+enum PresentModeArg {
+    #[default]
+    AutoVsync,
+    AutoNoVsync,
+    Fifo,
+    FifoRelaxed,
+    Mailbox,
+    Immediate,
+}
+
+impl From<PresentModeArg> for PresentMode {
+    fn from(value: PresentModeArg) -> Self {
+        match value {
+            PresentModeArg::AutoVsync => PresentMode::AutoVsync,
+            PresentModeArg::AutoNoVsync => PresentMode::AutoNoVsync,
+            PresentModeArg::Fifo => PresentMode::Fifo,
+            PresentModeArg::FifoRelaxed => PresentMode::FifoRelaxed,
+            PresentModeArg::Mailbox => PresentMode::Mailbox,
+            PresentModeArg::Immediate => PresentMode::Immediate,
+        }
+    }
+}
+
 #[derive(Parser, Clone, Debug, Default)]
 struct Args {
     #[arg(long)]
     scene: Option<grapher_egui::GrapherSceneMode>,
+    /// Function of x and z to graph at startup, in graph mode.
+    #[arg(long)]
+    function: Option<String>,
+    /// JSON file describing the scene, camera and render settings to start with.
+    #[arg(long)]
+    scene_file: Option<String>,
+    /// Target framerate when not uncapped.
+    #[arg(long, default_value_t = 60)]
+    fps: u32,
+    /// Render as fast as possible with vsync off, logging frame times.
+    #[arg(long)]
+    uncapped: bool,
+    /// Surface present mode; unsupported modes fall back to auto-vsync.
+    #[arg(long, value_enum, default_value_t = PresentModeArg::AutoVsync)]
+    present_mode: PresentModeArg,
+    /// Adapter to use, by index or name substring.
+    #[arg(long)]
+    gpu: Option<String>,
+    /// Power preference used when no adapter is given or it isn't found.
+    #[arg(long, value_enum)]
+    power: Option<PowerArg>,
+    /// Compute workgroup width and height for the wave equation solver:
+    /// 8, 16 or 32, if the GPU supports it.
+    #[arg(long)]
+    workgroup_size: Option<u32>,
+    /// Cycle through built-in scenes, for showcasing.
+    #[arg(long)]
+    demo: bool,
+    /// Seconds each demo scene is shown.
+    #[arg(long, default_value_t = 10.0)]
+    demo_interval: f32,
+    /// Model file to include in the demo.
+    #[arg(long)]
+    demo_model: Option<String>,
+    /// Window title.
+    #[arg(long, default_value = "Wgpu Grapher")]
+    title: String,
 }
 
 fn main() {
@@ -14,11 +98,36 @@ fn main() {
 }
 
 async fn run() {
+    log_console::install();
     let args = Args::parse();
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(ControlFlow::Wait);
 
-    let mut app = app::App::new(args.scene);
+    let frame_rate = FrameRateSettings {
+        target_fps: args.fps.max(1),
+        uncapped: args.uncapped,
+        present_mode: args.present_mode.into(),
+        ..Default::default()
+    };
+    let demo = DemoSettings {
+        enabled: args.demo,
+        interval_secs: args.demo_interval.max(1.0),
+    };
+    let adapter_selection = app::AdapterSelection {
+        gpu: args.gpu,
+        power_preference: args.power.map(Into::into).unwrap_or_default(),
+    };
+    let mut app = app::App::new(
+        args.scene,
+        args.function,
+        args.scene_file,
+        frame_rate,
+        adapter_selection,
+        args.workgroup_size,
+        demo,
+        args.demo_model,
+        &args.title,
+    );
     event_loop
         .run_app(&mut app)
         .expect("Winit event loop failed to start.");
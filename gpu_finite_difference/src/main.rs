@@ -14,9 +14,23 @@ use wgpu::{
 const TEXTURE_WIDTH: u32 = 1024;
 const TEXTURE_HEIGHT: u32 = 1024;
 
+// Workgroup width and height, unless given as the first argument.
+const DEFAULT_WORKGROUP_SIZE: u32 = 8;
+
 fn main() -> Result<(), ()> {
     env_logger::init();
 
+    let workgroup_size = match std::env::args().nth(1) {
+        Some(arg) => match arg.parse::<u32>() {
+            Ok(size) if [8, 16, 32].contains(&size) => size,
+            _ => {
+                log::error!("Workgroup size must be 8, 16 or 32, not {arg}.");
+                return Err(());
+            }
+        },
+        None => DEFAULT_WORKGROUP_SIZE,
+    };
+
     // ------------------
     // Wgpu device setup.
 
@@ -47,6 +61,16 @@ fn main() -> Result<(), ()> {
         return Err(());
     };
 
+    let limits = device.limits();
+    if workgroup_size > limits.max_compute_workgroup_size_x
+        || workgroup_size > limits.max_compute_workgroup_size_y
+        || workgroup_size * workgroup_size > limits.max_compute_invocations_per_workgroup
+    {
+        log::error!("Device doesn't support {workgroup_size}x{workgroup_size} workgroups.");
+        return Err(());
+    }
+    log::info!("Using {workgroup_size}x{workgroup_size} workgroups.");
+
     // --------------------------------------------------------
     // Create texture to hold solution data at three timesteps.
 
@@ -149,7 +173,10 @@ fn main() -> Result<(), ()> {
         layout: Some(&pipeline_layout),
         module: &module,
         entry_point: Some("advance"),
-        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        compilation_options: wgpu::PipelineCompilationOptions {
+            constants: &[("WORKGROUP_SIZE", workgroup_size as f64)],
+            ..Default::default()
+        },
         cache: None,
     });
 
@@ -184,13 +211,20 @@ fn main() -> Result<(), ()> {
     const NUM_STEPS: usize = 4000;
     const IMAGE_STEPS: usize = 25;
 
+    // time spent in compute passes, not counting image saves
+    let mut compute_time = std::time::Duration::ZERO;
+
     for i in 0..NUM_STEPS {
+        let start = std::time::Instant::now();
         compute_pass(
             &device,
             &queue,
             &pipeline,
             &[&bind_group, &uniform_bind_group],
+            workgroup_size,
         );
+        device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
+        compute_time += start.elapsed();
 
         if i.is_multiple_of(IMAGE_STEPS) {
             println!("Writing image for timestep {}...", uniform.timestep);
@@ -216,6 +250,12 @@ fn main() -> Result<(), ()> {
     // Block until all pipeline commands have run.
     device.poll(wgpu::PollType::wait_indefinitely()).unwrap();
     log::info!("Compute pipeline ran successfully!");
+    log::info!(
+        "{NUM_STEPS} timesteps with {workgroup_size}x{workgroup_size} workgroups took {:.1} ms \
+         of compute, {:.1} us per step.",
+        compute_time.as_secs_f64() * 1e3,
+        compute_time.as_secs_f64() * 1e6 / NUM_STEPS as f64,
+    );
     Ok(())
 }
 
@@ -224,6 +264,7 @@ fn compute_pass(
     queue: &Queue,
     pipeline: &ComputePipeline,
     bind_groups: &[&BindGroup],
+    workgroup_size: u32,
 ) {
     let mut encoder =
         device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
@@ -236,8 +277,8 @@ fn compute_pass(
         compute_pass.set_bind_group(i as u32, *bg, &[]);
     }
 
-    let workgroup_count_x = TEXTURE_WIDTH.div_ceil(8);
-    let workgroup_count_y = TEXTURE_HEIGHT.div_ceil(8);
+    let workgroup_count_x = TEXTURE_WIDTH.div_ceil(workgroup_size);
+    let workgroup_count_y = TEXTURE_HEIGHT.div_ceil(workgroup_size);
     compute_pass.dispatch_workgroups(workgroup_count_x, workgroup_count_y, 1);
 
     drop(compute_pass);
@@ -7,7 +7,10 @@ struct Uniform {
 
 const R: f32 = 0.35;
 
-@compute @workgroup_size(8, 8)
+// Workgroup width and height; must match the dispatch in main.rs.
+override WORKGROUP_SIZE: u32 = 8u;
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE)
 fn advance(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let texture_dims: vec2<u32> = textureDimensions(eqn_data);
 
@@ -0,0 +1,476 @@
+//! Camera shared by the grapher apps: look-at view with orthographic or
+//! perspective projection, user translation, and absolute (Euler angle)
+//! or relative rotation of the scene about the origin.
+//!
+//! This depends only on cgmath, so each app keeps its own GPU uniform
+//! and input handling around it.
+
+use cgmath::{InnerSpace, Rad, SquareMatrix, Transform};
+
+use std::f32::consts::PI;
+
+pub const X_AXIS: cgmath::Vector3<f32> = cgmath::Vector3::new(1.0, 0.0, 0.0);
+pub const Y_AXIS: cgmath::Vector3<f32> = cgmath::Vector3::new(0.0, 1.0, 0.0);
+pub const Z_AXIS: cgmath::Vector3<f32> = cgmath::Vector3::new(0.0, 0.0, 1.0);
+
+#[derive(Default, Clone)]
+pub enum ProjectionType {
+    Orthographic,
+    #[default]
+    Perspective,
+}
+
+#[derive(Clone)]
+pub struct Camera {
+    // for look-at matrix
+    pub eye: cgmath::Point3<f32>,
+    pub target: cgmath::Point3<f32>,
+    pub up: cgmath::Vector3<f32>,
+
+    // orthographic or perspective
+    pub projection_type: ProjectionType,
+
+    // for perspective matrix
+    pub aspect: f32,
+    pub fovy: f32,
+    pub znear: f32,
+    pub zfar: f32,
+
+    // for orthographic matrix
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+    pub ortho_scale: f32,
+
+    // translations
+    pub translation_x: f32,
+    pub translation_y: f32,
+
+    // For absolute rotation vs. relative to previous.
+    pub relative_rotation: bool,
+    pub euler_y: f32,
+    pub euler_x: f32,
+    pub euler_z: f32,
+
+    // Current user rotation for relative rotation.
+    pub user_rotation: cgmath::Matrix4<f32>,
+
+    // Map the near plane to depth 1 and the far plane to 0.
+    pub reverse_z: bool,
+}
+
+// Maps OpenGL's clip space depth range of -1 to 1 onto wgpu's 0 to 1,
+// so the near plane is at depth 0 and the far plane at 1.
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+// Replaces clip space depth z with 1 - z, so the near plane is at 1 and
+// the far plane at 0, where float depth is most precise.
+#[rustfmt::skip]
+pub const REVERSE_Z_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, -1.0, 0.0,
+    0.0, 0.0, 1.0, 1.0,
+);
+
+/// Vertical field of view of a new camera, in degrees. Scenes are framed
+/// for this view, about 1.5 times the height of a 45 degree one.
+pub const DEFAULT_FOVY: f32 = 63.7;
+
+impl Camera {
+    pub fn get_matrix(&self) -> cgmath::Matrix4<f32> {
+        let view = cgmath::Matrix4::look_at_rh(self.eye, self.target, self.up);
+        let translation = cgmath::Matrix4::from_translation(cgmath::Vector3 {
+            x: self.translation_x,
+            y: self.translation_y,
+            z: 0.0,
+        });
+
+        self.get_projection_matrix() * view * translation * self.rotation()
+    }
+
+    /// Projection from view space to wgpu clip space.
+    pub fn get_projection_matrix(&self) -> cgmath::Matrix4<f32> {
+        let proj = match self.projection_type {
+            ProjectionType::Perspective => self.get_perspective_proj(),
+            ProjectionType::Orthographic => cgmath::ortho(
+                self.left * self.aspect / self.ortho_scale,
+                self.right * self.aspect / self.ortho_scale,
+                self.bottom / self.ortho_scale,
+                self.top / self.ortho_scale,
+                2.0, // znear
+                self.zfar,
+            ),
+        };
+
+        if self.reverse_z {
+            REVERSE_Z_MATRIX * OPENGL_TO_WGPU_MATRIX * proj
+        } else {
+            OPENGL_TO_WGPU_MATRIX * proj
+        }
+    }
+
+    /// Inverse of the camera matrix without translation, mapping clip
+    /// coordinates back to world-space view directions for the skybox.
+    pub fn get_skybox_matrix(&self) -> cgmath::Matrix4<f32> {
+        let view = cgmath::Matrix4::look_to_rh(
+            cgmath::Point3::new(0.0, 0.0, 0.0),
+            self.target - self.eye,
+            self.up,
+        );
+
+        // Background uses perspective even with orthographic projection.
+        (OPENGL_TO_WGPU_MATRIX * self.get_perspective_proj() * view * self.rotation())
+            .invert()
+            .unwrap_or_else(cgmath::Matrix4::identity)
+    }
+
+    /// World-space ray through the point with the given normalized device
+    /// coordinates, as an origin on the near plane and a unit direction.
+    pub fn cursor_ray(&self, ndc: [f32; 2]) -> Option<([f32; 3], [f32; 3])> {
+        let inverse = self.get_matrix().invert()?;
+        let unproject = |depth: f32| {
+            let point = inverse * cgmath::Vector4::new(ndc[0], ndc[1], depth, 1.0);
+            point.truncate() / point.w
+        };
+        // clip space depth runs from 0 at near to 1 at far, or from 1 to 0
+        // with reverse-Z
+        let (near_depth, far_depth) = if self.reverse_z {
+            (1.0, 0.0)
+        } else {
+            (0.0, 1.0)
+        };
+        let near = unproject(near_depth);
+        let direction = (unproject(far_depth) - near).normalize();
+
+        Some((near.into(), direction.into()))
+    }
+
+    /// Unit vector from the eye toward the target.
+    pub fn look_direction(&self) -> cgmath::Vector3<f32> {
+        (self.target - self.eye).normalize()
+    }
+
+    /// Yaw and pitch of the view direction, in radians: yaw turns from the
+    /// negative z-axis toward the positive x-axis, and pitch is the angle
+    /// above the xz-plane.
+    pub fn look_angles(&self) -> (f32, f32) {
+        let direction = self.look_direction();
+        let yaw = direction.x.atan2(-direction.z);
+        let pitch = direction.y.clamp(-1.0, 1.0).asin();
+        (yaw, pitch)
+    }
+
+    /// Turn the view to the given yaw and pitch, as in `look_angles`,
+    /// keeping the eye in place and the distance to the target.
+    pub fn set_look_angles(&mut self, yaw: f32, pitch: f32) {
+        let distance = (self.target - self.eye).magnitude();
+        let direction = cgmath::Vector3::new(
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+            -pitch.cos() * yaw.cos(),
+        );
+        self.target = self.eye + distance * direction;
+    }
+
+    /// Move the eye and target together, `forward` along the view direction
+    /// and `right` along the horizontal direction to its right.
+    pub fn fly(&mut self, forward: f32, right: f32) {
+        let direction = self.look_direction();
+        let right_direction = direction.cross(self.up);
+        let offset = if right_direction.magnitude() > 1e-6 {
+            forward * direction + right * right_direction.normalize()
+        } else {
+            forward * direction
+        };
+        self.eye += offset;
+        self.target += offset;
+    }
+
+    /// Eye, target and up vector in world space, with the scene rotation
+    /// and translation folded in.
+    pub fn world_view(
+        &self,
+    ) -> (
+        cgmath::Point3<f32>,
+        cgmath::Point3<f32>,
+        cgmath::Vector3<f32>,
+    ) {
+        let translation = cgmath::Matrix4::from_translation(cgmath::Vector3 {
+            x: self.translation_x,
+            y: self.translation_y,
+            z: 0.0,
+        });
+        let to_world = (translation * self.rotation())
+            .invert()
+            .unwrap_or_else(cgmath::Matrix4::identity);
+
+        (
+            to_world.transform_point(self.eye),
+            to_world.transform_point(self.target),
+            to_world.transform_vector(self.up),
+        )
+    }
+
+    /// Look from `eye` toward `target` in world space, clearing the scene
+    /// rotation and translation so the view matches exactly.
+    pub fn set_world_view(
+        &mut self,
+        eye: cgmath::Point3<f32>,
+        target: cgmath::Point3<f32>,
+        up: cgmath::Vector3<f32>,
+    ) {
+        self.eye = eye;
+        self.target = target;
+        self.up = up;
+
+        self.translation_x = 0.0;
+        self.translation_y = 0.0;
+        self.euler_y = 0.0;
+        self.euler_x = 0.0;
+        self.euler_z = 0.0;
+        self.user_rotation = cgmath::Matrix4::identity();
+    }
+
+    /// Rotation applied to the scene by the user, about the origin.
+    pub fn rotation(&self) -> cgmath::Matrix4<f32> {
+        if self.relative_rotation {
+            self.user_rotation
+        } else {
+            self.get_absolute_rotation()
+        }
+    }
+
+    /// Height of the view, in world units, at the given distance from
+    /// the eye along the view direction.
+    pub fn view_height_at(&self, distance: f32) -> f32 {
+        match self.projection_type {
+            ProjectionType::Perspective => 2.0 * distance * (self.fovy / 2.0).to_radians().tan(),
+            ProjectionType::Orthographic => (self.top - self.bottom) / self.ortho_scale,
+        }
+    }
+
+    pub fn get_perspective_proj(&self) -> cgmath::Matrix4<f32> {
+        cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar)
+    }
+
+    /// Camera looking at the origin from the positive z-axis.
+    pub fn new(aspect: f32) -> Self {
+        Self {
+            eye: (0.0, 0.0, 8.0).into(),
+            target: (0.0, 0.0, 0.0).into(),
+            up: Y_AXIS,
+            //
+            projection_type: ProjectionType::Perspective,
+            //
+            aspect,
+            fovy: DEFAULT_FOVY,
+            znear: 0.1,
+            zfar: 100.0,
+            //
+            left: -0.5,
+            right: 0.5,
+            top: 0.5,
+            bottom: -0.5,
+            ortho_scale: 1.0,
+            //
+            translation_x: 0.0,
+            translation_y: 0.0,
+            //
+            relative_rotation: false,
+            euler_y: 0.0,
+            euler_x: 0.0,
+            euler_z: 0.0,
+            //
+            user_rotation: cgmath::Matrix4::identity(),
+            //
+            reverse_z: false,
+        }
+    }
+
+    fn store_absolute_rotation(&mut self) {
+        self.user_rotation = self.get_absolute_rotation();
+    }
+
+    /// Decompose the stored relative rotation into the angles used by
+    /// [`Self::get_absolute_rotation`].
+    fn set_euler_angles(&mut self) {
+        let m = &self.user_rotation;
+        // For R = Rz(c) Rx(a) Ry(b), the bottom row is (-cos a sin b, sin a,
+        // cos a cos b) and the middle column is (-sin c cos a, cos c cos a, sin a).
+        self.euler_x = m.y.z.clamp(-1.0, 1.0).asin();
+        self.euler_y = (-m.x.z).atan2(m.z.z);
+        self.euler_z = (-m.y.x).atan2(m.y.y);
+    }
+
+    pub fn on_relative_rotation_change(&mut self) {
+        if self.relative_rotation {
+            self.store_absolute_rotation();
+        } else {
+            self.set_euler_angles();
+        }
+    }
+
+    /// Rotation from the Euler angles: azimuth about the scene's y-axis,
+    /// then elevation about the x-axis, then roll about the view axis.
+    pub fn get_absolute_rotation(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::from_angle_z(Rad(self.euler_z))
+            * cgmath::Matrix4::from_angle_x(Rad(self.euler_x))
+            * cgmath::Matrix4::from_angle_y(Rad(self.euler_y))
+    }
+
+    pub fn increment_user_rotation(&mut self, alpha: f32, gamma: f32) {
+        if self.relative_rotation {
+            let alpha_rot = cgmath::Matrix4::from_axis_angle(Y_AXIS, cgmath::Rad(alpha));
+            let gamma_rot = cgmath::Matrix4::from_axis_angle(X_AXIS, cgmath::Rad(gamma));
+            self.user_rotation = alpha_rot * gamma_rot * self.user_rotation;
+        } else {
+            self.euler_y = (self.euler_y + alpha).rem_euclid(2.0 * PI);
+            self.euler_x = (self.euler_x + gamma).rem_euclid(2.0 * PI);
+        }
+    }
+
+    /// Roll the scene about the view axis.
+    pub fn increment_roll(&mut self, angle: f32) {
+        if self.relative_rotation {
+            let roll = cgmath::Matrix4::from_axis_angle(Z_AXIS, cgmath::Rad(angle));
+            self.user_rotation = roll * self.user_rotation;
+        } else {
+            self.euler_z = (self.euler_z + angle).rem_euclid(2.0 * PI);
+        }
+    }
+
+    /// Remove any roll, keeping the current azimuth and elevation.
+    pub fn level_horizon(&mut self) {
+        if self.relative_rotation {
+            self.set_euler_angles();
+            self.euler_z = 0.0;
+            self.store_absolute_rotation();
+        } else {
+            self.euler_z = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Depth a Depth32Float buffer stores for the point on the view axis at
+    // the given distance from the eye.
+    fn depth(camera: &Camera, distance: f32) -> f32 {
+        let eye = camera.eye;
+        let clip = camera.get_matrix() * cgmath::Vector4::new(eye.x, eye.y, eye.z - distance, 1.0);
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn depth_runs_from_near_to_far_plane() {
+        // the orthographic view volume starts 2 in front of the eye
+        for (projection_type, near) in [
+            (ProjectionType::Perspective, 0.1),
+            (ProjectionType::Orthographic, 2.0),
+        ] {
+            let camera = Camera {
+                projection_type,
+                ..Camera::new(1.0)
+            };
+            assert!(depth(&camera, near).abs() < 1e-5, "near {near}");
+            assert!(
+                (depth(&camera, camera.zfar) - 1.0).abs() < 1e-5,
+                "near {near}"
+            );
+        }
+    }
+
+    #[test]
+    fn perspective_height_matches_view_height() {
+        // a point at the top of the view height lands at the top of the view
+        let camera = Camera::new(1.5);
+        let distance = 5.0;
+        let eye = camera.eye;
+        let top = eye.y + camera.view_height_at(distance) / 2.0;
+        let clip = camera.get_matrix() * cgmath::Vector4::new(eye.x, top, eye.z - distance, 1.0);
+        assert!((clip.y / clip.w - 1.0).abs() < 1e-5);
+        assert!((clip.w - distance).abs() < 1e-5);
+    }
+
+    #[test]
+    fn reverse_z_maps_far_plane_to_zero() {
+        let camera = Camera::new(1.0);
+        let reversed = Camera {
+            reverse_z: true,
+            ..camera.clone()
+        };
+        let far = camera.zfar;
+        assert!((depth(&camera, far) - 1.0).abs() < 1e-6);
+        assert!(depth(&reversed, far).abs() < 1e-6);
+        for distance in [0.5, 1.0, 10.0, 50.0] {
+            let expected = 1.0 - depth(&camera, distance);
+            assert!((depth(&reversed, distance) - expected).abs() < 1e-5);
+        }
+    }
+
+    // Share of distances between the near and far planes where a point
+    // `gap` behind another doesn't get a farther depth, so the two would
+    // z-fight.
+    fn unresolved_share(reverse_z: bool, gap: f32) -> f32 {
+        let camera = Camera {
+            reverse_z,
+            ..Camera::new(1.0)
+        };
+        let steps = 10_000;
+        let (start, end) = (1.0, camera.zfar - gap);
+        let unresolved = (0..steps)
+            .map(|i| start + (end - start) * i as f32 / steps as f32)
+            .filter(|&distance| {
+                let (near, far) = (depth(&camera, distance), depth(&camera, distance + gap));
+                if reverse_z { far >= near } else { far <= near }
+            })
+            .count();
+        unresolved as f32 / steps as f32
+    }
+
+    #[test]
+    fn reverse_z_resolves_close_surfaces_at_distance() {
+        // without reverse-Z, points a thousandth apart share a depth over
+        // about two fifths of the range
+        assert!(unresolved_share(false, 1e-3) > 0.3);
+        for gap in [1e-2, 1e-3, 1e-4] {
+            assert_eq!(unresolved_share(true, gap), 0.0, "gap {gap}");
+        }
+    }
+
+    #[test]
+    fn cursor_ray_passes_through_the_point_under_the_cursor() {
+        for reverse_z in [false, true] {
+            let camera = Camera {
+                reverse_z,
+                ..Camera::new(1.5)
+            };
+            let (_, direction) = camera.cursor_ray([0.0, 0.0]).unwrap();
+            assert!((direction[2] + 1.0).abs() < 1e-5, "{direction:?}");
+
+            // a point off to the side and behind the target
+            let point = cgmath::Vector4::new(1.0, -0.5, -2.0, 1.0);
+            let clip = camera.get_matrix() * point;
+            let ndc = [clip.x / clip.w, clip.y / clip.w];
+            let (origin, direction) = camera.cursor_ray(ndc).unwrap();
+            let to_point = cgmath::Vector3::new(
+                point.x - origin[0],
+                point.y - origin[1],
+                point.z - origin[2],
+            );
+            let along = to_point.dot(direction.into());
+            let miss = (to_point - along * cgmath::Vector3::from(direction)).magnitude();
+            assert!(along > 0.0 && miss < 1e-3, "reverse-Z {reverse_z}: {miss}");
+        }
+    }
+}
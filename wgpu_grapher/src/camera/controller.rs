@@ -47,16 +47,16 @@ impl CameraController {
         let angle_incr = self.speed * PI / 4.0;
 
         if self.is_right_pressed {
-            camera.alpha += angle_incr;
+            camera.increment_user_rotation(angle_incr, 0.0);
         }
         if self.is_left_pressed {
-            camera.alpha -= angle_incr;
+            camera.increment_user_rotation(-angle_incr, 0.0);
         }
         if self.is_up_pressed {
-            camera.gamma += angle_incr;
+            camera.increment_user_rotation(0.0, angle_incr);
         }
         if self.is_down_pressed {
-            camera.gamma -= angle_incr;
+            camera.increment_user_rotation(0.0, -angle_incr);
         }
     }
 
@@ -39,6 +39,7 @@ pub async fn run(args: CliArgs) {
             &gpu_state.device,
             &gpu_state.config,
             &state,
+            args.seed,
         )),
         Command::HeatEquation => Box::from(mesh::heat_eqn_scene(
             &gpu_state.device,
@@ -57,6 +58,7 @@ pub async fn run(args: CliArgs) {
             &gpu_state.queue,
             &gpu_state.config,
             &state,
+            args.seed,
         )),
     };
 
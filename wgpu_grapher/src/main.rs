@@ -14,6 +14,10 @@ use clap::{Args, Parser, Subcommand};
 pub struct CliArgs {
     #[clap(subcommand)]
     pub command: Command,
+
+    /// Seed for the wave equation's random disturbances, to reproduce a run.
+    #[clap(long, global = true)]
+    pub seed: Option<u64>,
 }
 
 #[derive(Subcommand)]
@@ -1,4 +1,5 @@
-use rand::{Rng, rngs::ThreadRng};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // Finite-difference wave equation solver.
 
@@ -21,8 +22,9 @@ pub struct WaveEquationData {
     // 2x previous data
     u_2: Vec<Vec<f32>>,
 
-    // random number generator
-    rng: ThreadRng,
+    // random number generator and the seed it started from
+    rng: StdRng,
+    seed: u64,
 
     // grid size
     pub x_size: usize,
@@ -36,12 +38,16 @@ pub struct WaveEquationData {
 }
 
 impl WaveEquationData {
-    pub fn new(x_size: usize, y_size: usize) -> Self {
+    /// Seed the disturbances so a run can be reproduced; with no seed
+    /// one is taken from the system time.
+    pub fn with_seed(x_size: usize, y_size: usize, seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(time_seed);
         Self {
             u_0: vec![vec![0.0; y_size]; x_size],
             u_1: vec![vec![0.0; y_size]; x_size],
             u_2: vec![vec![0.0; y_size]; x_size],
-            rng: rand::rng(),
+            rng: StdRng::seed_from_u64(seed),
+            seed,
             //
             x_size,
             y_size,
@@ -53,6 +59,11 @@ impl WaveEquationData {
         }
     }
 
+    /// Seed of the disturbance generator, for reproducing this run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     pub fn update(&mut self) {
         self.add_random_disturbance();
 
@@ -103,6 +114,13 @@ impl WaveEquationData {
     }
 }
 
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
 // Finite-difference heat equation solver.
 
 pub struct HeatEquationData {
@@ -207,6 +207,7 @@ pub fn wave_eqn_scene(
     device: &Device,
     surface_config: &SurfaceConfiguration,
     state: &RenderState,
+    seed: Option<u64>,
 ) -> WaveEquationScene {
     const WAVE_EQN_SUBDIV: usize = 600;
     // number of squares is 1 less than number of gridpoints
@@ -223,7 +224,8 @@ pub fn wave_eqn_scene(
         state,
         vec![(mesh_data.clone(), matrix)],
     );
-    let mut wave_eqn = pde::WaveEquationData::new(WAVE_EQN_SUBDIV, WAVE_EQN_SUBDIV);
+    let mut wave_eqn = pde::WaveEquationData::with_seed(WAVE_EQN_SUBDIV, WAVE_EQN_SUBDIV, seed);
+    log::info!("Wave equation seed: {}", wave_eqn.seed());
 
     wave_eqn.disturbance_prob = 0.003;
     wave_eqn.disturbance_size = 2.0;
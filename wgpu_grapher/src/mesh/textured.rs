@@ -318,6 +318,7 @@ pub fn wave_eqn_texture_scene(
     queue: &Queue,
     surface_config: &SurfaceConfiguration,
     state: &RenderState,
+    seed: Option<u64>,
 ) -> WaveEquationTextureScene {
     let texture_dims: (u32, u32) = (pde::X_SIZE as u32, pde::Y_SIZE as u32);
 
@@ -343,7 +344,8 @@ pub fn wave_eqn_texture_scene(
     let meshes = vec![(mesh_data, MatrixUniform::x_rotation(90.0))];
 
     let scene = build_scene(device, surface_config, state, meshes);
-    let mut wave_eqn = pde::WaveEquationData::new(1000, 1000);
+    let mut wave_eqn = pde::WaveEquationData::with_seed(1000, 1000, seed);
+    log::info!("Wave equation seed: {}", wave_eqn.seed());
 
     // update solver properties
     wave_eqn.disturbance_prob = 0.01;